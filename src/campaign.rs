@@ -0,0 +1,490 @@
+//! 持续模糊测试活动
+//!
+//! A campaign keeps running random tests on the rayon pool until one of its
+//! [`CampaignBudget`] limits is reached or the user presses Ctrl-C. The first
+//! Ctrl-C stops scheduling new tests and kills the running emulators, which
+//! run in their own process groups and do not see the terminal's signal; the
+//! tests still in flight skip their remaining retry rounds and minimization,
+//! and their results are discarded. A second Ctrl-C exits immediately.
+
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use crate::emulators::process::kill_all_process_groups;
+use crate::error::Result;
+use crate::output_diff::analysis::bucket::{BUCKET_INDEX_FILE, BucketIndex, DivergenceSignature};
+use crate::output_diff::analysis::triage::{KnownBugDatabase, TriageClass};
+use crate::output_parser::util::get_current_timestamp;
+
+/// 活动汇总文件名 (不含扩展名)
+pub const CAMPAIGN_SUMMARY_FILE: &str = "campaign_summary";
+
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+extern "C" fn on_interrupt(_signal: libc::c_int) {
+    // 模拟器不在前台进程组中，由这里结束
+    kill_all_process_groups();
+    // 第二次 Ctrl-C 直接退出
+    if INTERRUPTED.swap(true, Ordering::SeqCst) {
+        unsafe { libc::_exit(130) };
+    }
+}
+
+/// Routes SIGINT to [`interrupted`] and kills the running emulators instead
+/// of killing the process
+pub fn install_interrupt_handler() {
+    let handler = on_interrupt as extern "C" fn(libc::c_int);
+    unsafe {
+        libc::signal(libc::SIGINT, handler as libc::sighandler_t);
+    }
+}
+
+/// 是否收到过 Ctrl-C
+pub fn interrupted() -> bool {
+    INTERRUPTED.load(Ordering::SeqCst)
+}
+
+/// Parses `90`, `90s`, `30m` or `8h`
+pub fn parse_duration(text: &str) -> std::result::Result<Duration, String> {
+    let text = text.trim();
+    let (number, unit) = match text.char_indices().find(|(_, c)| !c.is_ascii_digit()) {
+        Some((i, _)) => text.split_at(i),
+        None => (text, "s"),
+    };
+    let value: u64 = number
+        .parse()
+        .map_err(|_| format!("invalid duration `{}`", text))?;
+    let unit_seconds = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 3600,
+        _ => return Err(format!("invalid duration unit `{}` (use s, m or h)", unit)),
+    };
+    let seconds = value
+        .checked_mul(unit_seconds)
+        .ok_or_else(|| format!("duration `{}` is too long", text))?;
+    Ok(Duration::from_secs(seconds))
+}
+
+/// 活动的停止条件，均为 None 时一直运行到 Ctrl-C
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct CampaignBudget {
+    pub duration: Option<Duration>,
+    pub max_tests: Option<usize>,
+    pub max_divergences: Option<usize>,
+}
+
+/// 活动结束的原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StopReason {
+    TimeBudget,
+    TestBudget,
+    DivergenceBudget,
+    Interrupted,
+}
+
+impl fmt::Display for StopReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StopReason::TimeBudget => write!(f, "⏱️ time budget reached"),
+            StopReason::TestBudget => write!(f, "🔢 test budget reached"),
+            StopReason::DivergenceBudget => write!(f, "🎯 divergence budget reached"),
+            StopReason::Interrupted => write!(f, "🛑 interrupted"),
+        }
+    }
+}
+
+/// 单个测试的结论
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TestVerdict {
+    /// 模拟器的结果不一致
+    pub diverged: bool,
+    /// 至少一个模拟器超时
+    pub timed_out: bool,
+    /// 差异签名，用于分桶
+    pub signature: Option<DivergenceSignature>,
+    /// 能复现差异的最短汇编 (最小化 > 重试 > 原始)
+    pub reproducer: Option<PathBuf>,
+    /// 按已知缺陷库的分诊结论，未配置缺陷库时为 None
+    pub triage: Option<TriageClass>,
+    /// 命中的已知缺陷 ID
+    pub known_bugs: Vec<String>,
+}
+
+/// 输出目录中已有 `test_NNNNNN` 目录之后的第一个 ID
+fn next_free_test_id(output_dir: &Path) -> Result<usize> {
+    if !output_dir.exists() {
+        return Ok(0);
+    }
+    let mut next = 0;
+    for entry in fs::read_dir(output_dir)? {
+        let name = entry?.file_name();
+        let test_id = name
+            .to_str()
+            .and_then(|name| name.strip_prefix("test_"))
+            .and_then(|id| id.parse::<usize>().ok());
+        if let Some(test_id) = test_id {
+            next = next.max(test_id + 1);
+        }
+    }
+    Ok(next)
+}
+
+/// Live counters shared by the campaign workers
+pub struct Campaign {
+    pub seed: u64,
+    pub budget: CampaignBudget,
+    output_dir: PathBuf,
+    started_at: String,
+    start: Instant,
+    /// 本次活动的第一个测试 ID，之前的 ID 属于同一目录中已有的测试
+    first_test_id: usize,
+    next_test_id: AtomicUsize,
+    tests_run: AtomicUsize,
+    divergences: AtomicUsize,
+    known_divergences: AtomicUsize,
+    timeouts: AtomicUsize,
+    errors: AtomicUsize,
+    discarded: AtomicUsize,
+    divergent_tests: Mutex<Vec<usize>>,
+    buckets: Mutex<BucketIndex>,
+    known_bugs: Option<KnownBugDatabase>,
+    bug_hits: Mutex<BTreeMap<String, usize>>,
+    stop_reason: Mutex<Option<StopReason>>,
+}
+
+impl Campaign {
+    /// Buckets already indexed in `output_dir` are kept and extended, and
+    /// test IDs continue after the test directories already there
+    pub fn new<P: AsRef<Path>>(seed: u64, budget: CampaignBudget, output_dir: P) -> Result<Self> {
+        let buckets = BucketIndex::load_or_default(&output_dir)?;
+        let known_bugs = KnownBugDatabase::load_configured()?;
+        let first_test_id = next_free_test_id(output_dir.as_ref())?;
+        if first_test_id > 0 {
+            info!(
+                "📂 Resuming in {:?}, new tests start at #{}",
+                output_dir.as_ref(),
+                first_test_id
+            );
+        }
+        Ok(Self {
+            seed,
+            budget,
+            output_dir: output_dir.as_ref().to_path_buf(),
+            started_at: get_current_timestamp(),
+            start: Instant::now(),
+            first_test_id,
+            next_test_id: AtomicUsize::new(first_test_id),
+            tests_run: AtomicUsize::new(0),
+            divergences: AtomicUsize::new(0),
+            known_divergences: AtomicUsize::new(0),
+            timeouts: AtomicUsize::new(0),
+            errors: AtomicUsize::new(0),
+            discarded: AtomicUsize::new(0),
+            divergent_tests: Mutex::new(Vec::new()),
+            buckets: Mutex::new(buckets),
+            known_bugs,
+            bug_hits: Mutex::new(BTreeMap::new()),
+            stop_reason: Mutex::new(None),
+        })
+    }
+
+    /// 记录停止原因 (只保留第一个)
+    fn stop(&self, reason: StopReason) {
+        let mut stop_reason = self.stop_reason.lock().unwrap_or_else(|e| e.into_inner());
+        stop_reason.get_or_insert(reason);
+    }
+
+    fn stopped(&self) -> bool {
+        self.stop_reason
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .is_some()
+    }
+
+    /// Claims the next test ID, or None once the campaign should stop
+    pub fn next_test(&self) -> Option<usize> {
+        if interrupted() {
+            self.stop(StopReason::Interrupted);
+        }
+        if self
+            .budget
+            .duration
+            .is_some_and(|duration| self.start.elapsed() >= duration)
+        {
+            self.stop(StopReason::TimeBudget);
+        }
+        if self.stopped() {
+            return None;
+        }
+        let test_id = self.next_test_id.fetch_add(1, Ordering::SeqCst);
+        if self
+            .budget
+            .max_tests
+            .is_some_and(|max| test_id - self.first_test_id >= max)
+        {
+            self.stop(StopReason::TestBudget);
+            return None;
+        }
+        Some(test_id)
+    }
+
+    /// Records the result of a finished test and logs the live counters
+    pub fn record(&self, test_id: usize, test_dir: &Path, result: &Result<TestVerdict>) {
+        if interrupted() {
+            self.discarded.fetch_add(1, Ordering::SeqCst);
+            info!("🛑 Discarding test #{} finished after Ctrl-C", test_id);
+            return;
+        }
+
+        let tests_run = self.tests_run.fetch_add(1, Ordering::SeqCst) + 1;
+        match result {
+            Ok(verdict) => {
+                if verdict.timed_out {
+                    self.timeouts.fetch_add(1, Ordering::SeqCst);
+                }
+                if !verdict.known_bugs.is_empty() {
+                    let mut bug_hits = self.bug_hits.lock().unwrap_or_else(|e| e.into_inner());
+                    for id in &verdict.known_bugs {
+                        *bug_hits.entry(id.clone()).or_default() += 1;
+                    }
+                }
+                // 已知缺陷只计数，不计入差异预算和分桶
+                if verdict.diverged && verdict.triage == Some(TriageClass::Known) {
+                    self.known_divergences.fetch_add(1, Ordering::SeqCst);
+                    info!(
+                        "📚 Test #{} only hits known bugs: {}",
+                        test_id,
+                        verdict.known_bugs.join(", ")
+                    );
+                } else if verdict.diverged {
+                    self.divergent_tests
+                        .lock()
+                        .unwrap_or_else(|e| e.into_inner())
+                        .push(test_id);
+                    let divergences = self.divergences.fetch_add(1, Ordering::SeqCst) + 1;
+                    if self
+                        .budget
+                        .max_divergences
+                        .is_some_and(|max| divergences >= max)
+                    {
+                        self.stop(StopReason::DivergenceBudget);
+                    }
+                    if let (Some(signature), Some(reproducer)) =
+                        (&verdict.signature, &verdict.reproducer)
+                    {
+                        self.add_to_bucket(signature, test_dir, reproducer);
+                    }
+                }
+            }
+            Err(_) => {
+                self.errors.fetch_add(1, Ordering::SeqCst);
+            }
+        }
+
+        info!(
+            "📈 {} tests, {} divergences ({} known), {} timeouts, {} errors in {:.0?}",
+            tests_run,
+            self.divergences.load(Ordering::SeqCst),
+            self.known_divergences.load(Ordering::SeqCst),
+            self.timeouts.load(Ordering::SeqCst),
+            self.errors.load(Ordering::SeqCst),
+            self.start.elapsed()
+        );
+    }
+
+    /// 更新并立即保存分桶索引，中途退出也不会丢失
+    fn add_to_bucket(&self, signature: &DivergenceSignature, test_dir: &Path, reproducer: &Path) {
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+        let result = buckets
+            .record(&self.output_dir, signature, test_dir, reproducer)
+            .and_then(|_| buckets.save(&self.output_dir));
+        if let Err(e) = result {
+            warn!("⚠️ Failed to update bucket index for {:?}: {}", test_dir, e);
+        }
+    }
+
+    pub fn summary(&self) -> CampaignSummary {
+        let mut divergent_tests = self
+            .divergent_tests
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone();
+        divergent_tests.sort_unstable();
+        let known_bug_hits = self
+            .bug_hits
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone();
+        let not_reproduced = self.known_bugs.as_ref().map_or_else(Vec::new, |database| {
+            database
+                .not_reproduced(&known_bug_hits)
+                .into_iter()
+                .map(|bug| bug.id.clone())
+                .collect()
+        });
+        CampaignSummary {
+            seed: self.seed,
+            budget: self.budget,
+            started_at: self.started_at.clone(),
+            finished_at: get_current_timestamp(),
+            elapsed: self.start.elapsed(),
+            stop_reason: *self.stop_reason.lock().unwrap_or_else(|e| e.into_inner()),
+            tests_run: self.tests_run.load(Ordering::SeqCst),
+            divergences: self.divergences.load(Ordering::SeqCst),
+            known_divergences: self.known_divergences.load(Ordering::SeqCst),
+            timeouts: self.timeouts.load(Ordering::SeqCst),
+            errors: self.errors.load(Ordering::SeqCst),
+            discarded: self.discarded.load(Ordering::SeqCst),
+            divergent_tests,
+            buckets: self
+                .buckets
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .buckets
+                .len(),
+            known_bug_hits,
+            not_reproduced,
+        }
+    }
+}
+
+/// Final report of a campaign
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CampaignSummary {
+    pub seed: u64,
+    pub budget: CampaignBudget,
+    pub started_at: String,
+    pub finished_at: String,
+    pub elapsed: Duration,
+    pub stop_reason: Option<StopReason>,
+    pub tests_run: usize,
+    /// 新的或重新出现的差异
+    pub divergences: usize,
+    /// 仅命中未修复已知缺陷的差异
+    pub known_divergences: usize,
+    pub timeouts: usize,
+    /// 生成、编译或运行失败的测试数
+    pub errors: usize,
+    /// Ctrl-C 之后结束、未计入统计的测试数
+    pub discarded: usize,
+    pub divergent_tests: Vec<usize>,
+    /// 不同差异签名的数量，详见 `buckets.md`
+    pub buckets: usize,
+    /// 各已知缺陷的命中次数
+    pub known_bug_hits: BTreeMap<String, usize>,
+    /// 本次活动中未复现的未修复缺陷
+    pub not_reproduced: Vec<String>,
+}
+
+impl CampaignSummary {
+    /// 保存为 `campaign_summary.json` 和 `campaign_summary.md`
+    pub fn save<P: AsRef<Path>>(&self, output_dir: P) -> Result<()> {
+        let base = output_dir.as_ref().join(CAMPAIGN_SUMMARY_FILE);
+        fs::write(
+            base.with_extension("json"),
+            serde_json::to_string_pretty(self)?,
+        )?;
+        fs::write(base.with_extension("md"), self.to_string())?;
+        Ok(())
+    }
+}
+
+impl fmt::Display for CampaignSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "# 🏁 Fuzzing Campaign Summary")?;
+        writeln!(f)?;
+        writeln!(f, "- **Seed:** {}", self.seed)?;
+        writeln!(f, "- **Started:** {}", self.started_at)?;
+        writeln!(f, "- **Finished:** {}", self.finished_at)?;
+        writeln!(f, "- **Elapsed:** {:.0?}", self.elapsed)?;
+        match self.stop_reason {
+            Some(reason) => writeln!(f, "- **Stopped:** {}", reason)?,
+            None => writeln!(f, "- **Stopped:** -")?,
+        }
+        writeln!(f)?;
+
+        writeln!(f, "| Item | Count |")?;
+        writeln!(f, "|------|-------|")?;
+        writeln!(f, "| Tests Run | `{}` |", self.tests_run)?;
+        writeln!(f, "| Divergences | `{}` |", self.divergences)?;
+        writeln!(f, "| Known Divergences | `{}` |", self.known_divergences)?;
+        writeln!(f, "| Buckets | `{}` |", self.buckets)?;
+        writeln!(f, "| Timeouts | `{}` |", self.timeouts)?;
+        writeln!(f, "| Errors | `{}` |", self.errors)?;
+        writeln!(f, "| Discarded | `{}` |", self.discarded)?;
+        writeln!(f)?;
+        if self.buckets > 0 {
+            writeln!(
+                f,
+                "Divergences are grouped by signature in `{}.md`.",
+                BUCKET_INDEX_FILE
+            )?;
+            writeln!(f)?;
+        }
+
+        if !self.known_bug_hits.is_empty() || !self.not_reproduced.is_empty() {
+            writeln!(f, "## 🐞 Known Bugs")?;
+            writeln!(f)?;
+            writeln!(f, "| Bug | Hits |")?;
+            writeln!(f, "|-----|------|")?;
+            for (id, hits) in &self.known_bug_hits {
+                writeln!(f, "| `{}` | `{}` |", id, hits)?;
+            }
+            for id in &self.not_reproduced {
+                writeln!(f, "| `{}` | `0` ⚠️ not reproduced |", id)?;
+            }
+            writeln!(f)?;
+        }
+
+        if !self.divergent_tests.is_empty() {
+            writeln!(f, "## 🎯 Divergent Tests")?;
+            writeln!(f)?;
+            for test_id in &self.divergent_tests {
+                writeln!(f, "- `test_{:06}`", test_id)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("90"), Ok(Duration::from_secs(90)));
+        assert_eq!(parse_duration(" 90s "), Ok(Duration::from_secs(90)));
+        assert_eq!(parse_duration("30m"), Ok(Duration::from_secs(1800)));
+        assert_eq!(parse_duration("8h"), Ok(Duration::from_secs(28800)));
+        assert!(parse_duration("").is_err());
+        assert!(parse_duration("h").is_err());
+        assert!(parse_duration("-5s").is_err());
+        assert!(parse_duration("1.5h").is_err());
+        assert!(parse_duration("3d").is_err());
+        assert!(parse_duration("99999999999999999h").is_err());
+        assert!(parse_duration("99999999999999999999").is_err());
+    }
+
+    #[test]
+    fn test_next_free_test_id() {
+        let output_dir =
+            std::env::temp_dir().join(format!("campaign_test_ids_{}", std::process::id()));
+        assert_eq!(next_free_test_id(&output_dir).unwrap(), 0);
+
+        for name in ["test_000000", "test_000041", "test_abc", "buckets"] {
+            fs::create_dir_all(output_dir.join(name)).unwrap();
+        }
+        assert_eq!(next_free_test_id(&output_dir).unwrap(), 42);
+
+        fs::remove_dir_all(&output_dir).unwrap();
+    }
+}
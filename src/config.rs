@@ -0,0 +1,486 @@
+//! `riscv_fuzz.toml` 配置文件
+//!
+//! Every subcommand loads the file once at startup (see [`init`]); command line
+//! flags take precedence over it, and the `*_PATH` environment variables take
+//! precedence over the emulator paths configured here.
+
+use clap::Args;
+use log::{debug, info};
+use riscv_instruction::separated_instructions::RV64Extensions;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use crate::consts::rocket::RV64_ROCKET_SUPPORTED_EXTENSIONS;
+use crate::emulators::{DEFAULT_RUN_TIMEOUT, OutputFormat, RunLimits};
+use crate::error::{Result, RiscvFuzzError};
+use crate::output_diff::analysis::retry::RetryPolicy;
+use crate::output_diff::diff::ignore::DiffIgnoreRules;
+use crate::probe::{CapabilityProfile, DEFAULT_CAPABILITY_PROFILE};
+use crate::random_asm::denylist::DEFAULT_OPCODE_DENYLIST;
+use crate::random_asm::inst_generator::GenerationOrder;
+use crate::utils::{build_rv64_march, march_components};
+
+/// 默认配置文件名 (当前目录)
+pub const DEFAULT_CONFIG_FILE: &str = "riscv_fuzz.toml";
+
+static CONFIG: OnceLock<FuzzConfig> = OnceLock::new();
+
+/// Whole-tool configuration, one field per `riscv_fuzz.toml` section
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FuzzConfig {
+    pub emulators: EmulatorsConfig,
+    pub toolchain: ToolchainConfig,
+    pub isa: IsaConfig,
+    pub generation: GenerationConfig,
+    pub diff: DiffConfig,
+}
+
+/// `[emulators]`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EmulatorsConfig {
+    /// 注册的后端名称 (`spike`、`rocket`、`qemu`、`sail`)
+    pub enabled: Vec<String>,
+    /// Spike 可执行文件
+    pub spike_path: String,
+    /// Rocket 仿真器路径 (`ROCKET_EMULATOR_PATH` 优先)
+    pub rocket_path: Option<String>,
+    /// qemu-system-riscv64 路径 (`QEMU_SYSTEM_RISCV64_PATH` 优先)
+    pub qemu_path: Option<String>,
+    /// Sail riscv_sim_RV64 路径 (`SAIL_RISCV_SIM_PATH` 优先)
+    pub sail_path: Option<String>,
+    /// 单次运行墙钟超时 (秒, 0 表示不限制)
+    pub timeout: u64,
+    pub max_cycles: Option<u64>,
+    pub max_instructions: Option<u64>,
+}
+
+impl Default for EmulatorsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: vec!["spike".to_string(), "rocket".to_string()],
+            spike_path: "spike".to_string(),
+            rocket_path: None,
+            qemu_path: None,
+            sail_path: None,
+            timeout: DEFAULT_RUN_TIMEOUT.as_secs(),
+            max_cycles: None,
+            max_instructions: None,
+        }
+    }
+}
+
+/// 运行限制参数，优先于配置文件
+#[derive(Args, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LimitArgs {
+    /// Wall-clock timeout per emulator run in seconds (0 disables the timeout, default: 60)
+    #[arg(long, global = true)]
+    pub timeout: Option<u64>,
+    /// Maximum number of cycles per Rocket run
+    #[arg(long, global = true)]
+    pub max_cycles: Option<u64>,
+    /// Maximum number of instructions per Spike/Sail run
+    #[arg(long, global = true)]
+    pub max_instructions: Option<u64>,
+}
+
+impl LimitArgs {
+    /// `limits` (from `[emulators]` or a recorded run) with the given flags replacing them
+    pub fn apply(&self, mut limits: RunLimits) -> RunLimits {
+        if let Some(timeout) = self.timeout {
+            limits.timeout = (timeout > 0).then(|| Duration::from_secs(timeout));
+        }
+        limits.max_cycles = self.max_cycles.or(limits.max_cycles);
+        limits.max_instructions = self.max_instructions.or(limits.max_instructions);
+        limits
+    }
+}
+
+impl EmulatorsConfig {
+    pub fn is_enabled(&self, name: &str) -> bool {
+        self.enabled
+            .iter()
+            .any(|enabled| enabled.eq_ignore_ascii_case(name))
+    }
+
+    pub fn run_limits(&self) -> RunLimits {
+        RunLimits {
+            timeout: (self.timeout > 0).then(|| Duration::from_secs(self.timeout)),
+            max_cycles: self.max_cycles,
+            max_instructions: self.max_instructions,
+        }
+    }
+}
+
+/// `[toolchain]`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ToolchainConfig {
+    /// GNU 工具链前缀，如 `riscv64-unknown-elf-`
+    pub prefix: String,
+}
+
+impl Default for ToolchainConfig {
+    fn default() -> Self {
+        Self {
+            prefix: "riscv64-unknown-elf-".to_string(),
+        }
+    }
+}
+
+impl ToolchainConfig {
+    /// 工具完整名称，如 `tool("gcc")` → `riscv64-unknown-elf-gcc`
+    pub fn tool(&self, name: &str) -> String {
+        format!("{}{}", self.prefix, name)
+    }
+}
+
+/// `[isa]`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct IsaConfig {
+    /// 编译与运行使用的扩展 (默认 Rocket 支持的扩展加 D)
+    pub extensions: Option<Vec<RV64Extensions>>,
+    /// 直接指定 march 字符串，优先于 `extensions`
+    pub march: Option<String>,
+}
+
+impl IsaConfig {
+    pub fn march_string(&self) -> String {
+        if let Some(march) = &self.march {
+            return march.clone();
+        }
+        build_rv64_march(&self.base_extensions())
+    }
+
+    /// March string for random programs generated from `generated`.
+    ///
+    /// Without an explicit `march` this is the union of the configured and the
+    /// generated extensions; an explicit `march` must already cover them.
+    pub fn generation_march(&self, generated: &[RV64Extensions]) -> Result<String> {
+        if let Some(march) = &self.march {
+            let available = march_components(march);
+            let missing: Vec<String> = march_components(&build_rv64_march(generated))
+                .difference(&available)
+                .cloned()
+                .collect();
+            if !missing.is_empty() {
+                return Err(RiscvFuzzError::config(format!(
+                    "[isa] march `{}` does not cover generated extensions: {}",
+                    march,
+                    missing.join(", ")
+                )));
+            }
+            return Ok(march.clone());
+        }
+
+        let mut exts = self.base_extensions();
+        for ext in generated {
+            if !exts.contains(ext) {
+                exts.push(*ext);
+            }
+        }
+        Ok(build_rv64_march(&exts))
+    }
+
+    /// 配置的扩展 (默认 Rocket 支持的扩展加 D)
+    fn base_extensions(&self) -> Vec<RV64Extensions> {
+        self.extensions.clone().unwrap_or_else(|| {
+            let mut exts = RV64_ROCKET_SUPPORTED_EXTENSIONS.to_vec();
+            exts.push(RV64Extensions::D);
+            exts
+        })
+    }
+}
+
+/// `[generation]`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GenerationConfig {
+    /// 每个扩展生成的指令数
+    pub inst_num: usize,
+    pub order: GenerationOrder,
+    /// 随机生成使用的扩展，优先于能力描述文件
+    pub extensions: Option<Vec<RV64Extensions>>,
+    /// `probe` 生成的能力描述文件 (默认读取存在的 capability_profile.json)
+    pub capability_profile: Option<PathBuf>,
+    /// 按模拟器记录的非法操作码黑名单，生成时跳过其中的指令
+    pub opcode_denylist: PathBuf,
+    /// 是否把新发现的单边非法指令写入黑名单
+    pub learn_opcode_denylist: bool,
+    /// `random` 的活动种子 (默认每次随机)
+    pub seed: Option<u64>,
+}
+
+impl Default for GenerationConfig {
+    fn default() -> Self {
+        Self {
+            inst_num: 50,
+            order: GenerationOrder::RandomShuffle,
+            extensions: None,
+            capability_profile: None,
+            opcode_denylist: PathBuf::from(DEFAULT_OPCODE_DENYLIST),
+            learn_opcode_denylist: true,
+            seed: None,
+        }
+    }
+}
+
+impl GenerationConfig {
+    /// Extensions to generate from: the configured list, else the usable
+    /// extensions of the capability profile, else `RV64_ROCKET_SUPPORTED_EXTENSIONS`.
+    pub fn extensions(&self) -> Result<Vec<RV64Extensions>> {
+        if let Some(extensions) = &self.extensions {
+            return Ok(extensions.clone());
+        }
+
+        let profile_path = match &self.capability_profile {
+            Some(path) => Some(path.clone()),
+            None => Some(PathBuf::from(DEFAULT_CAPABILITY_PROFILE)).filter(|p| p.exists()),
+        };
+        if let Some(path) = profile_path {
+            let profile = CapabilityProfile::load(&path)?;
+            let extensions = profile.usable_extensions();
+            info!(
+                "🧪 Using {} extensions from capability profile {}",
+                extensions.len(),
+                path.display()
+            );
+            return Ok(extensions);
+        }
+
+        Ok(RV64_ROCKET_SUPPORTED_EXTENSIONS.to_vec())
+    }
+}
+
+/// `[diff]`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DiffConfig {
+    /// `run` 子命令默认的输出格式
+    pub format: OutputFormat,
+    /// 是否按 `[diff.retry]` 删除问题指令并自动重试
+    pub auto_retry: bool,
+    /// `[diff.ignore]`: 预期内的差异
+    pub ignore: DiffIgnoreRules,
+    /// 已知缺陷库 (TOML 或 JSON)，用于分诊
+    pub known_bugs: Option<PathBuf>,
+    /// `[diff.retry]`: 自动重试时删除哪些指令、最多几轮
+    pub retry: RetryPolicy,
+    /// 对有差异的 Standard 测试运行 ddmin，用验证过的最小程序作为复现程序
+    pub minimize: bool,
+    /// 有差异的 Standard 测试用参考解释器作为第三方投票
+    pub reference_vote: bool,
+}
+
+impl Default for DiffConfig {
+    fn default() -> Self {
+        Self {
+            format: OutputFormat::Standard,
+            auto_retry: true,
+            ignore: DiffIgnoreRules::default(),
+            known_bugs: None,
+            retry: RetryPolicy::default(),
+            minimize: false,
+            reference_vote: true,
+        }
+    }
+}
+
+impl FuzzConfig {
+    /// 读取并解析配置文件
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let content = fs::read_to_string(path.as_ref()).map_err(|e| {
+            RiscvFuzzError::config(format!(
+                "Failed to read config file {}: {}",
+                path.as_ref().display(),
+                e
+            ))
+        })?;
+        toml::from_str(&content).map_err(|e| {
+            RiscvFuzzError::config(format!(
+                "Invalid config file {}: {}",
+                path.as_ref().display(),
+                e
+            ))
+        })
+    }
+
+    /// Loads `path`, or [`DEFAULT_CONFIG_FILE`] if it exists, or the defaults.
+    /// An explicitly given file must exist.
+    pub fn load_or_default(path: Option<&Path>) -> Result<Self> {
+        match path {
+            Some(path) => {
+                info!("⚙️ Loading config from {}", path.display());
+                Self::load(path)
+            }
+            None if Path::new(DEFAULT_CONFIG_FILE).exists() => {
+                info!("⚙️ Loading config from {}", DEFAULT_CONFIG_FILE);
+                Self::load(DEFAULT_CONFIG_FILE)
+            }
+            None => {
+                debug!("No {} found, using default config", DEFAULT_CONFIG_FILE);
+                Ok(Self::default())
+            }
+        }
+    }
+}
+
+/// 设置全局配置，只能调用一次
+pub fn init(config: FuzzConfig) -> Result<()> {
+    CONFIG
+        .set(config)
+        .map_err(|_| RiscvFuzzError::config("Configuration already initialized"))
+}
+
+/// 全局配置，未调用 [`init`] 时为默认值
+pub fn get() -> &'static FuzzConfig {
+    CONFIG.get_or_init(FuzzConfig::default)
+}
+
+/// Emulator binary path: environment variable, then config file, then `default`
+pub fn resolve_emulator_path(env_var: &str, configured: Option<&String>, default: &str) -> String {
+    std::env::var(env_var)
+        .ok()
+        .or_else(|| configured.cloned())
+        .unwrap_or_else(|| default.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 写入临时配置文件并读取
+    fn load_toml(name: &str, content: &str) -> Result<FuzzConfig> {
+        let path =
+            std::env::temp_dir().join(format!("riscv_fuzz_{}_{}.toml", name, std::process::id()));
+        fs::write(&path, content).unwrap();
+        let config = FuzzConfig::load(&path);
+        fs::remove_file(&path).unwrap();
+        config
+    }
+
+    #[test]
+    fn test_load_keeps_defaults_for_missing_keys() {
+        let config = load_toml(
+            "partial",
+            r#"
+[emulators]
+enabled = ["spike", "qemu"]
+max_cycles = 1000
+
+[isa]
+march = "rv64gc"
+
+[generation]
+inst_num = 7
+"#,
+        )
+        .unwrap();
+        assert!(config.emulators.is_enabled("Qemu"));
+        assert!(!config.emulators.is_enabled("rocket"));
+        assert_eq!(config.emulators.spike_path, "spike");
+        assert_eq!(config.isa.march_string(), "rv64gc");
+        assert_eq!(config.generation.inst_num, 7);
+        assert_eq!(config.generation.order, GenerationOrder::RandomShuffle);
+        assert_eq!(config.diff, DiffConfig::default());
+    }
+
+    #[test]
+    fn test_load_rejects_unknown_keys() {
+        let err = load_toml("unknown", "[generation]\ninst_nums = 7\n").unwrap_err();
+        assert!(err.to_string().contains("inst_nums"));
+    }
+
+    #[test]
+    fn test_cli_limits_take_precedence_over_file() {
+        let config = load_toml(
+            "limits",
+            "[emulators]\ntimeout = 30\nmax_cycles = 1000\nmax_instructions = 2000\n",
+        )
+        .unwrap();
+        let file_limits = config.emulators.run_limits();
+        assert_eq!(file_limits.timeout, Some(Duration::from_secs(30)));
+
+        // 未给出的参数保留配置文件的值
+        assert_eq!(LimitArgs::default().apply(file_limits), file_limits);
+
+        let cli = LimitArgs {
+            timeout: Some(5),
+            max_cycles: Some(10),
+            max_instructions: None,
+        };
+        let limits = cli.apply(file_limits);
+        assert_eq!(limits.timeout, Some(Duration::from_secs(5)));
+        assert_eq!(limits.max_cycles, Some(10));
+        assert_eq!(limits.max_instructions, Some(2000));
+
+        // `--timeout 0` 取消配置文件中的超时
+        let no_timeout = LimitArgs {
+            timeout: Some(0),
+            ..LimitArgs::default()
+        };
+        assert_eq!(no_timeout.apply(file_limits).timeout, None);
+    }
+
+    #[test]
+    fn test_generation_march_without_explicit_march() {
+        let isa = IsaConfig {
+            extensions: Some(vec![RV64Extensions::I, RV64Extensions::M]),
+            march: None,
+        };
+        assert_eq!(
+            isa.march_string(),
+            build_rv64_march(&[RV64Extensions::I, RV64Extensions::M])
+        );
+        assert_eq!(
+            isa.generation_march(&[RV64Extensions::M, RV64Extensions::C])
+                .unwrap(),
+            build_rv64_march(&[RV64Extensions::I, RV64Extensions::M, RV64Extensions::C])
+        );
+    }
+
+    #[test]
+    fn test_explicit_march_must_cover_generated_extensions() {
+        let isa = IsaConfig {
+            extensions: Some(vec![RV64Extensions::I]),
+            march: Some("rv64imac_zicsr".to_string()),
+        };
+        // 显式 march 优先于 extensions
+        assert_eq!(isa.march_string(), "rv64imac_zicsr");
+        assert_eq!(
+            isa.generation_march(&[RV64Extensions::M, RV64Extensions::C])
+                .unwrap(),
+            "rv64imac_zicsr"
+        );
+
+        let err = isa
+            .generation_march(&[RV64Extensions::M, RV64Extensions::F])
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("rv64imac_zicsr"));
+        assert!(err.contains("does not cover generated extensions: f"));
+    }
+
+    #[test]
+    fn test_generation_extensions_precedence() {
+        // 配置的扩展优先，不读取能力描述文件
+        let configured = GenerationConfig {
+            extensions: Some(vec![RV64Extensions::M]),
+            capability_profile: Some(PathBuf::from("missing_capability_profile.json")),
+            ..GenerationConfig::default()
+        };
+        assert_eq!(configured.extensions().unwrap(), vec![RV64Extensions::M]);
+
+        // 显式指定的能力描述文件必须存在
+        let profile = GenerationConfig {
+            extensions: None,
+            ..configured
+        };
+        assert!(profile.extensions().is_err());
+    }
+}
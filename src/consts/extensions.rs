@@ -0,0 +1,59 @@
+use riscv_instruction::separated_instructions::RV64Extensions;
+
+// 全部 RV64 扩展，probe 子命令逐个探测
+pub const ALL_RV64_EXTENSIONS: &[RV64Extensions] = &[
+    RV64Extensions::B,
+    RV64Extensions::C,
+    RV64Extensions::D,
+    RV64Extensions::F,
+    RV64Extensions::H,
+    RV64Extensions::I,
+    RV64Extensions::M,
+    RV64Extensions::Q,
+    RV64Extensions::S,
+    RV64Extensions::Sdext,
+    RV64Extensions::Smdbltrp,
+    RV64Extensions::Smrnmi,
+    RV64Extensions::Svinval,
+    RV64Extensions::V,
+    RV64Extensions::Zaamo,
+    RV64Extensions::Zabha,
+    RV64Extensions::Zacas,
+    RV64Extensions::Zalasr,
+    RV64Extensions::Zalrsc,
+    RV64Extensions::Zawrs,
+    RV64Extensions::Zba,
+    RV64Extensions::Zbb,
+    RV64Extensions::Zbc,
+    RV64Extensions::Zbkb,
+    RV64Extensions::Zbkx,
+    RV64Extensions::Zbs,
+    RV64Extensions::Zcb,
+    RV64Extensions::Zcd,
+    RV64Extensions::Zcmop,
+    RV64Extensions::Zcmp,
+    RV64Extensions::Zfbfmin,
+    RV64Extensions::Zfh,
+    RV64Extensions::Zicbom,
+    RV64Extensions::Zicboz,
+    RV64Extensions::Zicfilp,
+    RV64Extensions::Zicfiss,
+    RV64Extensions::Zicond,
+    RV64Extensions::Zicsr,
+    RV64Extensions::Zifencei,
+    RV64Extensions::Zilsd,
+    RV64Extensions::Zimop,
+    RV64Extensions::Zkn,
+    RV64Extensions::Zknd,
+    RV64Extensions::Zkne,
+    RV64Extensions::Zknh,
+    RV64Extensions::Zks,
+    RV64Extensions::Zvbb,
+    RV64Extensions::Zvbc,
+    RV64Extensions::Zvfbfmin,
+    RV64Extensions::Zvfbfwma,
+    RV64Extensions::Zvkg,
+    RV64Extensions::Zvkned,
+    RV64Extensions::Zvknha,
+    RV64Extensions::Zvks,
+];
@@ -1,2 +1,4 @@
+pub mod extensions;
 pub mod linker_script;
+pub mod qemu;
 pub mod rocket;
@@ -0,0 +1,9 @@
+// QEMU virt 机器的输出通道参数
+//
+// QEMU 没有 HTIF，运行的是以 --defsym QEMU_UART_CONSOLE 构建的 ELF 变体，
+// 模板中的 HTIF_PRINT_RAW / EXIT_SIM 在该变体中改用 UART 输出和 sifive_test 退出。
+
+/// virt 机器 ns16550a UART 基地址
+pub const QEMU_UART_BASE: u64 = 0x1000_0000;
+/// virt 机器 sifive_test 退出设备地址
+pub const QEMU_TEST_FINISHER_ADDR: u64 = 0x10_0000;
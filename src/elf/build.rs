@@ -1,12 +1,19 @@
+use crate::config;
+use crate::emulators::ConsoleChannel;
 use crate::error::{Result, RiscvFuzzError};
 use log::{debug, error, info};
+use serde::{Deserialize, Serialize};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 use std::time::Instant;
 
 /// ELF 构建结果，包含所有生成的文件路径
-#[derive(Debug, Clone)]
+///
+/// Saved next to the executable (see [`build_record_path`]) so that the other
+/// [`ConsoleChannel`] variants can be built on demand by
+/// [`build_console_variant`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ElfBuildResult {
     /// 预处理后的汇编文件路径（仅当输入为 .S 文件时）
     pub preprocessed_assembly: Option<PathBuf>,
@@ -16,6 +23,10 @@ pub struct ElfBuildResult {
     pub executable_file: PathBuf,
     /// 反汇编文件路径
     pub disassembly_file: PathBuf,
+    /// 实际汇编的文件 (预处理结果或原文件)
+    pub assembled_file: PathBuf,
+    pub linker_script: PathBuf,
+    pub march: String,
 }
 
 impl ElfBuildResult {
@@ -31,6 +42,20 @@ impl ElfBuildResult {
         }
         files
     }
+
+    /// 读取 [`build_elf`] 保存在可执行文件旁的构建结果
+    pub fn load(executable_file: &Path) -> Result<Self> {
+        let record_file = build_record_path(executable_file);
+        let content = fs::read_to_string(&record_file).map_err(|e| {
+            RiscvFuzzError::file(format!("Cannot read {}: {}", record_file.display(), e))
+        })?;
+        Ok(serde_json::from_str(&content)?)
+    }
+}
+
+/// 构建结果文件，与可执行文件位于同一目录
+pub fn build_record_path(executable_file: &Path) -> PathBuf {
+    executable_file.with_extension("build.json")
 }
 
 /// 一键编译 ELF 文件，返回详细的构建结果
@@ -50,8 +75,14 @@ pub fn build_elf<P: AsRef<std::path::Path>>(
     let mut files_to_clean = vec![
         object_file.clone(),
         executable_file.clone(),
+        build_record_path(&executable_file),
         dump_file.clone(),
     ];
+    for channel in ConsoleChannel::VARIANTS {
+        let variant_file = channel.executable_path(&executable_file);
+        files_to_clean.push(variant_file.with_extension("o"));
+        files_to_clean.push(variant_file);
+    }
 
     if assembly_file
         .as_ref()
@@ -112,7 +143,7 @@ pub fn build_elf<P: AsRef<std::path::Path>>(
         debug!("Original march: {}, GCC march: {}", arch, gcc_arch);
 
         let preprocessed_file = assembly_file.as_ref().with_extension("s");
-        let output = Command::new("riscv64-unknown-elf-gcc")
+        let output = Command::new(config::get().toolchain.tool("gcc"))
             .args(&[
                 &format!("-march={}", gcc_arch),
                 "-E",
@@ -137,50 +168,18 @@ pub fn build_elf<P: AsRef<std::path::Path>>(
         (assembly_file.as_ref().to_path_buf(), None)
     };
 
-    // 汇编目标文件
-    let output = Command::new("riscv64-unknown-elf-as")
-        .args(&[
-            &format!("-march={}", arch),
-            "-g",
-            "-o",
-            object_file.to_str().unwrap(),
-            assembly_to_use.to_str().unwrap(),
-        ])
-        .output()?;
-
-    if !output.status.success() {
-        error!("❌ Assembly failed");
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        for line in stderr.lines().take(5) {
-            error!("Assembly error: {}", line);
-        }
-        return Err(RiscvFuzzError::elf_build("assembly", &stderr));
-    }
-
-    info!("✅ Assembly completed");
-
-    // 链接可执行文件
-    let output = Command::new("riscv64-unknown-elf-ld")
-        .args(&[
-            "-T",
-            linker_script.as_ref().to_str().unwrap(),
-            "-o",
-            executable_file.to_str().unwrap(),
-            object_file.to_str().unwrap(),
-        ])
-        .output()?;
-
-    if !output.status.success() {
-        error!("❌ Linking failed");
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        error!("Linker error: {}", stderr);
-        return Err(RiscvFuzzError::elf_build("linking", &stderr));
-    }
-
-    info!("✅ Linking completed");
+    assemble_and_link(
+        &assembly_to_use,
+        &object_file,
+        &executable_file,
+        linker_script.as_ref(),
+        arch,
+        ConsoleChannel::HtifSyscall,
+    )?;
+    info!("✅ Assembly and linking completed");
 
     // 生成反汇编文件
-    let output = Command::new("riscv64-unknown-elf-objdump")
+    let output = Command::new(config::get().toolchain.tool("objdump"))
         .args(&["-S", executable_file.to_str().unwrap()])
         .output()?;
 
@@ -203,7 +202,14 @@ pub fn build_elf<P: AsRef<std::path::Path>>(
         object_file,
         executable_file,
         disassembly_file: dump_file,
+        assembled_file: assembly_to_use,
+        linker_script: linker_script.as_ref().to_path_buf(),
+        march: arch.to_string(),
     };
+    fs::write(
+        build_record_path(&result.executable_file),
+        serde_json::to_string_pretty(&result)?,
+    )?;
 
     info!(
         "✅ ELF build completed successfully in {:.2}s!",
@@ -213,6 +219,83 @@ pub fn build_elf<P: AsRef<std::path::Path>>(
     Ok(result)
 }
 
+/// 返回 `channel` 对应的可执行文件，首次使用时才汇编链接
+///
+/// Only backends without the HTIF syscall device need a variant, such as Sail
+/// ([`ConsoleChannel::HtifPutchar`]) and QEMU ([`ConsoleChannel::QemuUart`]);
+/// a variant has the same code layout as `executable_file` and shares its
+/// disassembly.
+pub fn build_console_variant(executable_file: &Path, channel: ConsoleChannel) -> Result<PathBuf> {
+    let variant_file = channel.executable_path(executable_file);
+    if channel == ConsoleChannel::HtifSyscall || variant_file.exists() {
+        return Ok(variant_file);
+    }
+
+    let build = ElfBuildResult::load(executable_file)?;
+    assemble_and_link(
+        &build.assembled_file,
+        &variant_file.with_extension("o"),
+        &variant_file,
+        &build.linker_script,
+        &build.march,
+        channel,
+    )?;
+    debug!(
+        "Console {:?} variant linked: {}",
+        channel,
+        variant_file.display()
+    );
+    Ok(variant_file)
+}
+
+/// 汇编并链接一个输出通道的可执行文件
+fn assemble_and_link(
+    assembly_file: &Path,
+    object_file: &Path,
+    executable_file: &Path,
+    linker_script: &Path,
+    arch: &str,
+    channel: ConsoleChannel,
+) -> Result<()> {
+    let mut assemble = Command::new(config::get().toolchain.tool("as"));
+    assemble.arg(format!("-march={}", arch)).arg("-g");
+    if let Some(symbol) = channel.defsym() {
+        assemble.arg("--defsym").arg(format!("{}=1", symbol));
+    }
+    let output = assemble
+        .arg("-o")
+        .arg(object_file)
+        .arg(assembly_file)
+        .output()?;
+
+    if !output.status.success() {
+        error!("❌ Assembly failed");
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        for line in stderr.lines().take(5) {
+            error!("Assembly error: {}", line);
+        }
+        return Err(RiscvFuzzError::elf_build("assembly", &stderr));
+    }
+
+    // 链接可执行文件
+    let output = Command::new(config::get().toolchain.tool("ld"))
+        .arg("-T")
+        .arg(linker_script)
+        .arg("-o")
+        .arg(executable_file)
+        .arg(object_file)
+        .output()?;
+
+    if !output.status.success() {
+        error!("❌ Linking failed");
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        error!("Linker error: {}", stderr);
+        return Err(RiscvFuzzError::elf_build("linking", &stderr));
+    }
+
+    Ok(())
+}
+
 fn filter_extensions(extensions: &str) -> String {
     let supported_extensions = ['i', 'm', 'a', 'f', 'd', 'c'];
     let mut result = String::new();
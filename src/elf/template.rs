@@ -1,3 +1,5 @@
+use crate::consts::qemu::{QEMU_TEST_FINISHER_ADDR, QEMU_UART_BASE};
+
 /// Exception dump configuration
 #[derive(Debug, Clone)]
 pub enum DumpException {
@@ -36,7 +38,8 @@ pub fn generate_asm(
     dump_registers: Option<DumpRegister>
 ) -> String {
     format!(
-        "{}{}{}{}",
+        "{}{}{}{}{}",
+        get_channel_definitions(),
         get_macro_definitions(),
         get_data_sections(),
         get_exception_handler(&dump_exception),
@@ -44,6 +47,21 @@ pub fn generate_asm(
     )
 }
 
+fn get_channel_definitions() -> String {
+    format!(
+        r#"# ============================================================================
+# Output Channel Definitions (HTIF by default, UART + sifive_test on QEMU virt
+# when built with QEMU_UART_CONSOLE, HTIF console putchar when built with
+# HTIF_CONSOLE_PUTCHAR, e.g. for Sail)
+# ============================================================================
+.equ QEMU_UART_BASE, {:#x}
+.equ QEMU_TEST_FINISHER_ADDR, {:#x}
+
+"#,
+        QEMU_UART_BASE, QEMU_TEST_FINISHER_ADDR
+    )
+}
+
 fn get_macro_definitions() -> &'static str {
     r#"# ============================================================================
 # Macro Definitions
@@ -81,10 +99,70 @@ fn get_macro_definitions() -> &'static str {
     ld   t6,  48(t6)
 .endm
 
+# ----------------------------------------------------------------------------
+# .macro BRANCH_IF_QEMU (selected at build time by --defsym QEMU_UART_CONSOLE,
+# one uncompressed instruction like BRANCH_IF_HTIF_PUTCHAR)
+# ----------------------------------------------------------------------------
+.macro BRANCH_IF_QEMU target_label
+    .option push; .option norvc
+.ifdef QEMU_UART_CONSOLE
+    j    \target_label
+.else
+    nop
+.endif
+    .option pop
+.endm
+
+# ----------------------------------------------------------------------------
+# .macro BRANCH_IF_HTIF_PUTCHAR (selected at build time by --defsym
+# HTIF_CONSOLE_PUTCHAR; both variants are one uncompressed instruction so the
+# code layout does not depend on the channel)
+# ----------------------------------------------------------------------------
+.macro BRANCH_IF_HTIF_PUTCHAR target_label
+    .option push; .option norvc
+.ifdef HTIF_CONSOLE_PUTCHAR
+    j    \target_label
+.else
+    nop
+.endif
+    .option pop
+.endm
+
+# ----------------------------------------------------------------------------
+# .macro HTIF_PUTCHAR_RAW (HTIF console device, one byte per tohost write)
+# ----------------------------------------------------------------------------
+.macro HTIF_PUTCHAR_RAW data_label, data_size
+    la   t0, \data_label; li   t1, \data_size
+putchar_loop_\@:
+    beqz t1, putchar_done_\@
+    lbu  t3, 0(t0); li   t2, 0x0101; slli t2, t2, 48; or   t3, t3, t2
+    la   t2, tohost; sd   t3, 0(t2)
+    addi t0, t0, 1; addi t1, t1, -1
+    j    putchar_loop_\@
+putchar_done_\@:
+.endm
+
+# ----------------------------------------------------------------------------
+# .macro UART_PRINT_RAW (ns16550a on QEMU virt)
+# ----------------------------------------------------------------------------
+.macro UART_PRINT_RAW data_label, data_size
+    la   t0, \data_label; li   t1, \data_size; li   t2, QEMU_UART_BASE
+uart_print_loop_\@:
+    beqz t1, uart_print_done_\@
+uart_wait_thre_\@:
+    lbu  t3, 5(t2); andi t3, t3, 0x20; beqz t3, uart_wait_thre_\@
+    lbu  t3, 0(t0); sb   t3, 0(t2)
+    addi t0, t0, 1; addi t1, t1, -1
+    j    uart_print_loop_\@
+uart_print_done_\@:
+.endm
+
 # ----------------------------------------------------------------------------
 # .macro HTIF_PRINT_RAW
 # ----------------------------------------------------------------------------
 .macro HTIF_PRINT_RAW data_label, data_size
+    BRANCH_IF_QEMU uart_print_\@
+    BRANCH_IF_HTIF_PUTCHAR putchar_print_\@
     la   t0, htif_communication_buffer
     li   t1, 64; sd t1, 0(t0); li   t1, 1;   sd t1, 8(t0)
     la   t1, \data_label; sd t1, 16(t0); li   t1, \data_size;   sd t1, 24(t0)
@@ -92,6 +170,13 @@ fn get_macro_definitions() -> &'static str {
 wait_htif_print_\@:
     la   t2, fromhost; ld t3, 0(t2); beqz t3, wait_htif_print_\@
     sd   zero, 0(t2); fence
+    j    print_done_\@
+uart_print_\@:
+    UART_PRINT_RAW \data_label, \data_size
+    j    print_done_\@
+putchar_print_\@:
+    HTIF_PUTCHAR_RAW \data_label, \data_size
+print_done_\@:
 .endm
 
 # ----------------------------------------------------------------------------
@@ -296,7 +381,11 @@ wait_htif_print_\@:
 # .macro EXIT_SIM
 # ----------------------------------------------------------------------------
 .macro EXIT_SIM
+    BRANCH_IF_QEMU qemu_exit_\@
     li   t0, 1; la   t1, tohost; sd   t0, 0(t1)
+    j    infinite_exit_loop_\@
+qemu_exit_\@:
+    li   t0, 0x5555; li   t1, QEMU_TEST_FINISHER_ADDR; sw   t0, 0(t1)
 infinite_exit_loop_\@: j infinite_exit_loop_\@
 .endm
 
@@ -713,6 +802,36 @@ _exit:
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::emulators::ConsoleChannel;
+
+    /// `.macro name` 到 `.endm` 之间的宏体
+    fn macro_body<'a>(template: &'a str, name: &str) -> &'a str {
+        let start = template
+            .find(&format!(".macro {} ", name))
+            .unwrap_or_else(|| panic!("macro {} not found", name));
+        let len = template[start..].find(".endm").unwrap();
+        &template[start..start + len]
+    }
+
+    #[test]
+    fn test_console_channels_are_selected_at_build_time() {
+        let template = generate_standard_asm("    addi t0, t0, 1");
+        for (macro_name, channel) in [
+            ("BRANCH_IF_QEMU", ConsoleChannel::QemuUart),
+            ("BRANCH_IF_HTIF_PUTCHAR", ConsoleChannel::HtifPutchar),
+        ] {
+            let body = macro_body(&template, macro_name);
+            assert!(body.contains(&format!(".ifdef {}", channel.defsym().unwrap())));
+            // 两个分支都只有一条指令，且不访问内存
+            let instructions: Vec<&str> = body
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty() && !line.starts_with('.'))
+                .collect();
+            assert_eq!(instructions, ["j    \\target_label", "nop"]);
+        }
+        assert!(!template.contains("QEMU_CHANNEL"));
+    }
 
     #[test]
     fn test_template_generation() {
@@ -93,7 +93,7 @@ impl ElfTracer {
 }
 
 /// 解析ELF指令行，提取PC、反汇编文本和机器码
-fn parse_elf_instruction_line(line: &str) -> Option<(u64, String, String)> {
+pub(crate) fn parse_elf_instruction_line(line: &str) -> Option<(u64, String, String)> {
     let trimmed = line.trim();
 
     // 查找冒号分隔符
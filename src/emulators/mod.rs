@@ -1,36 +1,61 @@
+pub mod outcome;
+pub mod process;
+pub mod qemu;
+pub mod registry;
 pub mod rocket;
+pub mod sail;
 pub mod spike;
 use crate::{
-    elf::build::build_elf,
-    error::Result, // Added RiscvFuzzError for run_emulator
+    elf::build::{build_console_variant, build_elf},
+    error::{Result, RiscvFuzzError},
     output_parser::{common::CommonExecutionOutput, debug::DebugExecutionOutput},
 };
 use std::{
     fmt::Display,
     fs::{self, File},
     io::{self, Write as _},
-    path::PathBuf,
+    path::{Path, PathBuf},
+    process::{Command, Output},
+    time::Duration,
 };
 
 use clap::ValueEnum;
-use log::{error, info};
+use log::{debug, error, info};
 use serde::{Deserialize, Serialize};
 
 use crate::{
     emulators::{
-        rocket::{RocketConfig, rocket_run_programs_and_parse},
-        spike::{SpikeConfig, spike_run_programs_and_parse},
+        outcome::{
+            RunClassification, RunOutcome, STDERR_TAIL_BYTES, parse_htif_exit_code,
+            run_outcome_path, stderr_log_path,
+        },
+        process::run_with_timeout,
+        registry::EmulatorRegistry,
+    },
+    emulators::{rocket::verbose_log_path, spike::commit_log_path},
+    output_parser::{
+        OutputParser, parse_output_from_file, retire_trace::RetireTrace,
+        standard::StandardExecutionOutput,
     },
-    output_parser::{OutputParser, standard::StandardExecutionOutput},
 };
 
-// Add serde::Serialize to the imports if it's not already there for the whole crate
-// use serde::Serialize; // Assuming it's available at crate level or imported in this module
-
-#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize, ValueEnum, Hash)] // 添加 Default
+/// Serializable identifier of an emulator backend.
+///
+/// This only names a backend in parsed outputs and diffs; how a backend is run
+/// is described by its [`Emulator`] implementation.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Serialize, Deserialize, ValueEnum, Hash)]
 pub enum EmulatorType {
     Spike,
     Rocket,
+    Qemu,
+    Sail,
+    /// Out-of-tree backend registered through [`EmulatorRegistry`], named
+    /// so that several in-house simulators can be told apart
+    #[value(skip)]
+    Custom(String),
+    /// 内置参考解释器 ([`crate::interpreter`])
+    #[value(skip)]
+    Reference,
 }
 
 impl Display for EmulatorType {
@@ -38,121 +63,328 @@ impl Display for EmulatorType {
         match self {
             EmulatorType::Spike => write!(f, "Spike"),
             EmulatorType::Rocket => write!(f, "Rocket"),
+            EmulatorType::Qemu => write!(f, "Qemu"),
+            EmulatorType::Sail => write!(f, "Sail"),
+            EmulatorType::Custom(name) => write!(f, "{}", name),
+            EmulatorType::Reference => write!(f, "Reference"),
+        }
+    }
+}
+
+/// 默认单次运行墙钟超时
+pub const DEFAULT_RUN_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Limits applied to every emulator run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RunLimits {
+    /// 墙钟超时，超时后结束模拟器进程 (None 表示不限制)
+    pub timeout: Option<Duration>,
+    /// Rocket 最大周期数
+    pub max_cycles: Option<u64>,
+    /// Spike / Sail 最大执行指令数
+    pub max_instructions: Option<u64>,
+}
+
+impl Default for RunLimits {
+    fn default() -> Self {
+        Self {
+            timeout: Some(DEFAULT_RUN_TIMEOUT),
+            max_cycles: None,
+            max_instructions: None,
         }
     }
 }
 
+/// 程序输出寄存器转储的通道，在构建 ELF 时选定
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ConsoleChannel {
+    /// HTIF syscall 设备 (Spike、Rocket)
+    #[default]
+    HtifSyscall,
+    /// HTIF console 设备，每次写 tohost 输出一个字节 (Sail)
+    HtifPutchar,
+    /// virt 机器的 UART 和 sifive_test 退出设备 (QEMU)
+    QemuUart,
+}
+
+impl ConsoleChannel {
+    /// 需要单独构建的通道变体
+    pub const VARIANTS: [ConsoleChannel; 2] =
+        [ConsoleChannel::HtifPutchar, ConsoleChannel::QemuUart];
+
+    /// 汇编时通过 `--defsym` 定义的符号
+    pub fn defsym(&self) -> Option<&'static str> {
+        match self {
+            ConsoleChannel::HtifSyscall => None,
+            ConsoleChannel::HtifPutchar => Some("HTIF_CONSOLE_PUTCHAR"),
+            ConsoleChannel::QemuUart => Some("QEMU_UART_CONSOLE"),
+        }
+    }
+
+    /// 该通道的可执行文件，与 `executable_file` 位于同一目录
+    pub fn executable_path(&self, executable_file: &Path) -> PathBuf {
+        match self {
+            ConsoleChannel::HtifSyscall => executable_file.to_path_buf(),
+            ConsoleChannel::HtifPutchar => executable_file.with_extension("htif_putchar.elf"),
+            ConsoleChannel::QemuUart => executable_file.with_extension("qemu_uart.elf"),
+        }
+    }
+}
+
+/// A pluggable emulator backend.
+///
+/// Implementors describe how to launch the backend for a given ELF and how to
+/// judge the finished process. Output parsing stays format driven through
+/// [`OutputParser`], so a backend only needs to speak the HTIF dump protocol.
+pub trait Emulator: Send + Sync {
+    /// Identifier recorded in parsed outputs and diffs
+    fn emulator_type(&self) -> EmulatorType;
+
+    /// Short lowercase name, used for logs and output file names
+    fn name(&self) -> &str;
+
+    /// Builds the command that runs `program_path` on this backend
+    fn build_command(&self, program_path: &Path) -> Command;
+
+    /// Output channel the program must be built for; [`Emulator::execute`]
+    /// runs the matching variant of the ELF it is given
+    fn console_channel(&self) -> ConsoleChannel {
+        ConsoleChannel::HtifSyscall
+    }
+
+    /// Wall-clock limit after which the process is killed
+    fn timeout(&self) -> Option<Duration> {
+        None
+    }
+
+    /// Checks backend prerequisites (e.g. the binary exists) before running
+    fn check_available(&self) -> Result<()> {
+        Ok(())
+    }
+
+    /// Whether the run ended because it used up a cycle or instruction limit
+    /// of the backend, which is classified like a wall-clock timeout
+    fn hit_limit(&self, _output: &Output) -> bool {
+        false
+    }
+
+    /// HTIF exit code written by the program, parsed from the simulator report
+    fn tohost_exit_code(&self, output: &Output) -> Option<u64> {
+        parse_htif_exit_code(output)
+    }
+
+    /// Collects the raw dump bytes of a finished run, stdout by default
+    fn collect_raw_output(&self, _program_path: &Path, output: &Output) -> Result<Vec<u8>> {
+        Ok(output.stdout.clone())
+    }
+
+    /// File that [`Emulator::collect_raw_output`] already saved the full
+    /// stderr to, if any; otherwise a long stderr is saved next to the log
+    fn saved_stderr_log(&self, _program_path: &Path) -> Option<PathBuf> {
+        None
+    }
+
+    /// Runs `program_path`, writes the raw HTIF output to `log_file` and
+    /// classifies the run. Only setup and IO problems are returned as errors.
+    fn execute(&self, program_path: &Path, log_file: &Path) -> Result<RunOutcome> {
+        self.check_available()?;
+        let program_path = &build_console_variant(program_path, self.console_channel())?;
+
+        // 检查程序文件是否存在
+        if !program_path.exists() {
+            error!("Program file not found: {}", program_path.display());
+            return Err(RiscvFuzzError::file(format!(
+                "Program file not found: {}",
+                program_path.display()
+            )));
+        }
+
+        let mut cmd = self.build_command(program_path);
+        debug!("{} command: {:?}", self.name(), cmd);
+        let timed = run_with_timeout(&mut cmd, self.timeout())?;
+        debug!(
+            "{} execution completed in {:.3}s",
+            self.name(),
+            timed.elapsed.as_secs_f64()
+        );
+
+        let raw_output = self.collect_raw_output(program_path, &timed.output)?;
+        write_output_to_log(log_file, &raw_output)?;
+
+        // 运行结果只保留 stderr 末尾，完整内容留在文件中
+        let stderr_log = if timed.output.stderr.len() > STDERR_TAIL_BYTES {
+            match self.saved_stderr_log(program_path) {
+                Some(saved) => Some(saved),
+                None => {
+                    let stderr_log = stderr_log_path(log_file);
+                    write_output_to_log(&stderr_log, &timed.output.stderr)?;
+                    Some(stderr_log)
+                }
+            }
+        } else {
+            None
+        };
+
+        let outcome = RunOutcome::from_process(
+            self.emulator_type(),
+            &timed.output,
+            raw_output.len(),
+            self.tohost_exit_code(&timed.output),
+            timed.elapsed,
+            timed.timed_out || self.hit_limit(&timed.output),
+            stderr_log,
+        );
+        match outcome.classification {
+            RunClassification::CleanExit => info!(
+                "✅ {} simulation completed successfully in {:.2}s",
+                self.emulator_type(),
+                outcome.wall_time_secs
+            ),
+            RunClassification::Timeout => error!(
+                "⏰ {} simulation hit its run limit after {:.2}s",
+                self.emulator_type(),
+                outcome.wall_time_secs
+            ),
+            classification => error!(
+                "❌ {} simulation finished with {} after {:.2}s (exit code {:?}, signal {:?}, tohost {:?})",
+                self.emulator_type(),
+                classification,
+                outcome.wall_time_secs,
+                outcome.exit_code,
+                outcome.signal,
+                outcome.tohost_exit_code
+            ),
+        }
+        Ok(outcome)
+    }
+
+    /// Like [`Emulator::execute`], but fails unless the output is usable
+    fn run(&self, program_path: &Path, log_file: &Path) -> Result<RunOutcome> {
+        self.execute(program_path, log_file)?
+            .into_result(self.name())
+    }
+}
+
+/// 运行模拟器并解析输出为指定格式
+pub fn run_and_parse<T, P: AsRef<Path>>(
+    emulator: &dyn Emulator,
+    program_path: P,
+    dump_path: P,
+    log_file: P,
+) -> Result<T>
+where
+    T: OutputParser,
+{
+    emulator.run(program_path.as_ref(), log_file.as_ref())?;
+    T::parse_from_file(
+        log_file.as_ref(),
+        dump_path.as_ref(),
+        emulator.emulator_type(),
+    )
+}
+
+/// Parsed output of one emulator inside a [`SimulatorResult`]
+#[derive(Debug, Clone)]
+pub struct EmulatorRunOutput<T = StandardExecutionOutput>
+where
+    T: OutputParser,
+{
+    pub emulator_type: EmulatorType,
+    pub name: String,
+    pub output: Option<T>,
+    pub output_file: Option<PathBuf>,
+    pub log_file: PathBuf,
+}
+
 #[derive(Debug, Clone)]
 pub struct SimulatorResult<T = StandardExecutionOutput>
 where
     T: OutputParser,
 {
-    pub spike_output: Option<T>,
-    pub spike_output_file: Option<PathBuf>,
-    pub spike_log_file: Option<PathBuf>,
-    pub rocket_output: Option<T>,
-    pub rocket_output_file: Option<PathBuf>,
-    pub rocket_log_file: Option<PathBuf>,
+    pub results: Vec<EmulatorRunOutput<T>>,
+}
+
+impl<T: OutputParser> SimulatorResult<T> {
+    /// 按模拟器类型查找运行结果
+    pub fn get(&self, emulator_type: EmulatorType) -> Option<&EmulatorRunOutput<T>> {
+        self.results
+            .iter()
+            .find(|r| r.emulator_type == emulator_type)
+    }
 }
 
+/// Runs every emulator in `registry` on the program and parses the outputs.
 pub fn run_and_parse_all_simulators<T, P: AsRef<std::path::Path>>(
+    registry: &EmulatorRegistry,
     build_dir: P,
-    march_string: &str,
     executable_file: P,
     dump_file: P,
 ) -> SimulatorResult<T>
 where
-    T: OutputParser + Serialize, // Added Serialize
+    T: OutputParser + Serialize,
 {
-    // Run Spike
-    let spike_config = SpikeConfig {
-        isa: march_string.to_string(),
-        log_file: build_dir.as_ref().join("spike_execution_trace.log"),
-    };
-    let spike_output_path = build_dir.as_ref().join("spike_output.json");
-
-    let spike_execution_result = spike_run_programs_and_parse::<T, PathBuf>(
-        &spike_config,
-        executable_file.as_ref().to_path_buf(),
-        dump_file.as_ref().to_path_buf(),
-    );
-
-    let (spike_output, spike_output_file) = match spike_execution_result {
-        Ok(parsed_output) => {
-            // 序列化解析后的输出
-            let result_str = serde_json::to_string(&parsed_output).unwrap();
-            match std::fs::write(&spike_output_path, result_str) {
-                Ok(_) => (Some(parsed_output), Some(spike_output_path)),
-                Err(e) => {
-                    error!(
-                        "Failed to write Spike output to file {:?}: {}",
-                        spike_output_path, e
-                    );
-                    panic!(
-                        "Failed to write Spike output to file {:?}: {}",
-                        spike_output_path, e
-                    );
-                }
-            }
-        }
-        Err(e) => {
-            error!("Spike execution failed: {:?}", e);
-            (None, None)
-        }
-    };
+    let mut results = Vec::new();
 
-    // Run Rocket
-    let rocket_config = RocketConfig {
-        isa: march_string.to_string(),
-        verbose: false,
-        cycle_count: false,
-        max_cycles: None,
-        log_file: build_dir.as_ref().join("rocket_execution_trace.log"),
-        emulator_path: std::env::var("ROCKET_EMULATOR_PATH")
-            .unwrap_or_else(|_| "emulators/rocket_emulator".to_string()),
-    };
+    for emulator in registry.iter() {
+        let log_file = build_dir
+            .as_ref()
+            .join(format!("{}_execution_trace.log", emulator.name()));
+        let output_path = build_dir
+            .as_ref()
+            .join(format!("{}_output.json", emulator.name()));
+
+        let execution_result = run_and_parse::<T, &Path>(
+            emulator,
+            executable_file.as_ref(),
+            dump_file.as_ref(),
+            &log_file,
+        );
 
-    let rocket_output_path = build_dir.as_ref().join("rocket_output.json");
-
-    let rocket_execution_result =
-        rocket_run_programs_and_parse::<T, P>(&rocket_config, executable_file, dump_file);
-
-    let (rocket_output, rocket_output_file) = match rocket_execution_result {
-        Ok(parsed_output) => {
-            // 序列化解析后的输出
-            let result_str = serde_json::to_string(&parsed_output).unwrap();
-            match std::fs::write(&rocket_output_path, result_str) {
-                Ok(_) => (Some(parsed_output), Some(rocket_output_path)),
-                Err(e) => {
-                    error!(
-                        "Failed to write Rocket output to file {:?}: {}",
-                        rocket_output_path, e
-                    );
-                    panic!(
-                        "Failed to write Rocket output to file {:?}: {}",
-                        rocket_output_path, e
-                    );
+        let (output, output_file) = match execution_result {
+            Ok(parsed_output) => {
+                // 序列化解析后的输出
+                let result_str = serde_json::to_string(&parsed_output).unwrap();
+                match std::fs::write(&output_path, result_str) {
+                    Ok(_) => (Some(parsed_output), Some(output_path)),
+                    Err(e) => {
+                        error!(
+                            "Failed to write {} output to file {:?}: {}",
+                            emulator.emulator_type(),
+                            output_path,
+                            e
+                        );
+                        panic!(
+                            "Failed to write {} output to file {:?}: {}",
+                            emulator.emulator_type(),
+                            output_path,
+                            e
+                        );
+                    }
                 }
             }
-        }
-        Err(e) => {
-            error!("Rocket execution failed: {:?}", e);
-            (None, None)
-        }
-    };
+            Err(e) => {
+                error!("{} execution failed: {:?}", emulator.emulator_type(), e);
+                (None, None)
+            }
+        };
 
-    SimulatorResult {
-        spike_output,
-        spike_log_file: Some(spike_config.log_file),
-        spike_output_file,
-        rocket_output,
-        rocket_log_file: Some(rocket_config.log_file),
-        rocket_output_file,
+        results.push(EmulatorRunOutput {
+            emulator_type: emulator.emulator_type(),
+            name: emulator.name().to_string(),
+            output,
+            output_file,
+            log_file,
+        });
     }
+
+    SimulatorResult { results }
 }
 
 /// 将命令输出写入日志文件
-fn write_output_to_log<P: AsRef<std::path::Path>>(log_path: P, stdout: &[u8]) -> io::Result<()> {
+pub(crate) fn write_output_to_log<P: AsRef<std::path::Path>>(
+    log_path: P,
+    stdout: &[u8],
+) -> io::Result<()> {
     let mut file = File::create(log_path.as_ref())?;
 
     // 写入标准输出
@@ -164,7 +396,8 @@ fn write_output_to_log<P: AsRef<std::path::Path>>(log_path: P, stdout: &[u8]) ->
     Ok(())
 }
 
-#[derive(Debug, Clone, ValueEnum, PartialEq)]
+#[derive(Debug, Clone, ValueEnum, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum OutputFormat {
     /// Standard format (deduplicated exceptions + register dump)
     Standard,
@@ -172,6 +405,8 @@ pub enum OutputFormat {
     Debug,
     /// Common format (raw parsed data)
     Common,
+    /// Trace format (per-instruction retire trace, diffed in lockstep)
+    Trace,
 }
 
 impl Display for OutputFormat {
@@ -180,55 +415,90 @@ impl Display for OutputFormat {
             OutputFormat::Standard => write!(f, "Standard"),
             OutputFormat::Debug => write!(f, "Debug"),
             OutputFormat::Common => write!(f, "Common"),
+            OutputFormat::Trace => write!(f, "Trace"),
         }
     }
 }
 
-/// Runs a specified emulator with the given program and saves its raw output.
-/// The `output_format` parameter can influence emulator flags (e.g., Spike's -d).
+/// Text log a backend writes its retire trace to when tracing is enabled,
+/// or `None` if the backend has no retire trace support
+pub fn retire_trace_log_path(emulator_type: &EmulatorType, program_path: &Path) -> Option<PathBuf> {
+    match emulator_type {
+        EmulatorType::Spike => Some(commit_log_path(program_path)),
+        EmulatorType::Rocket => Some(verbose_log_path(program_path)),
+        _ => None,
+    }
+}
+
+/// Runs a backend of `registry` on the given program and saves its raw output
+/// together with the [`RunOutcome`] (see [`run_outcome_path`]).
+///
+/// The outcome is returned whatever its classification; only setup and IO
+/// problems are errors.
+pub fn execute_emulator(
+    registry: &EmulatorRegistry,
+    raw_output_target_path: &Path,
+    executable_file: &Path,
+    emulator_type: EmulatorType,
+) -> Result<RunOutcome> {
+    let emulator = registry.require(emulator_type)?;
+    let outcome = emulator.execute(executable_file, raw_output_target_path)?;
+
+    // 运行结果保存在原始输出文件旁
+    let outcome_path = run_outcome_path(raw_output_target_path);
+    outcome.save(&outcome_path)?;
+    debug!("Run outcome saved to: {:?}", outcome_path);
+    Ok(outcome)
+}
+
+/// Like [`execute_emulator`], but fails unless the output is usable and
+/// returns the raw output path
 pub fn run_emulator(
-    raw_output_target_path: &PathBuf,
-    executable_file: &PathBuf,
-    march_string: &str,
+    registry: &EmulatorRegistry,
+    raw_output_target_path: &Path,
+    executable_file: &Path,
     emulator_type: EmulatorType,
 ) -> Result<PathBuf> {
-    match emulator_type {
-        EmulatorType::Spike => {
-            let config = SpikeConfig {
-                isa: march_string.to_string(),
-                // Enable Spike's debug mode if the desired output format is Debug.
-                // This is a heuristic; Spike's native debug output might differ
-                // from what our DebugExecutionOutput expects, but it's a common case.
-                log_file: raw_output_target_path.clone(),
-            };
-            spike::spike_run_program(&config, executable_file)?;
-        }
-        EmulatorType::Rocket => {
-            let config = RocketConfig {
-                isa: march_string.to_string(), // Not directly used by Rocket command but kept for consistency
-                verbose: false, // Default, can be configured if needed based on output_format
-                cycle_count: false, // Default
-                max_cycles: None, // Default
-                log_file: raw_output_target_path.clone(),
-                emulator_path: std::env::var("ROCKET_EMULATOR_PATH")
-                    .unwrap_or_else(|_| "emulators/rocket_emulator".to_string()),
-            };
-            rocket::rocket_run_program(&config, executable_file)?;
-        }
-    }
-    Ok(raw_output_target_path.clone())
+    let emulator = registry.require(emulator_type.clone())?;
+    execute_emulator(
+        registry,
+        raw_output_target_path,
+        executable_file,
+        emulator_type,
+    )?
+    .into_result(emulator.name())?;
+    Ok(raw_output_target_path.to_path_buf())
 }
 
 /// 运行单个模拟器并解析输出
+///
+/// With `retire_trace` (implied by [`OutputFormat::Trace`]) the backend also
+/// records its retire trace log, which is parsed into a [`RetireTrace`] saved
+/// next to the ELF.
 pub fn run_single_emulator<P: AsRef<std::path::Path>>(
     build_dir: P,
     assembly_file: P,
     march_string: &str,
     emulator: EmulatorType,
     format: OutputFormat,
+    limits: &RunLimits,
+    retire_trace: bool,
 ) -> Result<()> {
     let build_dir = build_dir.as_ref().to_path_buf();
     let linker_script = PathBuf::from("assets/linker.ld");
+    let retire_trace = retire_trace || format == OutputFormat::Trace;
+
+    let registry = if retire_trace {
+        if !matches!(emulator, EmulatorType::Spike | EmulatorType::Rocket) {
+            return Err(RiscvFuzzError::config(format!(
+                "Retire trace is only supported for Spike and Rocket, not {}",
+                emulator
+            )));
+        }
+        EmulatorRegistry::with_retire_trace(march_string, limits)
+    } else {
+        EmulatorRegistry::with_limits(march_string, limits)
+    };
 
     // 编译汇编文件
     info!("🔨 Compiling assembly file...");
@@ -238,64 +508,57 @@ pub fn run_single_emulator<P: AsRef<std::path::Path>>(
         march_string,
     )?;
 
-    // 根据选择的模拟器和格式运行
-    match (emulator, format) {
-        (EmulatorType::Spike, OutputFormat::Standard) => {
+    // 根据选择的格式运行
+    match format {
+        OutputFormat::Standard => {
             run_emulator_with_format::<StandardExecutionOutput, &PathBuf>(
                 &build_dir,
                 &build_result.executable_file,
                 &build_result.disassembly_file,
-                march_string,
-                EmulatorType::Spike,
+                &registry,
+                emulator.clone(),
             )?;
         }
-        (EmulatorType::Spike, OutputFormat::Debug) => {
+        OutputFormat::Debug => {
             run_emulator_with_format::<DebugExecutionOutput, &PathBuf>(
                 &build_dir,
                 &build_result.executable_file,
                 &build_result.disassembly_file,
-                march_string,
-                EmulatorType::Spike,
+                &registry,
+                emulator.clone(),
             )?;
         }
-        (EmulatorType::Spike, OutputFormat::Common) => {
+        OutputFormat::Common => {
             run_emulator_with_format::<CommonExecutionOutput, &PathBuf>(
                 &build_dir,
                 &build_result.executable_file,
                 &build_result.disassembly_file,
-                march_string,
-                EmulatorType::Spike,
-            )?;
-        }
-        (EmulatorType::Rocket, OutputFormat::Standard) => {
-            run_emulator_with_format::<StandardExecutionOutput, &PathBuf>(
-                &build_dir,
-                &build_result.executable_file,
-                &build_result.disassembly_file,
-                march_string,
-                EmulatorType::Rocket,
-            )?;
-        }
-        (EmulatorType::Rocket, OutputFormat::Debug) => {
-            run_emulator_with_format::<DebugExecutionOutput, &PathBuf>(
-                &build_dir,
-                &build_result.executable_file,
-                &build_result.disassembly_file,
-                march_string,
-                EmulatorType::Rocket,
+                &registry,
+                emulator.clone(),
             )?;
         }
-        (EmulatorType::Rocket, OutputFormat::Common) => {
-            run_emulator_with_format::<CommonExecutionOutput, &PathBuf>(
-                &build_dir,
-                &build_result.executable_file,
-                &build_result.disassembly_file,
-                march_string,
-                EmulatorType::Rocket,
-            )?;
+        OutputFormat::Trace => {
+            // 原始输出不解析，trace 在下面单独处理
+            let backend = registry.require(emulator.clone())?;
+            let log_file = build_dir.join(format!("{}_execution_trace.log", backend.name()));
+            backend.run(&build_result.executable_file, &log_file)?;
         }
     }
 
+    let trace_log = retire_trace_log_path(&emulator, &build_result.executable_file);
+    if let Some(trace_log) = trace_log.filter(|_| retire_trace) {
+        let trace = parse_output_from_file::<RetireTrace, _>(
+            &trace_log,
+            &build_result.disassembly_file,
+            emulator,
+        )?;
+        info!(
+            "🧵 Retire trace with {} instructions saved next to {:?}",
+            trace.entries.len(),
+            trace_log
+        );
+    }
+
     Ok(())
 }
 
@@ -304,44 +567,37 @@ pub fn run_emulator_with_format<T, P: AsRef<std::path::Path>>(
     build_dir: P,
     executable_file: P,
     dump_file: P,
-    march_string: &str,
+    registry: &EmulatorRegistry,
     emulator: EmulatorType,
 ) -> Result<()>
 where
-    T: OutputParser + std::fmt::Display + Serialize, // Added Serialize
+    T: OutputParser + std::fmt::Display + Serialize,
 {
-    let parsed_output = match emulator {
-        EmulatorType::Spike => {
-            let config = SpikeConfig {
-                isa: march_string.to_string(),
-                log_file: build_dir.as_ref().join("spike_execution_trace.log"),
-            };
-            spike_run_programs_and_parse::<T, P>(&config, executable_file, dump_file)?
-        }
-        EmulatorType::Rocket => {
-            let config = RocketConfig {
-                isa: march_string.to_string(),
-                verbose: false,
-                cycle_count: false,
-                max_cycles: None,
-                log_file: build_dir.as_ref().join("rocket_execution_trace.log"),
-                emulator_path: std::env::var("ROCKET_EMULATOR_PATH")
-                    .unwrap_or_else(|_| "emulators/rocket_emulator".to_string()),
-            };
-            rocket_run_programs_and_parse::<T, P>(&config, executable_file, dump_file)?
-        }
-    };
+    let backend = registry.require(emulator.clone())?;
+    let log_file = build_dir
+        .as_ref()
+        .join(format!("{}_execution_trace.log", backend.name()));
+    let parsed_output = run_and_parse::<T, &Path>(
+        backend,
+        executable_file.as_ref(),
+        dump_file.as_ref(),
+        &log_file,
+    )?;
 
     // 保存结果到文件
-    let json_file = build_dir.as_ref().join(format!("{}_output.json", emulator));
+    let json_file = build_dir
+        .as_ref()
+        .join(format!("{}_output.json", backend.name()));
     let json_content = serde_json::to_string_pretty(&parsed_output)?;
     fs::write(&json_file, json_content)?;
     info!("💾 JSON output saved to: {:?}", json_file);
 
-    let text_file = build_dir.as_ref().join(format!("{}_output.md", emulator));
+    let text_file = build_dir
+        .as_ref()
+        .join(format!("{}_output.md", backend.name()));
     let text_content = format!("{}", parsed_output);
     fs::write(&text_file, text_content)?;
     info!("💾 Text output saved to: {:?}", text_file);
 
     Ok(())
-}
\ No newline at end of file
+}
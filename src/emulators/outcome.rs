@@ -0,0 +1,208 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::process::Output;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use crate::emulators::EmulatorType;
+use crate::error::{Result, RiscvFuzzError};
+
+/// 运行结果中保留的 stderr 末尾字节数
+pub const STDERR_TAIL_BYTES: usize = 4096;
+
+/// 运行结果分类
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum RunClassification {
+    /// 程序通过 HTIF 以退出码 0 正常结束
+    CleanExit,
+    /// 程序以非零 tohost 退出码结束
+    FailCode,
+    /// 超过墙钟限制被强制结束，或模拟器报告用完了周期/指令数限制
+    Timeout,
+    /// 模拟器异常退出 (信号、断言失败等)
+    Crash,
+    /// 模拟器结束但没有产生任何转储数据
+    NoOutput,
+}
+
+impl fmt::Display for RunClassification {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RunClassification::CleanExit => write!(f, "Clean exit"),
+            RunClassification::FailCode => write!(f, "Fail code"),
+            RunClassification::Timeout => write!(f, "Timeout"),
+            RunClassification::Crash => write!(f, "Crash"),
+            RunClassification::NoOutput => write!(f, "No output"),
+        }
+    }
+}
+
+/// Structured result of one emulator process run.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RunOutcome {
+    pub emulator_type: EmulatorType,
+    pub classification: RunClassification,
+    /// 进程退出码 (被信号结束时为 None)
+    pub exit_code: Option<i32>,
+    /// 结束进程的信号 (仅 Unix)
+    pub signal: Option<i32>,
+    /// 程序写入 tohost 的退出码
+    pub tohost_exit_code: Option<u64>,
+    pub wall_time_secs: f64,
+    /// 原始转储数据长度
+    pub raw_output_length: usize,
+    /// stderr 的最后 [`STDERR_TAIL_BYTES`] 字节
+    pub stderr: String,
+    /// stderr 被截断时，完整 stderr 所在的文件
+    #[serde(default)]
+    pub stderr_log: Option<PathBuf>,
+}
+
+impl RunOutcome {
+    /// Classifies a finished (or killed) process. `timed_out` covers both the
+    /// wall-clock kill and a cycle or instruction limit the backend reported
+    /// (see [`crate::emulators::Emulator::hit_limit`]). Only the tail of stderr
+    /// is kept; `stderr_log` names the file holding all of it when it is longer.
+    pub fn from_process(
+        emulator_type: EmulatorType,
+        output: &Output,
+        raw_output_length: usize,
+        tohost_exit_code: Option<u64>,
+        elapsed: Duration,
+        timed_out: bool,
+        stderr_log: Option<PathBuf>,
+    ) -> Self {
+        let signal = exit_signal(output);
+        let classification = if timed_out {
+            RunClassification::Timeout
+        } else if signal.is_some() {
+            RunClassification::Crash
+        } else {
+            match tohost_exit_code {
+                Some(0) if raw_output_length == 0 => RunClassification::NoOutput,
+                Some(0) => RunClassification::CleanExit,
+                Some(_) => RunClassification::FailCode,
+                None if raw_output_length == 0 => RunClassification::NoOutput,
+                None => RunClassification::Crash,
+            }
+        };
+
+        Self {
+            emulator_type,
+            classification,
+            exit_code: output.status.code(),
+            signal,
+            tohost_exit_code,
+            wall_time_secs: elapsed.as_secs_f64(),
+            raw_output_length,
+            stderr: stderr_tail(&output.stderr),
+            stderr_log: stderr_log.filter(|_| output.stderr.len() > STDERR_TAIL_BYTES),
+        }
+    }
+
+    /// 输出是否可用于解析和比较
+    pub fn is_usable(&self) -> bool {
+        matches!(
+            self.classification,
+            RunClassification::CleanExit | RunClassification::FailCode
+        )
+    }
+
+    /// Converts the outcome into the `Result` returned by [`crate::emulators::Emulator::run`]
+    pub fn into_result(self, emulator_name: &str) -> Result<RunOutcome> {
+        match self.classification {
+            RunClassification::CleanExit | RunClassification::FailCode => Ok(self),
+            RunClassification::Timeout => Err(RiscvFuzzError::timeout(
+                emulator_name,
+                Duration::from_secs_f64(self.wall_time_secs),
+            )),
+            RunClassification::Crash => Err(RiscvFuzzError::simulator(
+                emulator_name,
+                &format!(
+                    "Emulator crashed (exit code {:?}, signal {:?}): {}{}",
+                    self.exit_code,
+                    self.signal,
+                    self.stderr.trim(),
+                    self.stderr_log
+                        .as_ref()
+                        .map_or(String::new(), |log| format!(
+                            " (full stderr in {})",
+                            log.display()
+                        ))
+                ),
+            )),
+            RunClassification::NoOutput => Err(RiscvFuzzError::simulator(
+                emulator_name,
+                "Emulator produced no output",
+            )),
+        }
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let content = std::fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&content)?)
+    }
+}
+
+/// stderr 的末尾，从字符边界开始
+fn stderr_tail(stderr: &[u8]) -> String {
+    let text = String::from_utf8_lossy(stderr);
+    let mut start = text.len().saturating_sub(STDERR_TAIL_BYTES);
+    while !text.is_char_boundary(start) {
+        start += 1;
+    }
+    text[start..].to_string()
+}
+
+/// 原始输出文件旁的完整 stderr 文件路径 (spike_output.bin -> spike_output.stderr.log)
+pub fn stderr_log_path<P: AsRef<Path>>(raw_output_path: P) -> PathBuf {
+    raw_output_path.as_ref().with_extension("stderr.log")
+}
+
+/// 原始输出文件旁的运行结果文件路径 (spike_output.bin -> spike_output.outcome.json)
+pub fn run_outcome_path<P: AsRef<Path>>(raw_output_path: P) -> PathBuf {
+    raw_output_path.as_ref().with_extension("outcome.json")
+}
+
+/// Extracts the HTIF exit code reported by fesvr based simulators.
+///
+/// Spike and the Rocket emulator print `*** FAILED *** (tohost = N)`,
+/// `(code = N` or `via dtm (code = N` for a non-zero exit; a successful
+/// process exit means code 0.
+pub fn parse_htif_exit_code(output: &Output) -> Option<u64> {
+    static FAILED_RE: OnceLock<Regex> = OnceLock::new();
+    let re = FAILED_RE.get_or_init(|| {
+        Regex::new(r"\*\*\* FAILED \*\*\* (?:via \w+ )?\((?:tohost|code) = (\d+)").unwrap()
+    });
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    for text in [&stderr, &stdout] {
+        if let Some(code) = re
+            .captures(text)
+            .and_then(|caps| caps[1].parse::<u64>().ok())
+        {
+            return Some(code);
+        }
+    }
+
+    output.status.success().then_some(0)
+}
+
+#[cfg(unix)]
+fn exit_signal(output: &Output) -> Option<i32> {
+    use std::os::unix::process::ExitStatusExt;
+    output.status.signal()
+}
+
+#[cfg(not(unix))]
+fn exit_signal(_output: &Output) -> Option<i32> {
+    None
+}
@@ -0,0 +1,186 @@
+use std::io::{self, Read};
+use std::num::TryFromIntError;
+use std::os::unix::process::CommandExt;
+use std::process::{Child, Command, ExitStatus, Output, Stdio};
+use std::sync::atomic::{AtomicBool, AtomicI32, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// 轮询子进程状态的间隔
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// 同时记录的子进程组数上限
+const MAX_LIVE_GROUPS: usize = 1024;
+
+/// 正在运行的子进程组 ID，0 表示空位
+static LIVE_GROUPS: [AtomicI32; MAX_LIVE_GROUPS] = [const { AtomicI32::new(0) }; MAX_LIVE_GROUPS];
+
+/// 调用过 [`kill_all_process_groups`] 后，新启动的子进程组也立即结束
+static KILL_ALL: AtomicBool = AtomicBool::new(false);
+
+/// Kills every process group started by [`run_with_timeout`] that is still
+/// running, and every one started afterwards.
+///
+/// Emulators run in their own process group and do not see the terminal's
+/// SIGINT, so an interrupt handler calls this instead. It only touches
+/// atomics and calls `kill`, which is async-signal-safe.
+pub fn kill_all_process_groups() {
+    KILL_ALL.store(true, Ordering::SeqCst);
+    for slot in &LIVE_GROUPS {
+        let pgid = slot.load(Ordering::SeqCst);
+        if pgid > 0 {
+            unsafe {
+                libc::kill(-pgid, libc::SIGKILL);
+            }
+        }
+    }
+}
+
+/// 记录运行中的进程组，结束时清除
+struct LiveGroup {
+    slot: Option<&'static AtomicI32>,
+}
+
+impl LiveGroup {
+    fn register(pgid: i32) -> Self {
+        let slot = LIVE_GROUPS.iter().find(|slot| {
+            slot.compare_exchange(0, pgid, Ordering::SeqCst, Ordering::SeqCst)
+                .is_ok()
+        });
+        // 与 kill_all_process_groups 并发时，由这里或它结束进程组
+        if KILL_ALL.load(Ordering::SeqCst) {
+            unsafe {
+                libc::kill(-pgid, libc::SIGKILL);
+            }
+        }
+        Self { slot }
+    }
+}
+
+impl Drop for LiveGroup {
+    fn drop(&mut self) {
+        if let Some(slot) = self.slot {
+            slot.store(0, Ordering::SeqCst);
+        }
+    }
+}
+
+/// 带超时运行结果
+#[derive(Debug)]
+pub struct TimedOutput {
+    pub output: Output,
+    pub elapsed: Duration,
+    pub timed_out: bool,
+}
+
+/// Runs `cmd` to completion, killing it once `timeout` elapses.
+///
+/// Stdout and stderr are drained on helper threads so a chatty child cannot
+/// block on a full pipe while we poll. Output produced before the kill is kept.
+/// The child runs in its own process group, and the whole group is killed on
+/// timeout and once the child exits, so that processes spawned by a wrapper
+/// script cannot keep the pipes open. Live groups are also killed by [`kill_all_process_groups`].
+pub fn run_with_timeout(cmd: &mut Command, timeout: Option<Duration>) -> io::Result<TimedOutput> {
+    let start_time = Instant::now();
+    let mut child = cmd
+        .process_group(0)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    let live_group = i32::try_from(child.id()).ok().map(LiveGroup::register);
+
+    let stdout_reader = drain(child.stdout.take());
+    let stderr_reader = drain(child.stderr.take());
+
+    let mut timed_out = false;
+    let status: ExitStatus = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if timeout.is_some_and(|t| start_time.elapsed() >= t) {
+            // 超时后强制结束子进程所在的整个进程组
+            if kill_group(&child).is_err() {
+                let _ = child.kill();
+            }
+            timed_out = true;
+            break child.wait()?;
+        }
+        thread::sleep(POLL_INTERVAL);
+    };
+
+    // 子进程正常退出后，进程组中残留的进程可能仍持有管道，一并结束
+    let _ = kill_group(&child);
+    drop(live_group);
+
+    let stdout = stdout_reader.join().unwrap_or_default();
+    let stderr = stderr_reader.join().unwrap_or_default();
+
+    Ok(TimedOutput {
+        output: Output {
+            status,
+            stdout,
+            stderr,
+        },
+        elapsed: start_time.elapsed(),
+        timed_out,
+    })
+}
+
+/// 向子进程所在的进程组发送 SIGKILL
+fn kill_group(child: &Child) -> Result<(), TryFromIntError> {
+    let pgid = i32::try_from(child.id())?;
+    unsafe {
+        libc::kill(-pgid, libc::SIGKILL);
+    }
+    Ok(())
+}
+
+fn drain<R: Read + Send + 'static>(pipe: Option<R>) -> thread::JoinHandle<Vec<u8>> {
+    thread::spawn(move || {
+        let mut buf = Vec::new();
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_end(&mut buf);
+        }
+        buf
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn timeout_kills_processes_spawned_by_the_child() {
+        let mut cmd = Command::new("sh");
+        cmd.args(["-c", "echo started; sleep 100 & sleep 100"]);
+        let timed = run_with_timeout(&mut cmd, Some(Duration::from_millis(200))).unwrap();
+
+        assert!(timed.timed_out);
+        assert!(timed.elapsed < Duration::from_secs(10));
+        assert_eq!(timed.output.stdout, b"started\n");
+    }
+
+    #[test]
+    fn completed_run_keeps_output_and_status() {
+        let mut cmd = Command::new("sh");
+        cmd.args(["-c", "echo out; echo err >&2; exit 3"]);
+        let timed = run_with_timeout(&mut cmd, Some(Duration::from_secs(10))).unwrap();
+
+        assert!(!timed.timed_out);
+        assert_eq!(timed.output.status.code(), Some(3));
+        assert_eq!(timed.output.stdout, b"out\n");
+        assert_eq!(timed.output.stderr, b"err\n");
+    }
+
+    #[test]
+    fn exited_child_does_not_wait_for_background_processes() {
+        let mut cmd = Command::new("sh");
+        cmd.args(["-c", "echo done; sleep 100 &"]);
+        let timed = run_with_timeout(&mut cmd, None).unwrap();
+
+        assert!(!timed.timed_out);
+        assert!(timed.elapsed < Duration::from_secs(10));
+        assert_eq!(timed.output.stdout, b"done\n");
+    }
+}
@@ -0,0 +1,72 @@
+use std::path::Path;
+use std::process::Command;
+use std::time::Duration;
+
+use crate::config::{self, resolve_emulator_path};
+use crate::emulators::{ConsoleChannel, DEFAULT_RUN_TIMEOUT, Emulator, EmulatorType, RunLimits};
+
+/// QEMU (qemu-system-riscv64 -machine virt) 后端
+///
+/// QEMU has no HTIF, so it runs the [`ConsoleChannel::QemuUart`] build of the
+/// program, which prints through the UART and exits through the sifive_test
+/// device. The ELF is loaded with the generic loader, which also sets the
+/// entry PC. The UART is routed to stdout, so the log holds the same raw dump
+/// bytes as Spike and Rocket.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QemuEmulator {
+    /// qemu-system-riscv64 可执行文件路径
+    pub qemu_path: String,
+    /// -cpu 参数 (如 "max" 或 "rv64,v=true")
+    pub cpu: String,
+    /// 墙钟超时 (QEMU 没有指令数限制，只能依赖超时)
+    pub timeout: Option<Duration>,
+}
+
+impl Default for QemuEmulator {
+    fn default() -> Self {
+        Self {
+            qemu_path: resolve_emulator_path(
+                "QEMU_SYSTEM_RISCV64_PATH",
+                config::get().emulators.qemu_path.as_ref(),
+                "qemu-system-riscv64",
+            ),
+            cpu: "max".to_string(),
+            timeout: Some(DEFAULT_RUN_TIMEOUT),
+        }
+    }
+}
+
+impl QemuEmulator {
+    pub fn with_limits(mut self, limits: &RunLimits) -> Self {
+        self.timeout = limits.timeout;
+        self
+    }
+}
+
+impl Emulator for QemuEmulator {
+    fn emulator_type(&self) -> EmulatorType {
+        EmulatorType::Qemu
+    }
+
+    fn name(&self) -> &str {
+        "qemu"
+    }
+
+    fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    fn console_channel(&self) -> ConsoleChannel {
+        ConsoleChannel::QemuUart
+    }
+
+    fn build_command(&self, program_path: &Path) -> Command {
+        let mut cmd = Command::new(&self.qemu_path);
+        cmd.args(["-machine", "virt", "-cpu", &self.cpu, "-smp", "1"]);
+        cmd.args(["-bios", "none", "-display", "none", "-monitor", "none"]);
+        cmd.args(["-serial", "stdio"]);
+        cmd.arg("-device")
+            .arg(format!("loader,file={},cpu-num=0", program_path.display()));
+        cmd
+    }
+}
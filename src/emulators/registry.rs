@@ -0,0 +1,194 @@
+use crate::config;
+use crate::emulators::qemu::QemuEmulator;
+use crate::emulators::rocket::RocketEmulator;
+use crate::emulators::sail::SailEmulator;
+use crate::emulators::spike::SpikeEmulator;
+use crate::emulators::{Emulator, EmulatorType, RunLimits};
+use crate::error::{Result, RiscvFuzzError};
+
+/// 模拟器注册表
+///
+/// Holds the backends a run should use. [`EmulatorRegistry::with_defaults`]
+/// registers the built-in Spike, Rocket, QEMU and Sail backends listed in
+/// `[emulators] enabled`; further backends, including out-of-tree RTL
+/// simulators, are added with [`EmulatorRegistry::register`].
+#[derive(Default)]
+pub struct EmulatorRegistry {
+    emulators: Vec<Box<dyn Emulator>>,
+}
+
+impl EmulatorRegistry {
+    /// 创建空注册表
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 创建包含已启用的内置后端的注册表
+    pub fn with_defaults(march_string: &str) -> Self {
+        Self::with_limits(march_string, &RunLimits::default())
+    }
+
+    /// Like [`EmulatorRegistry::with_defaults`], applying `limits` to every backend
+    pub fn with_limits(march_string: &str, limits: &RunLimits) -> Self {
+        let enabled = &config::get().emulators;
+        let mut registry = Self::new();
+        if enabled.is_enabled("spike") {
+            registry.register(Box::new(
+                SpikeEmulator::new(march_string).with_limits(limits),
+            ));
+        }
+        if enabled.is_enabled("rocket") {
+            registry.register(Box::new(RocketEmulator::default().with_limits(limits)));
+        }
+        if enabled.is_enabled("qemu") {
+            registry.register(Box::new(QemuEmulator::default().with_limits(limits)));
+        }
+        if enabled.is_enabled("sail") {
+            registry.register(Box::new(SailEmulator::default().with_limits(limits)));
+        }
+        registry
+    }
+
+    /// Like [`EmulatorRegistry::with_limits`], with Spike and Rocket recording
+    /// their retire trace logs (see [`crate::emulators::retire_trace_log_path`])
+    pub fn with_retire_trace(march_string: &str, limits: &RunLimits) -> Self {
+        let mut registry = Self::with_limits(march_string, limits);
+        if registry.get(EmulatorType::Spike).is_some() {
+            registry.register(Box::new(
+                SpikeEmulator::new(march_string)
+                    .with_limits(limits)
+                    .with_commit_log(true),
+            ));
+        }
+        if registry.get(EmulatorType::Rocket).is_some() {
+            registry.register(Box::new(
+                RocketEmulator::default()
+                    .with_limits(limits)
+                    .with_verbose(true),
+            ));
+        }
+        registry
+    }
+
+    /// Builder-style variant of [`EmulatorRegistry::register`]
+    pub fn with<E: Emulator + 'static>(mut self, emulator: E) -> Self {
+        self.register(Box::new(emulator));
+        self
+    }
+
+    /// Adds a backend, replacing any backend registered under the same name
+    pub fn register(&mut self, emulator: Box<dyn Emulator>) {
+        if let Some(existing) = self
+            .emulators
+            .iter_mut()
+            .find(|e| e.name() == emulator.name())
+        {
+            *existing = emulator;
+        } else {
+            self.emulators.push(emulator);
+        }
+    }
+
+    /// 只保留指定类型的后端，顺序与 `emulator_types` 一致
+    pub fn select(mut self, emulator_types: &[EmulatorType]) -> Result<Self> {
+        let mut selected = Vec::new();
+        for emulator_type in emulator_types {
+            let idx = self
+                .emulators
+                .iter()
+                .position(|e| e.emulator_type() == *emulator_type)
+                .ok_or_else(|| not_registered(emulator_type))?;
+            selected.push(self.emulators.remove(idx));
+        }
+        self.emulators = selected;
+        Ok(self)
+    }
+
+    /// The two backends a pairwise comparison (`run`, `random`, `campaign`)
+    /// uses: the first two registered ones in `[emulators] enabled` order,
+    /// followed by backends that are registered but not listed there
+    pub fn compared_pair(&self) -> Result<[EmulatorType; 2]> {
+        let enabled = &config::get().emulators.enabled;
+        let mut emulators: Vec<&dyn Emulator> = self.iter().collect();
+        emulators.sort_by_key(|emulator| {
+            enabled
+                .iter()
+                .position(|name| name.eq_ignore_ascii_case(emulator.name()))
+                .unwrap_or(usize::MAX)
+        });
+        match &emulators[..] {
+            [sim1, sim2, ..] => Ok([sim1.emulator_type(), sim2.emulator_type()]),
+            _ => Err(RiscvFuzzError::config(
+                "Comparison needs two emulators, list at least two in [emulators] enabled",
+            )),
+        }
+    }
+
+    /// 按类型查找第一个匹配的后端
+    pub fn get(&self, emulator_type: EmulatorType) -> Option<&dyn Emulator> {
+        self.iter().find(|e| e.emulator_type() == emulator_type)
+    }
+
+    /// 按名称查找后端
+    pub fn get_by_name(&self, name: &str) -> Option<&dyn Emulator> {
+        self.iter().find(|e| e.name() == name)
+    }
+
+    /// Like [`EmulatorRegistry::get`], but fails if the backend is missing
+    pub fn require(&self, emulator_type: EmulatorType) -> Result<&dyn Emulator> {
+        self.get(emulator_type.clone())
+            .ok_or_else(|| not_registered(&emulator_type))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &dyn Emulator> {
+        self.emulators.iter().map(|e| e.as_ref())
+    }
+
+    pub fn len(&self) -> usize {
+        self.emulators.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.emulators.is_empty()
+    }
+}
+
+fn not_registered(emulator_type: &EmulatorType) -> RiscvFuzzError {
+    RiscvFuzzError::config(format!(
+        "Emulator {} is not registered, is it listed in [emulators] enabled?",
+        emulator_type
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compared_pair_follows_enabled_order() {
+        // 默认配置启用 spike、rocket；未列出的后端排在后面
+        let registry = EmulatorRegistry::new()
+            .with(SailEmulator::default())
+            .with(RocketEmulator::default())
+            .with(SpikeEmulator::new("rv64gc"));
+        assert_eq!(
+            registry.compared_pair().unwrap(),
+            [EmulatorType::Spike, EmulatorType::Rocket]
+        );
+
+        let registry = EmulatorRegistry::new()
+            .with(SailEmulator::default())
+            .with(RocketEmulator::default());
+        assert_eq!(
+            registry.compared_pair().unwrap(),
+            [EmulatorType::Rocket, EmulatorType::Sail]
+        );
+    }
+
+    #[test]
+    fn test_compared_pair_needs_two_emulators() {
+        let registry = EmulatorRegistry::new().with(SpikeEmulator::new("rv64gc"));
+        assert!(registry.compared_pair().is_err());
+        assert!(EmulatorRegistry::new().compared_pair().is_err());
+    }
+}
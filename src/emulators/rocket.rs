@@ -1,9 +1,11 @@
-use log::{debug, error, info, warn};
-use std::path::PathBuf;
-use std::process::Command;
-use std::time::Instant;
+use log::{error, info, warn};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+use std::time::Duration;
 
-use crate::emulators::{EmulatorType, write_output_to_log};
+use crate::config::{self, resolve_emulator_path};
+use crate::emulators::{DEFAULT_RUN_TIMEOUT, Emulator, EmulatorType, RunLimits};
 use crate::error::{Result, RiscvFuzzError};
 use crate::output_parser::OutputParser;
 
@@ -31,134 +33,161 @@ impl Default for RocketConfig {
             cycle_count: true,
             max_cycles: None,
             log_file: PathBuf::from("rocket_execution_trace.log"),
-            emulator_path: std::env::var("ROCKET_EMULATOR_PATH")
-                .unwrap_or_else(|_| "emulators/rocket_emulator".to_string()),
+            emulator_path: resolve_emulator_path(
+                "ROCKET_EMULATOR_PATH",
+                config::get().emulators.rocket_path.as_ref(),
+                "emulators/rocket_emulator",
+            ),
         }
     }
 }
 
-pub fn rocket_run_program<P: AsRef<std::path::Path>>(
-    config: &RocketConfig,
-    program_path: P,
-) -> Result<()> {
-    let start_time = Instant::now();
-    info!(
-        "🚀 Starting Rocket simulation for: {}, this may take a long time",
-        program_path.as_ref().display()
-    );
+/// Rocket 仿真器后端
+///
+/// With `verbose` the per-cycle retire lines printed on stderr are saved to
+/// [`verbose_log_path`] for [`crate::output_parser::retire_trace`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RocketEmulator {
+    /// 是否启用详细输出
+    pub verbose: bool,
+    /// 是否打印周期计数
+    pub cycle_count: bool,
+    /// 最大周期数限制
+    pub max_cycles: Option<u64>,
+    /// 仿真器可执行文件路径
+    pub emulator_path: String,
+    /// 墙钟超时
+    pub timeout: Option<Duration>,
+}
 
-    // 检查仿真器是否存在
-    if !std::path::Path::new(&config.emulator_path).exists() {
-        error!("Rocket emulator not found at: {}", config.emulator_path);
-        return Err(RiscvFuzzError::simulator(
-            "rocket",
-            &format!("Emulator not found: {}", config.emulator_path),
-        ));
+impl Default for RocketEmulator {
+    fn default() -> Self {
+        Self {
+            verbose: false,
+            cycle_count: false,
+            max_cycles: None,
+            emulator_path: resolve_emulator_path(
+                "ROCKET_EMULATOR_PATH",
+                config::get().emulators.rocket_path.as_ref(),
+                "emulators/rocket_emulator",
+            ),
+            timeout: Some(DEFAULT_RUN_TIMEOUT),
+        }
     }
+}
 
-    // 检查程序文件是否存在
-    if !program_path.as_ref().exists() {
-        error!(
-            "Program file not found: {}",
-            program_path.as_ref().display()
-        );
-        return Err(RiscvFuzzError::file(format!(
-            "Program file not found: {}",
-            program_path.as_ref().display()
-        )));
+impl RocketEmulator {
+    pub fn with_limits(mut self, limits: &RunLimits) -> Self {
+        self.max_cycles = limits.max_cycles;
+        self.timeout = limits.timeout;
+        self
     }
 
-    // 构建并执行命令
-    let exec_start = Instant::now();
-    debug!("Executing Rocket emulator");
+    pub fn with_verbose(mut self, verbose: bool) -> Self {
+        self.verbose = verbose;
+        self
+    }
+}
 
-    let mut cmd = Command::new(&config.emulator_path);
+/// Rocket `--verbose` 日志路径 (与 ELF 同目录)
+pub fn verbose_log_path(program_path: &Path) -> PathBuf {
+    program_path.with_extension("rocket_verbose.log")
+}
 
-    // 添加仿真器选项
-    if config.cycle_count {
-        cmd.arg("--cycle-count");
+impl From<&RocketConfig> for RocketEmulator {
+    fn from(config: &RocketConfig) -> Self {
+        Self {
+            verbose: config.verbose,
+            cycle_count: config.cycle_count,
+            max_cycles: config.max_cycles,
+            emulator_path: config.emulator_path.clone(),
+            timeout: Some(DEFAULT_RUN_TIMEOUT),
+        }
     }
+}
 
-    if config.verbose {
-        cmd.arg("--verbose");
+impl Emulator for RocketEmulator {
+    fn emulator_type(&self) -> EmulatorType {
+        EmulatorType::Rocket
     }
 
-    if let Some(max_cycles) = config.max_cycles {
-        cmd.arg(format!("--max-cycles={}", max_cycles));
+    fn name(&self) -> &str {
+        "rocket"
     }
 
-    // 添加要执行的程序
-    cmd.arg(program_path.as_ref());
-
-    debug!("Rocket command: {:?}", cmd);
-
-    let output = cmd.output()?;
-    let exec_time = exec_start.elapsed();
-    debug!(
-        "Rocket execution completed in {:.3}s",
-        exec_time.as_secs_f64()
-    );
+    fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
 
-    // 写入日志
-    let write_start = Instant::now();
-    write_output_to_log(&config.log_file, &output.stdout)?;
-    let write_time = write_start.elapsed();
-    debug!("Log writing completed in {:.3}s", write_time.as_secs_f64());
+    fn check_available(&self) -> Result<()> {
+        // 检查仿真器是否存在
+        if !Path::new(&self.emulator_path).exists() {
+            error!("Rocket emulator not found at: {}", self.emulator_path);
+            return Err(RiscvFuzzError::simulator(
+                "rocket",
+                &format!("Emulator not found: {}", self.emulator_path),
+            ));
+        }
+        Ok(())
+    }
 
-    // 检查输出内容而不是退出状态
-    let stdout_str = String::from_utf8_lossy(&output.stdout);
-    let stderr_str = String::from_utf8_lossy(&output.stderr);
-    let combined_output = format!("{}\n{}", stdout_str, stderr_str);
+    fn build_command(&self, program_path: &Path) -> Command {
+        let mut cmd = Command::new(&self.emulator_path);
 
-    let result = if combined_output.contains("PASSED") {
-        debug!("✅ Found 'PASSED' in Rocket output - treating as success");
-        Ok(())
-    } else if combined_output.contains("SUCCESS") || combined_output.contains("PASS") {
-        debug!("✅ Found success indicator in Rocket output");
-        Ok(())
-    } else {
-        // 记录详细错误信息但不失败（如果有重要输出的话）
-        if !output.status.success() {
-            warn!(
-                "⚠️ Rocket emulator exit status indicates failure: {}",
-                output.status
-            );
-            if !stderr_str.trim().is_empty() {
-                warn!("Rocket stderr: {}", stderr_str.trim());
-            }
+        // 添加仿真器选项
+        if self.cycle_count {
+            cmd.arg("--cycle-count");
         }
 
-        // 如果有任何输出，就认为是成功的（因为至少程序运行了）
-        if !stdout_str.trim().is_empty() || !stderr_str.trim().is_empty() {
-            debug!("✅ Rocket produced output - treating as successful execution");
-            Ok(())
-        } else {
-            error!("❌ Rocket emulator produced no output and failed");
-            Err(RiscvFuzzError::simulator(
-                "rocket",
-                "Emulator failed with no output",
-            ))
+        if self.verbose {
+            cmd.arg("--verbose");
         }
-    };
-
-    let elapsed = start_time.elapsed();
-    match result {
-        Ok(()) => {
-            info!(
-                "✅ Rocket simulation completed successfully in {:.2}s",
-                elapsed.as_secs_f64()
-            );
+
+        if let Some(max_cycles) = self.max_cycles {
+            cmd.arg(format!("--max-cycles={}", max_cycles));
         }
-        Err(ref e) => {
-            error!(
-                "❌ Rocket simulation failed after {:.2}s: {}",
-                elapsed.as_secs_f64(),
-                e
-            );
+
+        // 添加要执行的程序
+        cmd.arg(program_path);
+        cmd
+    }
+
+    fn hit_limit(&self, output: &Output) -> bool {
+        // --max-cycles 用完时: "*** FAILED *** via trace_count (timeout, seed N) after M cycles"
+        String::from_utf8_lossy(&output.stderr).contains("*** FAILED *** via trace_count (timeout")
+    }
+
+    fn collect_raw_output(&self, program_path: &Path, output: &Output) -> Result<Vec<u8>> {
+        if self.verbose {
+            // 详细输出在 stderr 上，单独保存供 retire trace 解析
+            let verbose_log = verbose_log_path(program_path);
+            if let Err(e) = fs::write(&verbose_log, &output.stderr) {
+                warn!(
+                    "Failed to save Rocket verbose log to {}: {}",
+                    verbose_log.display(),
+                    e
+                );
+            }
         }
+        Ok(output.stdout.clone())
     }
 
-    result
+    fn saved_stderr_log(&self, program_path: &Path) -> Option<PathBuf> {
+        self.verbose.then(|| verbose_log_path(program_path))
+    }
+}
+
+pub fn rocket_run_program<P: AsRef<std::path::Path>>(
+    config: &RocketConfig,
+    program_path: P,
+) -> Result<()> {
+    info!(
+        "🚀 Starting Rocket simulation for: {}, this may take a long time",
+        program_path.as_ref().display()
+    );
+    RocketEmulator::from(config)
+        .run(program_path.as_ref(), &config.log_file)
+        .map(|_| ())
 }
 
 /// 运行Rocket并解析输出为指定格式
@@ -180,3 +209,70 @@ where
 
     Ok(parsed)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::emulators::outcome::{RunClassification, RunOutcome, parse_htif_exit_code};
+    use std::os::unix::process::ExitStatusExt;
+    use std::process::ExitStatus;
+
+    fn rocket_output(exit_code: i32, stderr: &str) -> Output {
+        Output {
+            status: ExitStatus::from_raw(exit_code << 8),
+            stdout: b"dump".to_vec(),
+            stderr: stderr.as_bytes().to_vec(),
+        }
+    }
+
+    fn classify(output: &Output) -> RunClassification {
+        let rocket = RocketEmulator {
+            verbose: false,
+            cycle_count: false,
+            max_cycles: Some(1000),
+            emulator_path: "rocket_emulator".to_string(),
+            timeout: None,
+        };
+        RunOutcome::from_process(
+            EmulatorType::Rocket,
+            output,
+            output.stdout.len(),
+            rocket.tohost_exit_code(output),
+            Duration::from_millis(10),
+            rocket.hit_limit(output),
+            None,
+        )
+        .classification
+    }
+
+    #[test]
+    fn test_max_cycles_exhaustion_is_a_timeout() {
+        let output = rocket_output(
+            2,
+            "*** FAILED *** via trace_count (timeout, seed 1) after 1000 cycles\n",
+        );
+        assert_eq!(parse_htif_exit_code(&output), None);
+        assert_eq!(classify(&output), RunClassification::Timeout);
+    }
+
+    #[test]
+    fn test_dtm_exit_code_is_a_fail_code() {
+        let output = rocket_output(
+            3,
+            "*** FAILED *** via dtm (code = 3, seed 1) after 4242 cycles\n",
+        );
+        assert_eq!(parse_htif_exit_code(&output), Some(3));
+        assert_eq!(classify(&output), RunClassification::FailCode);
+    }
+
+    #[test]
+    fn test_clean_exit_and_assertion() {
+        assert_eq!(
+            classify(&rocket_output(0, "")),
+            RunClassification::CleanExit
+        );
+        let output = rocket_output(1, "Assertion failed: unexpected trap\n");
+        assert_eq!(parse_htif_exit_code(&output), None);
+        assert_eq!(classify(&output), RunClassification::Crash);
+    }
+}
@@ -0,0 +1,192 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+use std::time::Duration;
+
+use crate::config::{self, resolve_emulator_path};
+use crate::emulators::{ConsoleChannel, DEFAULT_RUN_TIMEOUT, Emulator, EmulatorType, RunLimits};
+use crate::error::{Result, RiscvFuzzError};
+use log::warn;
+
+/// Sail RISC-V 形式化参考模型后端 (riscv_sim_RV64)
+///
+/// Sail only implements the HTIF exit and console devices, so it runs the
+/// [`ConsoleChannel::HtifPutchar`] build of the program, which prints
+/// byte-wise through the console. The console bytes are written to
+/// a terminal log next to the ELF, keeping the model's own messages on stdout
+/// out of the raw dump.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SailEmulator {
+    /// riscv_sim_RV64 可执行文件路径
+    pub sim_path: String,
+    /// 最大执行指令数
+    pub max_instructions: Option<u64>,
+    /// 墙钟超时
+    pub timeout: Option<Duration>,
+}
+
+impl Default for SailEmulator {
+    fn default() -> Self {
+        Self {
+            sim_path: resolve_emulator_path(
+                "SAIL_RISCV_SIM_PATH",
+                config::get().emulators.sail_path.as_ref(),
+                "riscv_sim_RV64",
+            ),
+            max_instructions: None,
+            timeout: Some(DEFAULT_RUN_TIMEOUT),
+        }
+    }
+}
+
+impl SailEmulator {
+    pub fn with_limits(mut self, limits: &RunLimits) -> Self {
+        self.max_instructions = limits.max_instructions;
+        self.timeout = limits.timeout;
+        self
+    }
+
+    /// 终端日志路径 (与 ELF 同目录)
+    fn terminal_log_path(program_path: &Path) -> PathBuf {
+        program_path.with_extension("sail_terminal.bin")
+    }
+}
+
+impl Emulator for SailEmulator {
+    fn emulator_type(&self) -> EmulatorType {
+        EmulatorType::Sail
+    }
+
+    fn name(&self) -> &str {
+        "sail"
+    }
+
+    fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    fn console_channel(&self) -> ConsoleChannel {
+        ConsoleChannel::HtifPutchar
+    }
+
+    fn build_command(&self, program_path: &Path) -> Command {
+        let mut cmd = Command::new(&self.sim_path);
+        cmd.arg("--terminal-log")
+            .arg(Self::terminal_log_path(program_path));
+        if let Some(max_instructions) = self.max_instructions {
+            cmd.arg("--inst-limit").arg(max_instructions.to_string());
+        }
+        cmd.arg(program_path);
+        cmd
+    }
+
+    fn collect_raw_output(&self, program_path: &Path, _output: &Output) -> Result<Vec<u8>> {
+        let terminal_log = Self::terminal_log_path(program_path);
+        if !terminal_log.exists() {
+            // 模拟器在打开终端日志前就退出了
+            warn!("Sail terminal log not found: {}", terminal_log.display());
+            return Ok(Vec::new());
+        }
+        let data = fs::read(&terminal_log).map_err(|e| {
+            RiscvFuzzError::simulator(
+                self.name(),
+                &format!(
+                    "Failed to read Sail terminal log {}: {}",
+                    terminal_log.display(),
+                    e
+                ),
+            )
+        })?;
+        let _ = fs::remove_file(&terminal_log);
+        Ok(data)
+    }
+
+    fn hit_limit(&self, output: &Output) -> bool {
+        // --inst-limit 用完时 Sail 打印 instruction limit 并退出，不打印 SUCCESS/FAILURE
+        self.max_instructions.is_some()
+            && [&output.stdout, &output.stderr].into_iter().any(|text| {
+                String::from_utf8_lossy(text)
+                    .to_lowercase()
+                    .contains("instruction limit")
+            })
+    }
+
+    fn tohost_exit_code(&self, output: &Output) -> Option<u64> {
+        // Sail 在 stdout 上打印 "SUCCESS" 或 "FAILURE: <code>"
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        if let Some(code) = stdout
+            .lines()
+            .find_map(|line| line.trim().strip_prefix("FAILURE:"))
+        {
+            return code.trim().parse().ok();
+        }
+        if stdout.lines().any(|line| line.trim() == "SUCCESS") {
+            return Some(0);
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::emulators::outcome::{RunClassification, RunOutcome};
+    use std::os::unix::process::ExitStatusExt;
+    use std::process::ExitStatus;
+
+    fn sail_output(exit_code: i32, stdout: &str) -> Output {
+        Output {
+            status: ExitStatus::from_raw(exit_code << 8),
+            stdout: stdout.as_bytes().to_vec(),
+            stderr: Vec::new(),
+        }
+    }
+
+    fn sail(max_instructions: Option<u64>) -> SailEmulator {
+        SailEmulator {
+            sim_path: "riscv_sim_RV64".to_string(),
+            max_instructions,
+            timeout: None,
+        }
+    }
+
+    fn classify(sail: &SailEmulator, output: &Output) -> RunClassification {
+        // 原始输出来自终端日志，这里假定程序已写出部分内容
+        RunOutcome::from_process(
+            EmulatorType::Sail,
+            output,
+            4,
+            sail.tohost_exit_code(output),
+            Duration::from_millis(10),
+            sail.hit_limit(output),
+            None,
+        )
+        .classification
+    }
+
+    #[test]
+    fn test_instruction_limit_is_a_timeout() {
+        let output = sail_output(0, "Reached instruction limit, exiting.\n");
+        assert_eq!(sail(Some(1000)).tohost_exit_code(&output), None);
+        assert_eq!(
+            classify(&sail(Some(1000)), &output),
+            RunClassification::Timeout
+        );
+        // 未设置指令上限时不按超时处理
+        assert_eq!(classify(&sail(None), &output), RunClassification::Crash);
+    }
+
+    #[test]
+    fn test_success_and_failure_lines() {
+        assert_eq!(
+            classify(&sail(Some(1000)), &sail_output(0, "SUCCESS\n")),
+            RunClassification::CleanExit
+        );
+        let output = sail_output(1, "FAILURE: 3\n");
+        assert_eq!(sail(Some(1000)).tohost_exit_code(&output), Some(3));
+        assert_eq!(
+            classify(&sail(Some(1000)), &output),
+            RunClassification::FailCode
+        );
+    }
+}
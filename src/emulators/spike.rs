@@ -1,10 +1,10 @@
-use log::{debug, error, info};
-use std::path::PathBuf;
-use std::process::Command;
-use std::time::Instant;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+use std::time::Duration;
 
-use crate::emulators::{EmulatorType, write_output_to_log};
-use crate::error::{Result, RiscvFuzzError};
+use crate::config;
+use crate::emulators::{DEFAULT_RUN_TIMEOUT, Emulator, EmulatorType, RunLimits};
+use crate::error::Result;
 use crate::output_parser::OutputParser;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -13,6 +13,8 @@ pub struct SpikeConfig {
     pub isa: String,
     /// 输出日志文件路径
     pub log_file: PathBuf,
+    /// 记录提交日志 (`-l --log-commits`)
+    pub log_commits: bool,
 }
 
 impl Default for SpikeConfig {
@@ -20,56 +22,105 @@ impl Default for SpikeConfig {
         Self {
             isa: "RV64G".to_string(),
             log_file: PathBuf::from("execution_trace.log"),
+            log_commits: false,
         }
     }
 }
 
+/// Spike 模拟器后端
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpikeEmulator {
+    /// Spike 可执行文件
+    pub spike_path: String,
+    /// ISA 架构 (如 "RV64G")
+    pub isa: String,
+    /// 最大执行指令数
+    pub max_instructions: Option<u64>,
+    /// 墙钟超时
+    pub timeout: Option<Duration>,
+    /// 记录提交日志 (`-l --log-commits`)，写入 [`commit_log_path`]
+    pub log_commits: bool,
+}
+
+impl SpikeEmulator {
+    pub fn new(isa: &str) -> Self {
+        Self {
+            spike_path: config::get().emulators.spike_path.clone(),
+            isa: isa.to_string(),
+            max_instructions: None,
+            timeout: Some(DEFAULT_RUN_TIMEOUT),
+            log_commits: false,
+        }
+    }
+
+    pub fn with_limits(mut self, limits: &RunLimits) -> Self {
+        self.max_instructions = limits.max_instructions;
+        self.timeout = limits.timeout;
+        self
+    }
+
+    pub fn with_commit_log(mut self, log_commits: bool) -> Self {
+        self.log_commits = log_commits;
+        self
+    }
+}
+
+/// Spike 提交日志路径 (与 ELF 同目录)
+pub fn commit_log_path(program_path: &Path) -> PathBuf {
+    program_path.with_extension("spike_commits.log")
+}
+
+impl From<&SpikeConfig> for SpikeEmulator {
+    fn from(config: &SpikeConfig) -> Self {
+        Self::new(&config.isa).with_commit_log(config.log_commits)
+    }
+}
+
+impl Emulator for SpikeEmulator {
+    fn emulator_type(&self) -> EmulatorType {
+        EmulatorType::Spike
+    }
+
+    fn name(&self) -> &str {
+        "spike"
+    }
+
+    fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    fn build_command(&self, program_path: &Path) -> Command {
+        let mut cmd = Command::new(&self.spike_path);
+        cmd.arg(format!("--isa={}", self.isa));
+        if let Some(max_instructions) = self.max_instructions {
+            cmd.arg(format!("--instructions={}", max_instructions));
+        }
+        if self.log_commits {
+            // 提交日志写入单独文件，stdout 仍只含 HTIF 输出
+            cmd.arg("-l")
+                .arg("--log-commits")
+                .arg(format!("--log={}", commit_log_path(program_path).display()));
+        }
+        cmd.arg(program_path);
+        cmd
+    }
+
+    fn hit_limit(&self, output: &Output) -> bool {
+        // --instructions 用完时 Spike 在 stderr 上报告 instruction limit 并退出
+        self.max_instructions.is_some()
+            && String::from_utf8_lossy(&output.stderr)
+                .to_lowercase()
+                .contains("instruction limit")
+    }
+}
+
 pub fn spike_run_program<P: AsRef<std::path::Path>>(
     config: &SpikeConfig,
     program_path: P,
 ) -> Result<()> {
-    let start_time = Instant::now();
-
-    // 检查程序文件是否存在
-    if !program_path.as_ref().exists() {
-        error!(
-            "Program file not found: {}",
-            program_path.as_ref().display()
-        );
-        return Err(RiscvFuzzError::file(format!(
-            "Program file not found: {}",
-            program_path.as_ref().display()
-        )));
-    }
-
-    // 构建并执行命令
-    let exec_start = Instant::now();
-    debug!("Executing Spike simulator");
-
-    let mut cmd = Command::new("spike");
-    cmd.arg(format!("--isa={}", config.isa));
-    cmd.arg(program_path.as_ref());
-    let output = cmd.output()?;
-
-    let exec_time = exec_start.elapsed();
-    debug!(
-        "Spike execution completed in {:.3}s",
-        exec_time.as_secs_f64()
-    );
-
-    // 写入日志
-    let write_start = Instant::now();
-    write_output_to_log(&config.log_file, &output.stdout)?;
-    let write_time = write_start.elapsed();
-    debug!("Log writing completed in {:.3}s", write_time.as_secs_f64());
-
-    let elapsed = start_time.elapsed();
-    info!(
-        "✅ Spike simulation completed successfully in {:.2}s",
-        elapsed.as_secs_f64()
-    );
-
-    Ok(())
+    SpikeEmulator::from(config)
+        .run(program_path.as_ref(), &config.log_file)
+        .map(|_| ())
 }
 
 /// 运行Spike并解析输出为指定格式
@@ -92,3 +143,62 @@ where
 
     Ok(parsed)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::emulators::outcome::{RunClassification, RunOutcome};
+    use std::os::unix::process::ExitStatusExt;
+    use std::process::ExitStatus;
+
+    fn spike_output(exit_code: i32, stdout: &str, stderr: &str) -> Output {
+        Output {
+            status: ExitStatus::from_raw(exit_code << 8),
+            stdout: stdout.as_bytes().to_vec(),
+            stderr: stderr.as_bytes().to_vec(),
+        }
+    }
+
+    fn classify(max_instructions: Option<u64>, output: &Output) -> RunClassification {
+        let spike = SpikeEmulator {
+            spike_path: "spike".to_string(),
+            isa: "RV64G".to_string(),
+            max_instructions,
+            timeout: None,
+            log_commits: false,
+        };
+        RunOutcome::from_process(
+            EmulatorType::Spike,
+            output,
+            output.stdout.len(),
+            spike.tohost_exit_code(output),
+            Duration::from_millis(10),
+            spike.hit_limit(output),
+            None,
+        )
+        .classification
+    }
+
+    #[test]
+    fn test_instruction_limit_is_a_timeout() {
+        let output = spike_output(0, "", "Reached instruction limit of 1000\n");
+        assert_eq!(classify(Some(1000), &output), RunClassification::Timeout);
+        // 未设置指令上限时不按超时处理
+        assert_eq!(classify(None, &output), RunClassification::NoOutput);
+    }
+
+    #[test]
+    fn test_clean_exit_and_fail_code() {
+        assert_eq!(
+            classify(Some(1000), &spike_output(0, "dump", "")),
+            RunClassification::CleanExit
+        );
+        assert_eq!(
+            classify(
+                Some(1000),
+                &spike_output(1, "dump", "*** FAILED *** (tohost = 3)\n")
+            ),
+            RunClassification::FailCode
+        );
+    }
+}
@@ -15,6 +15,9 @@ pub enum RiscvFuzzError {
     #[error("Simulator execution failed: {simulator} - {message}")]
     Simulator { simulator: String, message: String },
 
+    #[error("Simulator timed out: {simulator} - killed after {seconds:.1}s")]
+    Timeout { simulator: String, seconds: f64 },
+
     #[error("Output parsing failed: {format} - {message}")]
     OutputParsing { format: String, message: String },
 
@@ -58,6 +61,18 @@ impl RiscvFuzzError {
         }
     }
 
+    pub fn timeout<S: Into<String>>(simulator: S, elapsed: std::time::Duration) -> Self {
+        Self::Timeout {
+            simulator: simulator.into(),
+            seconds: elapsed.as_secs_f64(),
+        }
+    }
+
+    /// 是否为模拟器超时错误
+    pub fn is_timeout(&self) -> bool {
+        matches!(self, Self::Timeout { .. })
+    }
+
     pub fn output_parsing<S: Into<String>>(format: S, message: S) -> Self {
         Self::OutputParsing {
             format: format.into(),
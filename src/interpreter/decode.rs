@@ -0,0 +1,188 @@
+//! 汇编文本解码
+//!
+//! The interpreter works on the assembly text of an instruction (the
+//! `Display` form of `RiscvInstruction`, or a line of a `.S` / objdump file),
+//! so operands are recognised by their shape rather than by an encoding.
+
+use crate::error::{Result, RiscvFuzzError};
+use crate::interpreter::float::RoundingMode;
+use crate::output_parser::util::{get_float_register_name, get_register_name};
+
+/// 单个操作数
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operand {
+    /// 整数寄存器 x0-x31
+    Reg(usize),
+    /// 浮点寄存器 f0-f31
+    FReg(usize),
+    Imm(i64),
+    /// `offset(base)` 形式的内存操作数
+    Mem {
+        offset: i64,
+        base: usize,
+    },
+    Csr(u16),
+    Rm(RoundingMode),
+}
+
+/// 解码后的指令: 小写助记符 (去掉 `.aq`/`.rl` 后缀) 和操作数
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodedInstruction {
+    pub mnemonic: String,
+    pub operands: Vec<Operand>,
+}
+
+/// 模型支持的 CSR 名称
+const CSR_NAMES: &[(&str, u16)] = &[
+    ("fflags", 0x001),
+    ("frm", 0x002),
+    ("fcsr", 0x003),
+    ("cycle", 0xC00),
+    ("time", 0xC01),
+    ("instret", 0xC02),
+    ("sstatus", 0x100),
+    ("sie", 0x104),
+    ("stvec", 0x105),
+    ("sscratch", 0x140),
+    ("sepc", 0x141),
+    ("scause", 0x142),
+    ("stval", 0x143),
+    ("sip", 0x144),
+    ("satp", 0x180),
+    ("mstatus", 0x300),
+    ("misa", 0x301),
+    ("medeleg", 0x302),
+    ("mideleg", 0x303),
+    ("mie", 0x304),
+    ("mtvec", 0x305),
+    ("mcounteren", 0x306),
+    ("mscratch", 0x340),
+    ("mepc", 0x341),
+    ("mcause", 0x342),
+    ("mtval", 0x343),
+    ("mip", 0x344),
+    ("mcycle", 0xB00),
+    ("minstret", 0xB02),
+    ("mvendorid", 0xF11),
+    ("marchid", 0xF12),
+    ("mimpid", 0xF13),
+    ("mhartid", 0xF14),
+];
+
+fn parse_int_register(name: &str) -> Option<usize> {
+    if name == "fp" {
+        return Some(8);
+    }
+    if let Some(index) = name.strip_prefix('x').and_then(|n| n.parse::<usize>().ok()) {
+        return (index < 32).then_some(index);
+    }
+    (0..32).find(|&i| get_register_name(i) == name)
+}
+
+fn parse_float_register(name: &str) -> Option<usize> {
+    if let Some(index) = name.strip_prefix('f').and_then(|n| n.parse::<usize>().ok()) {
+        return (index < 32).then_some(index);
+    }
+    (0..32).find(|&i| get_float_register_name(i) == name)
+}
+
+fn parse_immediate(text: &str) -> Option<i64> {
+    let (negative, digits) = match text.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, text.strip_prefix('+').unwrap_or(text)),
+    };
+    let value = if let Some(hex) = digits
+        .strip_prefix("0x")
+        .or_else(|| digits.strip_prefix("0X"))
+    {
+        u64::from_str_radix(hex, 16).ok()? as i64
+    } else {
+        digits.parse::<u64>().ok()? as i64
+    };
+    Some(if negative {
+        value.wrapping_neg()
+    } else {
+        value
+    })
+}
+
+/// CSR 名称或编号
+pub fn parse_csr(text: &str) -> Option<u16> {
+    if let Some((_, number)) = CSR_NAMES.iter().find(|(name, _)| *name == text) {
+        return Some(*number);
+    }
+    parse_immediate(text)
+        .filter(|n| (0..0x1000).contains(n))
+        .map(|n| n as u16)
+}
+
+fn parse_operand(text: &str) -> Option<Operand> {
+    // offset(base) 或 (base)
+    if let Some(open) = text.find('(') {
+        let base = text[open + 1..].strip_suffix(')')?.trim();
+        let offset = text[..open].trim();
+        let offset = if offset.is_empty() {
+            0
+        } else {
+            parse_immediate(offset)?
+        };
+        return Some(Operand::Mem {
+            offset,
+            base: parse_int_register(base)?,
+        });
+    }
+    if let Some(index) = parse_int_register(text) {
+        return Some(Operand::Reg(index));
+    }
+    if let Some(index) = parse_float_register(text) {
+        return Some(Operand::FReg(index));
+    }
+    if let Some(rm) = RoundingMode::from_name(text) {
+        return Some(Operand::Rm(rm));
+    }
+    if let Some(value) = parse_immediate(text) {
+        return Some(Operand::Imm(value));
+    }
+    CSR_NAMES
+        .iter()
+        .find(|(name, _)| *name == text)
+        .map(|(_, number)| Operand::Csr(*number))
+}
+
+/// 解码一行汇编，注释和标签之外的内容都必须能识别
+pub fn decode(text: &str) -> Result<DecodedInstruction> {
+    let code = text.split('#').next().unwrap_or("").trim();
+    let (mnemonic, rest) = code.split_once(char::is_whitespace).unwrap_or((code, ""));
+    if mnemonic.is_empty() {
+        return Err(RiscvFuzzError::instruction_generation(format!(
+            "Empty instruction: {:?}",
+            text
+        )));
+    }
+
+    let mut mnemonic = mnemonic.to_lowercase();
+    for suffix in [".aqrl", ".aq", ".rl"] {
+        if let Some(stripped) = mnemonic.strip_suffix(suffix) {
+            mnemonic = stripped.to_string();
+            break;
+        }
+    }
+
+    // fence 的 iorw 集合不影响单核结果
+    let rest = if mnemonic == "fence" { "" } else { rest };
+    let operands = rest
+        .split(',')
+        .map(str::trim)
+        .filter(|op| !op.is_empty())
+        .map(|op| {
+            parse_operand(&op.to_lowercase()).ok_or_else(|| {
+                RiscvFuzzError::instruction_generation(format!(
+                    "Cannot decode operand {:?} of {:?}",
+                    op, text
+                ))
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(DecodedInstruction { mnemonic, operands })
+}
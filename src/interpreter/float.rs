@@ -0,0 +1,663 @@
+//! IEEE 754 binary32/binary64 运算 (含舍入模式和异常标志)
+//!
+//! Results are computed with the host's round-to-nearest-even arithmetic. The
+//! exact rounding error is recovered with error-free transformations (TwoSum,
+//! FMA residuals), which gives the inexact flag and lets the other rounding
+//! modes step the result by one ulp where needed.
+
+use std::cmp::Ordering;
+use std::ops::{Add, Div, Mul, Neg, Sub};
+
+/// fflags 位
+pub const FLAG_NX: u64 = 1 << 0;
+pub const FLAG_UF: u64 = 1 << 1;
+pub const FLAG_OF: u64 = 1 << 2;
+pub const FLAG_DZ: u64 = 1 << 3;
+pub const FLAG_NV: u64 = 1 << 4;
+
+/// 舍入模式 (`Dyn` 使用 frm)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RoundingMode {
+    Rne,
+    Rtz,
+    Rdn,
+    Rup,
+    Rmm,
+    Dyn,
+}
+
+impl RoundingMode {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "rne" => Some(Self::Rne),
+            "rtz" => Some(Self::Rtz),
+            "rdn" => Some(Self::Rdn),
+            "rup" => Some(Self::Rup),
+            "rmm" => Some(Self::Rmm),
+            "dyn" => Some(Self::Dyn),
+            _ => None,
+        }
+    }
+
+    /// frm 字段，保留值返回 None
+    pub fn from_frm(frm: u64) -> Option<Self> {
+        match frm {
+            0 => Some(Self::Rne),
+            1 => Some(Self::Rtz),
+            2 => Some(Self::Rdn),
+            3 => Some(Self::Rup),
+            4 => Some(Self::Rmm),
+            _ => None,
+        }
+    }
+}
+
+/// binary32 / binary64 的公共操作
+pub trait Fp:
+    Copy
+    + PartialEq
+    + PartialOrd
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + Div<Output = Self>
+    + Neg<Output = Self>
+{
+    const ZERO: Self;
+    const MAX: Self;
+    const MIN_POSITIVE: Self;
+    const INFINITY: Self;
+    /// Canonical NaN as stored in a 64-bit FP register
+    const CANONICAL_NAN_REG: u64;
+    const SIGN_BIT: u64;
+    const QUIET_BIT: u64;
+    /// Significand precision in bits, including the hidden bit
+    const PRECISION: u32;
+
+    fn bits(self) -> u64;
+    fn from_bits_u64(bits: u64) -> Self;
+    /// Reads the value from a 64-bit FP register (NaN-boxing for binary32)
+    fn from_reg(reg: u64) -> Self;
+    fn to_reg(self) -> u64;
+    fn fma(self, b: Self, c: Self) -> Self;
+    fn sqrt(self) -> Self;
+    fn next_up(self) -> Self;
+    fn next_down(self) -> Self;
+    fn is_nan(self) -> bool;
+    fn is_infinite(self) -> bool;
+    fn is_sign_negative(self) -> bool;
+    fn abs(self) -> Self;
+    fn to_f64(self) -> f64;
+    /// `value * 2^exp` for an integer `value` exactly representable in `Self`
+    fn scaled(value: u64, exp: u32) -> Self;
+
+    fn is_snan(self) -> bool {
+        self.is_nan() && self.bits() & Self::QUIET_BIT == 0
+    }
+
+    fn is_zero(self) -> bool {
+        self == Self::ZERO
+    }
+
+    fn is_finite(self) -> bool {
+        !self.is_nan() && !self.is_infinite()
+    }
+}
+
+impl Fp for f32 {
+    const ZERO: Self = 0.0;
+    const MAX: Self = f32::MAX;
+    const MIN_POSITIVE: Self = f32::MIN_POSITIVE;
+    const INFINITY: Self = f32::INFINITY;
+    const CANONICAL_NAN_REG: u64 = 0xFFFF_FFFF_7FC0_0000;
+    const SIGN_BIT: u64 = 1 << 31;
+    const QUIET_BIT: u64 = 1 << 22;
+    const PRECISION: u32 = 24;
+
+    fn bits(self) -> u64 {
+        self.to_bits() as u64
+    }
+    fn from_bits_u64(bits: u64) -> Self {
+        f32::from_bits(bits as u32)
+    }
+    fn from_reg(reg: u64) -> Self {
+        if reg >> 32 == 0xFFFF_FFFF {
+            f32::from_bits(reg as u32)
+        } else {
+            f32::from_bits(Self::CANONICAL_NAN_REG as u32)
+        }
+    }
+    fn to_reg(self) -> u64 {
+        0xFFFF_FFFF_0000_0000 | self.to_bits() as u64
+    }
+    fn fma(self, b: Self, c: Self) -> Self {
+        self.mul_add(b, c)
+    }
+    fn sqrt(self) -> Self {
+        f32::sqrt(self)
+    }
+    fn next_up(self) -> Self {
+        f32::next_up(self)
+    }
+    fn next_down(self) -> Self {
+        f32::next_down(self)
+    }
+    fn is_nan(self) -> bool {
+        f32::is_nan(self)
+    }
+    fn is_infinite(self) -> bool {
+        f32::is_infinite(self)
+    }
+    fn is_sign_negative(self) -> bool {
+        f32::is_sign_negative(self)
+    }
+    fn abs(self) -> Self {
+        f32::abs(self)
+    }
+    fn to_f64(self) -> f64 {
+        self as f64
+    }
+    fn scaled(value: u64, exp: u32) -> Self {
+        value as f32 * 2f32.powi(exp as i32)
+    }
+}
+
+impl Fp for f64 {
+    const ZERO: Self = 0.0;
+    const MAX: Self = f64::MAX;
+    const MIN_POSITIVE: Self = f64::MIN_POSITIVE;
+    const INFINITY: Self = f64::INFINITY;
+    const CANONICAL_NAN_REG: u64 = 0x7FF8_0000_0000_0000;
+    const SIGN_BIT: u64 = 1 << 63;
+    const QUIET_BIT: u64 = 1 << 51;
+    const PRECISION: u32 = 53;
+
+    fn bits(self) -> u64 {
+        self.to_bits()
+    }
+    fn from_bits_u64(bits: u64) -> Self {
+        f64::from_bits(bits)
+    }
+    fn from_reg(reg: u64) -> Self {
+        f64::from_bits(reg)
+    }
+    fn to_reg(self) -> u64 {
+        self.to_bits()
+    }
+    fn fma(self, b: Self, c: Self) -> Self {
+        self.mul_add(b, c)
+    }
+    fn sqrt(self) -> Self {
+        f64::sqrt(self)
+    }
+    fn next_up(self) -> Self {
+        f64::next_up(self)
+    }
+    fn next_down(self) -> Self {
+        f64::next_down(self)
+    }
+    fn is_nan(self) -> bool {
+        f64::is_nan(self)
+    }
+    fn is_infinite(self) -> bool {
+        f64::is_infinite(self)
+    }
+    fn is_sign_negative(self) -> bool {
+        f64::is_sign_negative(self)
+    }
+    fn abs(self) -> Self {
+        f64::abs(self)
+    }
+    fn to_f64(self) -> f64 {
+        self
+    }
+    fn scaled(value: u64, exp: u32) -> Self {
+        value as f64 * 2f64.powi(exp as i32)
+    }
+}
+
+/// 运算结果: 写回寄存器的值和累积的 fflags
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FpResult {
+    pub reg: u64,
+    pub flags: u64,
+}
+
+impl FpResult {
+    fn value<T: Fp>(value: T, flags: u64) -> Self {
+        let reg = if value.is_nan() {
+            T::CANONICAL_NAN_REG
+        } else {
+            value.to_reg()
+        };
+        Self { reg, flags }
+    }
+
+    fn nan<T: Fp>(flags: u64) -> Self {
+        Self {
+            reg: T::CANONICAL_NAN_REG,
+            flags,
+        }
+    }
+}
+
+fn sign_of<T: Fp>(value: T) -> Ordering {
+    value.partial_cmp(&T::ZERO).unwrap_or(Ordering::Equal)
+}
+
+/// Applies `rm` to the round-to-nearest result `r` of a finite computation.
+///
+/// `error` is the sign of `exact - r` and `tie` tells whether the exact value
+/// lies halfway between `r` and its neighbour in that direction.
+fn round<T: Fp>(r: T, error: Ordering, tie: bool, rm: RoundingMode) -> FpResult {
+    if r.is_infinite() {
+        // 溢出: 按舍入方向取无穷或最大有限值
+        let negative = r.is_sign_negative();
+        let to_max = match rm {
+            RoundingMode::Rtz => true,
+            RoundingMode::Rdn => !negative,
+            RoundingMode::Rup => negative,
+            _ => false,
+        };
+        let value = if to_max { T::MAX } else { T::INFINITY };
+        let value = if negative { -value } else { value };
+        return FpResult::value(value, FLAG_OF | FLAG_NX);
+    }
+    if error == Ordering::Equal {
+        return FpResult::value(r, 0);
+    }
+
+    let towards = |r: T| {
+        if error == Ordering::Greater {
+            r.next_up()
+        } else {
+            r.next_down()
+        }
+    };
+    let value = match rm {
+        RoundingMode::Rne | RoundingMode::Dyn => r,
+        RoundingMode::Rmm => {
+            let neighbour = towards(r);
+            if tie && neighbour.abs() > r.abs() {
+                neighbour
+            } else {
+                r
+            }
+        }
+        RoundingMode::Rtz => {
+            // 精确值的绝对值更小时向零靠一步
+            let smaller = (error == Ordering::Less) != r.is_sign_negative();
+            if smaller && !r.is_zero() {
+                towards(r)
+            } else {
+                r
+            }
+        }
+        RoundingMode::Rdn if error == Ordering::Less => r.next_down(),
+        RoundingMode::Rup if error == Ordering::Greater => r.next_up(),
+        RoundingMode::Rdn | RoundingMode::Rup => r,
+    };
+
+    let mut flags = FLAG_NX;
+    if value.is_infinite() {
+        flags |= FLAG_OF;
+    } else if value.abs() < T::MIN_POSITIVE {
+        flags |= FLAG_UF;
+    }
+    FpResult::value(value, flags)
+}
+
+/// 是否为 `r` 与其 `error` 方向上相邻值的中点
+fn is_tie<T: Fp>(r: T, error: T) -> bool {
+    let neighbour = if error > T::ZERO {
+        r.next_up()
+    } else {
+        r.next_down()
+    };
+    neighbour.is_finite() && neighbour - r == error + error
+}
+
+/// NaN 输入或无效运算: 返回 Some(结果)
+fn nan_result<T: Fp>(inputs: &[T], result: T) -> Option<FpResult> {
+    if inputs.iter().any(|v| v.is_nan()) {
+        let flags = if inputs.iter().any(|v| v.is_snan()) {
+            FLAG_NV
+        } else {
+            0
+        };
+        return Some(FpResult::nan::<T>(flags));
+    }
+    result.is_nan().then(|| FpResult::nan::<T>(FLAG_NV))
+}
+
+/// 精确结果为零时 RDN 下取 -0
+fn exact_zero_sign<T: Fp>(r: T, a: T, b: T, rm: RoundingMode) -> T {
+    let both_positive_zero =
+        a.is_zero() && b.is_zero() && !a.is_sign_negative() && !b.is_sign_negative();
+    if r.is_zero() && rm == RoundingMode::Rdn && !both_positive_zero {
+        -T::ZERO
+    } else {
+        r
+    }
+}
+
+pub fn add<T: Fp>(a: T, b: T, rm: RoundingMode) -> FpResult {
+    let s = a + b;
+    if let Some(result) = nan_result(&[a, b], s) {
+        return result;
+    }
+    if !a.is_finite() || !b.is_finite() {
+        return FpResult::value(s, 0);
+    }
+    if s.is_infinite() {
+        return round(s, Ordering::Equal, false, rm);
+    }
+    // TwoSum
+    let bb = s - a;
+    let error = (a - (s - bb)) + (b - bb);
+    if error.is_zero() {
+        return FpResult::value(exact_zero_sign(s, a, b, rm), 0);
+    }
+    round(s, sign_of(error), is_tie(s, error), rm)
+}
+
+pub fn sub<T: Fp>(a: T, b: T, rm: RoundingMode) -> FpResult {
+    if b.is_nan() {
+        return add(a, b, rm);
+    }
+    add(a, -b, rm)
+}
+
+pub fn mul<T: Fp>(a: T, b: T, rm: RoundingMode) -> FpResult {
+    let p = a * b;
+    if let Some(result) = nan_result(&[a, b], p) {
+        return result;
+    }
+    if !a.is_finite() || !b.is_finite() {
+        return FpResult::value(p, 0);
+    }
+    if p.is_infinite() {
+        return round(p, Ordering::Equal, false, rm);
+    }
+    let error = a.fma(b, -p);
+    round(p, sign_of(error), !error.is_zero() && is_tie(p, error), rm)
+}
+
+pub fn div<T: Fp>(a: T, b: T, rm: RoundingMode) -> FpResult {
+    let q = a / b;
+    if let Some(result) = nan_result(&[a, b], q) {
+        return result;
+    }
+    if b.is_zero() {
+        // 非零有限数除以零
+        let flags = if a.is_finite() { FLAG_DZ } else { 0 };
+        return FpResult::value(q, flags);
+    }
+    if !a.is_finite() || !b.is_finite() {
+        return FpResult::value(q, 0);
+    }
+    if q.is_infinite() {
+        return round(q, Ordering::Equal, false, rm);
+    }
+    // 余数 a - q*b 精确，商不可能恰好落在中点
+    let remainder = (-q).fma(b, a);
+    let error = match sign_of(remainder) {
+        Ordering::Equal => Ordering::Equal,
+        sign if b.is_sign_negative() => sign.reverse(),
+        sign => sign,
+    };
+    round(q, error, false, rm)
+}
+
+pub fn sqrt<T: Fp>(a: T, rm: RoundingMode) -> FpResult {
+    let r = a.sqrt();
+    if let Some(result) = nan_result(&[a], r) {
+        return result;
+    }
+    if !a.is_finite() || a.is_zero() {
+        return FpResult::value(r, 0);
+    }
+    let remainder = (-r).fma(r, a);
+    round(r, sign_of(remainder), false, rm)
+}
+
+/// `a * b + c`，fmsub / fnmadd 等变体由调用方先对操作数取反
+pub fn fma<T: Fp>(a: T, b: T, c: T, rm: RoundingMode) -> FpResult {
+    // 0 * inf 即使加数是 qNaN 也是无效运算
+    let zero_times_inf = (a.is_zero() && b.is_infinite()) || (a.is_infinite() && b.is_zero());
+    if zero_times_inf {
+        return FpResult::nan::<T>(FLAG_NV);
+    }
+    let r1 = a.fma(b, c);
+    if let Some(result) = nan_result(&[a, b, c], r1) {
+        return result;
+    }
+    if !a.is_finite() || !b.is_finite() || !c.is_finite() {
+        return FpResult::value(r1, 0);
+    }
+    if r1.is_infinite() {
+        return round(r1, Ordering::Equal, false, rm);
+    }
+
+    // ErrFma (Boldo & Muller): a*b + c == r1 + r2 + r3 exactly
+    let two_sum = |x: T, y: T| {
+        let s = x + y;
+        let yy = s - x;
+        (s, (x - (s - yy)) + (y - yy))
+    };
+    let u1 = a * b;
+    let u2 = a.fma(b, -u1);
+    let (alpha1, alpha2) = two_sum(c, u2);
+    let (beta1, beta2) = two_sum(u1, alpha1);
+    let gamma = (beta1 - r1) + beta2;
+    let r2 = gamma + alpha2;
+    let r3 = alpha2 - (r2 - gamma);
+
+    if r2.is_zero() && r3.is_zero() {
+        let product = if a.is_sign_negative() != b.is_sign_negative() {
+            -u1.abs()
+        } else {
+            u1.abs()
+        };
+        return FpResult::value(exact_zero_sign(r1, product, c, rm), 0);
+    }
+    let error = if r2.is_zero() {
+        sign_of(r3)
+    } else {
+        sign_of(r2)
+    };
+    let tie = r3.is_zero() && !r2.is_zero() && is_tie(r1, r2);
+    round(r1, error, tie, rm)
+}
+
+/// Rounds `value` (exactly representable in f64) to `Self`'s precision and range
+fn narrow<T: Fp>(value: f64, rounded: T, rm: RoundingMode) -> FpResult {
+    let back = rounded.to_f64();
+    if rounded.is_infinite() {
+        return round(rounded, Ordering::Equal, false, rm);
+    }
+    let error = value - back;
+    let tie = error != 0.0 && {
+        let neighbour = if error > 0.0 {
+            rounded.next_up()
+        } else {
+            rounded.next_down()
+        };
+        neighbour.is_finite() && neighbour.to_f64() - back == error + error
+    };
+    round(
+        rounded,
+        error.partial_cmp(&0.0).unwrap_or(Ordering::Equal),
+        tie,
+        rm,
+    )
+}
+
+/// fcvt.s.d
+pub fn f64_to_f32(a: f64, rm: RoundingMode) -> FpResult {
+    if a.is_nan() {
+        let flags = if Fp::is_snan(a) { FLAG_NV } else { 0 };
+        return FpResult::nan::<f32>(flags);
+    }
+    if a.is_infinite() {
+        return FpResult::value(a as f32, 0);
+    }
+    narrow(a, a as f32, rm)
+}
+
+/// fcvt.d.s (精确)
+pub fn f32_to_f64(a: f32) -> FpResult {
+    if a.is_nan() {
+        let flags = if Fp::is_snan(a) { FLAG_NV } else { 0 };
+        return FpResult::nan::<f64>(flags);
+    }
+    FpResult::value(a as f64, 0)
+}
+
+/// fcvt.{s,d}.{w,wu,l,lu}: 整数 `negative ? -magnitude : magnitude` 转浮点
+pub fn int_to_float<T: Fp>(negative: bool, magnitude: u64, rm: RoundingMode) -> FpResult {
+    let bit_len = 64 - magnitude.leading_zeros();
+    if bit_len <= T::PRECISION {
+        let value = T::scaled(magnitude, 0);
+        return FpResult::value(if negative { -value } else { value }, 0);
+    }
+
+    let shift = bit_len - T::PRECISION;
+    let kept = magnitude >> shift;
+    let remainder = magnitude & ((1u64 << shift) - 1);
+    let half = 1u64 << (shift - 1);
+    if remainder == 0 {
+        let value = T::scaled(kept, shift);
+        return FpResult::value(if negative { -value } else { value }, 0);
+    }
+
+    // 以绝对值舍入，负数时 RDN/RUP 互换
+    let away = match rm {
+        RoundingMode::Rne | RoundingMode::Dyn => {
+            remainder > half || (remainder == half && kept & 1 == 1)
+        }
+        RoundingMode::Rmm => remainder >= half,
+        RoundingMode::Rtz => false,
+        RoundingMode::Rdn => negative,
+        RoundingMode::Rup => !negative,
+    };
+    let value = T::scaled(kept + away as u64, shift);
+    FpResult::value(if negative { -value } else { value }, FLAG_NX)
+}
+
+/// fcvt.{w,wu,l,lu}.{s,d}，返回写回整数寄存器的值 (32 位结果符号扩展) 和 fflags
+pub fn float_to_int(value: f64, rm: RoundingMode, signed: bool, bits: u32) -> (u64, u64) {
+    // [min, upper) 为可表示范围
+    let (min, upper): (f64, f64) = match (signed, bits) {
+        (true, 32) => (-2f64.powi(31), 2f64.powi(31)),
+        (false, 32) => (0.0, 2f64.powi(32)),
+        (true, _) => (-2f64.powi(63), 2f64.powi(63)),
+        (false, _) => (0.0, 2f64.powi(64)),
+    };
+    let saturate = |high: bool| -> u64 {
+        match (signed, bits, high) {
+            (true, 32, true) => i32::MAX as i64 as u64,
+            (true, 32, false) => i32::MIN as i64 as u64,
+            (false, 32, true) => u64::MAX,
+            (true, _, true) => i64::MAX as u64,
+            (true, _, false) => i64::MIN as u64,
+            (false, _, true) => u64::MAX,
+            (false, _, false) => 0,
+        }
+    };
+
+    if value.is_nan() {
+        return (saturate(true), FLAG_NV);
+    }
+    let rounded = match rm {
+        RoundingMode::Rne | RoundingMode::Dyn => value.round_ties_even(),
+        RoundingMode::Rtz => value.trunc(),
+        RoundingMode::Rdn => value.floor(),
+        RoundingMode::Rup => value.ceil(),
+        RoundingMode::Rmm => value.round(),
+    };
+    if rounded >= upper {
+        return (saturate(true), FLAG_NV);
+    }
+    if rounded < min {
+        return (saturate(false), FLAG_NV);
+    }
+
+    let flags = if rounded != value { FLAG_NX } else { 0 };
+    let result = match (signed, bits) {
+        (true, 32) => rounded as i32 as i64 as u64,
+        (false, 32) => rounded as u32 as i32 as i64 as u64,
+        (true, _) => rounded as i64 as u64,
+        (false, _) => rounded as u64,
+    };
+    (result, flags)
+}
+
+/// fmin / fmax (IEEE 754-2019 minimumNumber / maximumNumber)
+pub fn min_max<T: Fp>(a: T, b: T, max: bool) -> FpResult {
+    let flags = if a.is_snan() || b.is_snan() {
+        FLAG_NV
+    } else {
+        0
+    };
+    let value = match (a.is_nan(), b.is_nan()) {
+        (true, true) => return FpResult::nan::<T>(flags),
+        (true, false) => b,
+        (false, true) => a,
+        (false, false) if a.is_zero() && b.is_zero() => {
+            // -0 < +0
+            if (a.is_sign_negative() && !max) || (!a.is_sign_negative() && max) {
+                a
+            } else {
+                b
+            }
+        }
+        (false, false) if (a < b) != max => a,
+        (false, false) => b,
+    };
+    FpResult::value(value, flags)
+}
+
+/// feq / flt / fle，返回 (结果, fflags)
+pub fn compare<T: Fp>(a: T, b: T, op: Ordering, or_equal: bool, signaling: bool) -> (u64, u64) {
+    if a.is_nan() || b.is_nan() {
+        let invalid = signaling || a.is_snan() || b.is_snan();
+        return (0, if invalid { FLAG_NV } else { 0 });
+    }
+    let ordering = a.partial_cmp(&b).unwrap_or(Ordering::Equal);
+    let result = ordering == op || (or_equal && ordering == Ordering::Equal);
+    (result as u64, 0)
+}
+
+/// fclass
+pub fn classify<T: Fp>(a: T) -> u64 {
+    let negative = a.is_sign_negative();
+    let subnormal = a.is_finite() && !a.is_zero() && a.abs() < T::MIN_POSITIVE;
+    let bit = if a.is_nan() {
+        if a.is_snan() { 8 } else { 9 }
+    } else if a.is_infinite() {
+        if negative { 0 } else { 7 }
+    } else if a.is_zero() {
+        if negative { 3 } else { 4 }
+    } else if subnormal {
+        if negative { 2 } else { 5 }
+    } else if negative {
+        1
+    } else {
+        6
+    };
+    1 << bit
+}
+
+/// fsgnj / fsgnjn / fsgnjx
+pub fn sign_inject<T: Fp>(a: T, b: T, negate: bool, xor: bool) -> u64 {
+    let sign_b = b.bits() & T::SIGN_BIT;
+    let sign = if xor {
+        (a.bits() ^ sign_b) & T::SIGN_BIT
+    } else if negate {
+        sign_b ^ T::SIGN_BIT
+    } else {
+        sign_b
+    };
+    T::from_bits_u64((a.bits() & !T::SIGN_BIT) | sign).to_reg()
+}
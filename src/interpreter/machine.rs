@@ -0,0 +1,1127 @@
+//! 解释器的体系结构状态和指令语义
+
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+
+use crate::error::{Result, RiscvFuzzError};
+use crate::interpreter::decode::{DecodedInstruction, Operand, decode};
+use crate::interpreter::float::{self, Fp, FpResult, RoundingMode};
+use crate::interpreter::{
+    ProgramInstruction, REFERENCE_HTIF_SIZE, REFERENCE_RAM_BASE, REFERENCE_RAM_SIZE,
+};
+
+/// mcause 取值
+pub const CAUSE_ILLEGAL_INSTRUCTION: u64 = 2;
+pub const CAUSE_BREAKPOINT: u64 = 3;
+pub const CAUSE_LOAD_MISALIGNED: u64 = 4;
+pub const CAUSE_LOAD_ACCESS_FAULT: u64 = 5;
+pub const CAUSE_STORE_MISALIGNED: u64 = 6;
+pub const CAUSE_STORE_ACCESS_FAULT: u64 = 7;
+pub const CAUSE_ECALL_FROM_M: u64 = 11;
+
+const MSTATUS_MIE: u64 = 1 << 3;
+const MSTATUS_MPIE: u64 = 1 << 7;
+const MSTATUS_MPP: u64 = 3 << 11;
+const MSTATUS_FS: u64 = 3 << 13;
+const MSTATUS_FS_INITIAL: u64 = 1 << 13;
+const MSTATUS_SD: u64 = 1 << 63;
+/// UXL = SXL = 64
+const MSTATUS_XLEN: u64 = 0xA_0000_0000;
+/// sstatus 可见的 mstatus 位
+const SSTATUS_MASK: u64 = 0x8000_0003_000D_E762;
+
+/// 同步异常
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Trap {
+    pub cause: u64,
+    pub tval: u64,
+}
+
+impl Trap {
+    fn new(cause: u64, tval: u64) -> Self {
+        Self { cause, tval }
+    }
+}
+
+type Step = Result<Option<Trap>>;
+
+fn sext32(value: u64) -> u64 {
+    value as u32 as i32 as i64 as u64
+}
+
+fn unmodelled(text: &str, reason: &str) -> RiscvFuzzError {
+    RiscvFuzzError::simulator(
+        "Reference",
+        &format!("`{}` is not modelled: {}", text, reason),
+    )
+}
+
+/// 指令所属扩展，任一满足即可；None 表示不在模型范围内
+fn required_extensions(mnemonic: &str) -> Option<&'static [&'static str]> {
+    const I: &[&str] = &["i"];
+    const M: &[&str] = &["m"];
+    const F: &[&str] = &["f"];
+    const D: &[&str] = &["d"];
+    const C: &[&str] = &["c"];
+    const CD: &[&str] = &["zcd"];
+    const ZICSR: &[&str] = &["zicsr"];
+
+    let (base, format) = match mnemonic.rsplit_once('.') {
+        Some((base, format)) => (base, format),
+        None => (mnemonic, ""),
+    };
+    let required = match mnemonic {
+        "lui" | "auipc" | "addi" | "slti" | "sltiu" | "xori" | "ori" | "andi" | "slli" | "srli"
+        | "srai" | "add" | "sub" | "sll" | "slt" | "sltu" | "xor" | "srl" | "sra" | "or"
+        | "and" | "addiw" | "slliw" | "srliw" | "sraiw" | "addw" | "subw" | "sllw" | "srlw"
+        | "sraw" | "lb" | "lh" | "lw" | "ld" | "lbu" | "lhu" | "lwu" | "sb" | "sh" | "sw"
+        | "sd" | "fence" | "fence.tso" | "pause" | "ecall" | "ebreak" | "wfi" | "nop" | "mv"
+        | "li" | "not" | "neg" | "negw" | "sext.w" | "zext.b" | "seqz" | "snez" | "sltz"
+        | "sgtz" => I,
+        "fence.i" => &["zifencei"],
+        "csrrw" | "csrrs" | "csrrc" | "csrrwi" | "csrrsi" | "csrrci" | "csrr" | "csrw" | "csrs"
+        | "csrc" | "csrwi" | "csrsi" | "csrci" => ZICSR,
+        "frcsr" | "fscsr" | "frrm" | "fsrm" | "frflags" | "fsflags" | "fsrmi" | "fsflagsi" => F,
+        "mul" | "mulh" | "mulhsu" | "mulhu" | "div" | "divu" | "rem" | "remu" | "mulw" | "divw"
+        | "divuw" | "remw" | "remuw" => M,
+        "lr.w" | "lr.d" | "sc.w" | "sc.d" => &["zalrsc"],
+        _ if base.starts_with("amo") && matches!(format, "w" | "d") => &["zaamo"],
+        "add.uw" | "sh1add" | "sh2add" | "sh3add" | "sh1add.uw" | "sh2add.uw" | "sh3add.uw"
+        | "slli.uw" | "zext.w" => &["zba"],
+        "andn" | "orn" | "xnor" | "clz" | "clzw" | "ctz" | "ctzw" | "cpop" | "cpopw" | "max"
+        | "maxu" | "min" | "minu" | "sext.b" | "sext.h" | "zext.h" | "rol" | "rolw" | "ror"
+        | "rori" | "roriw" | "rorw" | "orc.b" | "rev8" => &["zbb"],
+        "bclr" | "bclri" | "bext" | "bexti" | "binv" | "binvi" | "bset" | "bseti" => &["zbs"],
+        "flw" | "fsw" | "fmv.x.w" | "fmv.w.x" | "fmv.x.s" | "fmv.s.x" => F,
+        "fld" | "fsd" | "fmv.x.d" | "fmv.d.x" => D,
+        "c.fld" | "c.fsd" | "c.fldsp" | "c.fsdsp" => CD,
+        "c.nop" | "c.addi" | "c.addiw" | "c.addi16sp" | "c.addi4spn" | "c.li" | "c.lui"
+        | "c.mv" | "c.add" | "c.sub" | "c.xor" | "c.or" | "c.and" | "c.subw" | "c.addw"
+        | "c.andi" | "c.slli" | "c.srli" | "c.srai" | "c.lw" | "c.ld" | "c.sw" | "c.sd"
+        | "c.lwsp" | "c.ldsp" | "c.swsp" | "c.sdsp" | "c.ebreak" => C,
+        // fcvt.s.d / fcvt.d.s 需要 D，其余转换按浮点格式
+        _ if mnemonic.starts_with("fcvt.") => {
+            if mnemonic.split('.').any(|part| part == "d") {
+                D
+            } else {
+                F
+            }
+        }
+        _ if base.starts_with('f') && format == "s" && is_fp_operation(base) => F,
+        _ if base.starts_with('f') && format == "d" && is_fp_operation(base) => D,
+        _ => return None,
+    };
+    Some(required)
+}
+
+/// 带格式后缀的浮点运算 (fadd.s, fsgnj.d ...)
+fn is_fp_operation(base: &str) -> bool {
+    matches!(
+        base,
+        "fadd"
+            | "fsub"
+            | "fmul"
+            | "fdiv"
+            | "fsqrt"
+            | "fmin"
+            | "fmax"
+            | "fmadd"
+            | "fmsub"
+            | "fnmsub"
+            | "fnmadd"
+            | "fsgnj"
+            | "fsgnjn"
+            | "fsgnjx"
+            | "fmv"
+            | "fneg"
+            | "fabs"
+            | "feq"
+            | "flt"
+            | "fle"
+            | "fclass"
+    )
+}
+
+/// 压缩指令展开为等价的基础指令
+fn expand_compressed(text: &str, decoded: DecodedInstruction) -> Result<DecodedInstruction> {
+    use Operand::{Imm, Reg};
+
+    let Some(op) = decoded.mnemonic.strip_prefix("c.") else {
+        return Ok(decoded);
+    };
+    let ops = decoded.operands;
+    let expanded = |mnemonic: &str, operands: Vec<Operand>| DecodedInstruction {
+        mnemonic: mnemonic.to_string(),
+        operands,
+    };
+    const SP: Operand = Reg(2);
+
+    let result = match (op, ops.as_slice()) {
+        ("nop", []) => expanded("nop", vec![]),
+        ("ebreak", []) => expanded("ebreak", vec![]),
+        ("addi" | "addiw" | "andi" | "slli" | "srli" | "srai", [rd, imm]) => {
+            expanded(op, vec![*rd, *rd, *imm])
+        }
+        ("li", [rd, imm]) => expanded("addi", vec![*rd, Reg(0), *imm]),
+        ("lui", [rd, imm]) => expanded("lui", vec![*rd, *imm]),
+        ("addi16sp", [imm]) | ("addi16sp", [_, imm]) => expanded("addi", vec![SP, SP, *imm]),
+        ("addi4spn", [rd, imm]) | ("addi4spn", [rd, _, imm]) => {
+            expanded("addi", vec![*rd, SP, *imm])
+        }
+        ("mv", [rd, rs]) => expanded("add", vec![*rd, Reg(0), *rs]),
+        ("add" | "sub" | "xor" | "or" | "and" | "subw" | "addw", [rd, rs]) => {
+            expanded(op, vec![*rd, *rd, *rs])
+        }
+        ("lw" | "ld" | "sw" | "sd" | "fld" | "fsd", [reg, mem @ Operand::Mem { .. }]) => {
+            expanded(op, vec![*reg, *mem])
+        }
+        ("lwsp" | "ldsp" | "swsp" | "sdsp" | "fldsp" | "fsdsp", [reg, mem]) => {
+            let mem = match mem {
+                Operand::Mem { .. } => *mem,
+                Imm(offset) => Operand::Mem {
+                    offset: *offset,
+                    base: 2,
+                },
+                _ => return Err(unmodelled(text, "unexpected operands")),
+            };
+            expanded(op.trim_end_matches("sp"), vec![*reg, mem])
+        }
+        _ => {
+            return Err(unmodelled(
+                text,
+                "unexpected compressed instruction operands",
+            ));
+        }
+    };
+    Ok(result)
+}
+
+/// RV64 hart state after the template's `RESET_MACHINE_STATE`
+pub(crate) struct Machine<'a> {
+    extensions: &'a HashSet<String>,
+    pub x: [u64; 32],
+    pub f: [u64; 32],
+    pub fflags: u64,
+    pub frm: u64,
+    pub mstatus: u64,
+    pub mscratch: u64,
+    pub mepc: u64,
+    pub mcause: u64,
+    pub mtval: u64,
+    pub mtvec: Option<u64>,
+    memory: HashMap<u64, u8>,
+    reservation: Option<u64>,
+    pub warnings: Vec<String>,
+}
+
+impl<'a> Machine<'a> {
+    pub fn new(extensions: &'a HashSet<String>, mtvec: Option<u64>) -> Self {
+        let mut mstatus = MSTATUS_XLEN;
+        if extensions.contains("f") {
+            mstatus |= MSTATUS_FS_INITIAL;
+        }
+        Self {
+            extensions,
+            x: [0; 32],
+            f: [0; 32],
+            fflags: 0,
+            frm: 0,
+            mstatus,
+            mscratch: 0,
+            mepc: 0,
+            mcause: 0,
+            mtval: 0,
+            mtvec,
+            memory: HashMap::new(),
+            reservation: None,
+            warnings: Vec::new(),
+        }
+    }
+
+    pub fn has_extension(&self, name: &str) -> bool {
+        self.extensions.contains(name)
+    }
+
+    pub fn misa(&self) -> u64 {
+        let mut misa = 2u64 << 62;
+        for letter in ['i', 'm', 'f', 'd', 'c'] {
+            if self.has_extension(&letter.to_string()) {
+                misa |= 1 << (letter as u8 - b'a');
+            }
+        }
+        if self.has_extension("zaamo") && self.has_extension("zalrsc") {
+            misa |= 1 << 0;
+        }
+        // S 和 U 模式
+        misa | (1 << 18) | (1 << 20)
+    }
+
+    pub fn mstatus(&self) -> u64 {
+        if self.mstatus & MSTATUS_FS == MSTATUS_FS {
+            self.mstatus | MSTATUS_SD
+        } else {
+            self.mstatus & !MSTATUS_SD
+        }
+    }
+
+    pub fn fcsr(&self) -> u64 {
+        (self.frm << 5) | self.fflags
+    }
+
+    /// 陷入时 mstatus 的值 (MPIE←MIE, MIE←0, MPP←M)
+    pub fn trap_mstatus(&self) -> u64 {
+        let mut mstatus = self.mstatus() & !(MSTATUS_MPIE | MSTATUS_MIE);
+        if self.mstatus & MSTATUS_MIE != 0 {
+            mstatus |= MSTATUS_MPIE;
+        }
+        mstatus | MSTATUS_MPP
+    }
+
+    /// Runs the template's exception handler: dump, skip the instruction, `mret`
+    pub fn handle_trap(&mut self, inst: &ProgramInstruction) {
+        let mie = self.mstatus & MSTATUS_MIE != 0;
+        self.mstatus = self.trap_mstatus();
+        // SAVE_T_REGS 把 t6 写入 mscratch
+        self.mscratch = self.x[31];
+        self.mepc = inst.pc + inst.len;
+        self.mcause = 0;
+        self.mtval = 0;
+        self.reservation = None;
+        // mret: MIE←MPIE, MPIE←1, MPP←U
+        self.mstatus &= !(MSTATUS_MPP | MSTATUS_MIE);
+        self.mstatus |= MSTATUS_MPIE;
+        if mie {
+            self.mstatus |= MSTATUS_MIE;
+        }
+    }
+
+    fn set_x(&mut self, rd: usize, value: u64) {
+        if rd != 0 {
+            self.x[rd] = value;
+        }
+    }
+
+    fn set_fs_dirty(&mut self) {
+        self.mstatus |= MSTATUS_FS;
+    }
+
+    fn set_f(&mut self, rd: usize, value: u64) {
+        self.f[rd] = value;
+        self.set_fs_dirty();
+    }
+
+    fn accrue(&mut self, flags: u64) {
+        if flags != 0 {
+            self.fflags |= flags;
+            self.set_fs_dirty();
+        }
+    }
+
+    fn write_fp(&mut self, rd: usize, result: FpResult) {
+        self.set_f(rd, result.reg);
+        self.accrue(result.flags);
+    }
+
+    // ------------------------------------------------------------------
+    // 内存
+    // ------------------------------------------------------------------
+
+    fn in_ram(address: u64, size: u64) -> bool {
+        address >= REFERENCE_RAM_BASE
+            && address
+                .checked_add(size)
+                .is_some_and(|end| end <= REFERENCE_RAM_BASE + REFERENCE_RAM_SIZE)
+    }
+
+    fn load(
+        &mut self,
+        address: u64,
+        size: u64,
+        cause_misaligned: u64,
+    ) -> std::result::Result<u64, Trap> {
+        if !address.is_multiple_of(size) {
+            return Err(Trap::new(cause_misaligned, address));
+        }
+        if !Self::in_ram(address, size) {
+            return Err(Trap::new(CAUSE_LOAD_ACCESS_FAULT, address));
+        }
+        let mut value = 0u64;
+        let mut unwritten = false;
+        for i in 0..size {
+            let byte = match self.memory.get(&(address + i)) {
+                Some(byte) => *byte,
+                None => {
+                    unwritten = true;
+                    0
+                }
+            };
+            value |= (byte as u64) << (8 * i);
+        }
+        if unwritten {
+            self.warnings.push(format!(
+                "Load from 0x{:016X} reads memory the program never wrote (assumed zero)",
+                address
+            ));
+        }
+        Ok(value)
+    }
+
+    fn check_store(text: &str, address: u64, size: u64) -> Result<std::result::Result<(), Trap>> {
+        if !address.is_multiple_of(size) {
+            return Ok(Err(Trap::new(CAUSE_STORE_MISALIGNED, address)));
+        }
+        if !Self::in_ram(address, size) {
+            return Ok(Err(Trap::new(CAUSE_STORE_ACCESS_FAULT, address)));
+        }
+        if address < REFERENCE_RAM_BASE + REFERENCE_HTIF_SIZE {
+            return Err(unmodelled(text, "store to the HTIF tohost/fromhost region"));
+        }
+        Ok(Ok(()))
+    }
+
+    fn write_memory(&mut self, address: u64, size: u64, value: u64) {
+        for i in 0..size {
+            self.memory.insert(address + i, (value >> (8 * i)) as u8);
+        }
+    }
+
+    // ------------------------------------------------------------------
+    // CSR
+    // ------------------------------------------------------------------
+
+    fn read_csr(&self, text: &str, csr: u16) -> Result<std::result::Result<u64, ()>> {
+        let fp_csr = matches!(csr, 0x001..=0x003);
+        if fp_csr && !self.has_extension("f") {
+            return Ok(Err(()));
+        }
+        let value = match csr {
+            0x001 => self.fflags,
+            0x002 => self.frm,
+            0x003 => self.fcsr(),
+            0x100 => self.mstatus() & SSTATUS_MASK,
+            0x300 => self.mstatus(),
+            0x301 => self.misa(),
+            0x302 | 0x303 | 0x304 | 0x306 | 0x344 | 0xF14 => 0,
+            0x305 => self
+                .mtvec
+                .ok_or_else(|| unmodelled(text, "mtvec is only known for built programs"))?,
+            0x340 => self.mscratch,
+            0x341 => self.mepc,
+            0x342 => self.mcause,
+            0x343 => self.mtval,
+            0xC00..=0xC02 | 0xB00 | 0xB02 | 0xF11..=0xF13 => {
+                return Err(unmodelled(
+                    text,
+                    "counter and ID CSRs are implementation specific",
+                ));
+            }
+            _ => return Err(unmodelled(text, &format!("CSR 0x{:03X}", csr))),
+        };
+        Ok(Ok(value))
+    }
+
+    fn write_csr(&mut self, text: &str, csr: u16, value: u64) -> Result<()> {
+        match csr {
+            0x001 => self.fflags = value & 0x1F,
+            0x002 => self.frm = value & 0x7,
+            0x003 => {
+                self.fflags = value & 0x1F;
+                self.frm = (value >> 5) & 0x7;
+            }
+            0x340 => self.mscratch = value,
+            0x341 => {
+                let mask = if self.has_extension("c") { !1 } else { !3 };
+                self.mepc = value & mask;
+            }
+            _ => {
+                return Err(unmodelled(
+                    text,
+                    &format!("writes to CSR 0x{:03X} (WARL behaviour differs)", csr),
+                ));
+            }
+        }
+        if matches!(csr, 0x001..=0x003) {
+            self.set_fs_dirty();
+        }
+        Ok(())
+    }
+
+    /// csrrw / csrrs / csrrc 及其立即数形式
+    fn csr_access(
+        &mut self,
+        inst: &ProgramInstruction,
+        kind: &str,
+        rd: usize,
+        csr: u16,
+        source: u64,
+        writes: bool,
+    ) -> Step {
+        let illegal = Trap::new(CAUSE_ILLEGAL_INSTRUCTION, inst.machine_code as u64);
+        if writes && csr >> 10 == 0b11 {
+            return Ok(Some(illegal));
+        }
+        // csrrw 在 rd = x0 时不读
+        let old = if kind == "w" && rd == 0 {
+            0
+        } else {
+            match self.read_csr(&inst.text, csr)? {
+                Ok(value) => value,
+                Err(()) => return Ok(Some(illegal)),
+            }
+        };
+        if writes {
+            let new = match kind {
+                "w" => source,
+                "s" => old | source,
+                _ => old & !source,
+            };
+            self.write_csr(&inst.text, csr, new)?;
+        }
+        self.set_x(rd, old);
+        Ok(None)
+    }
+
+    // ------------------------------------------------------------------
+    // 执行
+    // ------------------------------------------------------------------
+
+    /// Executes one instruction; `Ok(Some(trap))` is an exception taken at it
+    pub fn execute(&mut self, inst: &ProgramInstruction) -> Step {
+        let decoded = decode(&inst.text)?;
+        let illegal = Trap::new(CAUSE_ILLEGAL_INSTRUCTION, inst.machine_code as u64);
+
+        let Some(required) = required_extensions(&decoded.mnemonic) else {
+            return Err(unmodelled(
+                &inst.text,
+                "instruction outside the modelled subset",
+            ));
+        };
+        let enabled = required.iter().any(|ext| self.has_extension(ext))
+            || (required == ["zcd"] && self.has_extension("c") && self.has_extension("d"));
+        if !enabled {
+            return Ok(Some(illegal));
+        }
+
+        let decoded = expand_compressed(&inst.text, decoded)?;
+        let ops = Operands {
+            text: &inst.text,
+            operands: &decoded.operands,
+        };
+        let mnemonic = decoded.mnemonic.as_str();
+
+        if mnemonic.starts_with('f') && !mnemonic.starts_with("fence") {
+            if self.mstatus & MSTATUS_FS == 0 {
+                return Ok(Some(illegal));
+            }
+            return self.execute_fp(inst, mnemonic, &ops, illegal);
+        }
+        if mnemonic.starts_with("amo") || mnemonic.starts_with("lr.") || mnemonic.starts_with("sc.")
+        {
+            return self.execute_atomic(inst, mnemonic, &ops);
+        }
+        if mnemonic.starts_with("csr") {
+            return self.execute_csr(inst, mnemonic, &ops);
+        }
+
+        let x = |i: usize| -> Result<u64> { Ok(self.x[ops.reg(i)?]) };
+        let imm = |i: usize| ops.imm(i);
+        let value = match mnemonic {
+            "nop" | "fence" | "fence.tso" | "pause" | "fence.i" | "wfi" => return Ok(None),
+            "ecall" => return Ok(Some(Trap::new(CAUSE_ECALL_FROM_M, 0))),
+            "ebreak" => return Ok(Some(Trap::new(CAUSE_BREAKPOINT, inst.pc))),
+
+            "lui" => sext32((imm(1)? as u64 & 0xF_FFFF) << 12),
+            "auipc" => inst
+                .pc
+                .wrapping_add(sext32((imm(1)? as u64 & 0xF_FFFF) << 12)),
+            "li" => imm(1)? as u64,
+            "mv" => x(1)?,
+            "not" => !x(1)?,
+            "neg" => x(1)?.wrapping_neg(),
+            "negw" => sext32(x(1)?.wrapping_neg()),
+            "sext.w" => sext32(x(1)?),
+            "zext.b" => x(1)? & 0xFF,
+            "seqz" => (x(1)? == 0) as u64,
+            "snez" => (x(1)? != 0) as u64,
+            "sltz" => ((x(1)? as i64) < 0) as u64,
+            "sgtz" => ((x(1)? as i64) > 0) as u64,
+
+            "addi" => x(1)?.wrapping_add(imm(2)? as u64),
+            "slti" => ((x(1)? as i64) < imm(2)?) as u64,
+            "sltiu" => (x(1)? < imm(2)? as u64) as u64,
+            "xori" => x(1)? ^ imm(2)? as u64,
+            "ori" => x(1)? | imm(2)? as u64,
+            "andi" => x(1)? & imm(2)? as u64,
+            "slli" => x(1)? << (imm(2)? & 63),
+            "srli" => x(1)? >> (imm(2)? & 63),
+            "srai" => ((x(1)? as i64) >> (imm(2)? & 63)) as u64,
+            "addiw" => sext32(x(1)?.wrapping_add(imm(2)? as u64)),
+            "slliw" => sext32(((x(1)? as u32) << (imm(2)? & 31)) as u64),
+            "srliw" => sext32(((x(1)? as u32) >> (imm(2)? & 31)) as u64),
+            "sraiw" => ((x(1)? as i32) >> (imm(2)? & 31)) as i64 as u64,
+
+            "add" => x(1)?.wrapping_add(x(2)?),
+            "sub" => x(1)?.wrapping_sub(x(2)?),
+            "sll" => x(1)? << (x(2)? & 63),
+            "slt" => ((x(1)? as i64) < (x(2)? as i64)) as u64,
+            "sltu" => (x(1)? < x(2)?) as u64,
+            "xor" => x(1)? ^ x(2)?,
+            "srl" => x(1)? >> (x(2)? & 63),
+            "sra" => ((x(1)? as i64) >> (x(2)? & 63)) as u64,
+            "or" => x(1)? | x(2)?,
+            "and" => x(1)? & x(2)?,
+            "addw" => sext32(x(1)?.wrapping_add(x(2)?)),
+            "subw" => sext32(x(1)?.wrapping_sub(x(2)?)),
+            "sllw" => sext32(((x(1)? as u32) << (x(2)? & 31)) as u64),
+            "srlw" => sext32(((x(1)? as u32) >> (x(2)? & 31)) as u64),
+            "sraw" => ((x(1)? as i32) >> (x(2)? & 31)) as i64 as u64,
+
+            "mul" => x(1)?.wrapping_mul(x(2)?),
+            "mulh" => ((x(1)? as i64 as i128 * x(2)? as i64 as i128) >> 64) as u64,
+            "mulhsu" => ((x(1)? as i64 as i128).wrapping_mul(x(2)? as i128) >> 64) as u64,
+            "mulhu" => ((x(1)? as u128 * x(2)? as u128) >> 64) as u64,
+            "div" => {
+                let (a, b) = (x(1)? as i64, x(2)? as i64);
+                if b == 0 {
+                    u64::MAX
+                } else {
+                    a.wrapping_div(b) as u64
+                }
+            }
+            "divu" => x(1)?.checked_div(x(2)?).unwrap_or(u64::MAX),
+            "rem" => {
+                let (a, b) = (x(1)? as i64, x(2)? as i64);
+                if b == 0 {
+                    a as u64
+                } else {
+                    a.wrapping_rem(b) as u64
+                }
+            }
+            "remu" => {
+                let (a, b) = (x(1)?, x(2)?);
+                if b == 0 { a } else { a % b }
+            }
+            "mulw" => sext32((x(1)? as u32).wrapping_mul(x(2)? as u32) as u64),
+            "divw" => {
+                let (a, b) = (x(1)? as i32, x(2)? as i32);
+                if b == 0 {
+                    u64::MAX
+                } else {
+                    a.wrapping_div(b) as i64 as u64
+                }
+            }
+            "divuw" => {
+                let (a, b) = (x(1)? as u32, x(2)? as u32);
+                a.checked_div(b).map_or(u64::MAX, |q| sext32(q as u64))
+            }
+            "remw" => {
+                let (a, b) = (x(1)? as i32, x(2)? as i32);
+                if b == 0 {
+                    a as i64 as u64
+                } else {
+                    a.wrapping_rem(b) as i64 as u64
+                }
+            }
+            "remuw" => {
+                let (a, b) = (x(1)? as u32, x(2)? as u32);
+                if b == 0 {
+                    sext32(a as u64)
+                } else {
+                    sext32((a % b) as u64)
+                }
+            }
+
+            "add.uw" => (x(1)? & 0xFFFF_FFFF).wrapping_add(x(2)?),
+            "zext.w" => x(1)? & 0xFFFF_FFFF,
+            "sh1add" => (x(1)? << 1).wrapping_add(x(2)?),
+            "sh2add" => (x(1)? << 2).wrapping_add(x(2)?),
+            "sh3add" => (x(1)? << 3).wrapping_add(x(2)?),
+            "sh1add.uw" => ((x(1)? & 0xFFFF_FFFF) << 1).wrapping_add(x(2)?),
+            "sh2add.uw" => ((x(1)? & 0xFFFF_FFFF) << 2).wrapping_add(x(2)?),
+            "sh3add.uw" => ((x(1)? & 0xFFFF_FFFF) << 3).wrapping_add(x(2)?),
+            "slli.uw" => (x(1)? & 0xFFFF_FFFF) << (imm(2)? & 63),
+
+            "andn" => x(1)? & !x(2)?,
+            "orn" => x(1)? | !x(2)?,
+            "xnor" => !(x(1)? ^ x(2)?),
+            "clz" => x(1)?.leading_zeros() as u64,
+            "clzw" => (x(1)? as u32).leading_zeros() as u64,
+            "ctz" => x(1)?.trailing_zeros() as u64,
+            "ctzw" => (x(1)? as u32).trailing_zeros() as u64,
+            "cpop" => x(1)?.count_ones() as u64,
+            "cpopw" => (x(1)? as u32).count_ones() as u64,
+            "max" => (x(1)? as i64).max(x(2)? as i64) as u64,
+            "maxu" => x(1)?.max(x(2)?),
+            "min" => (x(1)? as i64).min(x(2)? as i64) as u64,
+            "minu" => x(1)?.min(x(2)?),
+            "sext.b" => x(1)? as i8 as i64 as u64,
+            "sext.h" => x(1)? as i16 as i64 as u64,
+            "zext.h" => x(1)? & 0xFFFF,
+            "rol" => x(1)?.rotate_left((x(2)? & 63) as u32),
+            "ror" => x(1)?.rotate_right((x(2)? & 63) as u32),
+            "rori" => x(1)?.rotate_right((imm(2)? & 63) as u32),
+            "rolw" => sext32((x(1)? as u32).rotate_left((x(2)? & 31) as u32) as u64),
+            "rorw" => sext32((x(1)? as u32).rotate_right((x(2)? & 31) as u32) as u64),
+            "roriw" => sext32((x(1)? as u32).rotate_right((imm(2)? & 31) as u32) as u64),
+            "orc.b" => {
+                let value = x(1)?;
+                (0..8)
+                    .filter(|i| (value >> (8 * i)) & 0xFF != 0)
+                    .fold(0, |acc, i| acc | (0xFF << (8 * i)))
+            }
+            "rev8" => x(1)?.swap_bytes(),
+
+            "bclr" => x(1)? & !(1 << (x(2)? & 63)),
+            "bclri" => x(1)? & !(1 << (imm(2)? & 63)),
+            "bext" => (x(1)? >> (x(2)? & 63)) & 1,
+            "bexti" => (x(1)? >> (imm(2)? & 63)) & 1,
+            "binv" => x(1)? ^ (1 << (x(2)? & 63)),
+            "binvi" => x(1)? ^ (1 << (imm(2)? & 63)),
+            "bset" => x(1)? | (1 << (x(2)? & 63)),
+            "bseti" => x(1)? | (1 << (imm(2)? & 63)),
+
+            "lb" | "lh" | "lw" | "ld" | "lbu" | "lhu" | "lwu" => {
+                let size = match &mnemonic[1..2] {
+                    "b" => 1,
+                    "h" => 2,
+                    "w" => 4,
+                    _ => 8,
+                };
+                let address = ops.address(1, &self.x)?;
+                let raw = match self.load(address, size, CAUSE_LOAD_MISALIGNED) {
+                    Ok(raw) => raw,
+                    Err(trap) => return Ok(Some(trap)),
+                };
+                match mnemonic {
+                    "lb" => raw as i8 as i64 as u64,
+                    "lh" => raw as i16 as i64 as u64,
+                    "lw" => raw as i32 as i64 as u64,
+                    _ => raw,
+                }
+            }
+            "sb" | "sh" | "sw" | "sd" => {
+                let size = match mnemonic {
+                    "sb" => 1,
+                    "sh" => 2,
+                    "sw" => 4,
+                    _ => 8,
+                };
+                let value = x(0)?;
+                let address = ops.address(1, &self.x)?;
+                if let Err(trap) = Self::check_store(&inst.text, address, size)? {
+                    return Ok(Some(trap));
+                }
+                self.write_memory(address, size, value);
+                return Ok(None);
+            }
+
+            _ => {
+                return Err(unmodelled(
+                    &inst.text,
+                    "control flow and privileged instructions",
+                ));
+            }
+        };
+
+        self.set_x(ops.reg(0)?, value);
+        Ok(None)
+    }
+
+    fn execute_csr(&mut self, inst: &ProgramInstruction, mnemonic: &str, ops: &Operands) -> Step {
+        // 伪指令: csrr rd, csr / csrw csr, rs / csrwi csr, imm ...
+        let (kind, rd, csr, source, writes) = match mnemonic {
+            "csrrw" | "csrrs" | "csrrc" => {
+                let rs1 = ops.reg(2)?;
+                let writes = mnemonic == "csrrw" || rs1 != 0;
+                (
+                    &mnemonic[4..],
+                    ops.reg(0)?,
+                    ops.csr(1)?,
+                    self.x[rs1],
+                    writes,
+                )
+            }
+            "csrrwi" | "csrrsi" | "csrrci" => {
+                let uimm = (ops.imm(2)? & 0x1F) as u64;
+                let writes = mnemonic == "csrrwi" || uimm != 0;
+                (&mnemonic[4..5], ops.reg(0)?, ops.csr(1)?, uimm, writes)
+            }
+            "csrr" => ("s", ops.reg(0)?, ops.csr(1)?, 0, false),
+            "csrw" | "csrs" | "csrc" => {
+                let rs1 = ops.reg(1)?;
+                let writes = mnemonic == "csrw" || rs1 != 0;
+                (&mnemonic[3..], 0, ops.csr(0)?, self.x[rs1], writes)
+            }
+            "csrwi" | "csrsi" | "csrci" => {
+                let uimm = (ops.imm(1)? & 0x1F) as u64;
+                let writes = mnemonic == "csrwi" || uimm != 0;
+                (&mnemonic[3..4], 0, ops.csr(0)?, uimm, writes)
+            }
+            _ => return Err(unmodelled(&inst.text, "unknown CSR instruction")),
+        };
+        self.csr_access(inst, kind, rd, csr, source, writes)
+    }
+
+    /// frcsr / fscsr / frrm / fsrm / frflags / fsflags 伪指令
+    fn execute_fp_csr(
+        &mut self,
+        inst: &ProgramInstruction,
+        mnemonic: &str,
+        ops: &Operands,
+    ) -> Step {
+        let csr = match &mnemonic[3..] {
+            "csr" => 0x003,
+            "rm" | "rmi" => 0x002,
+            _ => 0x001,
+        };
+        if mnemonic.starts_with("fr") {
+            return self.csr_access(inst, "s", ops.reg(0)?, csr, 0, false);
+        }
+        let immediate = mnemonic.ends_with('i');
+        let (rd, source_index) = if ops.len() == 2 {
+            (ops.reg(0)?, 1)
+        } else {
+            (0, 0)
+        };
+        let source = if immediate {
+            (ops.imm(source_index)? & 0x1F) as u64
+        } else {
+            self.x[ops.reg(source_index)?]
+        };
+        self.csr_access(inst, "w", rd, csr, source, true)
+    }
+
+    fn execute_atomic(
+        &mut self,
+        inst: &ProgramInstruction,
+        mnemonic: &str,
+        ops: &Operands,
+    ) -> Step {
+        let (op, width) = mnemonic.rsplit_once('.').unwrap_or((mnemonic, "d"));
+        let size = if width == "w" { 4 } else { 8 };
+        let rd = ops.reg(0)?;
+        let narrow = |value: u64| if size == 4 { sext32(value) } else { value };
+
+        match op {
+            "lr" => {
+                let address = ops.address(1, &self.x)?;
+                let value = match self.load(address, size, CAUSE_LOAD_MISALIGNED) {
+                    Ok(value) => value,
+                    Err(trap) => return Ok(Some(trap)),
+                };
+                self.reservation = Some(address);
+                self.set_x(rd, narrow(value));
+            }
+            "sc" => {
+                let value = self.x[ops.reg(1)?];
+                let address = ops.address(2, &self.x)?;
+                if let Err(trap) = Self::check_store(&inst.text, address, size)? {
+                    return Ok(Some(trap));
+                }
+                let success = self.reservation.take() == Some(address);
+                if success {
+                    self.write_memory(address, size, value);
+                }
+                self.set_x(rd, (!success) as u64);
+            }
+            _ => {
+                let source = self.x[ops.reg(1)?];
+                let address = ops.address(2, &self.x)?;
+                // AMO 的地址异常都报告为 store/AMO 异常
+                if let Err(trap) = Self::check_store(&inst.text, address, size)? {
+                    return Ok(Some(trap));
+                }
+                let old = match self.load(address, size, CAUSE_STORE_MISALIGNED) {
+                    Ok(value) => narrow(value),
+                    Err(trap) => return Ok(Some(trap)),
+                };
+                let (a, b) = (old, narrow(source));
+                let new = match op {
+                    "amoswap" => b,
+                    "amoadd" => a.wrapping_add(b),
+                    "amoxor" => a ^ b,
+                    "amoand" => a & b,
+                    "amoor" => a | b,
+                    "amomin" => (a as i64).min(b as i64) as u64,
+                    "amomax" => (a as i64).max(b as i64) as u64,
+                    "amominu" if size == 4 => (a as u32).min(b as u32) as u64,
+                    "amomaxu" if size == 4 => (a as u32).max(b as u32) as u64,
+                    "amominu" => a.min(b),
+                    "amomaxu" => a.max(b),
+                    _ => return Err(unmodelled(&inst.text, "unknown AMO")),
+                };
+                self.write_memory(address, size, new);
+                self.set_x(rd, old);
+            }
+        }
+        Ok(None)
+    }
+
+    /// 指令的舍入模式，dyn 时 frm 为保留值则为非法指令
+    fn rounding_mode(&self, ops: &Operands) -> Option<RoundingMode> {
+        match ops.rm() {
+            Some(RoundingMode::Dyn) | None => RoundingMode::from_frm(self.frm),
+            Some(rm) => Some(rm),
+        }
+    }
+
+    fn execute_fp(
+        &mut self,
+        inst: &ProgramInstruction,
+        mnemonic: &str,
+        ops: &Operands,
+        illegal: Trap,
+    ) -> Step {
+        match mnemonic {
+            "frcsr" | "fscsr" | "frrm" | "fsrm" | "frflags" | "fsflags" | "fsrmi" | "fsflagsi" => {
+                return self.execute_fp_csr(inst, mnemonic, ops);
+            }
+            "flw" | "fld" => {
+                let size = if mnemonic == "flw" { 4 } else { 8 };
+                let address = ops.address(1, &self.x)?;
+                let raw = match self.load(address, size, CAUSE_LOAD_MISALIGNED) {
+                    Ok(raw) => raw,
+                    Err(trap) => return Ok(Some(trap)),
+                };
+                // flw 写回时 NaN-box
+                let value = if size == 4 {
+                    f32::from_bits(raw as u32).to_reg()
+                } else {
+                    raw
+                };
+                self.set_f(ops.freg(0)?, value);
+                return Ok(None);
+            }
+            "fsw" | "fsd" => {
+                let size = if mnemonic == "fsw" { 4 } else { 8 };
+                let value = self.f[ops.freg(0)?];
+                let address = ops.address(1, &self.x)?;
+                if let Err(trap) = Self::check_store(&inst.text, address, size)? {
+                    return Ok(Some(trap));
+                }
+                self.write_memory(address, size, value);
+                return Ok(None);
+            }
+            "fmv.x.w" | "fmv.x.s" => {
+                self.set_x(ops.reg(0)?, sext32(self.f[ops.freg(1)?]));
+                return Ok(None);
+            }
+            "fmv.w.x" | "fmv.s.x" => {
+                let value = f32::from_bits(self.x[ops.reg(1)?] as u32).to_reg();
+                self.set_f(ops.freg(0)?, value);
+                return Ok(None);
+            }
+            "fmv.x.d" => {
+                self.set_x(ops.reg(0)?, self.f[ops.freg(1)?]);
+                return Ok(None);
+            }
+            "fmv.d.x" => {
+                self.set_f(ops.freg(0)?, self.x[ops.reg(1)?]);
+                return Ok(None);
+            }
+            _ => {}
+        }
+
+        let Some((base, format)) = mnemonic.rsplit_once('.') else {
+            return Err(unmodelled(&inst.text, "unknown floating-point instruction"));
+        };
+        // 需要舍入的指令在 frm 非法时触发非法指令异常
+        let rounds = matches!(
+            base,
+            "fadd" | "fsub" | "fmul" | "fdiv" | "fsqrt" | "fmadd" | "fmsub" | "fnmsub" | "fnmadd"
+        ) || base.starts_with("fcvt");
+        let rm = match self.rounding_mode(ops) {
+            Some(rm) => rm,
+            None if rounds => return Ok(Some(illegal)),
+            None => RoundingMode::Rne,
+        };
+
+        if let Some(destination) = base.strip_prefix("fcvt.") {
+            return self.execute_fp_convert(inst, destination, format, rm, ops);
+        }
+        match format {
+            "s" => self.execute_fp_op::<f32>(inst, base, rm, ops),
+            "d" => self.execute_fp_op::<f64>(inst, base, rm, ops),
+            _ => Err(unmodelled(&inst.text, "unknown floating-point format")),
+        }
+    }
+
+    fn execute_fp_op<T: Fp>(
+        &mut self,
+        inst: &ProgramInstruction,
+        base: &str,
+        rm: RoundingMode,
+        ops: &Operands,
+    ) -> Step {
+        let value = |i: usize| -> Result<T> { Ok(T::from_reg(self.f[ops.freg(i)?])) };
+        let result = match base {
+            "fadd" => float::add(value(1)?, value(2)?, rm),
+            "fsub" => float::sub(value(1)?, value(2)?, rm),
+            "fmul" => float::mul(value(1)?, value(2)?, rm),
+            "fdiv" => float::div(value(1)?, value(2)?, rm),
+            "fsqrt" => float::sqrt(value(1)?, rm),
+            "fmadd" => float::fma(value(1)?, value(2)?, value(3)?, rm),
+            "fmsub" => float::fma(value(1)?, value(2)?, -value(3)?, rm),
+            "fnmsub" => float::fma(-value(1)?, value(2)?, value(3)?, rm),
+            "fnmadd" => float::fma(-value(1)?, value(2)?, -value(3)?, rm),
+            "fmin" => float::min_max(value(1)?, value(2)?, false),
+            "fmax" => float::min_max(value(1)?, value(2)?, true),
+            "fsgnj" | "fsgnjn" | "fsgnjx" | "fmv" | "fneg" | "fabs" => {
+                let a = value(1)?;
+                let b = if ops.len() > 2 { value(2)? } else { a };
+                let reg = match base {
+                    "fsgnj" | "fmv" => float::sign_inject(a, b, false, false),
+                    "fsgnjn" | "fneg" => float::sign_inject(a, b, true, false),
+                    _ => float::sign_inject(a, b, false, true),
+                };
+                FpResult { reg, flags: 0 }
+            }
+            "feq" | "flt" | "fle" => {
+                let (a, b) = (value(1)?, value(2)?);
+                let (result, flags) = match base {
+                    "feq" => float::compare(a, b, Ordering::Equal, false, false),
+                    "flt" => float::compare(a, b, Ordering::Less, false, true),
+                    _ => float::compare(a, b, Ordering::Less, true, true),
+                };
+                self.set_x(ops.reg(0)?, result);
+                self.accrue(flags);
+                return Ok(None);
+            }
+            "fclass" => {
+                self.set_x(ops.reg(0)?, float::classify(value(1)?));
+                return Ok(None);
+            }
+            _ => return Err(unmodelled(&inst.text, "unknown floating-point operation")),
+        };
+        self.write_fp(ops.freg(0)?, result);
+        Ok(None)
+    }
+
+    /// fcvt.<destination>.<source>
+    fn execute_fp_convert(
+        &mut self,
+        inst: &ProgramInstruction,
+        destination: &str,
+        source: &str,
+        rm: RoundingMode,
+        ops: &Operands,
+    ) -> Step {
+        let int_format = |format: &str| match format {
+            "w" => Some((true, 32)),
+            "wu" => Some((false, 32)),
+            "l" => Some((true, 64)),
+            "lu" => Some((false, 64)),
+            _ => None,
+        };
+
+        // 浮点 → 整数
+        if let Some((signed, bits)) = int_format(destination) {
+            let raw = self.f[ops.freg(1)?];
+            let value = match source {
+                "s" => f32::from_reg(raw).to_f64(),
+                "d" => f64::from_reg(raw),
+                _ => return Err(unmodelled(&inst.text, "unknown conversion")),
+            };
+            let (result, flags) = float::float_to_int(value, rm, signed, bits);
+            self.set_x(ops.reg(0)?, result);
+            self.accrue(flags);
+            return Ok(None);
+        }
+
+        // 整数 → 浮点
+        if let Some((signed, bits)) = int_format(source) {
+            let raw = self.x[ops.reg(1)?];
+            let value = if bits == 32 {
+                if signed {
+                    raw as i32 as i64 as u64
+                } else {
+                    raw as u32 as u64
+                }
+            } else {
+                raw
+            };
+            let negative = signed && (value as i64) < 0;
+            let magnitude = if negative {
+                (value as i64).unsigned_abs()
+            } else {
+                value
+            };
+            let result = match destination {
+                "s" => float::int_to_float::<f32>(negative, magnitude, rm),
+                "d" => float::int_to_float::<f64>(negative, magnitude, rm),
+                _ => return Err(unmodelled(&inst.text, "unknown conversion")),
+            };
+            self.write_fp(ops.freg(0)?, result);
+            return Ok(None);
+        }
+
+        let raw = self.f[ops.freg(1)?];
+        let result = match (destination, source) {
+            ("s", "d") => float::f64_to_f32(f64::from_reg(raw), rm),
+            ("d", "s") => float::f32_to_f64(f32::from_reg(raw)),
+            _ => return Err(unmodelled(&inst.text, "unknown conversion")),
+        };
+        self.write_fp(ops.freg(0)?, result);
+        Ok(None)
+    }
+}
+
+/// 操作数访问，类型不符时报告为未建模
+struct Operands<'a> {
+    text: &'a str,
+    operands: &'a [Operand],
+}
+
+impl Operands<'_> {
+    fn len(&self) -> usize {
+        self.operands.len()
+    }
+
+    fn mismatch(&self, index: usize, expected: &str) -> RiscvFuzzError {
+        unmodelled(self.text, &format!("operand {} is not {}", index, expected))
+    }
+
+    fn reg(&self, index: usize) -> Result<usize> {
+        match self.operands.get(index) {
+            Some(Operand::Reg(reg)) => Ok(*reg),
+            _ => Err(self.mismatch(index, "an integer register")),
+        }
+    }
+
+    fn freg(&self, index: usize) -> Result<usize> {
+        match self.operands.get(index) {
+            Some(Operand::FReg(reg)) => Ok(*reg),
+            _ => Err(self.mismatch(index, "a floating-point register")),
+        }
+    }
+
+    fn imm(&self, index: usize) -> Result<i64> {
+        match self.operands.get(index) {
+            Some(Operand::Imm(value)) => Ok(*value),
+            _ => Err(self.mismatch(index, "an immediate")),
+        }
+    }
+
+    fn csr(&self, index: usize) -> Result<u16> {
+        match self.operands.get(index) {
+            Some(Operand::Csr(csr)) => Ok(*csr),
+            Some(Operand::Imm(value)) if (0..0x1000).contains(value) => Ok(*value as u16),
+            _ => Err(self.mismatch(index, "a CSR")),
+        }
+    }
+
+    fn rm(&self) -> Option<RoundingMode> {
+        match self.operands.last() {
+            Some(Operand::Rm(rm)) => Some(*rm),
+            _ => None,
+        }
+    }
+
+    /// `offset(base)` 操作数的有效地址
+    fn address(&self, index: usize, x: &[u64; 32]) -> Result<u64> {
+        match self.operands.get(index) {
+            Some(Operand::Mem { offset, base }) => Ok(x[*base].wrapping_add(*offset as u64)),
+            _ => Err(self.mismatch(index, "a memory operand")),
+        }
+    }
+}
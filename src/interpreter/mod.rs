@@ -0,0 +1,427 @@
+//! 内置 RV64 参考解释器
+//!
+//! A third oracle that needs no external process: it executes the user code
+//! of a test (I, M, A, F, D, C, Zicsr, Zifencei, Zba, Zbb, Zbs) on a model of
+//! the hart as left by the template's `RESET_MACHINE_STATE`, applies the
+//! template's exception handler (dump, skip the instruction, `mret`) to every
+//! trap, and reports the result as a [`StandardExecutionOutput`] that can be
+//! passed to `compare_outputs` like any emulator output. `run`, `random` and
+//! `campaign` add it as a third vote next to Spike and Rocket whenever a
+//! Standard test diverges (`[diff] reference_vote`).
+//!
+//! The model is straight-line only. Control flow, counter / ID CSRs, WARL CSR
+//! writes and stores to the HTIF region are rejected with an error instead of
+//! guessing, so a reference output is either exact or absent.
+//!
+//! Programs are read from the disassembly of a built ELF ([`ReferenceProgram::from_dump`]):
+//! only the assembler knows which instructions were compressed, and the
+//! encoding is needed for the `mtval` of illegal-instruction traps.
+
+pub mod decode;
+pub mod float;
+mod machine;
+
+use log::{debug, info};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+use crate::elf::tracer::{InstructionTrace, parse_elf_instruction_line};
+use crate::emulators::EmulatorType;
+use crate::error::{Result, RiscvFuzzError};
+use crate::output_parser::standard::{ConversionStats, StandardExecutionOutput};
+use crate::output_parser::{CoreCSRs, ExceptionCSRs, ExceptionDump, MarkerType, RegistersDump};
+use machine::Machine;
+
+/// RAM 起始地址 (linker.ld)
+pub const REFERENCE_RAM_BASE: u64 = 0x8000_0000;
+/// 模型中可访问的 RAM 大小
+pub const REFERENCE_RAM_SIZE: u64 = 0x1000_0000;
+/// RAM 开头的 tohost/fromhost 区域
+pub const REFERENCE_HTIF_SIZE: u64 = 0x1000;
+
+/// 模型不建模、总是报告 0 的计数器和 ID CSR，不参与投票
+pub const UNMODELLED_CSRS: &[&str] = &["mcycle", "minstret", "mvendorid", "marchid", "mimpid"];
+
+/// 每次异常 dump 的字节数 (8 字节标记 + 9 个 CSR)
+const EXCEPTION_DUMP_SIZE: usize = 80;
+
+/// 用户代码中的一条指令
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProgramInstruction {
+    pub pc: u64,
+    /// 指令长度 (2 或 4 字节)
+    pub len: u64,
+    /// 汇编文本
+    pub text: String,
+    /// 指令编码，非法指令异常的 mtval
+    pub machine_code: u32,
+    pub inst_trace: Option<InstructionTrace>,
+}
+
+/// The user code of a test, in program order
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ReferenceProgram {
+    pub instructions: Vec<ProgramInstruction>,
+    /// 异常处理入口 (mtvec)，未知时读取 mtvec 不被建模
+    pub trap_vector: Option<u64>,
+}
+
+impl ReferenceProgram {
+    /// Reads the `_user_code` section and the `exception_handler` address
+    /// from an `objdump -S` disassembly of a built test
+    pub fn from_dump<P: AsRef<Path>>(dump_path: P) -> Result<Self> {
+        let path = dump_path.as_ref();
+        let content = fs::read_to_string(path).map_err(|e| {
+            RiscvFuzzError::file(format!(
+                "Failed to read disassembly {}: {}",
+                path.display(),
+                e
+            ))
+        })?;
+
+        let lines: Vec<&str> = content.lines().collect();
+        let mut program = Self::default();
+        let mut symbol = String::new();
+        for (i, line) in lines.iter().enumerate() {
+            // 符号行: 0000000080001000 <exception_handler>:
+            if let Some((address, name)) = parse_symbol_line(line) {
+                if name == "exception_handler" {
+                    program.trap_vector = Some(address);
+                }
+                symbol = name;
+                continue;
+            }
+            if symbol != "_user_code" {
+                continue;
+            }
+            let Some((pc, disassembly, machine_code)) = parse_elf_instruction_line(line) else {
+                continue;
+            };
+            let encoding = u32::from_str_radix(&machine_code, 16).map_err(|_| {
+                RiscvFuzzError::file(format!(
+                    "Invalid machine code `{}` at 0x{:X} in {}",
+                    machine_code,
+                    pc,
+                    path.display()
+                ))
+            })?;
+            let original_instruction = lines[..i]
+                .last()
+                .map(|prev| prev.trim())
+                .filter(|prev| !prev.is_empty() && !prev.contains(':'))
+                .unwrap_or(&disassembly)
+                .to_string();
+            program.instructions.push(ProgramInstruction {
+                pc,
+                len: (machine_code.len() / 2) as u64,
+                text: disassembly.clone(),
+                machine_code: encoding,
+                inst_trace: Some(InstructionTrace {
+                    pc,
+                    disassembly,
+                    machine_code,
+                    original_instruction,
+                }),
+            });
+        }
+
+        debug!(
+            "Parsed {} user instructions from {}",
+            program.instructions.len(),
+            path.display()
+        );
+        Ok(program)
+    }
+}
+
+fn parse_symbol_line(line: &str) -> Option<(u64, String)> {
+    let (address, rest) = line.trim().split_once(' ')?;
+    let name = rest.strip_prefix('<')?.strip_suffix(">:")?;
+    Some((u64::from_str_radix(address, 16).ok()?, name.to_string()))
+}
+
+/// Extensions enabled by a march string such as `rv64imafdc_zicsr_zba`.
+///
+/// Zicsr and Zifencei are always enabled, as in every supported emulator.
+pub fn parse_march(march: &str) -> HashSet<String> {
+    let mut extensions: HashSet<String> = ["i", "zicsr", "zifencei"]
+        .into_iter()
+        .map(String::from)
+        .collect();
+    let march = march.to_lowercase();
+    let mut parts = march.split('_');
+    let base = parts.next().unwrap_or("");
+    let letters = base
+        .strip_prefix("rv64")
+        .or_else(|| base.strip_prefix("rv32"))
+        .unwrap_or(base);
+    for letter in letters.chars() {
+        let expanded: &[&str] = match letter {
+            'g' => &["i", "m", "f", "d", "zaamo", "zalrsc"],
+            'a' => &["zaamo", "zalrsc"],
+            'b' => &["zba", "zbb", "zbs"],
+            _ => &[],
+        };
+        if expanded.is_empty() {
+            extensions.insert(letter.to_string());
+        } else {
+            extensions.extend(expanded.iter().map(|ext| ext.to_string()));
+        }
+    }
+    for ext in parts.filter(|ext| !ext.is_empty()) {
+        extensions.insert(ext.to_string());
+    }
+    if extensions.contains("d") && extensions.contains("c") {
+        extensions.insert("zcd".to_string());
+    }
+    extensions
+}
+
+/// Runs [`ReferenceProgram`]s for one march string
+pub struct ReferenceInterpreter {
+    extensions: HashSet<String>,
+}
+
+impl ReferenceInterpreter {
+    pub fn new(march: &str) -> Self {
+        Self {
+            extensions: parse_march(march),
+        }
+    }
+
+    pub fn has_extension(&self, name: &str) -> bool {
+        self.extensions.contains(name)
+    }
+
+    /// 执行程序，返回与模拟器输出同构的结果
+    pub fn run(&self, program: &ReferenceProgram) -> Result<StandardExecutionOutput> {
+        let mut machine = Machine::new(&self.extensions, program.trap_vector);
+        let mut exceptions = Vec::new();
+
+        for inst in &program.instructions {
+            let Some(trap) = machine.execute(inst)? else {
+                continue;
+            };
+            debug!(
+                "Reference trap at 0x{:016X} `{}`: mcause {} mtval 0x{:X}",
+                inst.pc, inst.text, trap.cause, trap.tval
+            );
+            exceptions.push(ExceptionDump {
+                csrs: ExceptionCSRs {
+                    mstatus: machine.trap_mstatus(),
+                    mcause: trap.cause,
+                    mepc: inst.pc,
+                    mtval: trap.tval,
+                    mie: 0,
+                    mip: 0,
+                    mtvec: program.trap_vector.unwrap_or(0),
+                    mscratch: machine.x[31],
+                    mhartid: 0,
+                },
+                position: exceptions.len() * EXCEPTION_DUMP_SIZE,
+                inst_trace: inst.inst_trace.clone(),
+            });
+            machine.handle_trap(inst);
+        }
+
+        // DUMP_ALL_REGS 同样先把 t6 写入 mscratch
+        let has_float = self.has_extension("f");
+        let register_dump = RegistersDump {
+            dump_type: if has_float {
+                MarkerType::RegistersIntAndFloat
+            } else {
+                MarkerType::RegistersIntOnly
+            },
+            int_registers: machine.x,
+            core_csrs: CoreCSRs {
+                mstatus: machine.mstatus(),
+                misa: machine.misa(),
+                medeleg: 0,
+                mideleg: 0,
+                mie: 0,
+                mtvec: program.trap_vector.unwrap_or(0),
+                mcounteren: 0,
+                mscratch: machine.x[31],
+                mepc: machine.mepc,
+                mcause: machine.mcause,
+                mtval: machine.mtval,
+                mip: 0,
+                mcycle: 0,
+                minstret: 0,
+                mvendorid: 0,
+                marchid: 0,
+                mimpid: 0,
+                mhartid: 0,
+            },
+            float_registers: has_float.then_some(machine.f),
+            float_csr: has_float.then(|| machine.fcsr()),
+            position: exceptions.len() * EXCEPTION_DUMP_SIZE,
+        };
+
+        info!(
+            "🧮 Reference interpreter executed {} instructions ({} exceptions)",
+            program.instructions.len(),
+            exceptions.len()
+        );
+        Ok(StandardExecutionOutput {
+            emulator_type: EmulatorType::Reference,
+            conversion_stats: ConversionStats {
+                original_exception_count: exceptions.len(),
+                original_register_count: 1,
+                conversion_successful: true,
+                warnings: machine.warnings,
+            },
+            exceptions,
+            register_dump: Some(register_dump),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BASE_PC: u64 = 0x8000_1000;
+
+    /// 按 objdump 的写法构造程序: (反汇编, 编码, 长度)
+    fn program(instructions: &[(&str, u32, u64)]) -> ReferenceProgram {
+        let mut pc = BASE_PC;
+        let instructions = instructions
+            .iter()
+            .map(|&(text, machine_code, len)| {
+                let inst = ProgramInstruction {
+                    pc,
+                    len,
+                    text: text.to_string(),
+                    machine_code,
+                    inst_trace: None,
+                };
+                pc += len;
+                inst
+            })
+            .collect();
+        ReferenceProgram {
+            instructions,
+            trap_vector: None,
+        }
+    }
+
+    #[test]
+    fn test_integer_semantics() {
+        let output = ReferenceInterpreter::new("rv64im")
+            .run(&program(&[
+                ("addi t0,zero,-7", 0xff90_0293, 4),
+                ("addi t1,t0,100", 0x0642_8313, 4),
+                ("mul t2,t0,t1", 0x0262_83b3, 4),
+                ("div a0,t1,zero", 0x0203_4533, 4),
+                ("remu a1,t1,zero", 0x0203_75b3, 4),
+            ]))
+            .unwrap();
+        let x = output.register_dump.unwrap().int_registers;
+        assert_eq!(x[5] as i64, -7);
+        assert_eq!(x[6], 93);
+        assert_eq!(x[7] as i64, -651);
+        // 除以零: 商全 1，余数为被除数
+        assert_eq!(x[10], u64::MAX);
+        assert_eq!(x[11], 93);
+        assert!(output.exceptions.is_empty());
+    }
+
+    #[test]
+    fn test_compressed_instruction_pcs() {
+        let output = ReferenceInterpreter::new("rv64imc")
+            .run(&program(&[
+                ("addi t0,t0,1", 0x0285, 2),
+                ("auipc t1,0x0", 0x0000_0317, 4),
+                ("ebreak", 0x9002, 2),
+            ]))
+            .unwrap();
+        let dump = output.register_dump.unwrap();
+        assert_eq!(dump.int_registers[6], BASE_PC + 2);
+
+        let ebreak = &output.exceptions[0].csrs;
+        assert_eq!(ebreak.mcause, 3);
+        assert_eq!(ebreak.mepc, BASE_PC + 6);
+        assert_eq!(ebreak.mtval, BASE_PC + 6);
+        // 异常处理跳过 2 字节的压缩指令
+        assert_eq!(dump.core_csrs.mepc, BASE_PC + 8);
+    }
+
+    #[test]
+    fn test_illegal_instruction_reports_encoding() {
+        let output = ReferenceInterpreter::new("rv64i")
+            .run(&program(&[
+                ("addi t0,zero,3", 0x0030_0293, 4),
+                ("mul t2,t0,t0", 0x0252_83b3, 4),
+            ]))
+            .unwrap();
+        assert_eq!(output.exceptions.len(), 1);
+        let illegal = &output.exceptions[0].csrs;
+        assert_eq!(illegal.mcause, 2);
+        assert_eq!(illegal.mepc, BASE_PC + 4);
+        assert_eq!(illegal.mtval, 0x0252_83b3);
+
+        let dump = output.register_dump.unwrap();
+        assert_eq!(dump.int_registers[7], 0);
+        assert_eq!(dump.core_csrs.mepc, BASE_PC + 8);
+    }
+
+    #[test]
+    fn test_from_dump_reads_user_code_and_trap_vector() {
+        let dump = "\
+0000000080000100 <exception_handler>:
+    80000100:\t34102573          \tcsrr\ta0,mepc
+
+0000000080001000 <_user_code>:
+    addi t0, zero, 3
+    80001000:\t00300293          \tli\tt0,3
+    80001004:\t0285                \taddi\tt0,t0,1
+
+0000000080001006 <_end_user_code>:
+    80001006:\t00000073          \tecall
+";
+        let path = std::env::temp_dir().join(format!("reference_test_{}.dump", std::process::id()));
+        fs::write(&path, dump).unwrap();
+        let program = ReferenceProgram::from_dump(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(program.trap_vector, Some(0x8000_0100));
+        assert_eq!(program.instructions.len(), 2);
+        let li = &program.instructions[0];
+        assert_eq!(
+            (li.pc, li.len, li.machine_code),
+            (0x8000_1000, 4, 0x0030_0293)
+        );
+        assert_eq!(li.text, "li t0,3");
+        assert_eq!(
+            li.inst_trace.as_ref().unwrap().original_instruction,
+            "addi t0, zero, 3"
+        );
+        let c_addi = &program.instructions[1];
+        assert_eq!(
+            (c_addi.pc, c_addi.len, c_addi.machine_code),
+            (0x8000_1004, 2, 0x0285)
+        );
+    }
+
+    #[test]
+    fn test_parse_march_expands_groups() {
+        let extensions = parse_march("rv64gc_zba_zbb");
+        for ext in [
+            "i", "m", "f", "d", "c", "zaamo", "zalrsc", "zicsr", "zba", "zbb", "zcd",
+        ] {
+            assert!(extensions.contains(ext), "missing {}", ext);
+        }
+        assert!(!extensions.contains("zbs"));
+        assert!(!parse_march("rv64imac").contains("zcd"));
+    }
+
+    #[test]
+    fn test_unmodelled_control_flow_is_rejected() {
+        let result =
+            ReferenceInterpreter::new("rv64i").run(&program(&[("j 80001008", 0x0080_006f, 4)]));
+        assert!(result.is_err());
+    }
+}
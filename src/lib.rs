@@ -3,11 +3,18 @@
 //! This library provides functionality to generate RISC-V assembly code
 //! for testing and benchmarking purposes.
 
+pub mod campaign;
+pub mod config;
 pub mod consts;
 pub mod elf;
 pub mod emulators;
 pub mod error;
+pub mod interpreter;
+pub mod minimize;
 pub mod output_diff;
 pub mod output_parser;
+pub mod probe;
 pub mod random_asm;
+pub mod regress;
+pub mod replay;
 pub mod utils;
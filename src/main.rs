@@ -2,35 +2,69 @@ use clap::{Parser, Subcommand};
 use log::info;
 use log::warn;
 use rayon::prelude::*;
+use riscv_fuzz_test::campaign::{
+    CAMPAIGN_SUMMARY_FILE, Campaign, CampaignBudget, TestVerdict, install_interrupt_handler,
+    parse_duration,
+};
+use riscv_fuzz_test::config::{self, FuzzConfig, LimitArgs};
+use riscv_fuzz_test::consts::extensions::ALL_RV64_EXTENSIONS;
 use riscv_fuzz_test::consts::linker_script::LINKER_SCRIPT;
-use riscv_fuzz_test::consts::rocket::RV64_ROCKET_SUPPORTED_EXTENSIONS;
+use riscv_fuzz_test::elf::build::ElfBuildResult;
 use riscv_fuzz_test::elf::build::build_elf;
-use riscv_fuzz_test::emulators::{EmulatorType, OutputFormat, run_emulator, run_single_emulator};
+use riscv_fuzz_test::emulators::outcome::{RunClassification, RunOutcome};
+use riscv_fuzz_test::emulators::registry::EmulatorRegistry;
+use riscv_fuzz_test::emulators::{
+    EmulatorType, OutputFormat, RunLimits, execute_emulator, retire_trace_log_path,
+    run_and_parse_all_simulators, run_emulator, run_single_emulator,
+};
 use riscv_fuzz_test::error::{Result, RiscvFuzzError};
-use riscv_fuzz_test::output_diff::analysis::remove_rocket_illegal_inst::get_rocket_illegal_instruction_originals;
-use riscv_fuzz_test::output_diff::analysis::remove_rocket_illegal_inst::has_rocket_only_illegal_instructions;
+use riscv_fuzz_test::interpreter::{ReferenceInterpreter, ReferenceProgram, UNMODELLED_CSRS};
+use riscv_fuzz_test::minimize::{MINIMIZE_DIR, MinimizeReport, minimize, replace_user_code};
+use riscv_fuzz_test::output_diff::analysis::bucket::DivergenceSignature;
+use riscv_fuzz_test::output_diff::analysis::retry::{
+    RETRY_DIR, RETRY_REPORT_FILE, RetryAttempt, RetryChain, RetryDiff, RetryPolicy,
+    run_retry_rounds,
+};
 use riscv_fuzz_test::output_diff::analysis::shortten_asm_for_regs::extract_minimal_instructions_for_regs;
+use riscv_fuzz_test::output_diff::analysis::triage::KnownBugDatabase;
 use riscv_fuzz_test::output_diff::diff::RegistersDumpDiff;
 use riscv_fuzz_test::output_diff::diff::compare_outputs;
+use riscv_fuzz_test::output_diff::diff::ignore::DiffIgnoreRules;
+use riscv_fuzz_test::output_diff::diff::multi_diff::{
+    MultiEmulatorDiff, Votable, compare_outputs_n_way, compare_outputs_n_way_with,
+};
+use riscv_fuzz_test::output_diff::diff::run_diff::RunDivergence;
+use riscv_fuzz_test::output_diff::diff::run_diff::load_run_outcome_diff;
 use riscv_fuzz_test::output_diff::diff::standard_diff::StandardExecutionOutputDiff;
-// Added
-use riscv_fuzz_test::output_diff::diff_diff::compare_output_diffs; // Added
-use riscv_fuzz_test::output_diff::utils::remove_instructions_assembly;
-use riscv_fuzz_test::output_parser::common::CommonExecutionOutput; // Added
-use riscv_fuzz_test::output_parser::debug::DebugExecutionOutput; // Added
-use riscv_fuzz_test::output_parser::parse_output_from_file; // Added
+use riscv_fuzz_test::output_diff::diff_diff::compare_output_diffs;
+use riscv_fuzz_test::output_parser::OutputParser;
+use riscv_fuzz_test::output_parser::common::CommonExecutionOutput;
+use riscv_fuzz_test::output_parser::debug::DebugExecutionOutput;
+use riscv_fuzz_test::output_parser::load_or_parse_output;
+use riscv_fuzz_test::output_parser::parse_output_from_file;
+use riscv_fuzz_test::output_parser::retire_trace::RetireTrace;
 use riscv_fuzz_test::output_parser::standard::StandardExecutionOutput;
+use riscv_fuzz_test::output_parser::util::get_current_timestamp;
+use riscv_fuzz_test::probe::{
+    DEFAULT_CAPABILITY_PROFILE, ExtensionSupport, capability_report_path, probe_extensions,
+};
 use riscv_fuzz_test::random_asm::asm_maker::{
     generate_instructions, generate_standard_asm_from_insts,
 };
-use riscv_fuzz_test::random_asm::inst_generator::GenerationOrder;
-use riscv_fuzz_test::utils::{
-    build_rv64_march, extract_user_code_instructions, resolve_output_dir,
+use riscv_fuzz_test::random_asm::denylist::OpcodeDenylist;
+use riscv_fuzz_test::random_asm::seed::{SEED_FILE, SeedRecord, derive_test_seed, seeded_rng};
+use riscv_fuzz_test::regress::{REGRESSION_SUMMARY_FILE, run_regression};
+use riscv_fuzz_test::replay::{
+    EmulatorPaths, REPLAY_DIR, REPLAY_REPORT_FILE, ReplayReport, ReplayStatus, RunRecord,
+    load_seed_record, stored_emulators, stored_signature,
 };
+use riscv_fuzz_test::utils::{extract_user_code_instructions, resolve_output_dir};
+use riscv_instruction::separated_instructions::RV64Extensions;
 use std::collections::HashMap;
 use std::fs::{self, create_dir_all};
-use std::path::PathBuf;
-use std::sync::atomic::{AtomicUsize, Ordering}; // Added import for warn! macro
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
 
 #[derive(Parser)]
 #[command(name = "riscv-fuzz-test")]
@@ -38,15 +72,20 @@ use std::sync::atomic::{AtomicUsize, Ordering}; // Added import for warn! macro
 struct Cli {
     #[command(subcommand)]
     command: Commands,
+    /// Configuration file (default: riscv_fuzz.toml in the current directory, if present)
+    #[arg(long, global = true)]
+    config: Option<PathBuf>,
+    #[command(flatten)]
+    limits: LimitArgs,
 }
 
 #[derive(Subcommand)]
 enum Commands {
     /// Generate random assembly code and run comparison in parallel
     Random {
-        /// Number of instructions to generate per extension
-        #[arg(short, long, default_value = "50")]
-        inst_num: usize,
+        /// Number of instructions to generate per extension (default: 50)
+        #[arg(short, long)]
+        inst_num: Option<usize>,
         /// Number of parallel test instances (default: number of CPU cores)
         #[arg(short = 'p', long)]
         parallel: Option<usize>,
@@ -56,6 +95,40 @@ enum Commands {
         /// Workspace directory for random output directories (default mode)
         #[arg(long, default_value = "workspace", conflicts_with = "output_dir")]
         workspace_dir: Option<PathBuf>,
+        /// Campaign seed; test N uses a seed derived from it and N (default: random)
+        #[arg(long)]
+        seed: Option<u64>,
+        /// Regenerate a single test from the seed.json in this test directory
+        /// (seed, instruction count, extensions and opcode denylist)
+        #[arg(long, value_name = "TEST_DIR", conflicts_with_all = ["seed", "parallel", "inst_num"])]
+        test_seed: Option<PathBuf>,
+    },
+    /// Keep running random tests until a budget is exhausted or Ctrl-C
+    Campaign {
+        /// Number of instructions to generate per extension (default: 50)
+        #[arg(short, long)]
+        inst_num: Option<usize>,
+        /// Number of parallel workers (default: number of CPU cores)
+        #[arg(short = 'p', long)]
+        parallel: Option<usize>,
+        /// Fixed output directory
+        #[arg(long, conflicts_with = "workspace_dir")]
+        output_dir: Option<PathBuf>,
+        /// Workspace directory for random output directories (default mode)
+        #[arg(long, default_value = "workspace", conflicts_with = "output_dir")]
+        workspace_dir: Option<PathBuf>,
+        /// Campaign seed; test N uses a seed derived from it and N (default: random)
+        #[arg(long)]
+        seed: Option<u64>,
+        /// Stop after this long, e.g. 90s, 30m or 8h
+        #[arg(long, value_parser = parse_duration)]
+        duration: Option<Duration>,
+        /// Stop after this many tests
+        #[arg(long)]
+        max_tests: Option<usize>,
+        /// Stop after this many divergent tests
+        #[arg(long)]
+        max_divergences: Option<usize>,
     },
     /// Run comparison with existing assembly file
     Run {
@@ -65,12 +138,12 @@ enum Commands {
         /// Output build directory  
         #[arg(short, long, default_value = "build")]
         build_dir: PathBuf,
-        /// Output format for parsing and diffing
-        #[arg(short = 'f', long, value_enum, default_value = "standard")] // Added
-        format: OutputFormat, // Added
-        /// Enable automatic retry when Rocket-only illegal instructions are found
-        #[arg(long, default_value = "true")]
-        auto_retry: bool,
+        /// Output format for parsing and diffing (default: standard)
+        #[arg(short = 'f', long, value_enum)]
+        format: Option<OutputFormat>,
+        /// Remove offending instructions per [diff.retry] and retry (default: true)
+        #[arg(long, num_args = 0..=1, default_missing_value = "true")]
+        auto_retry: Option<bool>,
     },
     /// Run single emulator with specified output format
     Emulate {
@@ -86,12 +159,114 @@ enum Commands {
         /// Output build directory
         #[arg(short, long, default_value = "emulate_build")]
         build_dir: PathBuf,
+        /// Also record a per-instruction retire trace (Spike commit log, Rocket --verbose)
+        #[arg(long, alias = "log-commits")]
+        retire_trace: bool,
+    },
+    /// Probe which extensions each emulator supports and write a capability profile
+    Probe {
+        /// Emulators to probe
+        #[arg(short = 'e', long = "emulator", value_enum, num_args = 1.., default_values_t = [EmulatorType::Spike, EmulatorType::Rocket])]
+        emulators: Vec<EmulatorType>,
+        /// Number of instructions to generate per extension
+        #[arg(short, long, default_value = "20")]
+        inst_num: usize,
+        /// Output build directory
+        #[arg(short, long, default_value = "probe_build")]
+        build_dir: PathBuf,
+        /// Capability profile to write (loaded by `random` when present)
+        #[arg(short, long, default_value = DEFAULT_CAPABILITY_PROFILE)]
+        output: PathBuf,
+    },
+    /// Run N emulators on an assembly file and blame the odd one out by majority vote
+    Compare {
+        /// Path to assembly file (.s or .S)
+        #[arg(short, long)]
+        assembly_file: PathBuf,
+        /// Emulators to compare (at least two; three or more for a majority vote),
+        /// default: every backend enabled in `[emulators]`
+        #[arg(short = 'e', long = "emulator", value_enum, num_args = 1..)]
+        emulators: Vec<EmulatorType>,
+        /// Output format for parsing and diffing
+        #[arg(short = 'f', long, value_enum, default_value = "standard")]
+        format: OutputFormat,
+        /// Output build directory
+        #[arg(short, long, default_value = "compare_build")]
+        build_dir: PathBuf,
+    },
+    /// Diff two saved outputs (raw emulator logs or parsed .json) without re-running emulators
+    Diff {
+        /// First output: raw emulator log (.bin) or parsed JSON saved next to it
+        output1: PathBuf,
+        /// Second output
+        output2: PathBuf,
+        /// Disassembly (.dump) of the program, required for raw logs
+        #[arg(short, long)]
+        dump: Option<PathBuf>,
+        /// Emulators that produced the two raw logs
+        #[arg(short = 'e', long = "emulator", value_enum, num_args = 2, default_values_t = [EmulatorType::Spike, EmulatorType::Rocket])]
+        emulators: Vec<EmulatorType>,
+        /// Output format for parsing and diffing (default: standard)
+        #[arg(short = 'f', long, value_enum)]
+        format: Option<OutputFormat>,
+        /// Output directory for the diff
+        #[arg(short, long, default_value = "diff_build")]
+        build_dir: PathBuf,
+    },
+    /// Run a directory of .S files and check each emulator against the stored expected outputs
+    Regress {
+        /// Directory of .S files, each with an optional <name>.expected.json
+        suite_dir: PathBuf,
+        /// Emulators to check
+        #[arg(short = 'e', long = "emulator", value_enum, num_args = 1.., default_values_t = [EmulatorType::Spike, EmulatorType::Rocket])]
+        emulators: Vec<EmulatorType>,
+        /// Output build directory
+        #[arg(short, long, default_value = "regress_build")]
+        build_dir: PathBuf,
+        /// Number of cases run in parallel (default: number of CPU cores)
+        #[arg(short = 'p', long)]
+        parallel: Option<usize>,
+        /// Write missing expected outputs from the first emulator
+        #[arg(long)]
+        bless: bool,
+    },
+    /// Rebuild and re-run a saved test directory and check whether its divergence still reproduces
+    Replay {
+        /// Test directory containing run_config.json and/or seed.json
+        test_dir: PathBuf,
+    },
+    /// Shrink a divergent test with delta debugging, re-running both emulators per candidate
+    Minimize {
+        /// Test directory containing run_config.json, or an assembly file (.s or .S)
+        input: PathBuf,
+        /// Work directory (default: <test dir>/ddmin, or <file stem>_ddmin next to the file)
+        #[arg(short, long)]
+        build_dir: Option<PathBuf>,
+        /// Number of candidates run in parallel (default: number of CPU cores)
+        #[arg(short = 'p', long)]
+        parallel: Option<usize>,
+    },
+    /// Diff an emulator against the built-in reference interpreter
+    Reference {
+        /// Path to assembly file (.s or .S)
+        #[arg(short, long)]
+        assembly_file: PathBuf,
+        /// Emulator to check against the reference
+        #[arg(short = 'e', long, value_enum, default_value = "spike")]
+        emulator: EmulatorType,
+        /// Output build directory
+        #[arg(short, long, default_value = "reference_build")]
+        build_dir: PathBuf,
     },
 }
 
 fn main() -> Result<()> {
     let cli = Cli::parse();
-    let march_string = setup_environment()?;
+    let march_string = setup_environment(cli.config.as_deref())?;
+    let fuzz_config = config::get();
+
+    // 命令行参数优先于配置文件
+    let limits = cli.limits.apply(fuzz_config.emulators.run_limits());
 
     match cli.command {
         Commands::Random {
@@ -99,24 +274,197 @@ fn main() -> Result<()> {
             parallel,
             output_dir,
             workspace_dir,
+            seed,
+            test_seed,
         } => {
+            let resolved_output_dir = resolve_output_dir(output_dir, workspace_dir)?;
+            let _ = create_dir_all(&resolved_output_dir);
+
+            if let Some(seed_dir) = test_seed {
+                let record = load_seed_record(&seed_dir)?.ok_or_else(|| {
+                    RiscvFuzzError::file(format!("No {} found in {:?}", SEED_FILE, seed_dir))
+                })?;
+                let march_string = fuzz_config.isa.generation_march(&record.extensions)?;
+                info!(
+                    "🌱 Regenerating random test #{} from seed {} with {} instructions per extension",
+                    record.test_id, record.seed, record.inst_num
+                );
+                let test_dir = resolved_output_dir.join(format!("test_{:06}", record.test_id));
+                let _ = create_dir_all(&test_dir);
+                run_single_random_test(&test_dir, &record, &march_string, &limits)?;
+                return Ok(());
+            }
+
+            let inst_num = inst_num.unwrap_or(fuzz_config.generation.inst_num);
+            let extensions = fuzz_config.generation.extensions()?;
+            let march_string = fuzz_config.isa.generation_march(&extensions)?;
+
+            let campaign_seed = seed
+                .or(fuzz_config.generation.seed)
+                .unwrap_or_else(rand::random);
             let num_threads = parallel.unwrap_or_else(|| num_cpus::get());
             info!(
-                "🎲 Running in random mode with {} instructions per extension, {} parallel instances",
-                inst_num, num_threads
+                "🎲 Running in random mode with {} instructions per extension, {} parallel instances, seed {}",
+                inst_num, num_threads, campaign_seed
+            );
+
+            run_parallel_random_tests(
+                &resolved_output_dir,
+                inst_num,
+                &extensions,
+                num_threads,
+                campaign_seed,
+                &march_string,
+                &limits,
+            )?;
+        }
+        Commands::Campaign {
+            inst_num,
+            parallel,
+            output_dir,
+            workspace_dir,
+            seed,
+            duration,
+            max_tests,
+            max_divergences,
+        } => {
+            let inst_num = inst_num.unwrap_or(fuzz_config.generation.inst_num);
+            let extensions = fuzz_config.generation.extensions()?;
+            let march_string = fuzz_config.isa.generation_march(&extensions)?;
+            let num_threads = parallel.unwrap_or_else(|| num_cpus::get());
+            let campaign_seed = seed
+                .or(fuzz_config.generation.seed)
+                .unwrap_or_else(rand::random);
+            let budget = CampaignBudget {
+                duration,
+                max_tests,
+                max_divergences,
+            };
+            info!(
+                "🏁 Starting campaign with {} workers, {} instructions per extension, seed {}, budget {:?}",
+                num_threads, inst_num, campaign_seed, budget
             );
 
             let resolved_output_dir = resolve_output_dir(output_dir, workspace_dir)?;
             let _ = create_dir_all(&resolved_output_dir);
 
-            run_parallel_random_tests(&resolved_output_dir, inst_num, num_threads, &march_string)?;
+            let campaign = Campaign::new(campaign_seed, budget, &resolved_output_dir)?;
+            run_campaign(
+                &campaign,
+                &resolved_output_dir,
+                inst_num,
+                &extensions,
+                num_threads,
+                &march_string,
+                &limits,
+            )?;
+
+            let summary = campaign.summary();
+            summary.save(&resolved_output_dir)?;
+            info!("{}", summary);
+            info!(
+                "💾 Campaign summary saved to: {:?}",
+                resolved_output_dir.join(CAMPAIGN_SUMMARY_FILE)
+            );
+        }
+        Commands::Diff {
+            output1,
+            output2,
+            dump,
+            emulators,
+            format,
+            build_dir,
+        } => {
+            let format = format.unwrap_or_else(|| fuzz_config.diff.format.clone());
+            info!(
+                "🔄 Diffing saved outputs {:?} and {:?} in {} format",
+                output1, output2, format
+            );
+            for path in [Some(&output1), Some(&output2), dump.as_ref()]
+                .into_iter()
+                .flatten()
+            {
+                if !path.exists() {
+                    return Err(RiscvFuzzError::file(format!(
+                        "File does not exist: {:?}",
+                        path
+                    )));
+                }
+            }
+            let _ = create_dir_all(&build_dir);
+            run_offline_diff(
+                [&output1, &output2],
+                dump.as_deref(),
+                [emulators[0].clone(), emulators[1].clone()],
+                format,
+                &build_dir,
+            )?;
+        }
+        Commands::Regress {
+            suite_dir,
+            emulators,
+            build_dir,
+            parallel,
+            bless,
+        } => {
+            init_thread_pool(parallel.unwrap_or_else(num_cpus::get))?;
+            let summary = run_regression(
+                &suite_dir,
+                &build_dir,
+                &emulators,
+                &march_string,
+                &limits,
+                bless,
+            )?;
+            summary.save(&build_dir)?;
+            info!("{}", summary);
+            info!(
+                "💾 Regression summary saved to: {:?}",
+                build_dir.join(REGRESSION_SUMMARY_FILE)
+            );
+            let failures = summary.failures();
+            if failures > 0 {
+                return Err(RiscvFuzzError::diff_analysis(format!(
+                    "{} of {} regression cases failed",
+                    failures,
+                    summary.cases.len()
+                )));
+            }
+        }
+        Commands::Replay { test_dir } => {
+            let report = run_replay(&test_dir, &march_string, &limits, &cli.limits)?;
+            info!("{}", report);
+            if report.status == ReplayStatus::Inconclusive {
+                return Err(RiscvFuzzError::diff_analysis(format!(
+                    "Replay of {:?} is inconclusive, see {:?}",
+                    test_dir,
+                    test_dir.join(REPLAY_DIR)
+                )));
+            }
+        }
+        Commands::Minimize {
+            input,
+            build_dir,
+            parallel,
+        } => {
+            init_thread_pool(parallel.unwrap_or_else(num_cpus::get))?;
+            let report = run_minimize(&input, build_dir, &march_string, &limits, &cli.limits)?;
+            info!("{}", report);
+            if !report.verified {
+                return Err(RiscvFuzzError::diff_analysis(format!(
+                    "Minimized program {:?} did not reproduce the divergence",
+                    report.reproducer
+                )));
+            }
         }
         Commands::Run {
             assembly_file,
             build_dir,
-            format, // Added
+            format,
             auto_retry,
         } => {
+            let format = format.unwrap_or_else(|| fuzz_config.diff.format.clone());
+            let auto_retry = auto_retry.unwrap_or(fuzz_config.diff.auto_retry);
             info!(
                 "📁 Running in file mode with assembly file: {:?}, format: {:?}, auto_retry: {}", // Updated log
                 assembly_file, format, auto_retry
@@ -146,8 +494,10 @@ fn main() -> Result<()> {
                 &build_dir,
                 &assembly_file,
                 &march_string,
+                &limits,
                 format,
                 auto_retry,
+                true,
             )?; // Pass auto_retry
         }
         Commands::Emulate {
@@ -155,17 +505,16 @@ fn main() -> Result<()> {
             emulator,
             format,
             build_dir,
+            retire_trace,
         } => {
             info!(
                 "🔬 Running emulation mode with {} emulator, {} format",
-                match emulator {
-                    EmulatorType::Spike => "Spike",
-                    EmulatorType::Rocket => "Rocket",
-                },
+                emulator,
                 match format {
                     OutputFormat::Standard => "standard",
                     OutputFormat::Debug => "debug",
                     OutputFormat::Common => "common",
+                    OutputFormat::Trace => "trace",
                 }
             );
 
@@ -189,7 +538,168 @@ fn main() -> Result<()> {
 
             let _ = create_dir_all(&build_dir);
 
-            run_single_emulator(&build_dir, &assembly_file, &march_string, emulator, format)?;
+            run_single_emulator(
+                &build_dir,
+                &assembly_file,
+                &march_string,
+                emulator,
+                format,
+                &limits,
+                retire_trace,
+            )?;
+        }
+        Commands::Probe {
+            emulators,
+            inst_num,
+            build_dir,
+            output,
+        } => {
+            info!(
+                "🧪 Probing {} extensions on {:?} with {} instructions each",
+                ALL_RV64_EXTENSIONS.len(),
+                emulators,
+                inst_num
+            );
+
+            let profile = probe_extensions(
+                ALL_RV64_EXTENSIONS,
+                &emulators,
+                inst_num,
+                &build_dir,
+                &limits,
+            )?;
+
+            profile.save(&output)?;
+            let report_file = capability_report_path(&output);
+            fs::write(&report_file, profile.to_string())?;
+            info!(
+                "💾 Capability profile saved to: {:?} and {:?}",
+                output, report_file
+            );
+
+            // 探测到的非法操作码写入黑名单，生成时跳过
+            let generation = &fuzz_config.generation;
+            if generation.learn_opcode_denylist {
+                let seeded = OpcodeDenylist::record_denylist(
+                    &generation.opcode_denylist,
+                    &profile.illegal_opcodes(),
+                )?;
+                if seeded > 0 {
+                    info!(
+                        "🚫 Denylisted {} opcodes that trapped as illegal, saved to: {:?}",
+                        seeded, generation.opcode_denylist
+                    );
+                }
+            }
+
+            let untested = profile
+                .extensions
+                .iter()
+                .filter(|e| e.support() == ExtensionSupport::Untested)
+                .count();
+            info!(
+                "🧪 {} extensions usable for random generation, {} could not be tested",
+                profile.usable_extensions().len(),
+                untested
+            );
+        }
+        Commands::Compare {
+            assembly_file,
+            emulators,
+            format,
+            build_dir,
+        } => {
+            let registry = EmulatorRegistry::with_limits(&march_string, &limits);
+            let registry = if emulators.is_empty() {
+                registry
+            } else {
+                registry.select(&emulators)?
+            };
+            let names: Vec<&str> = registry.iter().map(|e| e.name()).collect();
+            info!(
+                "⚖️ Running multi-emulator comparison with {:?}, {} format",
+                names, format
+            );
+
+            if registry.len() < 2 {
+                return Err(RiscvFuzzError::config(
+                    "At least two emulators are required for comparison",
+                ));
+            }
+
+            if !assembly_file.exists() {
+                return Err(RiscvFuzzError::file(format!(
+                    "Assembly file does not exist: {:?}",
+                    assembly_file
+                )));
+            }
+
+            let _ = create_dir_all(&build_dir);
+
+            let linker_script = get_or_create_linker_script(&assembly_file)?;
+            let build_result = build_elf(&assembly_file, &linker_script, &march_string)?;
+
+            let multi_diff = match format {
+                OutputFormat::Standard => run_multi_comparison::<StandardExecutionOutput>(
+                    &registry,
+                    &build_dir,
+                    &build_result,
+                )?,
+                OutputFormat::Debug => run_multi_comparison::<DebugExecutionOutput>(
+                    &registry,
+                    &build_dir,
+                    &build_result,
+                )?,
+                OutputFormat::Common => run_multi_comparison::<CommonExecutionOutput>(
+                    &registry,
+                    &build_dir,
+                    &build_result,
+                )?,
+                OutputFormat::Trace => {
+                    return Err(RiscvFuzzError::config(
+                        "Trace format supports only the two-emulator `run` comparison",
+                    ));
+                }
+            };
+
+            match multi_diff.suspected_faulty() {
+                Some(emulator) => info!("🎯 Suspected faulty emulator: {}", emulator),
+                None if multi_diff.is_empty() => info!("✅ All emulators agree"),
+                None if multi_diff.top_suspects().len() > 1 => info!(
+                    "🤷 Blame is tied between {:?}, no single emulator is suspected",
+                    multi_diff.top_suspects()
+                ),
+                None => info!("🤷 Emulators disagree but no majority could be formed"),
+            }
+        }
+        Commands::Reference {
+            assembly_file,
+            emulator,
+            build_dir,
+        } => {
+            info!("🧮 Checking {} against the reference interpreter", emulator);
+
+            if !assembly_file.exists() {
+                return Err(RiscvFuzzError::file(format!(
+                    "Assembly file does not exist: {:?}",
+                    assembly_file
+                )));
+            }
+
+            let _ = create_dir_all(&build_dir);
+
+            let diff = run_reference_comparison(
+                &build_dir,
+                &assembly_file,
+                &march_string,
+                emulator.clone(),
+                &limits,
+            )?;
+            if diff.is_empty() {
+                info!("✅ {} matches the reference interpreter", emulator);
+            } else {
+                info!("❗ {} differs from the reference interpreter", emulator);
+            }
         }
     }
 
@@ -200,16 +710,15 @@ fn main() -> Result<()> {
 fn run_parallel_random_tests(
     base_output_dir: &PathBuf,
     inst_num: usize,
+    extensions: &[RV64Extensions],
     num_threads: usize,
+    campaign_seed: u64,
     march_string: &str,
+    limits: &RunLimits,
 ) -> Result<()> {
     let counter = AtomicUsize::new(0);
 
-    // 配置rayon线程池
-    rayon::ThreadPoolBuilder::new()
-        .num_threads(num_threads)
-        .build_global()
-        .map_err(|e| RiscvFuzzError::config(&format!("Failed to initialize thread pool: {}", e)))?;
+    init_thread_pool(num_threads)?;
 
     info!("🚀 Starting {} parallel random test instances", num_threads);
 
@@ -221,10 +730,21 @@ fn run_parallel_random_tests(
             let test_dir = base_output_dir.join(format!("test_{:06}", test_id));
             let _ = create_dir_all(&test_dir);
 
-            info!("🎯 Starting random test #{}", test_id);
+            let record = SeedRecord {
+                campaign_seed: Some(campaign_seed),
+                test_id,
+                seed: derive_test_seed(campaign_seed, test_id),
+                inst_num,
+                extensions: extensions.to_vec(),
+                denylist: None,
+            };
+            info!(
+                "🎯 Starting random test #{} (seed {})",
+                test_id, record.seed
+            );
 
-            match run_single_random_test(&test_dir, inst_num, march_string) {
-                Ok(()) => {
+            match run_single_random_test(&test_dir, &record, march_string, limits) {
+                Ok(_) => {
                     info!("✅ Random test #{} completed successfully", test_id);
                 }
                 Err(e) => {
@@ -239,80 +759,353 @@ fn run_parallel_random_tests(
     Ok(())
 }
 
+/// 在线程池上持续运行随机测试，直到活动停止
+fn run_campaign(
+    campaign: &Campaign,
+    base_output_dir: &Path,
+    inst_num: usize,
+    extensions: &[RV64Extensions],
+    num_threads: usize,
+    march_string: &str,
+    limits: &RunLimits,
+) -> Result<()> {
+    init_thread_pool(num_threads)?;
+    install_interrupt_handler();
+
+    (0..num_threads).into_par_iter().for_each(|_| {
+        while let Some(test_id) = campaign.next_test() {
+            let test_dir = base_output_dir.join(format!("test_{:06}", test_id));
+            let _ = create_dir_all(&test_dir);
+
+            let record = SeedRecord {
+                campaign_seed: Some(campaign.seed),
+                test_id,
+                seed: derive_test_seed(campaign.seed, test_id),
+                inst_num,
+                extensions: extensions.to_vec(),
+                denylist: None,
+            };
+            let result = run_single_random_test(&test_dir, &record, march_string, limits);
+            if let Err(e) = &result {
+                info!("❌ Random test #{} failed: {}", test_id, e);
+            }
+            campaign.record(test_id, &test_dir, &result);
+        }
+    });
+
+    Ok(())
+}
+
+/// 在 `test_dir/replay` 中按记录的配置重新构建并运行测试，比较差异签名
+///
+/// Without `run_config.json` the current march string and limits are used;
+/// without the assembly the program is regenerated from `seed.json`.
+fn run_replay(
+    test_dir: &Path,
+    march_string: &str,
+    limits: &RunLimits,
+    limit_args: &LimitArgs,
+) -> Result<ReplayReport> {
+    if !test_dir.is_dir() {
+        return Err(RiscvFuzzError::file(format!(
+            "Test directory does not exist: {:?}",
+            test_dir
+        )));
+    }
+    let record = RunRecord::load(test_dir)?;
+    let seed = load_seed_record(test_dir)?;
+
+    let replay_dir = test_dir.join(REPLAY_DIR);
+    if replay_dir.exists() {
+        fs::remove_dir_all(&replay_dir)?;
+    }
+    create_dir_all(&replay_dir)?;
+
+    let recorded_assembly = record
+        .as_ref()
+        .map(|record| record.assembly_file.clone())
+        .filter(|path| path.exists())
+        .or_else(|| Some(test_dir.join("generated_output.S")).filter(|path| path.exists()));
+    let (assembly_file, regenerated) = match (recorded_assembly, &seed) {
+        (Some(source), _) => {
+            let file_name = source
+                .file_name()
+                .ok_or_else(|| RiscvFuzzError::file("Recorded assembly has no file name"))?;
+            let assembly_file = replay_dir.join(file_name);
+            fs::copy(&source, &assembly_file)?;
+            (assembly_file, false)
+        }
+        (None, Some(seed)) => {
+            info!(
+                "🌱 Assembly not found, regenerating from seed {}",
+                seed.seed
+            );
+            (generate_random_assembly(&replay_dir, seed)?, true)
+        }
+        (None, None) => {
+            return Err(RiscvFuzzError::file(format!(
+                "No assembly or seed.json found in {:?}",
+                test_dir
+            )));
+        }
+    };
+    // 测试可能使用了自定义链接脚本
+    let linker_script = test_dir.join("linker.ld");
+    if linker_script.exists() {
+        fs::copy(&linker_script, replay_dir.join("linker.ld"))?;
+    }
+
+    let (march, limits, auto_retry) = match &record {
+        Some(record) => (
+            record.march.clone(),
+            limit_args.apply(record.run_limits()),
+            record.auto_retry,
+        ),
+        None => (march_string.to_string(), *limits, true),
+    };
+    info!(
+        "🔁 Replaying {:?} with march {} and limits {:?}",
+        test_dir, march, limits
+    );
+    process_assembly_file(
+        &replay_dir,
+        &assembly_file,
+        &march,
+        &limits,
+        OutputFormat::Standard,
+        auto_retry,
+        false, // 重放不改变黑名单
+    )?;
+
+    let stored = stored_signature(test_dir)?;
+    let replayed = stored_signature(&replay_dir)?;
+    let report = ReplayReport {
+        test_dir: test_dir.to_path_buf(),
+        timestamp: get_current_timestamp(),
+        assembly_file,
+        regenerated,
+        status: ReplayStatus::classify(stored.as_ref(), replayed.as_ref()),
+        record,
+        seed,
+        emulators: EmulatorPaths::current(),
+        stored_signature: stored,
+        replay_signature: replayed,
+    };
+    report.save(&replay_dir)?;
+    info!(
+        "💾 Replay report saved to: {:?}",
+        replay_dir.join(REPLAY_REPORT_FILE)
+    );
+    Ok(report)
+}
+
+/// 最小化测试目录或汇编文件；测试目录使用其记录的参数和差异签名
+fn run_minimize(
+    input: &Path,
+    build_dir: Option<PathBuf>,
+    march_string: &str,
+    limits: &RunLimits,
+    limit_args: &LimitArgs,
+) -> Result<MinimizeReport> {
+    if input.is_dir() {
+        let record = RunRecord::load(input)?;
+        let assembly_file = record
+            .as_ref()
+            .map(|record| record.assembly_file.clone())
+            .filter(|path| path.exists())
+            .or_else(|| Some(input.join("generated_output.S")).filter(|path| path.exists()))
+            .ok_or_else(|| RiscvFuzzError::file(format!("No assembly found in {:?}", input)))?;
+        let (march, limits) = match &record {
+            Some(record) => (record.march.clone(), limit_args.apply(record.run_limits())),
+            None => (march_string.to_string(), *limits),
+        };
+        let work_dir = build_dir.unwrap_or_else(|| input.join(MINIMIZE_DIR));
+        let target = stored_signature(input)?;
+        let emulators = match stored_emulators(input)? {
+            Some(emulators) => emulators,
+            None => EmulatorRegistry::with_limits(&march, &limits).compared_pair()?,
+        };
+        minimize(
+            &assembly_file,
+            &work_dir,
+            &march,
+            &limits,
+            &emulators,
+            target,
+        )
+    } else if input.exists() {
+        let work_dir = build_dir.unwrap_or_else(|| {
+            let stem = input.file_stem().unwrap_or_default().to_string_lossy();
+            input.with_file_name(format!("{}_{}", stem, MINIMIZE_DIR))
+        });
+        let emulators = EmulatorRegistry::with_limits(march_string, limits).compared_pair()?;
+        minimize(input, &work_dir, march_string, limits, &emulators, None)
+    } else {
+        Err(RiscvFuzzError::file(format!(
+            "Input does not exist: {:?}",
+            input
+        )))
+    }
+}
+
+/// 配置rayon线程池
+fn init_thread_pool(num_threads: usize) -> Result<()> {
+    rayon::ThreadPoolBuilder::new()
+        .num_threads(num_threads)
+        .build_global()
+        .map_err(|e| RiscvFuzzError::config(&format!("Failed to initialize thread pool: {}", e)))
+}
+
 /// 运行单个随机测试实例
-fn run_single_random_test(test_dir: &PathBuf, inst_num: usize, march_string: &str) -> Result<()> {
+fn run_single_random_test(
+    test_dir: &PathBuf,
+    record: &SeedRecord,
+    march_string: &str,
+    limits: &RunLimits,
+) -> Result<TestVerdict> {
     // 生成随机汇编代码
-    let assembly_file = generate_random_assembly(test_dir, inst_num)?;
+    let assembly_file = generate_random_assembly(test_dir, record)?;
 
     // 处理汇编文件, 随机测试默认使用 Standard 格式
     process_assembly_file(
         test_dir,
         &assembly_file,
         march_string,
+        limits,
         OutputFormat::Standard,
         true, // Random tests always enable auto_retry
-    )?;
-
-    Ok(())
+        true,
+    )
 }
 
 /// 处理汇编文件的完整流程：编译、运行模拟器、分析差异、可能的重试
+///
+/// The verdict counts run divergences (one emulator timed out, crashed, printed
+/// nothing or failed while the other completed) and, for the Standard format,
+/// the exception and register differences left after the illegal-instruction
+/// retry.
+/// With `learn_denylist`, one-sided illegal opcodes go into the opcode denylist
+/// (when `learn_opcode_denylist` is set); replays leave the denylist alone.
 fn process_assembly_file(
     build_dir: &PathBuf,
     assembly_file: &PathBuf,
     march_string: &str,
-    format: OutputFormat, // Added format parameter
-    auto_retry: bool,     // Added auto_retry parameter
-) -> Result<()> {
+    limits: &RunLimits,
+    format: OutputFormat,
+    auto_retry: bool,
+    learn_denylist: bool,
+) -> Result<TestVerdict> {
+    // 记录运行配置，供 replay 使用
+    RunRecord::capture(
+        assembly_file,
+        march_string,
+        limits,
+        format.clone(),
+        auto_retry,
+    )
+    .save(build_dir)?;
+
     let linker_script = get_or_create_linker_script(assembly_file)?;
 
     // 编译汇编文件
     let build_result = build_elf(assembly_file, &linker_script, march_string)?;
 
-    // 定义原始输出文件路径
-    let spike_raw_output_path = build_dir.join("spike_output.bin");
-    let rocket_raw_output_path = build_dir.join("rocket_output.bin");
+    // Trace 格式需要模拟器额外记录 retire trace
+    let registry = if format == OutputFormat::Trace {
+        EmulatorRegistry::with_retire_trace(march_string, limits)
+    } else {
+        EmulatorRegistry::with_limits(march_string, limits)
+    };
+    let emulators = registry.compared_pair()?;
+    let registry = registry.select(&emulators)?;
+
+    // 运行模拟器，原始输出按后端名称命名
+    let runs: Vec<(PathBuf, Result<RunOutcome>)> = registry
+        .iter()
+        .map(|emulator| {
+            let raw_output_path = build_dir.join(format!("{}_output.bin", emulator.name()));
+            info!("🏃 Running {} emulator...", emulator.emulator_type());
+            let run_res = execute_emulator(
+                &registry,
+                &raw_output_path,
+                &build_result.executable_file,
+                emulator.emulator_type(),
+            );
+            (raw_output_path, run_res)
+        })
+        .collect();
+    let [
+        (sim1_raw_output_path, sim1_run_res),
+        (sim2_raw_output_path, sim2_run_res),
+    ] = &runs[..]
+    else {
+        return Err(RiscvFuzzError::diff_analysis(
+            "Comparison needs exactly two emulators",
+        ));
+    };
+
+    let mut verdict = TestVerdict {
+        timed_out: [sim1_run_res, sim2_run_res].iter().any(|res| {
+            res.as_ref()
+                .is_ok_and(|outcome| outcome.classification == RunClassification::Timeout)
+        }),
+        ..TestVerdict::default()
+    };
+
+    // 仅一个模拟器未正常结束 (超时、崩溃、无输出、失败退出码) 时单独报告
+    let run_divergence = match (sim1_run_res, sim2_run_res) {
+        (Ok(sim1_outcome), Ok(sim2_outcome)) => {
+            RunDivergence::from_outcomes(sim1_outcome, sim2_outcome)
+        }
+        _ => None,
+    };
+    if let Some(run_divergence) = run_divergence {
+        verdict.diverged = true;
+        verdict.signature = Some(DivergenceSignature::from_run_divergence(&run_divergence));
+        verdict.reproducer = Some(assembly_file.clone());
+        warn!("⚠️ Run divergence detected: {:?}", run_divergence);
+        let run_diff_json_file = build_dir.join("diff_run.json");
+        let run_diff_text_file = build_dir.join("diff_run.md");
+        fs::write(
+            &run_diff_json_file,
+            serde_json::to_string_pretty(&run_divergence)?,
+        )?;
+        fs::write(&run_diff_text_file, run_divergence.to_string())?;
+        info!(
+            "💾 Run divergence saved to: {:?} and {:?}",
+            run_diff_json_file, run_diff_text_file
+        );
+    }
 
-    // 运行模拟器
-    info!("🏃 Running Spike emulator...");
-    let spike_run_res = run_emulator(
-        &spike_raw_output_path,
-        &build_result.executable_file,
-        march_string,
-        EmulatorType::Spike,
-    );
-
-    info!("🏃 Running Rocket emulator...");
-    let rocket_run_res = run_emulator(
-        &rocket_raw_output_path,
-        &build_result.executable_file,
-        march_string,
-        EmulatorType::Rocket,
-    );
+    // 只解析可用的原始输出
+    let sim1_raw_output = usable_raw_output(sim1_run_res, sim1_raw_output_path);
+    let sim2_raw_output = usable_raw_output(sim2_run_res, sim2_raw_output_path);
 
     // 根据格式处理输出和差异
     match format {
         OutputFormat::Standard => {
-            let spike_output = spike_run_res.ok().and_then(|p| {
+            let sim1_output = sim1_raw_output.as_deref().and_then(|p| {
                 parse_output_from_file::<StandardExecutionOutput, _>(
-                    &p,
+                    p,
                     &build_result.disassembly_file,
-                    EmulatorType::Spike,
+                    emulators[0].clone(),
                 )
                 .ok()
             });
-            let rocket_output = rocket_run_res.ok().and_then(|p| {
+            let sim2_output = sim2_raw_output.as_deref().and_then(|p| {
                 parse_output_from_file::<StandardExecutionOutput, _>(
-                    &p,
+                    p,
                     &build_result.disassembly_file,
-                    EmulatorType::Rocket,
+                    emulators[1].clone(),
                 )
                 .ok()
             });
 
-            if let (Some(spike_out), Some(rocket_out)) = (spike_output, rocket_output) {
+            if let (Some(sim1_out), Some(sim2_out)) = (sim1_output, sim2_output) {
                 info!("🔄 Comparing Standard outputs...");
-                let initial_diff = compare_outputs(&spike_out, &rocket_out);
+                let mut initial_diff = compare_outputs(&sim1_out, &sim2_out);
+                initial_diff.run_outcome_diff =
+                    load_run_outcome_diff(sim1_raw_output_path, sim2_raw_output_path);
 
                 let initial_diff_json = serde_json::to_string_pretty(&initial_diff)?;
                 let initial_diff_text = initial_diff.to_string();
@@ -324,197 +1117,116 @@ fn process_assembly_file(
                     "💾 Initial Standard diff saved to: {:?} and {:?}",
                     initial_diff_json_file, initial_diff_text_file
                 );
-
-                // 检查是否有Rocket中的Illegal instruction异常 (此逻辑特定于 StandardExecutionOutputDiff)
-                let rocket_has_illegal_instructions = initial_diff
-                    .exceptions_diff // This is StandardExecutionOutputDiff specific
+                if initial_diff.is_divergent() && config::get().diff.reference_vote {
+                    run_reference_vote(
+                        build_dir,
+                        &build_result.disassembly_file,
+                        march_string,
+                        [&sim1_out, &sim2_out],
+                    )?;
+                }
+                let mut standard_diverged = initial_diff.is_divergent();
+                let mut signature = DivergenceSignature::from_standard_diff(&initial_diff);
+                let mut reproducer = assembly_file.clone();
+                // 产生当前签名的程序，ddmin 从它开始
+                let mut signature_source = assembly_file.clone();
+                let known_bugs = KnownBugDatabase::load_configured()?;
+                let mut triage = known_bugs.as_ref().and_then(|db| db.triage(&initial_diff));
+
+                // 记录仅在单个模拟器中非法的操作码，后续生成时跳过
+                let generation = &config::get().generation;
+                if let Some(ex_diff) = initial_diff
+                    .exceptions_diff
                     .as_ref()
-                    .map_or(false, |ex_diff| {
-                        has_rocket_only_illegal_instructions(ex_diff)
-                    });
-
-                if rocket_has_illegal_instructions && auto_retry {
-                    let illegal_instructions = initial_diff
-                        .exceptions_diff
-                        .as_ref()
-                        .map_or_else(Vec::new, |ex_diff| {
-                            get_rocket_illegal_instruction_originals(ex_diff)
-                        });
-
-                    if !illegal_instructions.is_empty() {
+                    .filter(|_| learn_denylist && generation.learn_opcode_denylist)
+                {
+                    let learned =
+                        OpcodeDenylist::record_exceptions(&generation.opcode_denylist, ex_diff)?;
+                    if learned > 0 {
                         info!(
-                            "🚨 Found {} Rocket-only illegal instructions, attempting retry...",
-                            illegal_instructions.len()
-                        );
-
-                        let new_build_dir = build_dir.join("rocket_illegal_retry");
-                        let _ = create_dir_all(&new_build_dir);
-                        let new_assembly_file = new_build_dir.join("retry_output.S");
-
-                        remove_instructions_assembly::<PathBuf>(
-                            &assembly_file,
-                            &new_assembly_file,
-                            &illegal_instructions,
-                        )?;
-
-                        let new_linker_script = get_or_create_linker_script(&new_assembly_file)?;
-                        let new_build_result =
-                            build_elf(&new_assembly_file, &new_linker_script, march_string)?;
-
-                        // Re-run emulators for retry
-                        info!("🏃 Re-running Spike emulator for retry...");
-                        let new_spike_run_res = run_emulator(
-                            &new_build_dir.join("spike_output_retry.bin"),
-                            &new_build_result.executable_file,
-                            march_string,
-                            EmulatorType::Spike,
-                        );
-                        info!("🏃 Re-running Rocket emulator for retry...");
-                        let new_rocket_run_res = run_emulator(
-                            &new_build_dir.join("rocket_output_retry.bin"),
-                            &new_build_result.executable_file,
-                            march_string,
-                            EmulatorType::Rocket,
+                            "💾 Added {} opcodes to denylist {:?}",
+                            learned, generation.opcode_denylist
                         );
+                    }
+                }
 
-                        let new_spike_out_parsed = new_spike_run_res.ok().and_then(|p| {
-                            parse_output_from_file::<StandardExecutionOutput, _>(
-                                &p,
-                                &new_build_result.disassembly_file,
-                                EmulatorType::Spike,
-                            )
-                            .ok()
-                        });
-                        let new_rocket_out_parsed = new_rocket_run_res.ok().and_then(|p| {
-                            parse_output_from_file::<StandardExecutionOutput, _>(
-                                &p,
-                                &new_build_result.disassembly_file,
-                                EmulatorType::Rocket,
-                            )
-                            .ok()
-                        });
-
-                        if let (Some(new_spike_out), Some(new_rocket_out)) =
-                            (new_spike_out_parsed, new_rocket_out_parsed)
+                if auto_retry {
+                    let initial = RetryAttempt::new(
+                        assembly_file.clone(),
+                        initial_diff.clone(),
+                        [sim1_raw_output_path, sim2_raw_output_path],
+                        &build_result.disassembly_file,
+                    );
+                    let chain = run_retry_policy(&initial, build_dir, march_string, limits)?;
+                    if let Some(last) = &chain.last {
+                        standard_diverged = last.diff.is_divergent();
+                        signature = DivergenceSignature::from_standard_diff(&last.diff);
+                        reproducer = last.assembly_file.clone();
+                        signature_source = last.assembly_file.clone();
+                        triage = known_bugs.as_ref().and_then(|db| db.triage(&last.diff));
+
+                        // 删除指令后仍有寄存器差异时做切片分析
+                        if let Some(minimal_assembly_file) =
+                            run_register_slice_analysis(last, march_string, &registry)?
                         {
-                            info!("🔄 Comparing Standard outputs after retry...");
-                            let new_diff = compare_outputs(&new_spike_out, &new_rocket_out);
-
-                            let new_diff_json = serde_json::to_string_pretty(&new_diff)?;
-                            let new_diff_text = new_diff.to_string();
-                            let new_diff_json_file = new_build_dir.join("new_diff_standard.json");
-                            let new_diff_text_file = new_build_dir.join("new_diff_standard.md");
-                            fs::write(&new_diff_json_file, new_diff_json)?;
-                            fs::write(&new_diff_text_file, new_diff_text)?;
-                            info!(
-                                "💾 New Standard diff saved to: {:?} and {:?}",
-                                new_diff_json_file, new_diff_text_file
-                            );
-
-                            let retry_report = compare_output_diffs(&initial_diff, &new_diff);
-                            let retry_report_file = new_build_dir.join("retry_report.md");
-                            fs::write(&retry_report_file, retry_report.to_string())?;
-                            info!("💾 Retry report saved to: {:?}", retry_report_file);
-
-                            // 检查删除非法指令后是否仍有寄存器差异
-                            if let Some(reg_diff) = &new_diff.register_dump_diff {
-                                if !reg_diff.is_empty() && has_register_differences(reg_diff) {
-                                    info!(
-                                        "🎯 Found register differences after illegal instruction removal, performing minimal analysis..."
-                                    );
-
-                                    // 提取存在差异的寄存器列表
-                                    let differing_regs = extract_differing_registers(reg_diff);
-                                    if !differing_regs.is_empty() {
-                                        // 提取用户代码指令
-                                        let assembly_content =
-                                            fs::read_to_string(&new_assembly_file)?;
-                                        let user_instructions =
-                                            extract_user_code_instructions(&assembly_content);
-
-                                        // 进行最小化分析
-                                        let minimal_instructions =
-                                            extract_minimal_instructions_for_regs(
-                                                user_instructions,
-                                                differing_regs.clone(),
-                                            );
-
-                                        if !minimal_instructions.is_empty() {
-                                            info!(
-                                                "🔬 Performing minimal analysis with {} instructions for {} registers",
-                                                minimal_instructions.len(),
-                                                differing_regs.len()
-                                            );
-
-                                            // 创建最小化分析目录
-                                            let minimal_build_dir =
-                                                new_build_dir.join("minimal_analysis");
-                                            let _ = create_dir_all(&minimal_build_dir);
-
-                                            // 生成最小化汇编
-                                            let minimal_assembly_file =
-                                                minimal_build_dir.join("minimal_output.S");
-                                            generate_minimal_assembly_for_analysis(
-                                                &minimal_instructions,
-                                                &minimal_assembly_file,
-                                                &assembly_content,
-                                            )?;
-
-                                            // 运行最小化分析
-                                            run_minimal_analysis(
-                                                &minimal_build_dir,
-                                                &minimal_assembly_file,
-                                                march_string,
-                                                &new_diff, // 传递rocket retry的差异结果进行对比
-                                            )?;
-                                        } else {
-                                            info!("⚠️ No instructions found for minimal analysis");
-                                        }
-                                    }
-                                }
-                            }
-                        } else {
-                            warn!("⚠️ Failed to parse one or both emulator outputs after retry.");
+                            reproducer = minimal_assembly_file;
                         }
-                    } else {
-                        info!(
-                            "ℹ️ No specific illegal instructions identified for removal, no retry performed."
-                        );
                     }
-                } else if rocket_has_illegal_instructions && !auto_retry {
-                    info!(
-                        "ℹ️ Rocket-only illegal instructions found in Standard diff, but auto_retry is disabled."
-                    );
                 } else {
-                    info!(
-                        "ℹ️ No Rocket-only illegal instructions found in Standard diff, no retry needed."
-                    );
+                    info!("ℹ️ auto_retry is disabled, no retry performed.");
+                }
+                if standard_diverged && config::get().diff.minimize {
+                    match minimize(
+                        &signature_source,
+                        &build_dir.join(MINIMIZE_DIR),
+                        march_string,
+                        limits,
+                        &initial_diff.emulator_types(),
+                        Some(signature.clone()),
+                    ) {
+                        Ok(report) if report.verified => reproducer = report.reproducer,
+                        Ok(_) => {}
+                        Err(e) => warn!("⚠️ Minimization failed: {}", e),
+                    }
+                }
+                if standard_diverged {
+                    verdict.diverged = true;
+                    verdict.signature = Some(signature);
+                    verdict.reproducer = Some(reproducer);
+                    if let Some(report) = &triage {
+                        report.save(build_dir)?;
+                        info!("🐞 Triage: {}", report.classification);
+                        verdict.triage = Some(report.classification);
+                        verdict.known_bugs = report.bug_ids();
+                    }
                 }
             } else {
                 warn!("⚠️ Failed to parse one or both emulator outputs for Standard format.");
             }
         }
         OutputFormat::Debug => {
-            let spike_output = spike_run_res.ok().and_then(|p| {
+            let sim1_output = sim1_raw_output.as_deref().and_then(|p| {
                 parse_output_from_file::<DebugExecutionOutput, _>(
-                    &p,
+                    p,
                     &build_result.disassembly_file,
-                    EmulatorType::Spike,
+                    emulators[0].clone(),
                 )
                 .ok()
             });
-            let rocket_output = rocket_run_res.ok().and_then(|p| {
+            let sim2_output = sim2_raw_output.as_deref().and_then(|p| {
                 parse_output_from_file::<DebugExecutionOutput, _>(
-                    &p,
+                    p,
                     &build_result.disassembly_file,
-                    EmulatorType::Rocket,
+                    emulators[1].clone(),
                 )
                 .ok()
             });
 
-            if let (Some(spike_out), Some(rocket_out)) = (spike_output, rocket_output) {
+            if let (Some(sim1_out), Some(sim2_out)) = (sim1_output, sim2_output) {
                 info!("🔄 Comparing Debug outputs...");
-                let diff = compare_outputs(&spike_out, &rocket_out);
+                let mut diff = compare_outputs(&sim1_out, &sim2_out);
+                diff.run_outcome_diff =
+                    load_run_outcome_diff(sim1_raw_output_path, sim2_raw_output_path);
                 let diff_json = serde_json::to_string_pretty(&diff)?;
                 let diff_text = diff.to_string();
                 let diff_json_file = build_dir.join("diff_debug.json");
@@ -525,31 +1237,42 @@ fn process_assembly_file(
                     "💾 Debug diff saved to: {:?} and {:?}",
                     diff_json_file, diff_text_file
                 );
+                if auto_retry {
+                    let initial = RetryAttempt::new(
+                        assembly_file.clone(),
+                        diff,
+                        [sim1_raw_output_path, sim2_raw_output_path],
+                        &build_result.disassembly_file,
+                    );
+                    run_retry_policy(&initial, build_dir, march_string, limits)?;
+                }
             } else {
                 warn!("⚠️ Failed to parse one or both emulator outputs for Debug format.");
             }
         }
         OutputFormat::Common => {
-            let spike_output = spike_run_res.ok().and_then(|p| {
+            let sim1_output = sim1_raw_output.as_deref().and_then(|p| {
                 parse_output_from_file::<CommonExecutionOutput, _>(
-                    &p,
+                    p,
                     &build_result.disassembly_file,
-                    EmulatorType::Spike,
+                    emulators[0].clone(),
                 )
                 .ok()
             });
-            let rocket_output = rocket_run_res.ok().and_then(|p| {
+            let sim2_output = sim2_raw_output.as_deref().and_then(|p| {
                 parse_output_from_file::<CommonExecutionOutput, _>(
-                    &p,
+                    p,
                     &build_result.disassembly_file,
-                    EmulatorType::Rocket,
+                    emulators[1].clone(),
                 )
                 .ok()
             });
 
-            if let (Some(spike_out), Some(rocket_out)) = (spike_output, rocket_output) {
+            if let (Some(sim1_out), Some(sim2_out)) = (sim1_output, sim2_output) {
                 info!("🔄 Comparing Common outputs...");
-                let diff = compare_outputs(&spike_out, &rocket_out);
+                let mut diff = compare_outputs(&sim1_out, &sim2_out);
+                diff.run_outcome_diff =
+                    load_run_outcome_diff(sim1_raw_output_path, sim2_raw_output_path);
                 let diff_json = serde_json::to_string_pretty(&diff)?;
                 let diff_text = diff.to_string();
                 let diff_json_file = build_dir.join("diff_common.json");
@@ -560,15 +1283,311 @@ fn process_assembly_file(
                     "💾 Common diff saved to: {:?} and {:?}",
                     diff_json_file, diff_text_file
                 );
+                if auto_retry {
+                    let initial = RetryAttempt::new(
+                        assembly_file.clone(),
+                        diff,
+                        [sim1_raw_output_path, sim2_raw_output_path],
+                        &build_result.disassembly_file,
+                    );
+                    run_retry_policy(&initial, build_dir, march_string, limits)?;
+                }
             } else {
                 warn!("⚠️ Failed to parse one or both emulator outputs for Common format.");
             }
         }
+        OutputFormat::Trace => {
+            // 运行失败时 trace 仍可能记录到了分歧点，因此不看运行结果
+            let parse_trace = |emulator_type: EmulatorType| {
+                let trace_log =
+                    retire_trace_log_path(&emulator_type, &build_result.executable_file)?;
+                parse_output_from_file::<RetireTrace, _>(
+                    &trace_log,
+                    &build_result.disassembly_file,
+                    emulator_type.clone(),
+                )
+                .map_err(|e| warn!("⚠️ Failed to parse {} retire trace: {}", emulator_type, e))
+                .ok()
+            };
+            let sim1_output = parse_trace(emulators[0].clone());
+            let sim2_output = parse_trace(emulators[1].clone());
+
+            if let (Some(sim1_out), Some(sim2_out)) = (sim1_output, sim2_output) {
+                info!("🔄 Comparing retire traces in lockstep...");
+                let mut diff = compare_outputs(&sim1_out, &sim2_out);
+                diff.run_outcome_diff =
+                    load_run_outcome_diff(sim1_raw_output_path, sim2_raw_output_path);
+                if let Some(divergence) = &diff.first_divergence {
+                    warn!(
+                        "🎯 First divergence ({}) at {} #{} / {} #{}",
+                        divergence.kind,
+                        emulators[0],
+                        divergence.sim1_index,
+                        emulators[1],
+                        divergence.sim2_index
+                    );
+                }
+                let diff_json_file = build_dir.join("diff_trace.json");
+                let diff_text_file = build_dir.join("diff_trace.md");
+                fs::write(&diff_json_file, serde_json::to_string_pretty(&diff)?)?;
+                fs::write(&diff_text_file, diff.to_string())?;
+                info!(
+                    "💾 Trace diff saved to: {:?} and {:?}",
+                    diff_json_file, diff_text_file
+                );
+            } else {
+                warn!("⚠️ Failed to parse one or both emulator outputs for Trace format.");
+            }
+        }
+    }
+
+    Ok(verdict)
+}
+
+/// 运行结果可用时返回原始输出路径，否则记录原因
+fn usable_raw_output(run_res: &Result<RunOutcome>, raw_output_path: &Path) -> Option<PathBuf> {
+    match run_res {
+        Ok(outcome) if outcome.is_usable() => Some(raw_output_path.to_path_buf()),
+        Ok(outcome) => {
+            warn!(
+                "⚠️ {} output is not usable: {}",
+                outcome.emulator_type, outcome.classification
+            );
+            None
+        }
+        Err(e) => {
+            warn!("⚠️ Failed to run emulator: {}", e);
+            None
+        }
     }
+}
+
+/// 比较两个已保存的输出，不重新运行模拟器
+///
+/// The run outcomes are compared too when the `.bin` logs still have their
+/// `.outcome.json` next to them.
+fn run_offline_diff(
+    outputs: [&Path; 2],
+    dump: Option<&Path>,
+    emulators: [EmulatorType; 2],
+    format: OutputFormat,
+    build_dir: &Path,
+) -> Result<()> {
+    let raw_log = |path: &Path| {
+        if path.extension().is_some_and(|ext| ext == "json") {
+            path.with_extension("bin")
+        } else {
+            path.to_path_buf()
+        }
+    };
+    let run_outcome_diff = load_run_outcome_diff(raw_log(outputs[0]), raw_log(outputs[1]));
 
+    match format {
+        OutputFormat::Standard => {
+            let (output1, output2) =
+                load_output_pair::<StandardExecutionOutput>(outputs, dump, emulators)?;
+            let mut diff = compare_outputs(&output1, &output2);
+            diff.run_outcome_diff = run_outcome_diff;
+            save_offline_diff(build_dir, &format, &diff)
+        }
+        OutputFormat::Debug => {
+            let (output1, output2) =
+                load_output_pair::<DebugExecutionOutput>(outputs, dump, emulators)?;
+            let mut diff = compare_outputs(&output1, &output2);
+            diff.run_outcome_diff = run_outcome_diff;
+            save_offline_diff(build_dir, &format, &diff)
+        }
+        OutputFormat::Common => {
+            let (output1, output2) =
+                load_output_pair::<CommonExecutionOutput>(outputs, dump, emulators)?;
+            let mut diff = compare_outputs(&output1, &output2);
+            diff.run_outcome_diff = run_outcome_diff;
+            save_offline_diff(build_dir, &format, &diff)
+        }
+        OutputFormat::Trace => {
+            let (output1, output2) = load_output_pair::<RetireTrace>(outputs, dump, emulators)?;
+            let mut diff = compare_outputs(&output1, &output2);
+            diff.run_outcome_diff = run_outcome_diff;
+            save_offline_diff(build_dir, &format, &diff)
+        }
+    }
+}
+
+fn load_output_pair<T: OutputParser>(
+    outputs: [&Path; 2],
+    dump: Option<&Path>,
+    emulators: [EmulatorType; 2],
+) -> Result<(T, T)> {
+    Ok((
+        load_or_parse_output(outputs[0], dump, emulators[0].clone())?,
+        load_or_parse_output(outputs[1], dump, emulators[1].clone())?,
+    ))
+}
+
+/// 与 `run` 相同的文件名保存差异: `diff_<format>.json` / `.md`
+fn save_offline_diff<D>(build_dir: &Path, format: &OutputFormat, diff: &D) -> Result<()>
+where
+    D: serde::Serialize + std::fmt::Display,
+{
+    let name = format.to_string().to_lowercase();
+    let diff_json_file = build_dir.join(format!("diff_{}.json", name));
+    let diff_text_file = build_dir.join(format!("diff_{}.md", name));
+    fs::write(&diff_json_file, serde_json::to_string_pretty(diff)?)?;
+    fs::write(&diff_text_file, diff.to_string())?;
+    info!("{}", diff);
+    info!(
+        "💾 {} diff saved to: {:?} and {:?}",
+        format, diff_json_file, diff_text_file
+    );
     Ok(())
 }
 
+/// 在注册表中的所有模拟器上运行程序，并进行多数表决比较
+fn run_multi_comparison<T>(
+    registry: &EmulatorRegistry,
+    build_dir: &PathBuf,
+    build_result: &ElfBuildResult,
+) -> Result<MultiEmulatorDiff>
+where
+    T: OutputParser + Votable,
+{
+    let sim_result = run_and_parse_all_simulators::<T, &PathBuf>(
+        registry,
+        build_dir,
+        &build_result.executable_file,
+        &build_result.disassembly_file,
+    );
+
+    let outputs: Vec<T> = sim_result
+        .results
+        .into_iter()
+        .filter_map(|r| {
+            if r.output.is_none() {
+                warn!(
+                    "⚠️ {} produced no parsable output, excluded from vote",
+                    r.name
+                );
+            }
+            r.output
+        })
+        .collect();
+
+    if outputs.len() < 2 {
+        return Err(RiscvFuzzError::diff_analysis(
+            "Fewer than two emulators produced output, nothing to compare",
+        ));
+    }
+
+    info!("🔄 Comparing {} emulator outputs...", outputs.len());
+    let multi_diff = compare_outputs_n_way(&outputs);
+
+    let diff_json_file = build_dir.join("diff_multi.json");
+    let diff_text_file = build_dir.join("diff_multi.md");
+    fs::write(&diff_json_file, serde_json::to_string_pretty(&multi_diff)?)?;
+    fs::write(&diff_text_file, multi_diff.to_string())?;
+    info!(
+        "💾 Multi-emulator diff saved to: {:?} and {:?}",
+        diff_json_file, diff_text_file
+    );
+
+    Ok(multi_diff)
+}
+
+/// 参考解释器作为第三方与两个模拟器投票，程序超出解释器模型时跳过
+fn run_reference_vote(
+    build_dir: &Path,
+    disassembly_file: &Path,
+    march_string: &str,
+    outputs: [&StandardExecutionOutput; 2],
+) -> Result<()> {
+    let reference = match ReferenceProgram::from_dump(disassembly_file)
+        .and_then(|program| ReferenceInterpreter::new(march_string).run(&program))
+    {
+        Ok(reference) => reference,
+        Err(e) => {
+            info!("ℹ️ Reference interpreter skipped: {}", e);
+            return Ok(());
+        }
+    };
+
+    // 解释器不建模的 CSR 不参与投票
+    let mut rules = DiffIgnoreRules::current().clone();
+    rules
+        .csrs
+        .extend(UNMODELLED_CSRS.iter().map(|csr| csr.to_string()));
+    let vote =
+        compare_outputs_n_way_with(&[outputs[0].clone(), outputs[1].clone(), reference], &rules);
+
+    let vote_json_file = build_dir.join("diff_reference_vote.json");
+    let vote_text_file = build_dir.join("diff_reference_vote.md");
+    fs::write(&vote_json_file, serde_json::to_string_pretty(&vote)?)?;
+    fs::write(&vote_text_file, vote.to_string())?;
+    match vote.suspected_faulty() {
+        Some(emulator) => warn!("⚖️ Reference interpreter votes against {}", emulator),
+        None => info!("⚖️ Reference vote is undecided"),
+    }
+    info!(
+        "💾 Reference vote saved to: {:?} and {:?}",
+        vote_json_file, vote_text_file
+    );
+    Ok(())
+}
+
+/// 用参考解释器执行用户代码，与指定模拟器的 Standard 输出比较
+fn run_reference_comparison(
+    build_dir: &Path,
+    assembly_file: &PathBuf,
+    march_string: &str,
+    emulator: EmulatorType,
+    limits: &RunLimits,
+) -> Result<StandardExecutionOutputDiff> {
+    let linker_script = get_or_create_linker_script(assembly_file)?;
+    let build_result = build_elf(assembly_file, &linker_script, march_string)?;
+
+    let program = ReferenceProgram::from_dump(&build_result.disassembly_file)?;
+    let reference_output = ReferenceInterpreter::new(march_string).run(&program)?;
+    let reference_json_file = build_dir.join("Reference_output.json");
+    let reference_text_file = build_dir.join("Reference_output.md");
+    fs::write(
+        &reference_json_file,
+        serde_json::to_string_pretty(&reference_output)?,
+    )?;
+    fs::write(&reference_text_file, reference_output.to_string())?;
+    info!(
+        "💾 Reference output saved to: {:?} and {:?}",
+        reference_json_file, reference_text_file
+    );
+
+    let registry = EmulatorRegistry::with_limits(march_string, limits);
+    let raw_output_path = build_dir.join(format!(
+        "{}_output.bin",
+        registry.require(emulator.clone())?.name()
+    ));
+    run_emulator(
+        &registry,
+        &raw_output_path,
+        &build_result.executable_file,
+        emulator.clone(),
+    )?;
+    let emulator_output = parse_output_from_file::<StandardExecutionOutput, _>(
+        &raw_output_path,
+        &build_result.disassembly_file,
+        emulator,
+    )?;
+
+    let diff = compare_outputs(&emulator_output, &reference_output);
+    let diff_json_file = build_dir.join("diff_reference.json");
+    let diff_text_file = build_dir.join("diff_reference.md");
+    fs::write(&diff_json_file, serde_json::to_string_pretty(&diff)?)?;
+    fs::write(&diff_text_file, diff.to_string())?;
+    info!(
+        "💾 Reference diff saved to: {:?} and {:?}",
+        diff_json_file, diff_text_file
+    );
+
+    Ok(diff)
+}
+
 /// 获取或创建链接脚本文件
 fn get_or_create_linker_script(assembly_file: &PathBuf) -> Result<PathBuf> {
     let assembly_dir = assembly_file
@@ -589,9 +1608,99 @@ fn get_or_create_linker_script(assembly_file: &PathBuf) -> Result<PathBuf> {
     Ok(linker_script_path)
 }
 
-/// 检查是否存在整数或浮点寄存器差异
+/// 按 `[diff.retry]` 删除问题指令并重试，有重试轮次时保存重试链报告
+fn run_retry_policy<D: RetryDiff>(
+    initial: &RetryAttempt<D>,
+    build_dir: &Path,
+    march_string: &str,
+    limits: &RunLimits,
+) -> Result<RetryChain<D>> {
+    let chain = run_retry_rounds(
+        RetryPolicy::current(),
+        initial,
+        build_dir,
+        march_string,
+        limits,
+    );
+    if chain.rounds.is_empty() {
+        info!("ℹ️ No retry needed: {}", chain.stop);
+    } else {
+        let retry_dir = build_dir.join(RETRY_DIR);
+        chain.save(&retry_dir)?;
+        info!(
+            "💾 Retry report ({} rounds, {}) saved to: {:?}",
+            chain.rounds.len(),
+            chain.stop,
+            retry_dir.join(RETRY_REPORT_FILE)
+        );
+    }
+    Ok(chain)
+}
+
+/// 对重试后仍有寄存器差异的程序做切片分析，返回仍能复现的最小程序
+fn run_register_slice_analysis(
+    retry: &RetryAttempt<StandardExecutionOutputDiff>,
+    march_string: &str,
+    registry: &EmulatorRegistry,
+) -> Result<Option<PathBuf>> {
+    let Some(reg_diff) = retry
+        .diff
+        .register_dump_diff
+        .as_ref()
+        .filter(|reg_diff| has_register_differences(reg_diff))
+    else {
+        return Ok(None);
+    };
+    info!(
+        "🎯 Found register differences after instruction removal, performing minimal analysis..."
+    );
+
+    // 提取存在差异的寄存器列表
+    let differing_regs = extract_differing_registers(reg_diff);
+    let assembly_content = fs::read_to_string(&retry.assembly_file)?;
+    let user_instructions = extract_user_code_instructions(&assembly_content);
+    let minimal_instructions =
+        extract_minimal_instructions_for_regs(user_instructions, differing_regs.clone());
+    if minimal_instructions.is_empty() {
+        info!("⚠️ No instructions found for minimal analysis");
+        return Ok(None);
+    }
+    info!(
+        "🔬 Performing minimal analysis with {} instructions for {} registers",
+        minimal_instructions.len(),
+        differing_regs.len()
+    );
+
+    // 在重试目录下创建最小化分析目录
+    let minimal_build_dir = retry
+        .assembly_file
+        .parent()
+        .unwrap_or(Path::new("."))
+        .join("minimal_analysis");
+    let _ = create_dir_all(&minimal_build_dir);
+    let minimal_assembly_file = minimal_build_dir.join("minimal_output.S");
+    generate_minimal_assembly_for_analysis(
+        &minimal_instructions,
+        &minimal_assembly_file,
+        &assembly_content,
+    )?;
+
+    let reproduced = run_minimal_analysis(
+        &minimal_build_dir,
+        &minimal_assembly_file,
+        march_string,
+        registry,
+        &retry.diff,
+    )?;
+    Ok(reproduced.then_some(minimal_assembly_file))
+}
+
+/// 检查是否存在可切片的寄存器或 CSR 差异
 fn has_register_differences(reg_diff: &RegistersDumpDiff) -> bool {
-    !reg_diff.int_registers_diff.is_empty() || !reg_diff.float_registers_diff.is_empty()
+    !reg_diff.int_registers_diff.is_empty()
+        || !reg_diff.float_registers_diff.is_empty()
+        || !reg_diff.core_csrs_diff.is_empty()
+        || reg_diff.float_csr_diff.is_some()
 }
 
 /// 提取存在差异的寄存器名称
@@ -608,6 +1717,16 @@ fn extract_differing_registers(reg_diff: &RegistersDumpDiff) -> Vec<String> {
         differing_regs.push(format!("f{}", idx));
     }
 
+    // fcsr 差异按 fflags/frm 的依赖链切片
+    if reg_diff.float_csr_diff.is_some() {
+        differing_regs.push("fcsr".to_string());
+    }
+
+    // 添加 CSR 差异
+    for (name, _val1, _val2) in &reg_diff.core_csrs_diff {
+        differing_regs.push(name.clone());
+    }
+
     differing_regs
 }
 
@@ -617,98 +1736,59 @@ fn generate_minimal_assembly_for_analysis(
     output_file: &PathBuf,
     original_assembly: &str,
 ) -> Result<()> {
-    // 提取原汇编文件的头部和尾部
-    let lines: Vec<&str> = original_assembly.lines().collect();
-    let mut header_lines = Vec::new();
-    let mut footer_lines = Vec::new();
-    let mut in_user_code = false;
-    let mut after_user_code = false;
-
-    for line in lines {
-        let trimmed = line.trim();
-        if trimmed == "_user_code:" {
-            header_lines.push(line);
-            in_user_code = true;
-        } else if in_user_code && trimmed.ends_with(":") && !trimmed.contains(' ') {
-            after_user_code = true;
-            footer_lines.push(line);
-        } else if !in_user_code {
-            header_lines.push(line);
-        } else if after_user_code {
-            footer_lines.push(line);
-        }
-    }
-
-    let mut result = String::new();
-
-    // 添加头部
-    for line in header_lines {
-        result.push_str(line);
-        result.push('\n');
-    }
-
-    // 添加最小化指令
-    for inst in minimal_instructions {
-        result.push_str("    ");
-        result.push_str(inst);
-        result.push('\n');
-    }
-
-    // 添加尾部
-    for line in footer_lines {
-        result.push_str(line);
-        result.push('\n');
-    }
-
+    let result = replace_user_code(original_assembly, minimal_instructions);
     fs::write(output_file, result)?;
     Ok(())
 }
 
-/// 运行最小化分析
+/// 运行最小化分析，返回最小化程序是否仍有寄存器差异
 fn run_minimal_analysis(
     build_dir: &PathBuf,
     assembly_file: &PathBuf,
     march_string: &str,
-    retry_diff: &StandardExecutionOutputDiff, // 传递rocket retry的差异结果
-) -> Result<()> {
+    registry: &EmulatorRegistry,
+    retry_diff: &StandardExecutionOutputDiff, // 重试后的差异结果
+) -> Result<bool> {
+    let mut reproduced = false;
     let linker_script = get_or_create_linker_script(assembly_file)?;
 
     info!("🔬 Building minimal analysis ELF...");
     let build_result = build_elf(assembly_file, &linker_script, march_string)?;
 
-    // 运行模拟器
-    info!("🏃 Running minimal analysis - Spike emulator...");
-    let spike_run_res = run_emulator(
-        &build_dir.join("spike_minimal.bin"),
-        &build_result.executable_file,
-        march_string,
-        EmulatorType::Spike,
-    );
-
-    info!("🏃 Running minimal analysis - Rocket emulator...");
-    let rocket_run_res = run_emulator(
-        &build_dir.join("rocket_minimal.bin"),
-        &build_result.executable_file,
-        march_string,
-        EmulatorType::Rocket,
-    );
+    // 在重试差异比较的两个模拟器上运行
+    let emulators = retry_diff.emulator_types();
+    let [sim1_run_res, sim2_run_res] = emulators.clone().map(|emulator_type| {
+        info!(
+            "🏃 Running minimal analysis - {} emulator...",
+            emulator_type
+        );
+        let raw_output_path = registry
+            .require(emulator_type.clone())
+            .map(|emulator| build_dir.join(format!("{}_minimal.bin", emulator.name())))?;
+        run_emulator(
+            registry,
+            &raw_output_path,
+            &build_result.executable_file,
+            emulator_type,
+        )
+    });
 
     // 解析输出并比较
-    if let (Ok(spike_path), Ok(rocket_path)) = (spike_run_res, rocket_run_res) {
-        let spike_output = parse_output_from_file::<StandardExecutionOutput, _>(
-            &spike_path,
+    if let (Ok(sim1_path), Ok(sim2_path)) = (sim1_run_res, sim2_run_res) {
+        let sim1_output = parse_output_from_file::<StandardExecutionOutput, _>(
+            &sim1_path,
             &build_result.disassembly_file,
-            EmulatorType::Spike,
+            emulators[0].clone(),
         );
-        let rocket_output = parse_output_from_file::<StandardExecutionOutput, _>(
-            &rocket_path,
+        let sim2_output = parse_output_from_file::<StandardExecutionOutput, _>(
+            &sim2_path,
             &build_result.disassembly_file,
-            EmulatorType::Rocket,
+            emulators[1].clone(),
         );
 
-        if let (Ok(spike_out), Ok(rocket_out)) = (spike_output, rocket_output) {
+        if let (Ok(sim1_out), Ok(sim2_out)) = (sim1_output, sim2_output) {
             info!("🔄 Comparing minimal analysis outputs...");
-            let minimal_diff = compare_outputs(&spike_out, &rocket_out);
+            let minimal_diff = compare_outputs(&sim1_out, &sim2_out);
 
             // 保存最小化分析结果
             let minimal_diff_json = serde_json::to_string_pretty(&minimal_diff)?;
@@ -718,7 +1798,7 @@ fn run_minimal_analysis(
             fs::write(&minimal_diff_json_file, minimal_diff_json)?;
             fs::write(&minimal_diff_text_file, minimal_diff_text)?;
 
-            // 生成 diff diff 报告 (比较rocket retry的差异和最小化代码的差异)
+            // 生成 diff diff 报告 (比较重试后的差异和最小化代码的差异)
             let minimal_analysis_report = compare_output_diffs(retry_diff, &minimal_diff);
             let minimal_analysis_report_file = build_dir.join("minimal_vs_retry_diff_report.md");
             fs::write(
@@ -736,6 +1816,7 @@ fn run_minimal_analysis(
             if let Some(reg_diff) = &minimal_diff.register_dump_diff {
                 if !reg_diff.is_empty() && has_register_differences(reg_diff) {
                     info!("🎯 Minimal analysis still shows register differences");
+                    reproduced = true;
                 } else {
                     info!(
                         "✅ Minimal analysis shows no register differences - issue may be resolved"
@@ -749,17 +1830,30 @@ fn run_minimal_analysis(
         warn!("⚠️ Failed to run minimal analysis emulators");
     }
 
-    Ok(())
+    Ok(reproduced)
 }
 
-fn generate_random_assembly(build_dir: &PathBuf, inst_num: usize) -> Result<PathBuf> {
+/// 按 `record` 中的种子生成随机汇编，并把种子记录到 `build_dir`
+fn generate_random_assembly(build_dir: &PathBuf, record: &SeedRecord) -> Result<PathBuf> {
     let mut instruction_counts = HashMap::new();
-    for &extension in RV64_ROCKET_SUPPORTED_EXTENSIONS {
-        instruction_counts.insert(extension, inst_num);
+    for &extension in &record.extensions {
+        instruction_counts.insert(extension, record.inst_num);
+    }
+    let rng = &mut seeded_rng(record.seed);
+
+    // 记录生效的黑名单，复现时不受之后学习到的操作码影响
+    let generation = &config::get().generation;
+    let denylist = match &record.denylist {
+        Some(denylist) => denylist.clone(),
+        None => OpcodeDenylist::load_or_default(&generation.opcode_denylist)?,
+    };
+    SeedRecord {
+        denylist: Some(denylist.clone()),
+        ..record.clone()
     }
-    let rng = &mut rand::rng();
+    .save(build_dir)?;
 
-    let insts = generate_instructions(&instruction_counts, GenerationOrder::RandomShuffle, rng);
+    let insts = generate_instructions(&instruction_counts, generation.order, &denylist, rng);
 
     let asm_str = generate_standard_asm_from_insts(&insts);
 
@@ -769,15 +1863,14 @@ fn generate_random_assembly(build_dir: &PathBuf, inst_num: usize) -> Result<Path
     Ok(assembly_file)
 }
 
-fn setup_environment() -> Result<String> {
+fn setup_environment(config_path: Option<&Path>) -> Result<String> {
     env_logger::Builder::from_default_env()
         .filter_level(log::LevelFilter::Info)
         .format_timestamp_secs()
         .init();
 
-    let mut exts = RV64_ROCKET_SUPPORTED_EXTENSIONS.to_vec();
-    exts.push(riscv_instruction::separated_instructions::RV64Extensions::D);
+    config::init(FuzzConfig::load_or_default(config_path)?)?;
 
-    let march_string = build_rv64_march(&exts);
+    let march_string = config::get().isa.march_string();
     Ok(march_string)
 }
@@ -0,0 +1,495 @@
+//! 差异用例的 delta-debugging 最小化
+//!
+//! Unlike the static register slice of
+//! [`extract_minimal_instructions_for_regs`](crate::output_diff::analysis::shortten_asm_for_regs::extract_minimal_instructions_for_regs),
+//! [`minimize`] runs ddmin over the user-code instructions and rebuilds and
+//! re-runs the emulator pair of the target diff for every candidate. A candidate is kept only if
+//! its [`DivergenceSignature`] equals the target one, so the resulting
+//! `minimized.S` is a verified reproducer. The candidates of a round are
+//! tested in parallel on the rayon pool.
+
+use log::{info, warn};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::campaign::interrupted;
+use crate::consts::linker_script::LINKER_SCRIPT;
+use crate::elf::build::build_elf;
+use crate::emulators::outcome::run_outcome_path;
+use crate::emulators::registry::EmulatorRegistry;
+use crate::emulators::{EmulatorType, RunLimits};
+use crate::error::{Result, RiscvFuzzError};
+use crate::output_diff::analysis::bucket::DivergenceSignature;
+use crate::output_diff::diff::compare_outputs;
+use crate::output_diff::diff::run_diff::{RunDivergence, load_run_outcome_diff};
+use crate::output_diff::diff::standard_diff::StandardExecutionOutputDiff;
+use crate::output_parser::OutputParser;
+use crate::output_parser::standard::StandardExecutionOutput;
+use crate::utils::extract_user_code_instructions;
+
+/// 最小化工作目录名 (位于测试目录下)
+pub const MINIMIZE_DIR: &str = "ddmin";
+/// 验证过的最小复现程序
+pub const MINIMIZED_FILE: &str = "minimized.S";
+/// 最小化报告文件名 (不含扩展名)
+pub const MINIMIZE_REPORT_FILE: &str = "minimize_report";
+
+/// 用 `instructions` 替换 `_user_code:` 与下一个标签之间的指令
+pub fn replace_user_code(original_assembly: &str, instructions: &[String]) -> String {
+    let mut header_lines = Vec::new();
+    let mut footer_lines = Vec::new();
+    let mut in_user_code = false;
+    let mut after_user_code = false;
+
+    for line in original_assembly.lines() {
+        let trimmed = line.trim();
+        if trimmed == "_user_code:" {
+            header_lines.push(line);
+            in_user_code = true;
+        } else if in_user_code && trimmed.ends_with(':') && !trimmed.contains(' ') {
+            after_user_code = true;
+            footer_lines.push(line);
+        } else if !in_user_code {
+            header_lines.push(line);
+        } else if after_user_code {
+            footer_lines.push(line);
+        }
+    }
+
+    let mut result = String::new();
+    for line in header_lines {
+        result.push_str(line);
+        result.push('\n');
+    }
+    for inst in instructions {
+        result.push_str("    ");
+        result.push_str(inst);
+        result.push('\n');
+    }
+    for line in footer_lines {
+        result.push_str(line);
+        result.push('\n');
+    }
+    result
+}
+
+/// 一次候选运行的结果
+struct CandidateRun {
+    signature: DivergenceSignature,
+    /// 两个模拟器都正常结束时的标准差异
+    diff: Option<StandardExecutionOutputDiff>,
+}
+
+struct Minimizer<'a> {
+    template: &'a str,
+    linker_script: String,
+    work_dir: &'a Path,
+    march: &'a str,
+    registry: EmulatorRegistry,
+    next_candidate: AtomicUsize,
+}
+
+impl Minimizer<'_> {
+    /// 在 `dir` 中构建并运行一组用户指令，返回其差异签名
+    fn run(&self, instructions: &[String], dir: &Path) -> Result<CandidateRun> {
+        fs::create_dir_all(dir)?;
+        let assembly_file = dir.join("candidate.S");
+        let linker_script = dir.join("linker.ld");
+        fs::write(
+            &assembly_file,
+            replace_user_code(self.template, instructions),
+        )?;
+        fs::write(&linker_script, &self.linker_script)?;
+
+        let build_result = build_elf(&assembly_file, &linker_script, self.march)?;
+
+        let runs: Vec<_> = self
+            .registry
+            .iter()
+            .map(|emulator| {
+                let log_file = dir.join(format!("{}_output.bin", emulator.name()));
+                let outcome = emulator
+                    .execute(&build_result.executable_file, &log_file)
+                    .and_then(|outcome| {
+                        // 保存运行结果，供 load_run_outcome_diff 使用
+                        outcome.save(run_outcome_path(&log_file))?;
+                        Ok(outcome)
+                    });
+                (emulator.emulator_type(), log_file, outcome)
+            })
+            .collect();
+        let [(sim1, log1, outcome1), (sim2, log2, outcome2)] = &runs[..] else {
+            return Err(RiscvFuzzError::diff_analysis(
+                "Minimization needs exactly two emulators",
+            ));
+        };
+        let (outcome1, outcome2) = match (outcome1, outcome2) {
+            (Ok(outcome1), Ok(outcome2)) => (outcome1, outcome2),
+            (Err(e), _) | (_, Err(e)) => {
+                return Err(RiscvFuzzError::diff_analysis(format!(
+                    "Candidate in {} failed to run: {}",
+                    dir.display(),
+                    e
+                )));
+            }
+        };
+
+        if let Some(run_divergence) = RunDivergence::from_outcomes(outcome1, outcome2) {
+            return Ok(CandidateRun {
+                signature: DivergenceSignature::from_run_divergence(&run_divergence),
+                diff: None,
+            });
+        }
+        if let Some(outcome) = [outcome1, outcome2]
+            .into_iter()
+            .find(|outcome| !outcome.is_usable())
+        {
+            return Err(RiscvFuzzError::diff_analysis(format!(
+                "Candidate in {} failed to run: {} on {}",
+                dir.display(),
+                outcome.classification,
+                outcome.emulator_type
+            )));
+        }
+
+        let output1 = StandardExecutionOutput::parse_from_file(
+            log1,
+            &build_result.disassembly_file,
+            sim1.clone(),
+        )?;
+        let output2 = StandardExecutionOutput::parse_from_file(
+            log2,
+            &build_result.disassembly_file,
+            sim2.clone(),
+        )?;
+        let mut diff = compare_outputs(&output1, &output2);
+        diff.run_outcome_diff = load_run_outcome_diff(log1, log2);
+        Ok(CandidateRun {
+            signature: DivergenceSignature::from_standard_diff(&diff),
+            diff: Some(diff),
+        })
+    }
+
+    /// 候选是否保留目标签名；候选目录在测试后删除
+    fn preserves(&self, instructions: &[String], target: &DivergenceSignature) -> bool {
+        let id = self.next_candidate.fetch_add(1, Ordering::Relaxed);
+        let dir = self.work_dir.join(format!("candidate_{:05}", id));
+        let preserved = match self.run(instructions, &dir) {
+            Ok(run) => run.signature == *target,
+            Err(e) => {
+                warn!("⚠️ Candidate {} rejected: {}", id, e);
+                false
+            }
+        };
+        let _ = fs::remove_dir_all(&dir);
+        preserved
+    }
+}
+
+/// 最小化结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MinimizeReport {
+    pub source: PathBuf,
+    pub reproducer: PathBuf,
+    pub original_len: usize,
+    pub minimized_len: usize,
+    pub rounds: usize,
+    pub candidates_tested: usize,
+    pub target: DivergenceSignature,
+    /// 最终程序重新运行后的签名
+    pub signature: DivergenceSignature,
+    pub verified: bool,
+    pub instructions: Vec<String>,
+}
+
+impl MinimizeReport {
+    pub fn save<P: AsRef<Path>>(&self, dir: P) -> Result<()> {
+        let base = dir.as_ref().join(MINIMIZE_REPORT_FILE);
+        fs::write(
+            base.with_extension("json"),
+            serde_json::to_string_pretty(self)?,
+        )?;
+        fs::write(base.with_extension("md"), self.to_string())?;
+        Ok(())
+    }
+}
+
+impl fmt::Display for MinimizeReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "# ✂️ Minimization Report")?;
+        writeln!(f)?;
+        writeln!(f, "| Item | Value |")?;
+        writeln!(f, "|------|-------|")?;
+        writeln!(f, "| Source | `{}` |", self.source.display())?;
+        writeln!(f, "| Reproducer | `{}` |", self.reproducer.display())?;
+        writeln!(
+            f,
+            "| Instructions | {} → {} |",
+            self.original_len, self.minimized_len
+        )?;
+        writeln!(f, "| Rounds | {} |", self.rounds)?;
+        writeln!(f, "| Candidates tested | {} |", self.candidates_tested)?;
+        writeln!(f, "| Target signature | `{}` |", self.target)?;
+        writeln!(f, "| Final signature | `{}` |", self.signature)?;
+        writeln!(
+            f,
+            "| Verified | {} |",
+            if self.verified { "✅ yes" } else { "❌ no" }
+        )?;
+        writeln!(f)?;
+        writeln!(f, "## 📜 Minimized User Code")?;
+        writeln!(f)?;
+        writeln!(f, "```asm")?;
+        for inst in &self.instructions {
+            writeln!(f, "{}", inst)?;
+        }
+        writeln!(f, "```")
+    }
+}
+
+/// ddmin 主循环：返回保持 `preserves` 成立的最小指令序列及轮数
+///
+/// 每轮先试各子集，再试补集，同一轮的候选在 rayon 线程池上并行测试。
+/// 收到 Ctrl-C 后不再开始新的一轮。
+fn ddmin<F>(original: Vec<String>, preserves: F) -> (Vec<String>, usize)
+where
+    F: Fn(&[String]) -> bool + Sync,
+{
+    let mut current = original;
+    let mut granularity = 2;
+    let mut rounds = 0;
+    while current.len() >= 2 {
+        if interrupted() {
+            warn!("🛑 Minimization interrupted after {} rounds", rounds);
+            break;
+        }
+        rounds += 1;
+        let chunk_len = current.len().div_ceil(granularity);
+        let subsets: Vec<Vec<String>> = current.chunks(chunk_len).map(<[_]>::to_vec).collect();
+        let subset_count = subsets.len();
+        // 先试子集，再试补集；两块时补集与子集相同
+        let mut candidates = subsets.clone();
+        if subset_count > 2 {
+            candidates.extend((0..subset_count).map(|skip| {
+                subsets
+                    .iter()
+                    .enumerate()
+                    .filter(|(i, _)| *i != skip)
+                    .flat_map(|(_, chunk)| chunk.iter().cloned())
+                    .collect()
+            }));
+        }
+
+        let found = candidates
+            .par_iter()
+            .position_first(|candidate| preserves(candidate));
+        match found {
+            Some(i) => {
+                current = candidates.swap_remove(i);
+                granularity = if i < subset_count {
+                    2
+                } else {
+                    (granularity - 1).max(2)
+                };
+            }
+            None if granularity >= current.len() => break,
+            None => granularity = (granularity * 2).min(current.len()),
+        }
+        info!(
+            "✂️ Round {}: {} instructions left (granularity {})",
+            rounds,
+            current.len(),
+            granularity
+        );
+    }
+    (current, rounds)
+}
+
+/// 对 `assembly_file` 的用户代码做 ddmin，结果写入 `work_dir`
+///
+/// 每个候选都在 `emulators` 上运行，应与产生 `target` 的差异比较的模拟器一致。
+/// `target` 为空时以原程序的签名为目标。原程序必须复现目标签名。
+pub fn minimize(
+    assembly_file: &Path,
+    work_dir: &Path,
+    march: &str,
+    limits: &RunLimits,
+    emulators: &[EmulatorType; 2],
+    target: Option<DivergenceSignature>,
+) -> Result<MinimizeReport> {
+    let template = fs::read_to_string(assembly_file).map_err(|e| {
+        RiscvFuzzError::file(format!("Failed to read {}: {}", assembly_file.display(), e))
+    })?;
+    let original = extract_user_code_instructions(&template);
+    if original.is_empty() {
+        return Err(RiscvFuzzError::file(format!(
+            "No user code instructions in {}",
+            assembly_file.display()
+        )));
+    }
+    fs::create_dir_all(work_dir)?;
+
+    let source_linker = assembly_file.with_file_name("linker.ld");
+    let linker_script = if source_linker.exists() {
+        fs::read_to_string(&source_linker)?
+    } else {
+        LINKER_SCRIPT.to_string()
+    };
+    let minimizer = Minimizer {
+        template: &template,
+        linker_script,
+        work_dir,
+        march,
+        registry: EmulatorRegistry::with_limits(march, limits).select(emulators)?,
+        next_candidate: AtomicUsize::new(0),
+    };
+
+    // 原程序必须复现目标签名
+    let original_signature = minimizer
+        .run(&original, &work_dir.join("original"))?
+        .signature;
+    let target = target.unwrap_or_else(|| original_signature.clone());
+    if target.is_empty() {
+        return Err(RiscvFuzzError::diff_analysis(format!(
+            "{} does not diverge, nothing to minimize",
+            assembly_file.display()
+        )));
+    }
+    if original_signature != target {
+        return Err(RiscvFuzzError::diff_analysis(format!(
+            "{} no longer reproduces `{}` (got `{}`)",
+            assembly_file.display(),
+            target,
+            original_signature
+        )));
+    }
+    info!(
+        "✂️ Minimizing {} instructions for `{}`",
+        original.len(),
+        target
+    );
+
+    let (current, rounds) = ddmin(original.clone(), |candidate| {
+        minimizer.preserves(candidate, &target)
+    });
+    if interrupted() {
+        return Err(RiscvFuzzError::system("Minimization interrupted"));
+    }
+
+    // 最终结果重新构建运行一次，保存差异
+    let reproducer = work_dir.join(MINIMIZED_FILE);
+    fs::write(&reproducer, replace_user_code(&template, &current))?;
+    let verify_dir = work_dir.join("verify");
+    let final_run = minimizer.run(&current, &verify_dir)?;
+    if let Some(diff) = &final_run.diff {
+        fs::write(
+            verify_dir.join("diff_standard.json"),
+            serde_json::to_string_pretty(diff)?,
+        )?;
+        fs::write(verify_dir.join("diff_standard.md"), diff.to_string())?;
+    }
+
+    let report = MinimizeReport {
+        source: assembly_file.to_path_buf(),
+        reproducer,
+        original_len: original.len(),
+        minimized_len: current.len(),
+        rounds,
+        candidates_tested: minimizer.next_candidate.load(Ordering::Relaxed),
+        verified: final_run.signature == target,
+        target,
+        signature: final_run.signature,
+        instructions: current,
+    };
+    report.save(work_dir)?;
+    if report.verified {
+        info!(
+            "✅ Minimized {} → {} instructions: {:?}",
+            report.original_len, report.minimized_len, report.reproducer
+        );
+    } else {
+        warn!(
+            "⚠️ Minimized program did not reproduce `{}` on re-run (got `{}`)",
+            report.target, report.signature
+        );
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    fn insts(names: &str) -> Vec<String> {
+        names.split_whitespace().map(str::to_string).collect()
+    }
+
+    fn contains_all(candidate: &[String], needed: &[&str]) -> bool {
+        needed.iter().all(|n| candidate.iter().any(|i| i == n))
+    }
+
+    #[test]
+    fn test_ddmin_finds_single_culprit() {
+        let (minimized, rounds) = ddmin(insts("a b c d e f g h"), |c| contains_all(c, &["f"]));
+        assert_eq!(minimized, insts("f"));
+        assert!(rounds >= 3);
+    }
+
+    #[test]
+    fn test_ddmin_keeps_interacting_instructions_in_order() {
+        let (minimized, _) = ddmin(insts("a b c d e f g h i"), |c| {
+            contains_all(c, &["b", "e", "h"])
+        });
+        assert_eq!(minimized, insts("b e h"));
+    }
+
+    #[test]
+    fn test_ddmin_candidates_are_ordered_proper_subsets() {
+        let original = insts("a b c d e f g");
+        let tested = Mutex::new(Vec::new());
+        let (minimized, _) = ddmin(original.clone(), |c| {
+            tested.lock().unwrap().push(c.to_vec());
+            contains_all(c, &["c", "g"])
+        });
+        assert_eq!(minimized, insts("c g"));
+        for candidate in tested.into_inner().unwrap() {
+            assert!(!candidate.is_empty() && candidate.len() < original.len());
+            let mut rest = original.iter();
+            assert!(
+                candidate.iter().all(|i| rest.any(|o| o == i)),
+                "{:?} is not an ordered subset",
+                candidate
+            );
+        }
+    }
+
+    #[test]
+    fn test_ddmin_terminates_when_nothing_can_be_removed() {
+        let original = insts("a b c d e");
+        let calls = AtomicUsize::new(0);
+        let (minimized, _) = ddmin(original.clone(), |c| {
+            calls.fetch_add(1, Ordering::Relaxed);
+            c.len() == original.len()
+        });
+        assert_eq!(minimized, original);
+        assert!(calls.into_inner() > 0);
+
+        let (minimized, rounds) = ddmin(insts("a"), |_| unreachable!());
+        assert_eq!((minimized, rounds), (insts("a"), 0));
+    }
+
+    #[test]
+    fn test_replace_user_code_keeps_surrounding_sections() {
+        let template = "_start:\n    j _user_code\n_user_code:\n    addi a0, a0, 1\n    add a1, a0, a0\n_end:\n    ecall\n";
+        let replaced = replace_user_code(template, &insts("nop"));
+        assert_eq!(
+            replaced,
+            "_start:\n    j _user_code\n_user_code:\n    nop\n_end:\n    ecall\n"
+        );
+    }
+}
@@ -1,2 +1,5 @@
+pub mod bucket;
 pub mod remove_rocket_illegal_inst;
-pub mod shortten_asm_for_regs;
\ No newline at end of file
+pub mod retry;
+pub mod shortten_asm_for_regs;
+pub mod triage;
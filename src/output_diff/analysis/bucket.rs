@@ -0,0 +1,586 @@
+//! 差异签名与分桶
+//!
+//! A [`DivergenceSignature`] reduces a Standard diff to what identifies the
+//! bug rather than the random program: the first divergent exception in
+//! program order (its category, mcause and the opcode that raised it), the
+//! class of the first differing register, and whether only one side produced
+//! a register dump. Later differences are left out, since they mostly follow
+//! from the first one and vary with the registers the program happens to use.
+//! Tests with the same signature land in one [`Bucket`] of the campaign's
+//! [`BucketIndex`], which keeps the shortest reproducer seen so far.
+
+use log::info;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::error::{Result, RiscvFuzzError};
+use crate::output_diff::diff::run_diff::RunDivergence;
+use crate::output_diff::diff::standard_diff::StandardExecutionOutputDiff;
+use crate::output_diff::diff::{
+    ExceptionDiffCategory, ExceptionDiffInfo, ExceptionEdit, ExceptionListDiff, RegistersDumpDiff,
+    format_category_title,
+};
+use crate::output_parser::ExceptionDump;
+use crate::output_parser::util::get_current_timestamp;
+use crate::random_asm::denylist::opcode_of;
+use crate::utils::extract_user_code_instructions;
+
+/// 分桶索引文件名 (不含扩展名)，同名目录保存各桶的复现程序
+pub const BUCKET_INDEX_FILE: &str = "buckets";
+
+/// 每个桶保留的最近测试目录数 (`hits` 仍统计全部)
+pub const MAX_BUCKET_TESTS: usize = 32;
+
+/// What identifies a divergence, independent of the random program around it
+#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct DivergenceSignature {
+    /// 首个异常差异的类别、mcause 及触发指令的助记符
+    pub exception: Option<String>,
+    /// 首个不同的寄存器类别 (`int`、`float`) 或 CSR 名
+    pub register: Option<String>,
+    /// 仅一侧有寄存器 dump (如 "Present in spike, Absent in rocket")
+    #[serde(default)]
+    pub register_dump: Option<String>,
+    /// 运行结果差异 (如仅一个模拟器超时)
+    pub run: Option<String>,
+}
+
+/// 异常对应的触发指令助记符，没有指令追踪时为 `?`
+fn exception_opcode(ex: &ExceptionDump) -> String {
+    ex.inst_trace
+        .as_ref()
+        .and_then(|trace| opcode_of(&trace.disassembly))
+        .unwrap_or_else(|| "?".to_string())
+}
+
+/// 按程序顺序的首个异常差异
+fn first_exception(ex_diff: &ExceptionListDiff) -> Option<String> {
+    let only_in = |ex: &ExceptionDump, simulator| {
+        let category = ExceptionDiffCategory::OnlyInSimulator {
+            simulator,
+            mcause: ex.csrs.mcause,
+        };
+        Some(format!(
+            "{} @ {}",
+            format_category_title(&category),
+            exception_opcode(ex)
+        ))
+    };
+    for edit in &ex_diff.edits {
+        match edit {
+            ExceptionEdit::Matched(k) | ExceptionEdit::Substituted(k) => {
+                let paired = &ex_diff.paired_exceptions_diffs[*k];
+                let Some((csr_name, sim1_value, sim2_value)) = paired.csrs_differences.first()
+                else {
+                    continue;
+                };
+                let ex = &paired.exception1;
+                let category = ExceptionDiffInfo::CsrDifference {
+                    pc: ex.csrs.mepc,
+                    csr_name: csr_name.clone(),
+                    sim1_value: *sim1_value,
+                    sim2_value: *sim2_value,
+                    sim1_description: None,
+                    sim2_description: None,
+                    instruction_trace: None,
+                }
+                .get_category();
+                let title = format_category_title(&category);
+                return Some(match category {
+                    ExceptionDiffCategory::McauseDifference { .. } => {
+                        format!("{} @ {}", title, exception_opcode(ex))
+                    }
+                    _ => format!(
+                        "{} (mcause: 0x{:X}) @ {}",
+                        title,
+                        ex.csrs.mcause,
+                        exception_opcode(ex)
+                    ),
+                });
+            }
+            ExceptionEdit::Deleted(k) => {
+                return only_in(
+                    &ex_diff.list1_only_exceptions[*k],
+                    ex_diff.sim1_emulator_type.clone(),
+                );
+            }
+            ExceptionEdit::Inserted(k) => {
+                return only_in(
+                    &ex_diff.list2_only_exceptions[*k],
+                    ex_diff.sim2_emulator_type.clone(),
+                );
+            }
+        }
+    }
+
+    // 没有对齐结果的旧 diff 只能按类别汇总的顺序取第一个
+    ex_diff.categorized_summary.first().map(|cat_diff| {
+        let opcode = cat_diff
+            .pc_instruction_traces
+            .iter()
+            .flatten()
+            .find_map(|trace| opcode_of(&trace.disassembly))
+            .unwrap_or_else(|| "?".to_string());
+        format!("{} @ {}", format_category_title(&cat_diff.category), opcode)
+    })
+}
+
+/// 首个不同的寄存器类别: 整数寄存器、浮点寄存器、fcsr，然后按 dump 顺序的 CSR
+fn first_register_class(reg_diff: &RegistersDumpDiff) -> Option<String> {
+    if !reg_diff.int_registers_diff.is_empty() {
+        return Some("int".to_string());
+    }
+    if !reg_diff.float_registers_diff.is_empty()
+        || reg_diff.float_registers_status_changed.is_some()
+    {
+        return Some("float".to_string());
+    }
+    if reg_diff.float_csr_diff.is_some() || reg_diff.float_csr_status_changed.is_some() {
+        return Some("fcsr".to_string());
+    }
+    // `[diff.ignore]` 中的 CSR 已在比较时移出 core_csrs_diff
+    reg_diff
+        .core_csrs_diff
+        .first()
+        .map(|(name, _, _)| name.clone())
+}
+
+impl DivergenceSignature {
+    pub fn from_standard_diff(diff: &StandardExecutionOutputDiff) -> Self {
+        let run = diff
+            .run_outcome_diff
+            .as_ref()
+            .filter(|d| !d.is_empty())
+            .map(|run_diff| match &run_diff.classification_changed {
+                Some((class1, class2)) => format!("{:?} vs {:?}", class1, class2),
+                None => "tohost exit code".to_string(),
+            });
+
+        Self {
+            exception: diff.exceptions_diff.as_ref().and_then(first_exception),
+            register: diff
+                .register_dump_diff
+                .as_ref()
+                .and_then(first_register_class),
+            register_dump: diff.register_dump_status.clone(),
+            run,
+        }
+    }
+
+    pub fn from_run_divergence(divergence: &RunDivergence) -> Self {
+        let run = match divergence {
+            RunDivergence::Timeout { timed_out, .. } => format!("{} timeout", timed_out),
+            RunDivergence::Crash { crashed, .. } => format!("{} crash", crashed),
+            RunDivergence::NoOutput { silent, .. } => format!("{} no output", silent),
+            RunDivergence::FailCode { failed, .. } => format!("{} fail code", failed),
+        };
+        Self {
+            run: Some(run),
+            ..Self::default()
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.exception.is_none()
+            && self.register.is_none()
+            && self.register_dump.is_none()
+            && self.run.is_none()
+    }
+
+    /// 稳定的桶标识 (签名文本的 FNV-1a 哈希)
+    pub fn key(&self) -> String {
+        let hash = self
+            .to_string()
+            .bytes()
+            .fold(0xCBF2_9CE4_8422_2325u64, |hash, byte| {
+                (hash ^ byte as u64).wrapping_mul(0x0000_0100_0000_01B3)
+            });
+        format!("{:016x}", hash)
+    }
+}
+
+impl fmt::Display for DivergenceSignature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut parts = Vec::new();
+        if let Some(exception) = &self.exception {
+            parts.push(format!("exception: {}", exception));
+        }
+        if let Some(register) = &self.register {
+            parts.push(format!("register: {}", register));
+        }
+        if let Some(status) = &self.register_dump {
+            parts.push(format!("register dump: {}", status));
+        }
+        if let Some(run) = &self.run {
+            parts.push(format!("run: {}", run));
+        }
+        write!(f, "{}", parts.join("; "))
+    }
+}
+
+/// 同一签名的所有测试
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Bucket {
+    pub signature: DivergenceSignature,
+    pub hits: usize,
+    pub first_seen: String,
+    /// 最近的 [`MAX_BUCKET_TESTS`] 个测试目录
+    pub tests: Vec<PathBuf>,
+    /// 最短复现程序的来源
+    pub representative_source: PathBuf,
+    /// Copy of the representative inside the bucket directory
+    pub reproducer: PathBuf,
+    /// 复现程序的用户代码指令数
+    pub reproducer_len: usize,
+}
+
+/// Campaign-level index of divergence buckets
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct BucketIndex {
+    pub buckets: BTreeMap<String, Bucket>,
+}
+
+impl BucketIndex {
+    /// 读取输出目录中已有的索引，便于在同一目录继续活动
+    pub fn load_or_default<P: AsRef<Path>>(output_dir: P) -> Result<Self> {
+        let path = output_dir
+            .as_ref()
+            .join(BUCKET_INDEX_FILE)
+            .with_extension("json");
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = fs::read_to_string(&path).map_err(|e| {
+            RiscvFuzzError::file(format!(
+                "Failed to read bucket index {}: {}",
+                path.display(),
+                e
+            ))
+        })?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    /// Adds one divergent test, replacing the bucket's reproducer when
+    /// `reproducer` is shorter. Returns whether the bucket is new.
+    pub fn record<P: AsRef<Path>>(
+        &mut self,
+        output_dir: P,
+        signature: &DivergenceSignature,
+        test_dir: &Path,
+        reproducer: &Path,
+    ) -> Result<bool> {
+        let key = signature.key();
+        let content = fs::read_to_string(reproducer)?;
+        let reproducer_len = extract_user_code_instructions(&content).len();
+        let bucket_dir = output_dir.as_ref().join(BUCKET_INDEX_FILE).join(&key);
+        let copy = bucket_dir.join("reproducer.S");
+
+        let is_new = !self.buckets.contains_key(&key);
+        let bucket = self.buckets.entry(key.clone()).or_insert_with(|| Bucket {
+            signature: signature.clone(),
+            hits: 0,
+            first_seen: get_current_timestamp(),
+            tests: Vec::new(),
+            representative_source: reproducer.to_path_buf(),
+            reproducer: copy.clone(),
+            reproducer_len: usize::MAX,
+        });
+        bucket.hits += 1;
+        bucket.tests.push(test_dir.to_path_buf());
+        if bucket.tests.len() > MAX_BUCKET_TESTS {
+            bucket.tests.remove(0);
+        }
+
+        if reproducer_len < bucket.reproducer_len {
+            fs::create_dir_all(&bucket_dir)?;
+            fs::write(&copy, content)?;
+            bucket.representative_source = reproducer.to_path_buf();
+            bucket.reproducer_len = reproducer_len;
+            info!(
+                "🪣 Bucket {} reproducer: {:?} ({} instructions)",
+                key, reproducer, reproducer_len
+            );
+        }
+        if is_new {
+            info!("🆕 New divergence bucket {}: {}", key, signature);
+        }
+        Ok(is_new)
+    }
+
+    /// 保存为 `buckets.json` 和 `buckets.md`
+    pub fn save<P: AsRef<Path>>(&self, output_dir: P) -> Result<()> {
+        let base = output_dir.as_ref().join(BUCKET_INDEX_FILE);
+        fs::write(
+            base.with_extension("json"),
+            serde_json::to_string_pretty(self)?,
+        )?;
+        fs::write(base.with_extension("md"), self.to_string())?;
+        Ok(())
+    }
+}
+
+impl fmt::Display for BucketIndex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "# 🪣 Divergence Buckets")?;
+        writeln!(f)?;
+        writeln!(f, "**Total:** `{} buckets`", self.buckets.len())?;
+        writeln!(f)?;
+
+        let mut buckets: Vec<(&String, &Bucket)> = self.buckets.iter().collect();
+        buckets.sort_by(|a, b| b.1.hits.cmp(&a.1.hits).then(a.0.cmp(b.0)));
+
+        writeln!(f, "| Bucket | Hits | Reproducer | Signature |")?;
+        writeln!(f, "|--------|------|------------|-----------|")?;
+        for (key, bucket) in buckets {
+            writeln!(
+                f,
+                "| `{}` | `{}` | `{}` ({} insts) | {} |",
+                key,
+                bucket.hits,
+                bucket.reproducer.display(),
+                bucket.reproducer_len,
+                bucket.signature
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::elf::tracer::InstructionTrace;
+    use crate::emulators::EmulatorType;
+    use crate::output_diff::diff::PairedExceptionDiff;
+    use crate::output_parser::ExceptionCSRs;
+
+    fn ex(mepc: u64, mcause: u64, instruction: &str) -> ExceptionDump {
+        ExceptionDump {
+            csrs: ExceptionCSRs {
+                mstatus: 0,
+                mcause,
+                mepc,
+                mtval: 0,
+                mie: 0,
+                mip: 0,
+                mtvec: 0,
+                mscratch: 0,
+                mhartid: 0,
+            },
+            position: 0,
+            inst_trace: Some(InstructionTrace {
+                pc: mepc,
+                disassembly: instruction.to_string(),
+                machine_code: String::new(),
+                original_instruction: instruction.to_string(),
+            }),
+        }
+    }
+
+    fn exceptions(
+        list2_only: Vec<ExceptionDump>,
+        paired: Vec<(ExceptionDump, &str)>,
+    ) -> ExceptionListDiff {
+        let mut edits = Vec::new();
+        let mut paired_exceptions_diffs = Vec::new();
+        for (k, (ex, csr)) in paired.into_iter().enumerate() {
+            edits.push(ExceptionEdit::Matched(k));
+            paired_exceptions_diffs.push(PairedExceptionDiff {
+                exception1: ex.clone(),
+                exception2: ex,
+                csrs_differences: vec![(csr.to_string(), 1, 2)],
+            });
+        }
+        edits.extend((0..list2_only.len()).map(ExceptionEdit::Inserted));
+        ExceptionListDiff {
+            sim1_emulator_type: EmulatorType::Spike,
+            sim2_emulator_type: EmulatorType::Rocket,
+            list1_only_exceptions: Vec::new(),
+            list2_only_exceptions: list2_only,
+            paired_exceptions_diffs,
+            categorized_summary: Vec::new(),
+            edits,
+            suppressed: Vec::new(),
+        }
+    }
+
+    fn registers(int_registers: &[usize], core_csrs: &[&str]) -> RegistersDumpDiff {
+        RegistersDumpDiff {
+            emulator_type1: EmulatorType::Spike,
+            emulator_type2: EmulatorType::Rocket,
+            int_registers_diff: int_registers
+                .iter()
+                .map(|&i| (i, format!("x{}", i), 1, 2))
+                .collect(),
+            core_csrs_diff: core_csrs
+                .iter()
+                .map(|csr| (csr.to_string(), 1, 2))
+                .collect(),
+            float_registers_status_changed: None,
+            float_registers_diff: Vec::new(),
+            float_csr_status_changed: None,
+            float_csr_diff: None,
+            suppressed: Vec::new(),
+        }
+    }
+
+    fn standard_diff(
+        exceptions_diff: Option<ExceptionListDiff>,
+        register_dump_diff: Option<RegistersDumpDiff>,
+    ) -> StandardExecutionOutputDiff {
+        StandardExecutionOutputDiff {
+            sim1_emulator_type: EmulatorType::Spike,
+            sim2_emulator_type: EmulatorType::Rocket,
+            exceptions_diff,
+            register_dump_status: None,
+            register_dump_diff,
+            conversion_stats_diff: None,
+            run_outcome_diff: None,
+            suppressed: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_signature_ignores_later_differences() {
+        // 同一个缺陷在不同随机程序中影响不同的寄存器和之后的异常
+        let first = standard_diff(
+            Some(exceptions(vec![ex(0x100, 2, "fli.s fa0, 1.0")], vec![])),
+            Some(registers(&[10, 11], &[])),
+        );
+        let second = standard_diff(
+            Some(exceptions(
+                vec![ex(0x200, 2, "fli.s ft1, 0.5"), ex(0x240, 5, "lw a0, 0(a1)")],
+                vec![],
+            )),
+            Some(registers(&[5], &["mstatus"])),
+        );
+
+        let signature = DivergenceSignature::from_standard_diff(&first);
+        assert_eq!(signature, DivergenceSignature::from_standard_diff(&second));
+        assert_eq!(
+            signature.key(),
+            DivergenceSignature::from_standard_diff(&second).key()
+        );
+        assert_eq!(signature.register.as_deref(), Some("int"));
+        let exception = signature.exception.unwrap();
+        assert!(exception.contains("Only in Rocket (mcause: 0x2"));
+        assert!(exception.ends_with("@ fli.s"));
+    }
+
+    #[test]
+    fn test_signature_follows_first_divergence() {
+        let only_in = standard_diff(
+            Some(exceptions(vec![ex(0x100, 2, "fli.s fa0, 1.0")], vec![])),
+            None,
+        );
+        // 配对异常的 mtval 差异在程序顺序上先于仅在 Rocket 中出现的异常
+        let mtval_first = standard_diff(
+            Some(exceptions(
+                vec![ex(0x100, 2, "fli.s fa0, 1.0")],
+                vec![(ex(0x80, 5, "lw a0, 0(a1)"), "mtval")],
+            )),
+            None,
+        );
+        let other_opcode = standard_diff(
+            Some(exceptions(vec![ex(0x100, 2, "csrr a0, 0x7c0")], vec![])),
+            None,
+        );
+
+        let signature = |diff| DivergenceSignature::from_standard_diff(diff);
+        assert_ne!(signature(&only_in), signature(&mtval_first));
+        assert_ne!(signature(&only_in), signature(&other_opcode));
+        assert_eq!(
+            signature(&mtval_first).exception.as_deref(),
+            Some("MTVAL Value Difference (mcause: 0x5) @ lw")
+        );
+
+        let csrs = standard_diff(None, Some(registers(&[], &["mtval", "mstatus"])));
+        assert_eq!(signature(&csrs).register.as_deref(), Some("mtval"));
+        assert!(signature(&standard_diff(None, None)).is_empty());
+    }
+
+    fn write_reproducer(dir: &Path, name: &str, instructions: usize) -> PathBuf {
+        let path = dir.join(name);
+        let body: Vec<&str> = vec!["    add a0, a0, a1"; instructions];
+        fs::write(&path, format!("_user_code:\n{}\n_end:\n", body.join("\n"))).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_record_keeps_shortest_reproducer() {
+        let output_dir = std::env::temp_dir().join(format!("bucket_test_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&output_dir);
+        fs::create_dir_all(&output_dir).unwrap();
+        let signature = DivergenceSignature {
+            register: Some("int".to_string()),
+            ..DivergenceSignature::default()
+        };
+        let long = write_reproducer(&output_dir, "long.S", 8);
+        let short = write_reproducer(&output_dir, "short.S", 3);
+        let longer = write_reproducer(&output_dir, "longer.S", 12);
+
+        let mut index = BucketIndex::default();
+        assert!(
+            index
+                .record(&output_dir, &signature, Path::new("test_000000"), &long)
+                .unwrap()
+        );
+        assert!(
+            !index
+                .record(&output_dir, &signature, Path::new("test_000001"), &short)
+                .unwrap()
+        );
+        assert!(
+            !index
+                .record(&output_dir, &signature, Path::new("test_000002"), &longer)
+                .unwrap()
+        );
+
+        let bucket = &index.buckets[&signature.key()];
+        assert_eq!(bucket.hits, 3);
+        assert_eq!(bucket.reproducer_len, 3);
+        assert_eq!(bucket.representative_source, short);
+        assert_eq!(
+            fs::read_to_string(&bucket.reproducer).unwrap(),
+            fs::read_to_string(&short).unwrap()
+        );
+
+        fs::remove_dir_all(&output_dir).unwrap();
+    }
+
+    #[test]
+    fn test_record_caps_bucket_tests() {
+        let output_dir =
+            std::env::temp_dir().join(format!("bucket_cap_test_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&output_dir);
+        fs::create_dir_all(&output_dir).unwrap();
+        let signature = DivergenceSignature {
+            run: Some("Rocket timeout".to_string()),
+            ..DivergenceSignature::default()
+        };
+        let reproducer = write_reproducer(&output_dir, "test.S", 4);
+
+        let mut index = BucketIndex::default();
+        let total = MAX_BUCKET_TESTS + 5;
+        for i in 0..total {
+            let test_dir = PathBuf::from(format!("test_{:06}", i));
+            index
+                .record(&output_dir, &signature, &test_dir, &reproducer)
+                .unwrap();
+        }
+
+        let bucket = &index.buckets[&signature.key()];
+        assert_eq!(bucket.hits, total);
+        assert_eq!(bucket.tests.len(), MAX_BUCKET_TESTS);
+        assert_eq!(bucket.tests[0], PathBuf::from("test_000005"));
+        assert_eq!(
+            bucket.tests.last(),
+            Some(&PathBuf::from(format!("test_{:06}", total - 1)))
+        );
+
+        fs::remove_dir_all(&output_dir).unwrap();
+    }
+}
@@ -0,0 +1,790 @@
+//! 删除问题指令并重试的策略
+//!
+//! A [`RetryPolicy`] (`[diff.retry]`) lists [`RetryRule`]s over the
+//! [`ExceptionDiffCategory`]s of a diff. Every round removes the instructions
+//! that raised a matching exception, rebuilds the program and re-runs the two
+//! emulators the diff compares, until the divergence is gone, no rule selects a remaining
+//! instruction (fixpoint) or `max_rounds` is reached. Each round is compared
+//! with the previous one by [`compare_output_diffs`] and the chain is saved as
+//! `retry/retry_report.md`. Debug diffs carry no exceptions, so their rounds
+//! select from a Standard parse of the same logs.
+
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::campaign::interrupted;
+use crate::config;
+use crate::consts::linker_script::LINKER_SCRIPT;
+use crate::elf::build::build_elf;
+use crate::emulators::registry::EmulatorRegistry;
+use crate::emulators::{EmulatorType, RunLimits};
+use crate::error::{Result, RiscvFuzzError};
+use crate::output_diff::analysis::triage::CategoryKind;
+use crate::output_diff::diff::common_diff::CommonExecutionOutputDiff;
+use crate::output_diff::diff::debug_diff::DebugExecutionOutputDiff;
+use crate::output_diff::diff::run_diff::{RunOutcomeDiff, load_run_outcome_diff};
+use crate::output_diff::diff::standard_diff::StandardExecutionOutputDiff;
+use crate::output_diff::diff::{
+    Diffable, ExceptionDiffCategory, ExceptionListDiff, compare_outputs, format_category_title,
+};
+use crate::output_diff::diff_diff::{DiffDiffable, compare_output_diffs};
+use crate::output_diff::utils::remove_instructions_assembly;
+use crate::output_parser::common::CommonExecutionOutput;
+use crate::output_parser::debug::DebugExecutionOutput;
+use crate::output_parser::standard::StandardExecutionOutput;
+use crate::output_parser::{OutputParser, parse_output_from_file};
+use crate::random_asm::denylist::opcode_of;
+
+/// 重试目录名 (位于构建目录下)
+pub const RETRY_DIR: &str = "retry";
+/// 重试链报告文件名 (不含扩展名)
+pub const RETRY_REPORT_FILE: &str = "retry_report";
+
+/// `[[diff.retry.rules]]`: selects the instructions behind matching
+/// exception differences; unset fields match anything
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RetryRule {
+    pub category: Option<CategoryKind>,
+    /// 仅在该模拟器中出现的异常
+    pub simulator: Option<EmulatorType>,
+    /// 仅一侧出现的异常的 mcause，或 mcause 差异中任一侧的值
+    pub mcause: Option<u64>,
+    /// 只删除该助记符的指令
+    pub opcode: Option<String>,
+}
+
+impl RetryRule {
+    pub fn matches_category(&self, category: &ExceptionDiffCategory) -> bool {
+        self.category
+            .is_none_or(|kind| CategoryKind::of(category) == kind)
+            && self.simulator.as_ref().is_none_or(|expected| {
+                matches!(category, ExceptionDiffCategory::OnlyInSimulator { simulator, .. } if simulator == expected)
+            })
+            && self.mcause.is_none_or(|expected| match category {
+                ExceptionDiffCategory::OnlyInSimulator { mcause, .. } => *mcause == expected,
+                ExceptionDiffCategory::McauseDifference {
+                    sim1_cause,
+                    sim2_cause,
+                } => *sim1_cause == expected || *sim2_cause == expected,
+                _ => false,
+            })
+    }
+
+    fn matches_instruction(&self, disassembly: &str) -> bool {
+        self.opcode.as_ref().is_none_or(|opcode| {
+            opcode_of(disassembly).is_some_and(|actual| actual.eq_ignore_ascii_case(opcode))
+        })
+    }
+}
+
+/// `[diff.retry]`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RetryPolicy {
+    /// 最多重试轮数
+    pub max_rounds: usize,
+    pub rules: Vec<RetryRule>,
+}
+
+impl Default for RetryPolicy {
+    /// 删除仅 Rocket 报非法指令 (mcause=2) 的指令
+    fn default() -> Self {
+        Self {
+            max_rounds: 3,
+            rules: vec![RetryRule {
+                category: Some(CategoryKind::OnlyInSimulator),
+                simulator: Some(EmulatorType::Rocket),
+                mcause: Some(2),
+                opcode: None,
+            }],
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// 配置文件中的策略
+    pub fn current() -> &'static RetryPolicy {
+        &config::get().diff.retry
+    }
+
+    /// 被规则选中的差异类别和要删除的原始指令 (去重)
+    pub fn select(&self, exceptions: &ExceptionListDiff) -> (Vec<String>, Vec<String>) {
+        let mut categories = Vec::new();
+        let mut instructions = Vec::new();
+        for cat_diff in &exceptions.categorized_summary {
+            let rules: Vec<&RetryRule> = self
+                .rules
+                .iter()
+                .filter(|rule| rule.matches_category(&cat_diff.category))
+                .collect();
+            let before = instructions.len();
+            for trace in cat_diff.pc_instruction_traces.iter().flatten() {
+                if rules
+                    .iter()
+                    .any(|rule| rule.matches_instruction(&trace.disassembly))
+                {
+                    instructions.push(trace.original_instruction.clone());
+                }
+            }
+            if instructions.len() > before {
+                categories.push(format_category_title(&cat_diff.category));
+            }
+        }
+        instructions.sort_unstable();
+        instructions.dedup();
+        (categories, instructions)
+    }
+
+    /// 上一轮结果之后的决定：按顺序检查差异消失、轮数上限和不动点
+    fn next_step(
+        &self,
+        diverged: bool,
+        exceptions: Option<&ExceptionListDiff>,
+        rounds: usize,
+    ) -> RetryStep {
+        if !diverged {
+            return RetryStep::Stop(RetryStop::Resolved);
+        }
+        if rounds >= self.max_rounds {
+            return RetryStep::Stop(RetryStop::RoundLimit);
+        }
+        let (categories, removed) = exceptions
+            .map(|exceptions| self.select(exceptions))
+            .unwrap_or_default();
+        if removed.is_empty() {
+            return RetryStep::Stop(RetryStop::Fixpoint);
+        }
+        RetryStep::Remove {
+            categories,
+            removed,
+        }
+    }
+}
+
+/// 每轮开始前的决定
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum RetryStep {
+    Stop(RetryStop),
+    Remove {
+        categories: Vec<String>,
+        removed: Vec<String>,
+    },
+}
+
+/// A diff format the retry engine can drive
+pub trait RetryDiff:
+    DiffDiffable<DiffDiffOutput: fmt::Display> + Serialize + fmt::Display + Sized
+{
+    type Output: OutputParser + Diffable<DiffOutput = Self>;
+    /// 差异文件名中的格式名: `diff_<name>.json`
+    const NAME: &'static str;
+
+    /// 比较的两个模拟器，按 sim1、sim2 的顺序
+    fn emulator_types(&self) -> [EmulatorType; 2];
+    /// 可供规则选择的异常差异；没有时从同一日志的 Standard 解析中获取
+    fn exceptions(&self) -> Option<&ExceptionListDiff>;
+    fn is_divergent(&self) -> bool;
+    fn set_run_outcome_diff(&mut self, run_outcome_diff: Option<RunOutcomeDiff>);
+}
+
+impl RetryDiff for StandardExecutionOutputDiff {
+    type Output = StandardExecutionOutput;
+    const NAME: &'static str = "standard";
+
+    fn emulator_types(&self) -> [EmulatorType; 2] {
+        [
+            self.sim1_emulator_type.clone(),
+            self.sim2_emulator_type.clone(),
+        ]
+    }
+
+    fn exceptions(&self) -> Option<&ExceptionListDiff> {
+        self.exceptions_diff.as_ref()
+    }
+
+    /// 异常、寄存器值或寄存器 dump 的有无存在差异 (不含性能计数器等 CSR)
+    fn is_divergent(&self) -> bool {
+        self.exceptions_diff.as_ref().is_some_and(|e| !e.is_empty())
+            || self.register_dump_status.is_some()
+            || self
+                .register_dump_diff
+                .as_ref()
+                .is_some_and(|r| r.has_register_differences())
+    }
+
+    fn set_run_outcome_diff(&mut self, run_outcome_diff: Option<RunOutcomeDiff>) {
+        self.run_outcome_diff = run_outcome_diff;
+    }
+}
+
+impl RetryDiff for DebugExecutionOutputDiff {
+    type Output = DebugExecutionOutput;
+    const NAME: &'static str = "debug";
+
+    fn emulator_types(&self) -> [EmulatorType; 2] {
+        [
+            self.sim1_emulator_type.clone(),
+            self.sim2_emulator_type.clone(),
+        ]
+    }
+
+    fn exceptions(&self) -> Option<&ExceptionListDiff> {
+        None
+    }
+
+    fn is_divergent(&self) -> bool {
+        !self.is_empty()
+    }
+
+    fn set_run_outcome_diff(&mut self, run_outcome_diff: Option<RunOutcomeDiff>) {
+        self.run_outcome_diff = run_outcome_diff;
+    }
+}
+
+impl RetryDiff for CommonExecutionOutputDiff {
+    type Output = CommonExecutionOutput;
+    const NAME: &'static str = "common";
+
+    fn emulator_types(&self) -> [EmulatorType; 2] {
+        [
+            self.sim1_emulator_type.clone(),
+            self.sim2_emulator_type.clone(),
+        ]
+    }
+
+    fn exceptions(&self) -> Option<&ExceptionListDiff> {
+        self.exception_dumps_diff.as_ref()
+    }
+
+    fn is_divergent(&self) -> bool {
+        !self.is_empty()
+    }
+
+    fn set_run_outcome_diff(&mut self, run_outcome_diff: Option<RunOutcomeDiff>) {
+        self.run_outcome_diff = run_outcome_diff;
+    }
+}
+
+/// 一次构建、运行和比较的结果
+#[derive(Debug, Clone)]
+pub struct RetryAttempt<D> {
+    pub assembly_file: PathBuf,
+    pub diff: D,
+    /// 供下一轮选择指令的异常差异
+    pub exceptions: Option<ExceptionListDiff>,
+}
+
+impl<D: RetryDiff> RetryAttempt<D> {
+    /// `raw_outputs` 为 `diff` 中两个模拟器的原始输出，仅在 `diff` 不含异常时重新解析
+    pub fn new(assembly_file: PathBuf, diff: D, raw_outputs: [&Path; 2], dump_file: &Path) -> Self {
+        let exceptions = match diff.exceptions() {
+            Some(exceptions) => Some(exceptions.clone()),
+            None => standard_exceptions(diff.emulator_types(), raw_outputs, dump_file),
+        };
+        Self {
+            assembly_file,
+            diff,
+            exceptions,
+        }
+    }
+}
+
+fn standard_exceptions(
+    emulator_types: [EmulatorType; 2],
+    raw_outputs: [&Path; 2],
+    dump_file: &Path,
+) -> Option<ExceptionListDiff> {
+    let parse = |raw_output: &Path, emulator_type| {
+        StandardExecutionOutput::parse_from_file(raw_output, dump_file, emulator_type)
+            .map_err(|e| {
+                warn!(
+                    "⚠️ Cannot read exceptions from {}: {}",
+                    raw_output.display(),
+                    e
+                )
+            })
+            .ok()
+    };
+    let [emulator1, emulator2] = emulator_types;
+    let output1 = parse(raw_outputs[0], emulator1)?;
+    let output2 = parse(raw_outputs[1], emulator2)?;
+    compare_outputs(&output1, &output2).exceptions_diff
+}
+
+/// 重试停止的原因
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RetryStop {
+    /// 差异消失
+    Resolved,
+    /// 没有规则能选中剩余的指令
+    Fixpoint,
+    RoundLimit,
+    /// 构建、运行或解析失败
+    Failed(String),
+    /// 收到 Ctrl-C
+    Interrupted,
+}
+
+impl fmt::Display for RetryStop {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RetryStop::Resolved => write!(f, "✅ divergence resolved"),
+            RetryStop::Fixpoint => write!(f, "🔒 fixpoint, no instruction left to remove"),
+            RetryStop::RoundLimit => write!(f, "⏹️ round limit reached"),
+            RetryStop::Failed(reason) => write!(f, "💥 failed: {}", reason),
+            RetryStop::Interrupted => write!(f, "🛑 interrupted"),
+        }
+    }
+}
+
+/// 一轮重试
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RetryRound {
+    pub round: usize,
+    /// 选中指令的差异类别
+    pub categories: Vec<String>,
+    pub removed: Vec<String>,
+    pub assembly_file: PathBuf,
+    pub diverged: bool,
+    /// 与上一轮差异的比较 (Markdown)
+    #[serde(skip)]
+    pub report: String,
+}
+
+/// 所有轮次及最终结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RetryChain<D> {
+    pub format: String,
+    pub max_rounds: usize,
+    pub rounds: Vec<RetryRound>,
+    pub stop: RetryStop,
+    /// 最后一轮的结果，没有进行任何一轮时为 None
+    #[serde(skip)]
+    pub last: Option<RetryAttempt<D>>,
+}
+
+impl<D> RetryChain<D> {
+    pub fn save<P: AsRef<Path>>(&self, retry_dir: P) -> Result<()> {
+        let base = retry_dir.as_ref().join(RETRY_REPORT_FILE);
+        fs::write(
+            base.with_extension("json"),
+            serde_json::to_string_pretty(self)?,
+        )?;
+        fs::write(base.with_extension("md"), self.to_string())?;
+        Ok(())
+    }
+}
+
+impl<D> fmt::Display for RetryChain<D> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "# 🔁 Retry Report")?;
+        writeln!(f)?;
+        writeln!(f, "| Item | Value |")?;
+        writeln!(f, "|------|-------|")?;
+        writeln!(f, "| Format | {} |", self.format)?;
+        writeln!(
+            f,
+            "| Rounds | {} / {} |",
+            self.rounds.len(),
+            self.max_rounds
+        )?;
+        writeln!(f, "| Stopped | {} |", self.stop)?;
+        writeln!(f)?;
+
+        for round in &self.rounds {
+            writeln!(f, "## Round {}", round.round)?;
+            writeln!(f)?;
+            writeln!(f, "| Item | Value |")?;
+            writeln!(f, "|------|-------|")?;
+            writeln!(f, "| Program | `{}` |", round.assembly_file.display())?;
+            writeln!(f, "| Categories | {} |", round.categories.join(", "))?;
+            writeln!(
+                f,
+                "| Still diverges | {} |",
+                if round.diverged { "yes" } else { "no" }
+            )?;
+            writeln!(f)?;
+            writeln!(f, "### Removed Instructions")?;
+            writeln!(f)?;
+            writeln!(f, "```asm")?;
+            for inst in &round.removed {
+                writeln!(f, "{}", inst)?;
+            }
+            writeln!(f, "```")?;
+            writeln!(f)?;
+            // 嵌入的报告标题下降两级
+            for line in round.report.lines() {
+                if line.starts_with('#') {
+                    writeln!(f, "##{}", line)?;
+                } else {
+                    writeln!(f, "{}", line)?;
+                }
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+/// 从 `initial` 开始按策略逐轮删除指令并重试，各轮写入 `build_dir/retry/round_<n>`
+pub fn run_retry_rounds<D: RetryDiff>(
+    policy: &RetryPolicy,
+    initial: &RetryAttempt<D>,
+    build_dir: &Path,
+    march: &str,
+    limits: &RunLimits,
+) -> RetryChain<D> {
+    let retry_dir = build_dir.join(RETRY_DIR);
+    let mut chain = RetryChain {
+        format: D::NAME.to_string(),
+        max_rounds: policy.max_rounds,
+        rounds: Vec::new(),
+        stop: RetryStop::RoundLimit,
+        last: None,
+    };
+
+    let emulators = initial.diff.emulator_types();
+    let registry = match EmulatorRegistry::with_limits(march, limits).select(&emulators) {
+        Ok(registry) => registry,
+        Err(e) => {
+            chain.stop = RetryStop::Failed(e.to_string());
+            return chain;
+        }
+    };
+    let mut current: Option<RetryAttempt<D>> = None;
+    loop {
+        if interrupted() {
+            chain.stop = RetryStop::Interrupted;
+            break;
+        }
+        let previous = current.as_ref().unwrap_or(initial);
+        let (categories, removed) = match policy.next_step(
+            previous.diff.is_divergent(),
+            previous.exceptions.as_ref(),
+            chain.rounds.len(),
+        ) {
+            RetryStep::Remove {
+                categories,
+                removed,
+            } => (categories, removed),
+            RetryStep::Stop(stop) => {
+                chain.stop = stop;
+                break;
+            }
+        };
+
+        let round = chain.rounds.len() + 1;
+        let round_dir = retry_dir.join(format!("round_{}", round));
+        info!(
+            "🔁 Retry round {}: removing {} instructions ({})",
+            round,
+            removed.len(),
+            categories.join(", ")
+        );
+        let attempt = match run_round::<D>(
+            &previous.assembly_file,
+            &removed,
+            &round_dir,
+            march,
+            &registry,
+        ) {
+            Ok(Some(attempt)) => attempt,
+            Ok(None) => {
+                chain.stop = RetryStop::Fixpoint;
+                break;
+            }
+            Err(e) => {
+                warn!("⚠️ Retry round {} failed: {}", round, e);
+                chain.stop = RetryStop::Failed(e.to_string());
+                break;
+            }
+        };
+
+        let report = compare_output_diffs(&previous.diff, &attempt.diff).to_string();
+        if let Err(e) = fs::write(round_dir.join(format!("{}.md", RETRY_REPORT_FILE)), &report) {
+            warn!("⚠️ Failed to write retry report of round {}: {}", round, e);
+        }
+        chain.rounds.push(RetryRound {
+            round,
+            categories,
+            removed,
+            assembly_file: attempt.assembly_file.clone(),
+            diverged: attempt.diff.is_divergent(),
+            report,
+        });
+        current = Some(attempt);
+    }
+    chain.last = current;
+    chain
+}
+
+/// 删除指令后构建并运行一轮；删除没有改变程序时返回 None
+fn run_round<D: RetryDiff>(
+    assembly_file: &Path,
+    removed: &[String],
+    round_dir: &Path,
+    march: &str,
+    registry: &EmulatorRegistry,
+) -> Result<Option<RetryAttempt<D>>> {
+    fs::create_dir_all(round_dir)?;
+    let new_assembly_file = round_dir.join("retry_output.S");
+    remove_instructions_assembly::<PathBuf>(
+        &assembly_file.to_path_buf(),
+        &new_assembly_file,
+        removed,
+    )?;
+    if fs::read_to_string(assembly_file)? == fs::read_to_string(&new_assembly_file)? {
+        return Ok(None);
+    }
+
+    let source_linker = assembly_file.with_file_name("linker.ld");
+    let linker_script = round_dir.join("linker.ld");
+    if source_linker.exists() {
+        fs::copy(&source_linker, &linker_script)?;
+    } else {
+        fs::write(&linker_script, LINKER_SCRIPT)?;
+    }
+    let build_result = build_elf(&new_assembly_file, &linker_script, march)?;
+
+    let mut raw_outputs = Vec::new();
+    let mut outputs = Vec::new();
+    for emulator in registry.iter() {
+        let raw_output = round_dir.join(format!("{}_output.bin", emulator.name()));
+        emulator.run(&build_result.executable_file, &raw_output)?;
+        outputs.push(parse_output_from_file::<D::Output, _>(
+            &raw_output,
+            &build_result.disassembly_file,
+            emulator.emulator_type(),
+        )?);
+        raw_outputs.push(raw_output);
+    }
+    let ([output1, output2], [raw1, raw2]) = (&outputs[..], &raw_outputs[..]) else {
+        return Err(RiscvFuzzError::diff_analysis(
+            "Retry needs exactly two emulators",
+        ));
+    };
+
+    let mut diff = compare_outputs(output1, output2);
+    diff.set_run_outcome_diff(load_run_outcome_diff(raw1, raw2));
+    let diff_file = round_dir.join(format!("diff_{}", D::NAME));
+    fs::write(
+        diff_file.with_extension("json"),
+        serde_json::to_string_pretty(&diff)?,
+    )?;
+    fs::write(diff_file.with_extension("md"), diff.to_string())?;
+
+    Ok(Some(RetryAttempt::new(
+        new_assembly_file,
+        diff,
+        [raw1.as_path(), raw2.as_path()],
+        &build_result.disassembly_file,
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::elf::tracer::InstructionTrace;
+    use crate::output_diff::diff::CategorizedExceptionDiffs;
+    use crate::output_diff::diff::standard_diff::ConversionStatsDiff;
+
+    fn only_in(simulator: EmulatorType, mcause: u64) -> ExceptionDiffCategory {
+        ExceptionDiffCategory::OnlyInSimulator { simulator, mcause }
+    }
+
+    fn category(
+        category: ExceptionDiffCategory,
+        instructions: &[&str],
+    ) -> CategorizedExceptionDiffs {
+        CategorizedExceptionDiffs {
+            category,
+            diffs_summary: Vec::new(),
+            count: instructions.len(),
+            pc_list: (0..instructions.len() as u64)
+                .map(|i| 0x1000 + 4 * i)
+                .collect(),
+            pc_instruction_traces: instructions
+                .iter()
+                .enumerate()
+                .map(|(i, inst)| {
+                    Some(InstructionTrace {
+                        pc: 0x1000 + 4 * i as u64,
+                        disassembly: inst.to_string(),
+                        machine_code: String::new(),
+                        original_instruction: format!("{} # original", inst),
+                    })
+                })
+                .collect(),
+        }
+    }
+
+    fn exceptions(categorized_summary: Vec<CategorizedExceptionDiffs>) -> ExceptionListDiff {
+        ExceptionListDiff {
+            sim1_emulator_type: EmulatorType::Spike,
+            sim2_emulator_type: EmulatorType::Rocket,
+            list1_only_exceptions: Vec::new(),
+            list2_only_exceptions: Vec::new(),
+            paired_exceptions_diffs: Vec::new(),
+            categorized_summary,
+            edits: Vec::new(),
+            suppressed: Vec::new(),
+        }
+    }
+
+    fn standard_diff() -> StandardExecutionOutputDiff {
+        StandardExecutionOutputDiff {
+            sim1_emulator_type: EmulatorType::Spike,
+            sim2_emulator_type: EmulatorType::Rocket,
+            exceptions_diff: None,
+            register_dump_status: None,
+            register_dump_diff: None,
+            conversion_stats_diff: None,
+            run_outcome_diff: None,
+            suppressed: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_rule_matches_category() {
+        let rule = RetryPolicy::default().rules.remove(0);
+        assert!(rule.matches_category(&only_in(EmulatorType::Rocket, 2)));
+        assert!(!rule.matches_category(&only_in(EmulatorType::Spike, 2)));
+        assert!(!rule.matches_category(&only_in(EmulatorType::Rocket, 5)));
+        assert!(!rule.matches_category(&ExceptionDiffCategory::MtvalDifference));
+
+        let mcause = RetryRule {
+            mcause: Some(2),
+            ..Default::default()
+        };
+        assert!(
+            mcause.matches_category(&ExceptionDiffCategory::McauseDifference {
+                sim1_cause: 5,
+                sim2_cause: 2,
+            })
+        );
+        assert!(!mcause.matches_category(&ExceptionDiffCategory::MtvalDifference));
+        assert!(RetryRule::default().matches_category(&ExceptionDiffCategory::MtvalDifference));
+    }
+
+    #[test]
+    fn test_rule_matches_opcode_case_insensitively() {
+        let rule = RetryRule {
+            opcode: Some("FLI.S".to_string()),
+            ..Default::default()
+        };
+        assert!(rule.matches_instruction("fli.s fa0, 1.0"));
+        assert!(!rule.matches_instruction("fli.d fa0, 1.0"));
+        assert!(!rule.matches_instruction(""));
+        assert!(RetryRule::default().matches_instruction(""));
+    }
+
+    #[test]
+    fn test_select_dedups_and_names_contributing_categories() {
+        let policy = RetryPolicy {
+            max_rounds: 3,
+            rules: vec![RetryRule {
+                category: Some(CategoryKind::OnlyInSimulator),
+                opcode: Some("csrr".to_string()),
+                ..Default::default()
+            }],
+        };
+        let list = exceptions(vec![
+            category(
+                only_in(EmulatorType::Rocket, 2),
+                &["csrr a0, 0x7c0", "add a0, a0, a0"],
+            ),
+            category(ExceptionDiffCategory::MtvalDifference, &["csrr a1, 0x7c1"]),
+            category(only_in(EmulatorType::Spike, 2), &["csrr a0, 0x7c0"]),
+            category(only_in(EmulatorType::Spike, 5), &["lw a0, 0(a1)"]),
+        ]);
+        let (categories, removed) = policy.select(&list);
+        assert_eq!(
+            categories,
+            vec![
+                format_category_title(&only_in(EmulatorType::Rocket, 2)),
+                format_category_title(&only_in(EmulatorType::Spike, 2)),
+            ]
+        );
+        assert_eq!(removed, vec!["csrr a0, 0x7c0 # original"]);
+    }
+
+    #[test]
+    fn test_next_step_decision_table() {
+        let policy = RetryPolicy::default();
+        let selectable = exceptions(vec![category(
+            only_in(EmulatorType::Rocket, 2),
+            &["fli.s fa0, 1.0"],
+        )]);
+        let unselectable = exceptions(vec![category(
+            only_in(EmulatorType::Spike, 2),
+            &["fli.s fa0, 1.0"],
+        )]);
+        let remove = RetryStep::Remove {
+            categories: vec![format_category_title(&only_in(EmulatorType::Rocket, 2))],
+            removed: vec!["fli.s fa0, 1.0 # original".to_string()],
+        };
+        let stop = RetryStep::Stop;
+
+        // (diverged, exceptions, rounds) -> step
+        let table = [
+            (false, Some(&selectable), 0, stop(RetryStop::Resolved)),
+            (false, None, 5, stop(RetryStop::Resolved)),
+            (true, Some(&selectable), 3, stop(RetryStop::RoundLimit)),
+            (true, None, 3, stop(RetryStop::RoundLimit)),
+            (true, None, 0, stop(RetryStop::Fixpoint)),
+            (true, Some(&unselectable), 1, stop(RetryStop::Fixpoint)),
+            (true, Some(&selectable), 0, remove.clone()),
+            (true, Some(&selectable), 2, remove),
+        ];
+        for (diverged, exceptions, rounds, expected) in table {
+            assert_eq!(
+                policy.next_step(diverged, exceptions, rounds),
+                expected,
+                "diverged={} rounds={}",
+                diverged,
+                rounds
+            );
+        }
+        let no_rounds = RetryPolicy {
+            max_rounds: 0,
+            ..RetryPolicy::default()
+        };
+        assert_eq!(
+            no_rounds.next_step(true, Some(&selectable), 0),
+            RetryStep::Stop(RetryStop::RoundLimit)
+        );
+    }
+
+    #[test]
+    fn test_standard_diff_divergence_ignores_conversion_stats() {
+        let mut diff = standard_diff();
+        assert!(!diff.is_divergent());
+        diff.exceptions_diff = Some(exceptions(Vec::new()));
+        assert!(!diff.is_divergent());
+
+        diff.conversion_stats_diff = Some(ConversionStatsDiff {
+            sim1_emulator_type: EmulatorType::Spike,
+            sim2_emulator_type: EmulatorType::Rocket,
+            original_exception_count_changed: Some((1, 2)),
+            original_register_count_changed: None,
+            conversion_successful_changed: None,
+            warnings_changed: None,
+        });
+        assert!(!diff.is_empty());
+        assert!(!diff.is_divergent());
+
+        diff.register_dump_status = Some("Only Spike dumped registers".to_string());
+        assert!(diff.is_divergent());
+
+        let mut diff = standard_diff();
+        diff.exceptions_diff = Some(exceptions(vec![category(
+            ExceptionDiffCategory::MtvalDifference,
+            &["lw a0, 0(a1)"],
+        )]));
+        assert!(diff.is_divergent());
+    }
+}
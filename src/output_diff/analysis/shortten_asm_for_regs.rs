@@ -1,6 +1,384 @@
-use crate::utils::get_regs_in_inst;
+//! 基于数据流的寄存器差异切片
+//!
+//! Every user-code line is decoded with [`decode`], which reads the `Display`
+//! form of `RiscvInstruction` including ABI register names, and turned into
+//! the [`Location`]s it reads and writes. A backward pass then follows the
+//! def-use chains of the differing registers: through registers, CSRs,
+//! `fflags`/`frm` (accrued by FP arithmetic, read under dynamic rounding) and
+//! memory from stores to later loads. Addresses are not known statically, so
+//! memory is a single location that stores update but never overwrite.
+//! A line the decoder does not understand has unknown effects, so the program
+//! is then returned unsliced rather than guessing its registers.
+
 use std::collections::HashSet;
 
+use log::warn;
+
+use crate::error::Result;
+use crate::interpreter::decode::{DecodedInstruction, Operand, decode, parse_csr};
+use crate::interpreter::float::RoundingMode;
+use crate::output_diff::diff::ignore::RegisterRef;
+
+const CSR_FFLAGS: u16 = 0x001;
+const CSR_FRM: u16 = 0x002;
+const CSR_FCSR: u16 = 0x003;
+
+/// 指令可读写的状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Location {
+    Reg(RegisterRef),
+    Csr(u16),
+    /// `fcsr` 的异常标志位
+    Fflags,
+    /// `fcsr` 的舍入模式
+    Frm,
+    Memory,
+}
+
+impl Location {
+    /// 寄存器名 (`x5`、`a0`、`fa1`) 或 CSR 名
+    pub fn parse(name: &str) -> Option<Vec<Location>> {
+        let name = name.trim().to_lowercase();
+        if let Ok(register) = RegisterRef::try_from(name.clone()) {
+            return Some(register_location(register).into_iter().collect());
+        }
+        parse_csr(&name).map(csr_locations)
+    }
+}
+
+fn register_location(register: RegisterRef) -> Option<Location> {
+    // x0 恒为 0，不形成依赖
+    (register != RegisterRef::Int(0)).then_some(Location::Reg(register))
+}
+
+/// `fcsr` 拆成 `fflags` 与 `frm`，分别跟踪
+fn csr_locations(csr: u16) -> Vec<Location> {
+    match csr {
+        CSR_FFLAGS => vec![Location::Fflags],
+        CSR_FRM => vec![Location::Frm],
+        CSR_FCSR => vec![Location::Fflags, Location::Frm],
+        other => vec![Location::Csr(other)],
+    }
+}
+
+fn operand_locations(operand: &Operand) -> Vec<Location> {
+    match operand {
+        Operand::Reg(index) => register_location(RegisterRef::Int(*index))
+            .into_iter()
+            .collect(),
+        Operand::FReg(index) => vec![Location::Reg(RegisterRef::Float(*index))],
+        Operand::Csr(csr) => csr_locations(*csr),
+        Operand::Imm(_) | Operand::Mem { .. } | Operand::Rm(_) => Vec::new(),
+    }
+}
+
+/// CSR 指令中写作编号的 CSR 会被解码为立即数
+fn csr_operand_locations(operand: Option<&Operand>) -> Vec<Location> {
+    match operand {
+        Some(Operand::Imm(csr)) => u16::try_from(*csr)
+            .ok()
+            .map(csr_locations)
+            .unwrap_or_default(),
+        Some(operand) => operand_locations(operand),
+        None => Vec::new(),
+    }
+}
+
+/// 单条指令读写的位置；部分更新 (置位/清位的 CSR、内存) 同时出现在
+/// `uses` 和 `defs` 中，因此不会终止依赖链
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct InstructionEffects {
+    pub uses: Vec<Location>,
+    pub defs: Vec<Location>,
+    /// 累加更新的位置 (fflags)：旧值只流向其自身，不流向其它 `defs`
+    pub accrued: Vec<Location>,
+    /// 控制流指令总是保留
+    pub control: bool,
+}
+
+impl InstructionEffects {
+    fn read(&mut self, locations: impl IntoIterator<Item = Location>) {
+        self.uses.extend(locations);
+    }
+
+    fn write(&mut self, locations: impl IntoIterator<Item = Location>) {
+        self.defs.extend(locations);
+    }
+
+    fn update(&mut self, locations: impl IntoIterator<Item = Location>) {
+        for location in locations {
+            self.uses.push(location);
+            self.defs.push(location);
+        }
+    }
+
+    fn accrue(&mut self, locations: impl IntoIterator<Item = Location>) {
+        for location in locations {
+            self.defs.push(location);
+            self.accrued.push(location);
+        }
+    }
+
+    /// 解码一行汇编
+    pub fn of(instruction: &str) -> Result<Self> {
+        decode(instruction).map(|decoded| Self::of_decoded(&decoded))
+    }
+
+    pub fn of_decoded(decoded: &DecodedInstruction) -> Self {
+        let mnemonic = decoded.mnemonic.as_str();
+        let operands = &decoded.operands;
+        let mut effects = Self::default();
+
+        // 访存的基址寄存器总是被读取
+        for operand in operands {
+            if let Operand::Mem { base, .. } = operand {
+                effects.read(register_location(RegisterRef::Int(*base)));
+            }
+        }
+        let has_memory = operands.iter().any(|op| matches!(op, Operand::Mem { .. }));
+        let read_all = |effects: &mut Self, operands: &[Operand]| {
+            for operand in operands {
+                effects.read(operand_locations(operand));
+            }
+        };
+        let write_first_read_rest = |effects: &mut Self| {
+            if let Some((first, rest)) = operands.split_first() {
+                effects.write(operand_locations(first));
+                read_all(effects, rest);
+            }
+        };
+
+        if let Some(CsrInstruction {
+            rd,
+            csrs,
+            src,
+            access,
+        }) = csr_access(mnemonic, operands)
+        {
+            let rd = rd.map(operand_locations).unwrap_or_default();
+            if !rd.is_empty() {
+                effects.write(rd);
+                effects.read(csrs.iter().copied());
+            }
+            let src_nonzero = match src {
+                Some(Operand::Imm(value)) => *value != 0,
+                Some(operand) => !operand_locations(operand).is_empty(),
+                None => false,
+            };
+            match access {
+                CsrAccess::Read => {}
+                CsrAccess::Write => effects.write(csrs),
+                CsrAccess::SetClear if src_nonzero => effects.update(csrs),
+                CsrAccess::SetClear => {}
+            }
+            if let Some(src) = src {
+                effects.read(operand_locations(src));
+            }
+        } else if is_control(mnemonic) {
+            effects.control = true;
+            match mnemonic {
+                "jal" | "jalr" if operands.len() > 1 => write_first_read_rest(&mut effects),
+                "c.jal" | "c.jalr" | "call" => {
+                    effects.write(register_location(RegisterRef::Int(1)));
+                    read_all(&mut effects, operands);
+                }
+                _ => read_all(&mut effects, operands),
+            }
+        } else if is_system(mnemonic) {
+            read_all(&mut effects, operands);
+            if mnemonic.starts_with("cbo.zero") {
+                effects.update([Location::Memory]);
+            }
+        } else if mnemonic.starts_with("amo") || mnemonic.starts_with("sc.") {
+            write_first_read_rest(&mut effects);
+            effects.update([Location::Memory]);
+        } else if has_memory && is_store(mnemonic) {
+            read_all(&mut effects, operands);
+            effects.update([Location::Memory]);
+        } else if has_memory {
+            // 加载 (含 lr)
+            write_first_read_rest(&mut effects);
+            effects.read([Location::Memory]);
+        } else {
+            write_first_read_rest(&mut effects);
+            // 压缩指令的 rd 同时是源操作数
+            if mnemonic.starts_with("c.")
+                && !matches!(mnemonic, "c.li" | "c.lui" | "c.mv" | "c.addi4spn")
+            {
+                effects.read(operands.first().map(operand_locations).unwrap_or_default());
+            }
+            if sets_fflags(mnemonic) {
+                effects.accrue([Location::Fflags]);
+                let static_rm = operands
+                    .iter()
+                    .any(|op| matches!(op, Operand::Rm(rm) if *rm != RoundingMode::Dyn));
+                if rounds(mnemonic) && !static_rm {
+                    effects.read([Location::Frm]);
+                }
+            }
+        }
+        effects
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CsrAccess {
+    Read,
+    Write,
+    SetClear,
+}
+
+/// CSR 指令及其伪指令的操作数
+struct CsrInstruction<'a> {
+    rd: Option<&'a Operand>,
+    csrs: Vec<Location>,
+    src: Option<&'a Operand>,
+    access: CsrAccess,
+}
+
+fn csr_access<'a>(mnemonic: &str, operands: &'a [Operand]) -> Option<CsrInstruction<'a>> {
+    let set_or_write = |write_prefix: &str| {
+        if mnemonic.starts_with(write_prefix) {
+            CsrAccess::Write
+        } else {
+            CsrAccess::SetClear
+        }
+    };
+    let fcsr_pseudo = |csr: u16, access: CsrAccess| {
+        let (rd, src) = match (access, operands) {
+            (CsrAccess::Read, [rd, ..]) => (Some(rd), None),
+            (_, [rd, src]) => (Some(rd), Some(src)),
+            (_, [src]) => (None, Some(src)),
+            _ => return None,
+        };
+        Some(CsrInstruction {
+            rd,
+            csrs: csr_locations(csr),
+            src,
+            access,
+        })
+    };
+    match mnemonic {
+        "csrrw" | "csrrwi" | "csrrs" | "csrrsi" | "csrrc" | "csrrci" => Some(CsrInstruction {
+            rd: operands.first(),
+            csrs: csr_operand_locations(operands.get(1)),
+            src: operands.get(2),
+            access: set_or_write("csrrw"),
+        }),
+        "csrr" => Some(CsrInstruction {
+            rd: operands.first(),
+            csrs: csr_operand_locations(operands.get(1)),
+            src: None,
+            access: CsrAccess::Read,
+        }),
+        "csrw" | "csrwi" | "csrs" | "csrsi" | "csrc" | "csrci" => Some(CsrInstruction {
+            rd: None,
+            csrs: csr_operand_locations(operands.first()),
+            src: operands.get(1),
+            access: set_or_write("csrw"),
+        }),
+        "frcsr" => fcsr_pseudo(CSR_FCSR, CsrAccess::Read),
+        "frflags" => fcsr_pseudo(CSR_FFLAGS, CsrAccess::Read),
+        "frrm" => fcsr_pseudo(CSR_FRM, CsrAccess::Read),
+        "fscsr" => fcsr_pseudo(CSR_FCSR, CsrAccess::Write),
+        "fsflags" | "fsflagsi" => fcsr_pseudo(CSR_FFLAGS, CsrAccess::Write),
+        "fsrm" | "fsrmi" => fcsr_pseudo(CSR_FRM, CsrAccess::Write),
+        _ => None,
+    }
+}
+
+fn is_control(mnemonic: &str) -> bool {
+    matches!(
+        mnemonic,
+        "beq"
+            | "bne"
+            | "blt"
+            | "bge"
+            | "bltu"
+            | "bgeu"
+            | "beqz"
+            | "bnez"
+            | "blez"
+            | "bgez"
+            | "bltz"
+            | "bgtz"
+            | "bgt"
+            | "ble"
+            | "bgtu"
+            | "bleu"
+            | "c.beqz"
+            | "c.bnez"
+            | "j"
+            | "jal"
+            | "jalr"
+            | "jr"
+            | "ret"
+            | "call"
+            | "tail"
+            | "c.j"
+            | "c.jal"
+            | "c.jr"
+            | "c.jalr"
+    )
+}
+
+/// 不写寄存器的系统、屏障和提示指令
+fn is_system(mnemonic: &str) -> bool {
+    matches!(
+        mnemonic,
+        "ecall"
+            | "ebreak"
+            | "c.ebreak"
+            | "mret"
+            | "sret"
+            | "dret"
+            | "mnret"
+            | "wfi"
+            | "nop"
+            | "c.nop"
+            | "pause"
+            | "wrs.nto"
+            | "wrs.sto"
+            | "fence"
+            | "fence.i"
+            | "fence.tso"
+    ) || [
+        "sfence.",
+        "sinval.",
+        "hfence.",
+        "hinval.",
+        "prefetch.",
+        "cbo.",
+    ]
+    .iter()
+    .any(|prefix| mnemonic.starts_with(prefix))
+}
+
+/// `sb`/`sw`/`fsd`/`c.sdsp` 等存储指令
+fn is_store(mnemonic: &str) -> bool {
+    let base = mnemonic.strip_prefix("c.").unwrap_or(mnemonic);
+    let base = base.strip_prefix('f').unwrap_or(base);
+    let base = base.strip_suffix("sp").unwrap_or(base);
+    matches!(base, "sb" | "sh" | "sw" | "sd" | "sq") || mnemonic.starts_with("hsv.")
+}
+
+/// 会累加 `fflags` 的浮点运算；符号注入、搬移、分类和访存不会
+fn sets_fflags(mnemonic: &str) -> bool {
+    let base = mnemonic.strip_prefix("c.").unwrap_or(mnemonic);
+    base.starts_with('f')
+        && !["fsgnj", "fmv", "fneg", "fabs", "fclass", "fli"]
+            .iter()
+            .any(|prefix| base.starts_with(prefix))
+}
+
+/// 结果依赖舍入模式的浮点运算
+fn rounds(mnemonic: &str) -> bool {
+    !["fmin", "fmax", "feq", "flt", "fle"]
+        .iter()
+        .any(|prefix| mnemonic.starts_with(prefix))
+}
+
+/// 保留 `target_regs` (寄存器或 CSR 名) 的最终值所依赖的指令
 pub fn extract_minimal_instructions_for_regs(
     insts: Vec<String>,
     target_regs: Vec<String>,
@@ -9,31 +387,195 @@ pub fn extract_minimal_instructions_for_regs(
         return Vec::new();
     }
 
-    // 初始化感兴趣的寄存器集合
-    let mut interested_regs: HashSet<String> = target_regs.into_iter().collect();
+    let mut live: HashSet<Location> = HashSet::new();
+    for name in &target_regs {
+        match Location::parse(name) {
+            Some(locations) => live.extend(locations),
+            None => warn!("⚠️ Ignoring unknown slicing target {:?}", name),
+        }
+    }
+    // 读写集合未知的指令无法安全地切片，此时保留全部指令
+    let effects = match insts
+        .iter()
+        .map(|inst| InstructionEffects::of(inst))
+        .collect::<Result<Vec<_>>>()
+    {
+        Ok(effects) => effects,
+        Err(e) => {
+            warn!("⚠️ Keeping all instructions, cannot slice: {}", e);
+            return insts;
+        }
+    };
+
     let mut result_instructions = Vec::new();
 
     // 从后往前遍历指令流
-    for inst in insts.iter().rev() {
-        // 获取当前指令中涉及的所有寄存器
-        let regs_in_inst = get_regs_in_inst(inst);
-
-        // 检查当前指令是否涉及感兴趣的寄存器
-        let has_interested_reg = regs_in_inst.iter().any(|reg| interested_regs.contains(reg));
-
-        if has_interested_reg {
-            // 保留这条指令
-            result_instructions.push(inst.clone());
-
-            // 将这条指令中的所有寄存器加入感兴趣集合
-            // 因为它们可能影响我们关心的寄存器
-            for reg in regs_in_inst {
-                interested_regs.insert(reg);
-            }
+    for (inst, effects) in insts.iter().zip(effects).rev() {
+        if !effects.control && !effects.defs.iter().any(|def| live.contains(def)) {
+            continue;
         }
+        let accrued: Vec<Location> = effects
+            .accrued
+            .iter()
+            .filter(|location| live.contains(location))
+            .copied()
+            .collect();
+        for def in &effects.defs {
+            live.remove(def);
+        }
+        live.extend(effects.uses);
+        live.extend(accrued);
+        result_instructions.push(inst.clone());
     }
 
     // 反转结果以恢复原始执行顺序
     result_instructions.reverse();
     result_instructions
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn slice(insts: &[&str], targets: &[&str]) -> Vec<String> {
+        extract_minimal_instructions_for_regs(
+            insts.iter().map(|i| i.to_string()).collect(),
+            targets.iter().map(|t| t.to_string()).collect(),
+        )
+    }
+
+    #[test]
+    fn test_location_parse() {
+        assert_eq!(
+            Location::parse("a0"),
+            Some(vec![Location::Reg(RegisterRef::Int(10))])
+        );
+        assert_eq!(
+            Location::parse("FA1"),
+            Some(vec![Location::Reg(RegisterRef::Float(11))])
+        );
+        assert_eq!(Location::parse("zero"), Some(Vec::new()));
+        assert_eq!(
+            Location::parse("fcsr"),
+            Some(vec![Location::Fflags, Location::Frm])
+        );
+        assert_eq!(
+            Location::parse("mscratch"),
+            Some(vec![Location::Csr(0x340)])
+        );
+        assert_eq!(Location::parse("bogus"), None);
+    }
+
+    #[test]
+    fn test_register_def_use_chain() {
+        let insts = [
+            "li a0, 1",
+            "li a1, 2",
+            "add a2, a0, a0",
+            "li a0, 5",
+            "add a3, a0, a1",
+            "mv a4, a2",
+        ];
+        assert_eq!(
+            slice(&insts, &["a3"]),
+            vec!["li a1, 2", "li a0, 5", "add a3, a0, a1"]
+        );
+        assert_eq!(
+            slice(&insts, &["a4"]),
+            vec!["li a0, 1", "add a2, a0, a0", "mv a4, a2"]
+        );
+        assert!(slice(&insts, &["t0"]).is_empty());
+        assert!(slice(&insts, &[]).is_empty());
+    }
+
+    #[test]
+    fn test_writes_to_x0_do_not_start_chains() {
+        let insts = ["li a0, 1", "add zero, a0, a0", "add a1, zero, zero"];
+        assert_eq!(slice(&insts, &["a1"]), vec!["add a1, zero, zero"]);
+    }
+
+    #[test]
+    fn test_control_flow_is_always_kept() {
+        let insts = ["li t0, 1", "li a0, 2", "beqz t0, 1f", "addi a0, a0, 1"];
+        assert_eq!(
+            slice(&insts, &["a0"]),
+            vec!["li t0, 1", "li a0, 2", "beqz t0, 1f", "addi a0, a0, 1"]
+        );
+    }
+
+    #[test]
+    fn test_csr_targets() {
+        let insts = [
+            "li a0, 7",
+            "li a1, 8",
+            "csrw mscratch, a0",
+            "csrs mscratch, a1",
+            "csrw mtvec, a1",
+        ];
+        assert_eq!(
+            slice(&insts, &["mscratch"]),
+            vec![
+                "li a0, 7",
+                "li a1, 8",
+                "csrw mscratch, a0",
+                "csrs mscratch, a1"
+            ]
+        );
+        let insts = ["li a0, 7", "csrw mscratch, a0", "csrr a2, mscratch"];
+        assert_eq!(slice(&insts, &["a2"]), insts.to_vec());
+    }
+
+    #[test]
+    fn test_fflags_and_frm_chains() {
+        let insts = [
+            "fsrmi 1",
+            "fadd.s fa0, fa1, fa2",
+            "fsgnj.s fa3, fa1, fa1",
+            "fadd.s fa4, fa1, fa2, rtz",
+            "frflags a0",
+        ];
+        // 两次加法都累加 fflags，只有动态舍入的一次读 frm
+        assert_eq!(
+            slice(&insts, &["a0"]),
+            vec![
+                "fsrmi 1",
+                "fadd.s fa0, fa1, fa2",
+                "fadd.s fa4, fa1, fa2, rtz",
+                "frflags a0"
+            ]
+        );
+        assert_eq!(slice(&insts, &["fa4"]), vec!["fadd.s fa4, fa1, fa2, rtz"]);
+        assert_eq!(
+            slice(&insts, &["fa0"]),
+            vec!["fsrmi 1", "fadd.s fa0, fa1, fa2"]
+        );
+    }
+
+    #[test]
+    fn test_memory_chain_keeps_every_earlier_store() {
+        let insts = [
+            "li a0, 1",
+            "li a1, 2",
+            "sw a0, 0(sp)",
+            "sw a1, 4(sp)",
+            "li a2, 3",
+            "lw a3, 0(sp)",
+        ];
+        assert_eq!(
+            slice(&insts, &["a3"]),
+            vec![
+                "li a0, 1",
+                "li a1, 2",
+                "sw a0, 0(sp)",
+                "sw a1, 4(sp)",
+                "lw a3, 0(sp)"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_undecodable_line_keeps_whole_program() {
+        let insts = ["li a0, 1", "vadd.vv v1, v2, v3", "li a1, 2"];
+        assert_eq!(slice(&insts, &["a1"]), insts.to_vec());
+    }
+}
@@ -0,0 +1,829 @@
+//! 已知缺陷库与自动分诊
+//!
+//! A [`KnownBugDatabase`] (TOML or JSON, path in `[diff] known_bugs`) lists
+//! emulator discrepancies that are already tracked. Every divergent
+//! [`StandardExecutionOutputDiff`] is split into [`DivergenceItem`]s and each
+//! item is matched against the bugs' [`BugPattern`]s: a diff whose items are
+//! all covered is known, one with an uncovered item is new, and one that only
+//! hits bugs marked fixed has reappeared.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use crate::config;
+use crate::emulators::EmulatorType;
+use crate::error::{Result, RiscvFuzzError};
+use crate::output_diff::diff::ignore::RegisterRef;
+use crate::output_diff::diff::standard_diff::StandardExecutionOutputDiff;
+use crate::output_diff::diff::{ExceptionDiffCategory, ExceptionDiffInfo, format_category_title};
+use crate::output_parser::ExceptionDump;
+use crate::random_asm::denylist::opcode_of;
+
+/// 分诊报告文件名 (不含扩展名)
+pub const TRIAGE_REPORT_FILE: &str = "triage";
+
+/// 已知缺陷的状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BugStatus {
+    Open,
+    Fixed,
+}
+
+impl fmt::Display for BugStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BugStatus::Open => write!(f, "open"),
+            BugStatus::Fixed => write!(f, "fixed"),
+        }
+    }
+}
+
+/// [`ExceptionDiffCategory`] without its values
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CategoryKind {
+    FixedMipDifference,
+    McauseDifference,
+    OnlyInSimulator,
+    MtvalDifference,
+    OtherCsrDifference,
+}
+
+impl CategoryKind {
+    pub fn of(category: &ExceptionDiffCategory) -> Self {
+        match category {
+            ExceptionDiffCategory::FixedMipDifference { .. } => CategoryKind::FixedMipDifference,
+            ExceptionDiffCategory::McauseDifference { .. } => CategoryKind::McauseDifference,
+            ExceptionDiffCategory::OnlyInSimulator { .. } => CategoryKind::OnlyInSimulator,
+            ExceptionDiffCategory::MtvalDifference => CategoryKind::MtvalDifference,
+            ExceptionDiffCategory::OtherCsrDifference { .. } => CategoryKind::OtherCsrDifference,
+        }
+    }
+}
+
+/// Predicate over one [`DivergenceItem`]; unset fields match anything.
+///
+/// Values are compared under `mask`: `sim1_value = 2` with `mask = 0xff`
+/// matches any value whose low byte is 2.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BugPattern {
+    pub category: Option<CategoryKind>,
+    /// 仅在该模拟器中出现的异常
+    pub simulator: Option<EmulatorType>,
+    /// 触发异常的指令助记符
+    pub opcode: Option<String>,
+    /// 存在差异的 CSR (`mtval`、`fcsr` 等)
+    pub csr: Option<String>,
+    pub register: Option<RegisterRef>,
+    pub mask: Option<u64>,
+    pub sim1_value: Option<u64>,
+    pub sim2_value: Option<u64>,
+}
+
+impl BugPattern {
+    fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    pub fn matches(&self, item: &DivergenceItem) -> bool {
+        let mask = self.mask.unwrap_or(u64::MAX);
+        let value_matches = |expected: Option<u64>, actual: Option<u64>| {
+            expected.is_none_or(|expected| actual.is_some_and(|v| v & mask == expected & mask))
+        };
+        self.category
+            .is_none_or(|kind| item.category.as_ref().map(CategoryKind::of) == Some(kind))
+            && self
+                .simulator
+                .as_ref()
+                .is_none_or(|s| item.simulator.as_ref() == Some(s))
+            && self.opcode.as_ref().is_none_or(|opcode| {
+                item.opcode
+                    .as_ref()
+                    .is_some_and(|actual| actual.eq_ignore_ascii_case(opcode))
+            })
+            && self
+                .csr
+                .as_ref()
+                .is_none_or(|csr| item.csr.as_ref() == Some(csr))
+            && self.register.is_none_or(|r| item.register == Some(r))
+            && value_matches(self.sim1_value, item.sim1_value)
+            && value_matches(self.sim2_value, item.sim2_value)
+    }
+}
+
+/// One tracked emulator discrepancy
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct KnownBug {
+    pub id: String,
+    /// 缺陷单链接或编号
+    pub ticket: Option<String>,
+    pub status: BugStatus,
+    pub description: Option<String>,
+    pub pattern: BugPattern,
+}
+
+/// `known_bugs.toml`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct KnownBugDatabase {
+    pub bugs: Vec<KnownBug>,
+}
+
+impl KnownBugDatabase {
+    /// 按扩展名读取 JSON 或 TOML
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let content = fs::read_to_string(path).map_err(|e| {
+            RiscvFuzzError::config(format!(
+                "Failed to read known bug database {}: {}",
+                path.display(),
+                e
+            ))
+        })?;
+        let database: Self = if path.extension().is_some_and(|ext| ext == "json") {
+            serde_json::from_str(&content).map_err(|e| e.to_string())
+        } else {
+            toml::from_str(&content).map_err(|e| e.to_string())
+        }
+        .map_err(|e| {
+            RiscvFuzzError::config(format!(
+                "Invalid known bug database {}: {}",
+                path.display(),
+                e
+            ))
+        })?;
+
+        // 空模式会匹配所有差异
+        if let Some(bug) = database.bugs.iter().find(|bug| bug.pattern.is_empty()) {
+            return Err(RiscvFuzzError::config(format!(
+                "Known bug `{}` has an empty pattern",
+                bug.id
+            )));
+        }
+        Ok(database)
+    }
+
+    /// 配置中 `[diff] known_bugs` 指定的缺陷库，未配置时返回 None
+    pub fn load_configured() -> Result<Option<Self>> {
+        config::get()
+            .diff
+            .known_bugs
+            .as_ref()
+            .map(Self::load)
+            .transpose()
+    }
+
+    /// Open bugs without any hit in `hits`, which may have been fixed
+    pub fn not_reproduced(&self, hits: &BTreeMap<String, usize>) -> Vec<&KnownBug> {
+        self.bugs
+            .iter()
+            .filter(|bug| bug.status == BugStatus::Open && !hits.contains_key(&bug.id))
+            .collect()
+    }
+
+    pub fn find(&self, item: &DivergenceItem) -> Option<&KnownBug> {
+        self.bugs.iter().find(|bug| bug.pattern.matches(item))
+    }
+
+    /// Matches every item of `diff`; None when the diff has no items
+    pub fn triage(&self, diff: &StandardExecutionOutputDiff) -> Option<TriageReport> {
+        let items: Vec<TriagedItem> = DivergenceItem::from_standard_diff(diff)
+            .into_iter()
+            .map(|item| {
+                let bug = self.find(&item);
+                TriagedItem {
+                    bug_id: bug.map(|bug| bug.id.clone()),
+                    ticket: bug.and_then(|bug| bug.ticket.clone()),
+                    status: bug.map(|bug| bug.status),
+                    item,
+                }
+            })
+            .collect();
+        if items.is_empty() {
+            return None;
+        }
+
+        let classification = if items.iter().any(|item| item.bug_id.is_none()) {
+            TriageClass::New
+        } else if items
+            .iter()
+            .any(|item| item.status == Some(BugStatus::Fixed))
+        {
+            TriageClass::Reappeared
+        } else {
+            TriageClass::Known
+        };
+        Some(TriageReport {
+            classification,
+            items,
+        })
+    }
+}
+
+/// A single difference of a Standard diff, as seen by [`BugPattern`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DivergenceItem {
+    /// 异常差异的类别，寄存器 dump 中的差异为 None
+    pub category: Option<ExceptionDiffCategory>,
+    pub simulator: Option<EmulatorType>,
+    pub pc: Option<u64>,
+    pub opcode: Option<String>,
+    pub csr: Option<String>,
+    pub register: Option<RegisterRef>,
+    pub sim1_value: Option<u64>,
+    pub sim2_value: Option<u64>,
+    /// 无法细分的差异 (dump 缺失、运行结果等)
+    pub note: Option<String>,
+}
+
+impl DivergenceItem {
+    fn new() -> Self {
+        Self {
+            category: None,
+            simulator: None,
+            pc: None,
+            opcode: None,
+            csr: None,
+            register: None,
+            sim1_value: None,
+            sim2_value: None,
+            note: None,
+        }
+    }
+
+    fn note(note: String) -> Self {
+        Self {
+            note: Some(note),
+            ..Self::new()
+        }
+    }
+
+    pub fn from_standard_diff(diff: &StandardExecutionOutputDiff) -> Vec<Self> {
+        let mut items = Vec::new();
+        let opcode = |ex: &ExceptionDump| {
+            ex.inst_trace
+                .as_ref()
+                .and_then(|t| opcode_of(&t.disassembly))
+        };
+
+        if let Some(ex_diff) = &diff.exceptions_diff {
+            for (list, simulator, in_sim1) in [
+                (
+                    &ex_diff.list1_only_exceptions,
+                    ex_diff.sim1_emulator_type.clone(),
+                    true,
+                ),
+                (
+                    &ex_diff.list2_only_exceptions,
+                    ex_diff.sim2_emulator_type.clone(),
+                    false,
+                ),
+            ] {
+                for ex in list {
+                    let mcause = ex.csrs.mcause;
+                    items.push(Self {
+                        category: Some(ExceptionDiffCategory::OnlyInSimulator {
+                            simulator: simulator.clone(),
+                            mcause,
+                        }),
+                        simulator: Some(simulator.clone()),
+                        pc: Some(ex.csrs.mepc),
+                        opcode: opcode(ex),
+                        csr: Some("mcause".to_string()),
+                        sim1_value: in_sim1.then_some(mcause),
+                        sim2_value: (!in_sim1).then_some(mcause),
+                        ..Self::new()
+                    });
+                }
+            }
+            for paired in &ex_diff.paired_exceptions_diffs {
+                for (csr_name, val1, val2) in &paired.csrs_differences {
+                    let info = ExceptionDiffInfo::CsrDifference {
+                        pc: paired.exception1.csrs.mepc,
+                        csr_name: csr_name.clone(),
+                        sim1_value: *val1,
+                        sim2_value: *val2,
+                        sim1_description: None,
+                        sim2_description: None,
+                        instruction_trace: None,
+                    };
+                    items.push(Self {
+                        category: Some(info.get_category()),
+                        pc: Some(info.get_pc()),
+                        opcode: opcode(&paired.exception1),
+                        csr: Some(csr_name.clone()),
+                        sim1_value: Some(*val1),
+                        sim2_value: Some(*val2),
+                        ..Self::new()
+                    });
+                }
+            }
+        }
+
+        if let Some(status) = &diff.register_dump_status {
+            items.push(Self::note(format!("register dump {}", status)));
+        }
+        if let Some(reg_diff) = &diff.register_dump_diff {
+            for (index, _, val1, val2) in &reg_diff.int_registers_diff {
+                items.push(Self {
+                    register: Some(RegisterRef::Int(*index)),
+                    sim1_value: Some(*val1),
+                    sim2_value: Some(*val2),
+                    ..Self::new()
+                });
+            }
+            for (index, val1, val2) in &reg_diff.float_registers_diff {
+                items.push(Self {
+                    register: Some(RegisterRef::Float(*index)),
+                    sim1_value: Some(*val1),
+                    sim2_value: Some(*val2),
+                    ..Self::new()
+                });
+            }
+            let fcsr = reg_diff
+                .float_csr_diff
+                .map(|(val1, val2)| ("fcsr".to_string(), val1, val2));
+            for (name, val1, val2) in reg_diff.core_csrs_diff.iter().cloned().chain(fcsr) {
+                items.push(Self {
+                    csr: Some(name),
+                    sim1_value: Some(val1),
+                    sim2_value: Some(val2),
+                    ..Self::new()
+                });
+            }
+            if let Some((status1, status2)) = &reg_diff.float_registers_status_changed {
+                items.push(Self::note(format!(
+                    "float registers {} vs {}",
+                    status1, status2
+                )));
+            }
+            if let Some((status1, status2)) = &reg_diff.float_csr_status_changed {
+                items.push(Self::note(format!("fcsr {} vs {}", status1, status2)));
+            }
+        }
+        if let Some(run_diff) = diff.run_outcome_diff.as_ref().filter(|d| !d.is_empty()) {
+            items.push(Self::note(match &run_diff.classification_changed {
+                Some((class1, class2)) => format!("run outcome {:?} vs {:?}", class1, class2),
+                None => "tohost exit code".to_string(),
+            }));
+        }
+        items
+    }
+}
+
+impl fmt::Display for DivergenceItem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(note) = &self.note {
+            return write!(f, "{}", note);
+        }
+        let mut parts = Vec::new();
+        if let Some(category) = &self.category {
+            parts.push(format_category_title(category));
+        }
+        if let Some(register) = self.register {
+            parts.push(format!("register {}", register));
+        } else if let Some(csr) = self.csr.as_ref().filter(|_| self.category.is_none()) {
+            parts.push(format!("csr {}", csr));
+        }
+        if let Some(pc) = self.pc {
+            parts.push(format!("@ 0x{:X}", pc));
+        }
+        if let Some(opcode) = &self.opcode {
+            parts.push(format!("({})", opcode));
+        }
+        write!(f, "{}", parts.join(" "))
+    }
+}
+
+/// 分诊结论
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TriageClass {
+    /// 所有差异都属于未修复的已知缺陷
+    Known,
+    /// 至少一个差异不在缺陷库中
+    New,
+    /// 差异属于已标记为修复的缺陷
+    Reappeared,
+}
+
+impl fmt::Display for TriageClass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TriageClass::Known => write!(f, "📚 known"),
+            TriageClass::New => write!(f, "🆕 new"),
+            TriageClass::Reappeared => write!(f, "♻️ fixed but reappeared"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TriagedItem {
+    pub item: DivergenceItem,
+    pub bug_id: Option<String>,
+    pub ticket: Option<String>,
+    pub status: Option<BugStatus>,
+}
+
+/// Triage of one Standard diff
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TriageReport {
+    pub classification: TriageClass,
+    pub items: Vec<TriagedItem>,
+}
+
+impl TriageReport {
+    /// 匹配到的缺陷 ID (去重)
+    pub fn bug_ids(&self) -> Vec<String> {
+        let mut ids: Vec<String> = self
+            .items
+            .iter()
+            .filter_map(|item| item.bug_id.clone())
+            .collect();
+        ids.sort();
+        ids.dedup();
+        ids
+    }
+
+    /// 保存为 `triage.json` 和 `triage.md`
+    pub fn save<P: AsRef<Path>>(&self, build_dir: P) -> Result<()> {
+        let base = build_dir.as_ref().join(TRIAGE_REPORT_FILE);
+        fs::write(
+            base.with_extension("json"),
+            serde_json::to_string_pretty(self)?,
+        )?;
+        fs::write(base.with_extension("md"), self.to_string())?;
+        Ok(())
+    }
+}
+
+impl fmt::Display for TriageReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "# 🐞 Triage")?;
+        writeln!(f)?;
+        writeln!(f, "**Result:** {}", self.classification)?;
+        writeln!(f)?;
+        writeln!(f, "| Difference | Known Bug | Ticket | Status |")?;
+        writeln!(f, "|------------|-----------|--------|--------|")?;
+        for triaged in &self.items {
+            writeln!(
+                f,
+                "| {} | {} | {} | {} |",
+                triaged.item,
+                triaged.bug_id.as_deref().unwrap_or("🆕 -"),
+                triaged.ticket.as_deref().unwrap_or("-"),
+                triaged
+                    .status
+                    .map_or("-".to_string(), |status| status.to_string())
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::elf::tracer::InstructionTrace;
+    use crate::output_diff::diff::{ExceptionListDiff, PairedExceptionDiff, RegistersDumpDiff};
+    use crate::output_parser::ExceptionCSRs;
+
+    fn ex(mepc: u64, mcause: u64, instruction: &str) -> ExceptionDump {
+        ExceptionDump {
+            csrs: ExceptionCSRs {
+                mstatus: 0,
+                mcause,
+                mepc,
+                mtval: 0,
+                mie: 0,
+                mip: 0,
+                mtvec: 0,
+                mscratch: 0,
+                mhartid: 0,
+            },
+            position: 0,
+            inst_trace: Some(InstructionTrace {
+                pc: mepc,
+                disassembly: instruction.to_string(),
+                machine_code: String::new(),
+                original_instruction: instruction.to_string(),
+            }),
+        }
+    }
+
+    /// Rocket 上 fence.i 非法、lw 的 mtval 不同以及 a0 不同的差异
+    fn diff() -> StandardExecutionOutputDiff {
+        let paired = ex(0x80, 5, "lw a0, 0(a1)");
+        StandardExecutionOutputDiff {
+            sim1_emulator_type: EmulatorType::Spike,
+            sim2_emulator_type: EmulatorType::Rocket,
+            exceptions_diff: Some(ExceptionListDiff {
+                sim1_emulator_type: EmulatorType::Spike,
+                sim2_emulator_type: EmulatorType::Rocket,
+                list1_only_exceptions: Vec::new(),
+                list2_only_exceptions: vec![ex(0x100, 2, "fence.i")],
+                paired_exceptions_diffs: vec![PairedExceptionDiff {
+                    exception1: paired.clone(),
+                    exception2: paired,
+                    csrs_differences: vec![("mtval".to_string(), 0x1000, 0)],
+                }],
+                categorized_summary: Vec::new(),
+                edits: Vec::new(),
+                suppressed: Vec::new(),
+            }),
+            register_dump_status: None,
+            register_dump_diff: Some(RegistersDumpDiff {
+                emulator_type1: EmulatorType::Spike,
+                emulator_type2: EmulatorType::Rocket,
+                int_registers_diff: vec![(10, "a0".to_string(), 1, 2)],
+                core_csrs_diff: Vec::new(),
+                float_registers_status_changed: None,
+                float_registers_diff: Vec::new(),
+                float_csr_status_changed: None,
+                float_csr_diff: None,
+                suppressed: Vec::new(),
+            }),
+            conversion_stats_diff: None,
+            run_outcome_diff: None,
+            suppressed: Vec::new(),
+        }
+    }
+
+    fn bug(id: &str, status: BugStatus, pattern: BugPattern) -> KnownBug {
+        KnownBug {
+            id: id.to_string(),
+            ticket: None,
+            status,
+            description: None,
+            pattern,
+        }
+    }
+
+    fn fence_i() -> BugPattern {
+        BugPattern {
+            category: Some(CategoryKind::OnlyInSimulator),
+            simulator: Some(EmulatorType::Rocket),
+            opcode: Some("fence.i".to_string()),
+            ..Default::default()
+        }
+    }
+
+    fn mtval() -> BugPattern {
+        BugPattern {
+            category: Some(CategoryKind::MtvalDifference),
+            csr: Some("mtval".to_string()),
+            ..Default::default()
+        }
+    }
+
+    fn a0() -> BugPattern {
+        BugPattern {
+            register: Some(RegisterRef::Int(10)),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_triage_classes() {
+        use BugStatus::{Fixed, Open};
+
+        let cases: Vec<(&str, Vec<KnownBug>, TriageClass)> = vec![
+            (
+                "every item covered by open bugs",
+                vec![
+                    bug("fence", Open, fence_i()),
+                    bug("mtval", Open, mtval()),
+                    bug("a0", Open, a0()),
+                ],
+                TriageClass::Known,
+            ),
+            (
+                "one item uncovered",
+                vec![bug("fence", Open, fence_i()), bug("mtval", Open, mtval())],
+                TriageClass::New,
+            ),
+            ("empty database", vec![], TriageClass::New),
+            (
+                "covered, one bug fixed",
+                vec![
+                    bug("fence", Open, fence_i()),
+                    bug("mtval", Fixed, mtval()),
+                    bug("a0", Open, a0()),
+                ],
+                TriageClass::Reappeared,
+            ),
+            (
+                "uncovered item wins over a fixed bug",
+                vec![bug("fence", Fixed, fence_i()), bug("mtval", Open, mtval())],
+                TriageClass::New,
+            ),
+        ];
+
+        for (name, bugs, expected) in cases {
+            let report = KnownBugDatabase { bugs }.triage(&diff()).unwrap();
+            assert_eq!(report.classification, expected, "{}", name);
+            assert_eq!(report.items.len(), 3, "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_triage_reports_first_matching_bug() {
+        let database = KnownBugDatabase {
+            bugs: vec![
+                bug("fence", BugStatus::Open, fence_i()),
+                bug(
+                    "any-rocket",
+                    BugStatus::Fixed,
+                    BugPattern {
+                        simulator: Some(EmulatorType::Rocket),
+                        ..Default::default()
+                    },
+                ),
+                bug("mtval", BugStatus::Open, mtval()),
+                bug("a0", BugStatus::Open, a0()),
+            ],
+        };
+        let report = database.triage(&diff()).unwrap();
+        assert_eq!(report.classification, TriageClass::Known);
+        assert_eq!(report.bug_ids(), vec!["a0", "fence", "mtval"]);
+
+        let hits = BTreeMap::from([("fence".to_string(), 1), ("a0".to_string(), 1)]);
+        let not_reproduced: Vec<&str> = database
+            .not_reproduced(&hits)
+            .iter()
+            .map(|bug| bug.id.as_str())
+            .collect();
+        assert_eq!(not_reproduced, vec!["mtval"]);
+    }
+
+    #[test]
+    fn test_triage_without_items_is_none() {
+        let mut clean = diff();
+        clean.exceptions_diff = None;
+        clean.register_dump_diff = None;
+        let database = KnownBugDatabase {
+            bugs: vec![bug("a0", BugStatus::Open, a0())],
+        };
+        assert_eq!(database.triage(&clean), None);
+    }
+
+    #[test]
+    fn test_pattern_matching_edge_cases() {
+        let item = |sim1_value: Option<u64>, sim2_value: Option<u64>| DivergenceItem {
+            category: Some(ExceptionDiffCategory::McauseDifference {
+                sim1_cause: 2,
+                sim2_cause: 5,
+            }),
+            opcode: Some("FLI.S".to_string()),
+            csr: Some("mcause".to_string()),
+            sim1_value,
+            sim2_value,
+            ..DivergenceItem::new()
+        };
+        let register = DivergenceItem {
+            register: Some(RegisterRef::Int(10)),
+            sim1_value: Some(0x1234),
+            sim2_value: Some(0x5678),
+            ..DivergenceItem::new()
+        };
+        let masked = |sim1_value: u64, mask: u64| BugPattern {
+            sim1_value: Some(sim1_value),
+            mask: Some(mask),
+            ..Default::default()
+        };
+
+        let cases: Vec<(&str, BugPattern, DivergenceItem, bool)> = vec![
+            (
+                "empty pattern",
+                BugPattern::default(),
+                register.clone(),
+                true,
+            ),
+            (
+                "category ignores values",
+                BugPattern {
+                    category: Some(CategoryKind::McauseDifference),
+                    ..Default::default()
+                },
+                item(Some(2), Some(5)),
+                true,
+            ),
+            (
+                "category of register item",
+                BugPattern {
+                    category: Some(CategoryKind::McauseDifference),
+                    ..Default::default()
+                },
+                register.clone(),
+                false,
+            ),
+            (
+                "opcode case-insensitive",
+                BugPattern {
+                    opcode: Some("fli.s".to_string()),
+                    ..Default::default()
+                },
+                item(Some(2), Some(5)),
+                true,
+            ),
+            (
+                "opcode without trace",
+                BugPattern {
+                    opcode: Some("fli.s".to_string()),
+                    ..Default::default()
+                },
+                register.clone(),
+                false,
+            ),
+            (
+                "simulator mismatch",
+                BugPattern {
+                    simulator: Some(EmulatorType::Spike),
+                    ..Default::default()
+                },
+                item(Some(2), Some(5)),
+                false,
+            ),
+            (
+                "exact value",
+                masked(0x1234, u64::MAX),
+                register.clone(),
+                true,
+            ),
+            (
+                "masked low byte",
+                masked(0xFF34, 0xFF),
+                register.clone(),
+                true,
+            ),
+            (
+                "masked mismatch",
+                masked(0x1235, 0xFF),
+                register.clone(),
+                false,
+            ),
+            ("zero mask", masked(0xDEAD, 0), register.clone(), true),
+            (
+                "value absent on one side",
+                masked(2, u64::MAX),
+                item(None, Some(2)),
+                false,
+            ),
+            (
+                "both values",
+                BugPattern {
+                    sim1_value: Some(2),
+                    sim2_value: Some(5),
+                    ..Default::default()
+                },
+                item(Some(2), Some(5)),
+                true,
+            ),
+            (
+                "int register is not float",
+                BugPattern {
+                    register: Some(RegisterRef::Float(10)),
+                    ..Default::default()
+                },
+                register.clone(),
+                false,
+            ),
+            (
+                "csr pattern on register item",
+                BugPattern {
+                    csr: Some("mcause".to_string()),
+                    ..Default::default()
+                },
+                register,
+                false,
+            ),
+        ];
+
+        for (name, pattern, item, expected) in cases {
+            assert_eq!(pattern.matches(&item), expected, "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_load_rejects_empty_pattern() {
+        let example = KnownBugDatabase::load("known_bugs.example.toml").unwrap();
+        assert_eq!(example.bugs.len(), 2);
+        assert_eq!(example.bugs[0].pattern, fence_i());
+
+        let path =
+            std::env::temp_dir().join(format!("known_bugs_test_{}.toml", std::process::id()));
+        fs::write(
+            &path,
+            "[[bugs]]\nid = \"catch-all\"\nstatus = \"open\"\n[bugs.pattern]\n",
+        )
+        .unwrap();
+        let err = KnownBugDatabase::load(&path).unwrap_err();
+        assert!(err.to_string().contains("catch-all"));
+        fs::remove_file(&path).unwrap();
+    }
+}
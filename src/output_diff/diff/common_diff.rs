@@ -1,4 +1,6 @@
 use crate::emulators::EmulatorType;
+use crate::output_diff::diff::ignore::{SuppressedDiff, write_suppressed};
+use crate::output_diff::diff::run_diff::RunOutcomeDiff;
 use crate::output_diff::diff::{
     ExceptionListDiff, RegistersDumpDiff, compare_exception_dump_lists, compare_registers_dumps,
 };
@@ -14,6 +16,11 @@ pub struct CommonExecutionOutputDiff {
     pub register_dumps_count_changed: Option<(usize, usize)>,
     pub differing_register_dumps: Vec<(usize, RegistersDumpDiff)>,
     pub exception_dumps_diff: Option<ExceptionListDiff>,
+    #[serde(default)]
+    pub run_outcome_diff: Option<RunOutcomeDiff>,
+    /// 被 `[diff.ignore]` 忽略的差异
+    #[serde(default)]
+    pub suppressed: Vec<SuppressedDiff>,
 }
 
 impl CommonExecutionOutputDiff {
@@ -25,6 +32,7 @@ impl CommonExecutionOutputDiff {
                 .exception_dumps_diff
                 .as_ref()
                 .map_or(true, |e| e.is_empty())
+            && self.run_outcome_diff.as_ref().is_none_or(|r| r.is_empty())
     }
 }
 
@@ -46,6 +54,7 @@ impl fmt::Display for CommonExecutionOutputDiff {
                 "No significant differences found - outputs from both simulators match exactly!"
             )?;
             writeln!(f)?;
+            write_suppressed(f, &self.suppressed, &sim1_name, &sim2_name)?;
             return Ok(());
         }
 
@@ -81,6 +90,11 @@ impl fmt::Display for CommonExecutionOutputDiff {
             writeln!(f, "| Exception Dumps | Exception information differs |")?;
         }
 
+        if self.run_outcome_diff.is_some() {
+            diff_count += 1;
+            writeln!(f, "| Run Outcome | Emulators finished differently |")?;
+        }
+
         if diff_count == 0 {
             writeln!(f, "| - | No Differences |")?;
         }
@@ -90,6 +104,13 @@ impl fmt::Display for CommonExecutionOutputDiff {
         writeln!(f, "## Detailed Diff Analysis")?;
         writeln!(f)?;
 
+        if let Some(run_diff) = self.run_outcome_diff.as_ref().filter(|r| !r.is_empty()) {
+            writeln!(f, "### Run Outcome Difference")?;
+            writeln!(f)?;
+            writeln!(f, "{}", run_diff)?;
+            writeln!(f)?;
+        }
+
         if let Some((count1, count2)) = self.register_dumps_count_changed {
             writeln!(f, "### Register Dump Count Difference")?;
             writeln!(f)?;
@@ -134,6 +155,8 @@ impl fmt::Display for CommonExecutionOutputDiff {
             }
         }
 
+        write_suppressed(f, &self.suppressed, &sim1_name, &sim2_name)?;
+
         Ok(())
     }
 }
@@ -143,12 +166,14 @@ pub fn compare_execution_outputs(
     output2: &CommonExecutionOutput,
 ) -> CommonExecutionOutputDiff {
     let mut diff = CommonExecutionOutputDiff {
-        sim1_emulator_type: output1.emulator_type,
-        sim2_emulator_type: output2.emulator_type,
+        sim1_emulator_type: output1.emulator_type.clone(),
+        sim2_emulator_type: output2.emulator_type.clone(),
         output_items_status: None,
         register_dumps_count_changed: None,
         differing_register_dumps: Vec::new(),
         exception_dumps_diff: None,
+        run_outcome_diff: None,
+        suppressed: Vec::new(),
     };
 
     if output1.output_items.len() != output2.output_items.len() {
@@ -187,20 +212,35 @@ pub fn compare_execution_outputs(
             .zip(output2.register_dumps.iter())
             .enumerate()
         {
-            let reg_dump_diff =
-                compare_registers_dumps(rd1, rd2, output1.emulator_type, output2.emulator_type);
+            let mut reg_dump_diff = compare_registers_dumps(
+                rd1,
+                rd2,
+                output1.emulator_type.clone(),
+                output2.emulator_type.clone(),
+            );
+            diff.suppressed
+                .extend(
+                    reg_dump_diff
+                        .suppressed
+                        .drain(..)
+                        .map(|suppressed| SuppressedDiff {
+                            location: format!("register dump #{}", i),
+                            ..suppressed
+                        }),
+                );
             if !reg_dump_diff.is_empty() {
                 diff.differing_register_dumps.push((i, reg_dump_diff));
             }
         }
     }
 
-    let ex_list_diff = compare_exception_dump_lists(
+    let mut ex_list_diff = compare_exception_dump_lists(
         &output1.exception_dumps,
         &output2.exception_dumps,
-        output1.emulator_type,
-        output2.emulator_type,
+        output1.emulator_type.clone(),
+        output2.emulator_type.clone(),
     );
+    diff.suppressed.append(&mut ex_list_diff.suppressed);
     if !ex_list_diff.is_empty() {
         diff.exception_dumps_diff = Some(ex_list_diff);
     }
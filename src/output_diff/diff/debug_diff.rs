@@ -1,4 +1,6 @@
 use crate::emulators::EmulatorType;
+use crate::output_diff::diff::ignore::{SuppressedDiff, write_suppressed};
+use crate::output_diff::diff::run_diff::RunOutcomeDiff;
 use crate::output_diff::diff::{RegistersDumpDiff, compare_registers_dumps};
 use crate::output_parser::debug::DebugExecutionOutput;
 use serde::{Deserialize, Serialize};
@@ -11,6 +13,11 @@ pub struct DebugExecutionOutputDiff {
     pub register_dumps_count_changed: Option<(usize, usize)>,
     pub differing_register_dumps: Vec<(usize, RegistersDumpDiff)>,
     pub total_dumps_changed: Option<(usize, usize)>,
+    #[serde(default)]
+    pub run_outcome_diff: Option<RunOutcomeDiff>,
+    /// 被 `[diff.ignore]` 忽略的差异
+    #[serde(default)]
+    pub suppressed: Vec<SuppressedDiff>,
 }
 
 impl DebugExecutionOutputDiff {
@@ -18,6 +25,7 @@ impl DebugExecutionOutputDiff {
         self.register_dumps_count_changed.is_none()
             && self.differing_register_dumps.is_empty()
             && self.total_dumps_changed.is_none()
+            && self.run_outcome_diff.as_ref().is_none_or(|r| r.is_empty())
     }
 }
 
@@ -39,6 +47,7 @@ impl fmt::Display for DebugExecutionOutputDiff {
                 "No significant differences found - debug outputs from both simulators match exactly!"
             )?;
             writeln!(f)?;
+            write_suppressed(f, &self.suppressed, &sim1_name, &sim2_name)?;
             return Ok(());
         }
 
@@ -77,6 +86,11 @@ impl fmt::Display for DebugExecutionOutputDiff {
             )?;
         }
 
+        if self.run_outcome_diff.is_some() {
+            diff_count += 1;
+            writeln!(f, "| Run Outcome | Emulators finished differently |")?;
+        }
+
         if diff_count == 0 {
             writeln!(f, "| - | No Differences |")?;
         }
@@ -86,6 +100,13 @@ impl fmt::Display for DebugExecutionOutputDiff {
         writeln!(f, "## Detailed Diff Analysis")?;
         writeln!(f)?;
 
+        if let Some(run_diff) = self.run_outcome_diff.as_ref().filter(|r| !r.is_empty()) {
+            writeln!(f, "### Run Outcome Difference")?;
+            writeln!(f)?;
+            writeln!(f, "{}", run_diff)?;
+            writeln!(f)?;
+        }
+
         if let Some((count1, count2)) = self.register_dumps_count_changed {
             writeln!(f, "### Valid Register Dump Count Difference")?;
             writeln!(f)?;
@@ -153,6 +174,8 @@ impl fmt::Display for DebugExecutionOutputDiff {
             writeln!(f)?;
         }
 
+        write_suppressed(f, &self.suppressed, &sim1_name, &sim2_name)?;
+
         Ok(())
     }
 }
@@ -162,11 +185,13 @@ pub fn compare_debug_execution_outputs(
     output2: &DebugExecutionOutput,
 ) -> DebugExecutionOutputDiff {
     let mut diff = DebugExecutionOutputDiff {
-        sim1_emulator_type: output1.emulator_type,
-        sim2_emulator_type: output2.emulator_type,
+        sim1_emulator_type: output1.emulator_type.clone(),
+        sim2_emulator_type: output2.emulator_type.clone(),
         register_dumps_count_changed: None,
         differing_register_dumps: Vec::new(),
         total_dumps_changed: None,
+        run_outcome_diff: None,
+        suppressed: Vec::new(),
     };
 
     if output1.total_dumps != output2.total_dumps {
@@ -183,8 +208,22 @@ pub fn compare_debug_execution_outputs(
             .zip(output2.register_dumps.iter())
             .enumerate()
         {
-            let reg_dump_diff =
-                compare_registers_dumps(rd1, rd2, output1.emulator_type, output2.emulator_type);
+            let mut reg_dump_diff = compare_registers_dumps(
+                rd1,
+                rd2,
+                output1.emulator_type.clone(),
+                output2.emulator_type.clone(),
+            );
+            diff.suppressed
+                .extend(
+                    reg_dump_diff
+                        .suppressed
+                        .drain(..)
+                        .map(|suppressed| SuppressedDiff {
+                            location: format!("register dump #{}", i),
+                            ..suppressed
+                        }),
+                );
             if !reg_dump_diff.is_empty() {
                 diff.differing_register_dumps.push((i, reg_dump_diff));
             }
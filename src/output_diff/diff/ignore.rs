@@ -0,0 +1,540 @@
+//! 差异忽略规则
+//!
+//! The `[diff.ignore]` config section lists CSRs, CSR bits, registers and
+//! exceptions whose differences are expected (e.g. `marchid` always differs
+//! between Spike and Rocket). Matching differences are not reported as
+//! differences but collected as [`SuppressedDiff`]s, which every diff report
+//! lists in its own section.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use crate::config;
+use crate::emulators::EmulatorType;
+use crate::output_parser::ExceptionDump;
+use crate::output_parser::util::get_register_name;
+use crate::random_asm::denylist::opcode_of;
+
+/// Named CSR bits usable in `bits` (`csr.NAME`); any CSR also accepts `csr.<bit>`
+const NAMED_BITS: &[(&str, &str, u64)] = &[
+    ("mstatus", "SIE", 1 << 1),
+    ("mstatus", "MIE", 1 << 3),
+    ("mstatus", "SPIE", 1 << 5),
+    ("mstatus", "UBE", 1 << 6),
+    ("mstatus", "MPIE", 1 << 7),
+    ("mstatus", "SPP", 1 << 8),
+    ("mstatus", "VS", 0b11 << 9),
+    ("mstatus", "MPP", 0b11 << 11),
+    ("mstatus", "FS", 0b11 << 13),
+    ("mstatus", "XS", 0b11 << 15),
+    ("mstatus", "MPRV", 1 << 17),
+    ("mstatus", "SUM", 1 << 18),
+    ("mstatus", "MXR", 1 << 19),
+    ("mstatus", "TVM", 1 << 20),
+    ("mstatus", "TW", 1 << 21),
+    ("mstatus", "TSR", 1 << 22),
+    ("mstatus", "UXL", 0b11 << 32),
+    ("mstatus", "SXL", 0b11 << 34),
+    ("mstatus", "SBE", 1 << 36),
+    ("mstatus", "MBE", 1 << 37),
+    ("mstatus", "SD", 1 << 63),
+    ("mip", "SSIP", 1 << 1),
+    ("mip", "MSIP", 1 << 3),
+    ("mip", "STIP", 1 << 5),
+    ("mip", "MTIP", 1 << 7),
+    ("mip", "SEIP", 1 << 9),
+    ("mip", "MEIP", 1 << 11),
+    ("mie", "SSIE", 1 << 1),
+    ("mie", "MSIE", 1 << 3),
+    ("mie", "STIE", 1 << 5),
+    ("mie", "MTIE", 1 << 7),
+    ("mie", "SEIE", 1 << 9),
+    ("mie", "MEIE", 1 << 11),
+    ("fcsr", "NX", 1 << 0),
+    ("fcsr", "UF", 1 << 1),
+    ("fcsr", "OF", 1 << 2),
+    ("fcsr", "DZ", 1 << 3),
+    ("fcsr", "NV", 1 << 4),
+    ("fcsr", "FFLAGS", 0b1_1111),
+    ("fcsr", "FRM", 0b111 << 5),
+];
+
+/// 浮点寄存器 ABI 名称，按编号排列
+const FLOAT_ABI_NAMES: [&str; 32] = [
+    "ft0", "ft1", "ft2", "ft3", "ft4", "ft5", "ft6", "ft7", "fs0", "fs1", "fa0", "fa1", "fa2",
+    "fa3", "fa4", "fa5", "fa6", "fa7", "fs2", "fs3", "fs4", "fs5", "fs6", "fs7", "fs8", "fs9",
+    "fs10", "fs11", "ft8", "ft9", "ft10", "ft11",
+];
+
+/// Bits of one CSR masked out before comparing, written `mstatus.SD` or `mip.7`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct CsrBits {
+    pub csr: String,
+    pub mask: u64,
+    spec: String,
+}
+
+impl TryFrom<String> for CsrBits {
+    type Error = String;
+
+    fn try_from(spec: String) -> std::result::Result<Self, String> {
+        let (csr, bit) = spec
+            .split_once('.')
+            .ok_or_else(|| format!("invalid CSR bits `{}`, expected `csr.BIT`", spec))?;
+        let csr = csr.to_lowercase();
+        let mask = match bit.parse::<u32>() {
+            Ok(index) if index < 64 => 1u64 << index,
+            Ok(_) => return Err(format!("bit index out of range in `{}`", spec)),
+            Err(_) => NAMED_BITS
+                .iter()
+                .find(|(name, field, _)| *name == csr && field.eq_ignore_ascii_case(bit))
+                .map(|&(_, _, mask)| mask)
+                .ok_or_else(|| format!("unknown CSR bit `{}`", spec))?,
+        };
+        Ok(Self { csr, mask, spec })
+    }
+}
+
+impl From<CsrBits> for String {
+    fn from(bits: CsrBits) -> Self {
+        bits.spec
+    }
+}
+
+/// 整数或浮点寄存器，写作 `x5`、`t0`、`f3` 或 `fa0`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum RegisterRef {
+    Int(usize),
+    Float(usize),
+}
+
+impl TryFrom<String> for RegisterRef {
+    type Error = String;
+
+    fn try_from(name: String) -> std::result::Result<Self, String> {
+        let lower = name.to_lowercase();
+        let numbered = |prefix: &str| {
+            lower
+                .strip_prefix(prefix)
+                .and_then(|n| n.parse::<usize>().ok())
+                .filter(|&n| n < 32)
+        };
+        if let Some(index) = numbered("x") {
+            return Ok(RegisterRef::Int(index));
+        }
+        if let Some(index) = numbered("f") {
+            return Ok(RegisterRef::Float(index));
+        }
+        if lower == "fp" {
+            return Ok(RegisterRef::Int(8));
+        }
+        if let Some(index) = (0..32).find(|&i| get_register_name(i) == lower) {
+            return Ok(RegisterRef::Int(index));
+        }
+        if let Some(index) = FLOAT_ABI_NAMES.iter().position(|&abi| abi == lower) {
+            return Ok(RegisterRef::Float(index));
+        }
+        Err(format!("unknown register `{}`", name))
+    }
+}
+
+impl From<RegisterRef> for String {
+    fn from(register: RegisterRef) -> Self {
+        register.to_string()
+    }
+}
+
+impl fmt::Display for RegisterRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RegisterRef::Int(index) => write!(f, "x{}", index),
+            RegisterRef::Float(index) => write!(f, "f{}", index),
+        }
+    }
+}
+
+/// Predicate over exceptions; unset fields match anything.
+///
+/// `simulator` restricts the rule to exceptions raised only by that emulator,
+/// `csr` to paired exceptions whose given CSR differs.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ExceptionIgnoreRule {
+    pub simulator: Option<EmulatorType>,
+    pub mcause: Option<u64>,
+    /// 触发指令的助记符
+    pub opcode: Option<String>,
+    pub csr: Option<String>,
+}
+
+impl ExceptionIgnoreRule {
+    fn matches_exception(&self, exception: &ExceptionDump) -> bool {
+        self.mcause
+            .is_none_or(|mcause| mcause == exception.csrs.mcause)
+            && self.opcode.as_ref().is_none_or(|opcode| {
+                exception
+                    .inst_trace
+                    .as_ref()
+                    .and_then(|trace| opcode_of(&trace.disassembly))
+                    .is_some_and(|actual| actual.eq_ignore_ascii_case(opcode))
+            })
+    }
+
+    /// 仅在 `simulator` 中出现的异常
+    pub fn matches_only_in(&self, exception: &ExceptionDump, simulator: &EmulatorType) -> bool {
+        self.csr.is_none()
+            && self.simulator.as_ref().is_none_or(|s| s == simulator)
+            && self.matches_exception(exception)
+    }
+
+    /// 配对异常中 `csr_name` 的差异
+    pub fn matches_paired(
+        &self,
+        csr_name: &str,
+        exception1: &ExceptionDump,
+        exception2: &ExceptionDump,
+    ) -> bool {
+        self.simulator.is_none()
+            && self.csr.as_ref().is_none_or(|csr| csr == csr_name)
+            && (self.matches_exception(exception1) || self.matches_exception(exception2))
+    }
+}
+
+impl fmt::Display for ExceptionIgnoreRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut parts = Vec::new();
+        if let Some(simulator) = &self.simulator {
+            parts.push(format!("simulator = {}", simulator));
+        }
+        if let Some(mcause) = self.mcause {
+            parts.push(format!("mcause = {}", mcause));
+        }
+        if let Some(opcode) = &self.opcode {
+            parts.push(format!("opcode = {}", opcode));
+        }
+        if let Some(csr) = &self.csr {
+            parts.push(format!("csr = {}", csr));
+        }
+        if parts.is_empty() {
+            write!(f, "exceptions: any")
+        } else {
+            write!(f, "exceptions: {}", parts.join(", "))
+        }
+    }
+}
+
+/// `[diff.ignore]`, empty unless configured
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DiffIgnoreRules {
+    /// 完全忽略的 CSR (寄存器 dump 和异常 dump 中均生效)
+    pub csrs: Vec<String>,
+    /// 比较前屏蔽的 CSR 位
+    pub bits: Vec<CsrBits>,
+    pub registers: Vec<RegisterRef>,
+    pub exceptions: Vec<ExceptionIgnoreRule>,
+}
+
+impl DiffIgnoreRules {
+    /// 当前配置中的规则
+    pub fn current() -> &'static Self {
+        &config::get().diff.ignore
+    }
+
+    /// The rule suppressing a difference of `csr_name`, if any
+    pub fn csr_rule(&self, csr_name: &str, value1: u64, value2: u64) -> Option<String> {
+        if self.csrs.iter().any(|csr| csr == csr_name) {
+            return Some(format!("csrs: {}", csr_name));
+        }
+        let masked: Vec<&CsrBits> = self.bits.iter().filter(|b| b.csr == csr_name).collect();
+        let mask = masked.iter().fold(0, |mask, bits| mask | bits.mask);
+        if mask != 0 && (value1 & !mask) == (value2 & !mask) {
+            let specs: Vec<&str> = masked.iter().map(|bits| bits.spec.as_str()).collect();
+            return Some(format!("bits: {}", specs.join(", ")));
+        }
+        None
+    }
+
+    /// Bits of `csr_name` still compared: none for an ignored CSR, else all
+    /// but the masked `bits`
+    pub fn compared_bits(&self, csr_name: &str) -> u64 {
+        if self.csrs.iter().any(|csr| csr == csr_name) {
+            return 0;
+        }
+        !self
+            .bits
+            .iter()
+            .filter(|b| b.csr == csr_name)
+            .fold(0, |mask, bits| mask | bits.mask)
+    }
+
+    pub fn register_rule(&self, register: RegisterRef) -> Option<String> {
+        self.registers
+            .contains(&register)
+            .then(|| format!("registers: {}", register))
+    }
+
+    pub fn only_in_rule(
+        &self,
+        exception: &ExceptionDump,
+        simulator: &EmulatorType,
+    ) -> Option<String> {
+        self.exceptions
+            .iter()
+            .find(|rule| rule.matches_only_in(exception, simulator))
+            .map(|rule| rule.to_string())
+    }
+
+    pub fn paired_rule(
+        &self,
+        csr_name: &str,
+        exception1: &ExceptionDump,
+        exception2: &ExceptionDump,
+    ) -> Option<String> {
+        // 异常 dump 中没有的 CSR 不匹配任何规则
+        let (Some(value1), Some(value2)) = (
+            field_value(exception1, csr_name),
+            field_value(exception2, csr_name),
+        ) else {
+            return None;
+        };
+        self.csr_rule(csr_name, value1, value2).or_else(|| {
+            self.exceptions
+                .iter()
+                .find(|rule| rule.matches_paired(csr_name, exception1, exception2))
+                .map(|rule| rule.to_string())
+        })
+    }
+}
+
+fn field_value(exception: &ExceptionDump, csr_name: &str) -> Option<u64> {
+    let csrs = &exception.csrs;
+    match csr_name {
+        "mstatus" => Some(csrs.mstatus),
+        "mcause" => Some(csrs.mcause),
+        "mepc" => Some(csrs.mepc),
+        "mtval" => Some(csrs.mtval),
+        "mie" => Some(csrs.mie),
+        "mip" => Some(csrs.mip),
+        "mtvec" => Some(csrs.mtvec),
+        "mscratch" => Some(csrs.mscratch),
+        "mhartid" => Some(csrs.mhartid),
+        _ => None,
+    }
+}
+
+/// A difference left out of a report by a [`DiffIgnoreRules`] rule
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SuppressedDiff {
+    /// 差异所在位置，如 `register dump` 或 `exception @ 0x80002000`
+    pub location: String,
+    pub item: String,
+    pub sim1_value: Option<u64>,
+    pub sim2_value: Option<u64>,
+    /// 匹配的规则
+    pub rule: String,
+}
+
+/// 在报告末尾列出被忽略的差异
+pub fn write_suppressed(
+    f: &mut fmt::Formatter<'_>,
+    suppressed: &[SuppressedDiff],
+    sim1_name: &str,
+    sim2_name: &str,
+) -> fmt::Result {
+    if suppressed.is_empty() {
+        return Ok(());
+    }
+    writeln!(f, "## 🙈 Suppressed Differences")?;
+    writeln!(f)?;
+    writeln!(
+        f,
+        "{} differences matched `[diff.ignore]` rules and are not reported above.",
+        suppressed.len()
+    )?;
+    writeln!(f)?;
+    writeln!(
+        f,
+        "| Location | Item | {} | {} | Rule |",
+        sim1_name, sim2_name
+    )?;
+    writeln!(f, "|----------|------|------|------|------|")?;
+    let value = |v: Option<u64>| v.map_or("-".to_string(), |v| format!("0x{:016X}", v));
+    for diff in suppressed {
+        writeln!(
+            f,
+            "| {} | {} | {} | {} | {} |",
+            diff.location,
+            diff.item,
+            value(diff.sim1_value),
+            value(diff.sim2_value),
+            diff.rule
+        )?;
+    }
+    writeln!(f)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::elf::tracer::InstructionTrace;
+    use crate::output_parser::ExceptionCSRs;
+
+    fn exception(mcause: u64, mtval: u64, disassembly: &str) -> ExceptionDump {
+        ExceptionDump {
+            csrs: ExceptionCSRs {
+                mstatus: 0,
+                mcause,
+                mepc: 0x8000_1000,
+                mtval,
+                mie: 0,
+                mip: 0,
+                mtvec: 0x8000_0100,
+                mscratch: 0,
+                mhartid: 0,
+            },
+            position: 0,
+            inst_trace: Some(InstructionTrace {
+                pc: 0x8000_1000,
+                disassembly: disassembly.to_string(),
+                machine_code: "00000000".to_string(),
+                original_instruction: disassembly.to_string(),
+            }),
+        }
+    }
+
+    fn rules(toml_text: &str) -> DiffIgnoreRules {
+        toml::from_str(toml_text).unwrap()
+    }
+
+    #[test]
+    fn test_default_ignores_nothing() {
+        let rules = DiffIgnoreRules::default();
+        assert_eq!(rules.csr_rule("mcycle", 1, 2), None);
+        assert_eq!(rules.csr_rule("marchid", 1, 2), None);
+        assert_eq!(rules.compared_bits("mcycle"), u64::MAX);
+    }
+
+    #[test]
+    fn test_csr_bits() {
+        let sd = CsrBits::try_from("mstatus.SD".to_string()).unwrap();
+        assert_eq!((sd.csr.as_str(), sd.mask), ("mstatus", 1 << 63));
+        let mpp = CsrBits::try_from("MSTATUS.mpp".to_string()).unwrap();
+        assert_eq!((mpp.csr.as_str(), mpp.mask), ("mstatus", 0b11 << 11));
+        assert_eq!(CsrBits::try_from("mip.7".to_string()).unwrap().mask, 1 << 7);
+        assert_eq!(
+            CsrBits::try_from("mip.63".to_string()).unwrap().mask,
+            1 << 63
+        );
+
+        assert!(CsrBits::try_from("mip.64".to_string()).is_err());
+        assert!(CsrBits::try_from("mstatus.FOO".to_string()).is_err());
+        // 命名位只属于所在的 CSR
+        assert!(CsrBits::try_from("mie.SD".to_string()).is_err());
+        assert!(CsrBits::try_from("mstatus".to_string()).is_err());
+    }
+
+    #[test]
+    fn test_register_names() {
+        let parse = |name: &str| RegisterRef::try_from(name.to_string());
+        assert_eq!(parse("x5"), Ok(RegisterRef::Int(5)));
+        assert_eq!(parse("T6"), Ok(RegisterRef::Int(31)));
+        assert_eq!(parse("zero"), Ok(RegisterRef::Int(0)));
+        assert_eq!(parse("fp"), Ok(RegisterRef::Int(8)));
+        assert_eq!(parse("s0"), Ok(RegisterRef::Int(8)));
+        assert_eq!(parse("f3"), Ok(RegisterRef::Float(3)));
+        assert_eq!(parse("fa0"), Ok(RegisterRef::Float(10)));
+        assert_eq!(parse("ft11"), Ok(RegisterRef::Float(31)));
+        assert!(parse("x32").is_err());
+        assert!(parse("f32").is_err());
+        assert!(parse("mstatus").is_err());
+    }
+
+    #[test]
+    fn test_csr_and_register_rules() {
+        let rules = rules(
+            r#"
+            csrs = ["mcycle"]
+            bits = ["mstatus.SD", "mip.7"]
+            registers = ["t6"]
+            "#,
+        );
+        assert!(rules.csr_rule("mcycle", 1, 2).is_some());
+        assert!(rules.csr_rule("mstatus", 1 << 63, 0).is_some());
+        // 屏蔽位之外还有差异时照常报告
+        assert!(rules.csr_rule("mstatus", (1 << 63) | 1, 0).is_none());
+        assert!(rules.csr_rule("mip", 1 << 7, 0).is_some());
+        assert!(rules.csr_rule("minstret", 1, 2).is_none());
+
+        assert_eq!(rules.compared_bits("mcycle"), 0);
+        assert_eq!(rules.compared_bits("mstatus"), !(1 << 63));
+        assert_eq!(rules.compared_bits("mepc"), u64::MAX);
+
+        assert!(rules.register_rule(RegisterRef::Int(31)).is_some());
+        assert!(rules.register_rule(RegisterRef::Float(31)).is_none());
+    }
+
+    #[test]
+    fn test_only_in_and_paired_exception_rules() {
+        let rules = rules(
+            r#"
+            [[exceptions]]
+            simulator = "Rocket"
+            mcause = 2
+            opcode = "fence.i"
+
+            [[exceptions]]
+            csr = "mtval"
+            mcause = 2
+            "#,
+        );
+        let illegal = exception(2, 0, "fence.i");
+
+        // simulator 规则只匹配仅出现在该模拟器中的异常
+        assert!(
+            rules
+                .only_in_rule(&illegal, &EmulatorType::Rocket)
+                .is_some()
+        );
+        assert!(rules.only_in_rule(&illegal, &EmulatorType::Spike).is_none());
+        assert!(
+            rules
+                .only_in_rule(&exception(2, 0, "addi t0,t0,1"), &EmulatorType::Rocket)
+                .is_none()
+        );
+
+        // csr 规则只匹配配对异常中该 CSR 的差异
+        let other = exception(2, 0x100f, "fence.i");
+        assert!(rules.paired_rule("mtval", &illegal, &other).is_some());
+        assert!(rules.paired_rule("mepc", &illegal, &other).is_none());
+        assert!(
+            rules
+                .paired_rule(
+                    "mtval",
+                    &exception(3, 0, "ebreak"),
+                    &exception(3, 1, "ebreak")
+                )
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_unknown_csrs_match_no_exception_rule() {
+        let rules = rules(
+            r#"
+            csrs = ["mcycle"]
+
+            [[exceptions]]
+            mcause = 2
+            "#,
+        );
+        let (e1, e2) = (exception(2, 0, "fence.i"), exception(2, 1, "fence.i"));
+        assert!(rules.paired_rule("mtval", &e1, &e2).is_some());
+        // 异常 dump 中没有 mcycle 和未知 CSR
+        assert_eq!(rules.paired_rule("mcycle", &e1, &e2), None);
+        assert_eq!(rules.paired_rule("nonexistent", &e1, &e2), None);
+    }
+}
@@ -1,6 +1,10 @@
 pub mod common_diff;
 pub mod debug_diff;
+pub mod ignore;
+pub mod multi_diff;
+pub mod run_diff;
 pub mod standard_diff;
+pub mod trace_diff;
 
 use crate::elf::tracer::InstructionTrace;
 use crate::emulators::EmulatorType; // Use the canonical EmulatorType
@@ -8,6 +12,7 @@ use crate::output_parser::{
     CoreCSRs, ExceptionCSRs, ExceptionDump, RegistersDump, util::get_exception_description,
     util::get_register_name,
 };
+use log::warn;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fmt;
@@ -15,9 +20,12 @@ use std::fmt;
 // 引入必要的输出类型和 Diff 类型
 use self::common_diff::CommonExecutionOutputDiff;
 use self::debug_diff::DebugExecutionOutputDiff;
+use self::ignore::{DiffIgnoreRules, RegisterRef, SuppressedDiff};
 use self::standard_diff::StandardExecutionOutputDiff;
+use self::trace_diff::RetireTraceDiff;
 use crate::output_parser::common::CommonExecutionOutput;
 use crate::output_parser::debug::DebugExecutionOutput;
+use crate::output_parser::retire_trace::RetireTrace;
 use crate::output_parser::standard::StandardExecutionOutput;
 
 /// 异常差异类别
@@ -72,7 +80,7 @@ impl ExceptionDiffInfo {
             ExceptionDiffInfo::OnlyInSimulator {
                 simulator, mcause, ..
             } => ExceptionDiffCategory::OnlyInSimulator {
-                simulator: *simulator, // Dereference if it's a copy type, or clone
+                simulator: simulator.clone(),
                 mcause: *mcause,
             },
             ExceptionDiffInfo::CsrDifference {
@@ -191,14 +199,14 @@ pub fn format_category_title(category: &ExceptionDiffCategory) -> String {
         } => {
             let sim1_desc = get_exception_description(*sim1_cause);
             let sim2_desc = get_exception_description(*sim2_cause);
-            format!("MCAUSE Difference (Cause1: {} vs Cause2: {})", sim1_desc, sim2_desc)
+            format!(
+                "MCAUSE Difference (Cause1: {} vs Cause2: {})",
+                sim1_desc, sim2_desc
+            )
         }
         ExceptionDiffCategory::OnlyInSimulator { simulator, mcause } => {
             let desc = get_exception_description(*mcause);
-            format!(
-                "Only in {} (mcause: 0x{:X} - {})",
-                simulator, mcause, desc
-            )
+            format!("Only in {} (mcause: 0x{:X} - {})", simulator, mcause, desc)
         }
         ExceptionDiffCategory::MtvalDifference => "MTVAL Value Difference".to_string(),
         ExceptionDiffCategory::OtherCsrDifference { csr_name } => {
@@ -346,6 +354,9 @@ pub struct RegistersDumpDiff {
     pub float_registers_diff: Vec<(usize, u64, u64)>,       // index, val1, val2
     pub float_csr_status_changed: Option<(String, String)>, // e.g. (Some, None)
     pub float_csr_diff: Option<(u64, u64)>,
+    /// 被 `[diff.ignore]` 忽略的差异，不计入 is_empty
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub suppressed: Vec<SuppressedDiff>,
 }
 
 impl RegistersDumpDiff {
@@ -417,7 +428,11 @@ impl fmt::Display for RegistersDumpDiff {
         if !self.int_registers_diff.is_empty() {
             writeln!(f, "## Integer Register Differences")?;
             writeln!(f)?;
-            writeln!(f, "Difference count: {} / 32", self.int_registers_diff.len())?;
+            writeln!(
+                f,
+                "Difference count: {} / 32",
+                self.int_registers_diff.len()
+            )?;
             writeln!(f)?;
             writeln!(
                 f,
@@ -532,26 +547,47 @@ pub fn compare_registers_dumps(
         float_registers_diff: Vec::new(),
         float_csr_status_changed: None,
         float_csr_diff: None,
+        suppressed: Vec::new(),
+    };
+    let rules = DiffIgnoreRules::current();
+    let mut suppress = |item: String, val1: u64, val2: u64, rule: String| {
+        diff.suppressed.push(SuppressedDiff {
+            location: "register dump".to_string(),
+            item,
+            sim1_value: Some(val1),
+            sim2_value: Some(val2),
+            rule,
+        });
     };
 
     for i in 0..32 {
-        if dump1.int_registers[i] != dump2.int_registers[i] {
-            diff.int_registers_diff.push((
-                i,
-                get_register_name(i).to_string(),
-                dump1.int_registers[i],
-                dump2.int_registers[i],
-            ));
+        let (val1, val2) = (dump1.int_registers[i], dump2.int_registers[i]);
+        if val1 != val2 {
+            let name = get_register_name(i).to_string();
+            match rules.register_rule(RegisterRef::Int(i)) {
+                Some(rule) => suppress(format!("x{} ({})", i, name), val1, val2, rule),
+                None => diff.int_registers_diff.push((i, name, val1, val2)),
+            }
         }
     }
 
-    compare_core_csrs(&dump1.core_csrs, &dump2.core_csrs, &mut diff.core_csrs_diff);
+    let mut core_csrs_diff = Vec::new();
+    compare_core_csrs(&dump1.core_csrs, &dump2.core_csrs, &mut core_csrs_diff);
+    for (name, val1, val2) in core_csrs_diff {
+        match rules.csr_rule(&name, val1, val2) {
+            Some(rule) => suppress(name, val1, val2, rule),
+            None => diff.core_csrs_diff.push((name, val1, val2)),
+        }
+    }
 
     match (&dump1.float_registers, &dump2.float_registers) {
         (Some(fr1), Some(fr2)) => {
             for i in 0..32 {
                 if fr1[i] != fr2[i] {
-                    diff.float_registers_diff.push((i, fr1[i], fr2[i]));
+                    match rules.register_rule(RegisterRef::Float(i)) {
+                        Some(rule) => suppress(format!("f{}", i), fr1[i], fr2[i], rule),
+                        None => diff.float_registers_diff.push((i, fr1[i], fr2[i])),
+                    }
                 }
             }
         }
@@ -569,7 +605,10 @@ pub fn compare_registers_dumps(
     match (dump1.float_csr, dump2.float_csr) {
         (Some(fcsr1), Some(fcsr2)) => {
             if fcsr1 != fcsr2 {
-                diff.float_csr_diff = Some((fcsr1, fcsr2));
+                match rules.csr_rule("fcsr", fcsr1, fcsr2) {
+                    Some(rule) => suppress("fcsr".to_string(), fcsr1, fcsr2, rule),
+                    None => diff.float_csr_diff = Some((fcsr1, fcsr2)),
+                }
             }
         }
         (Some(_), None) => {
@@ -645,7 +684,7 @@ fn compare_core_csrs(csrs1: &CoreCSRs, csrs2: &CoreCSRs, diff_list: &mut Vec<(St
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct PairedExceptionDiff {
     pub exception1: ExceptionDump,                 // Cloned from list1
-    pub exception2: ExceptionDump,                 // Cloned from list2 (the matched one)
+    pub exception2: ExceptionDump,                 // Cloned from list2 (the aligned one)
     pub csrs_differences: Vec<(String, u64, u64)>, // field_name, val_from_ex1, val_from_ex2
 }
 
@@ -654,7 +693,7 @@ impl PairedExceptionDiff {
         let mut result = String::new();
 
         result.push_str(&format!(
-            "  Paired Exception Difference (matched by MEPC 0x{:016X}):\n",
+            "  Paired Exception Difference (aligned at MEPC 0x{:016X}):\n",
             self.exception1.csrs.mepc
         ));
 
@@ -706,6 +745,12 @@ pub struct ExceptionListDiff {
     pub list2_only_exceptions: Vec<ExceptionDump>,
     pub paired_exceptions_diffs: Vec<PairedExceptionDiff>,
     pub categorized_summary: Vec<CategorizedExceptionDiffs>,
+    /// 按程序顺序排列的对齐结果
+    #[serde(default)]
+    pub edits: Vec<ExceptionEdit>,
+    /// 被 `[diff.ignore]` 忽略的差异，不计入 is_empty
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub suppressed: Vec<SuppressedDiff>,
 }
 
 impl ExceptionListDiff {
@@ -724,6 +769,89 @@ impl ExceptionListDiff {
     }
 }
 
+impl ExceptionListDiff {
+    /// 按程序顺序列出插入、删除、替换以及 CSR 不同的配对
+    fn fmt_alignment(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        sim1_name: &str,
+        sim2_name: &str,
+    ) -> fmt::Result {
+        let changed = |edit: &&ExceptionEdit| match edit {
+            ExceptionEdit::Matched(k) => {
+                !self.paired_exceptions_diffs[*k].csrs_differences.is_empty()
+            }
+            _ => true,
+        };
+        let steps: Vec<&ExceptionEdit> = self.edits.iter().filter(changed).collect();
+        if steps.is_empty() {
+            return Ok(());
+        }
+
+        writeln!(f, "## Exception Alignment")?;
+        writeln!(f)?;
+        writeln!(
+            f,
+            "Differing steps in program order: {} / {}",
+            steps.len(),
+            self.edits.len()
+        )?;
+        writeln!(f)?;
+        writeln!(
+            f,
+            "| # | Edit | {} | {} | Disassembly |",
+            sim1_name, sim2_name
+        )?;
+        writeln!(f, "|---|------|------------|------------|-------------|")?;
+        let cell = |ex: Option<&ExceptionDump>| {
+            ex.map_or("-".to_string(), |ex| {
+                format!("0x{:X} / mcause 0x{:X}", ex.csrs.mepc, ex.csrs.mcause)
+            })
+        };
+        for (i, edit) in steps.into_iter().enumerate() {
+            let (label, ex1, ex2) = match *edit {
+                ExceptionEdit::Matched(k) => {
+                    let pair = &self.paired_exceptions_diffs[k];
+                    (
+                        "✏️ CSRs differ",
+                        Some(&pair.exception1),
+                        Some(&pair.exception2),
+                    )
+                }
+                ExceptionEdit::Substituted(k) => {
+                    let pair = &self.paired_exceptions_diffs[k];
+                    (
+                        "🔀 substituted",
+                        Some(&pair.exception1),
+                        Some(&pair.exception2),
+                    )
+                }
+                ExceptionEdit::Deleted(k) => {
+                    ("➖ deleted", Some(&self.list1_only_exceptions[k]), None)
+                }
+                ExceptionEdit::Inserted(k) => {
+                    ("➕ inserted", None, Some(&self.list2_only_exceptions[k]))
+                }
+            };
+            let disassembly = ex1
+                .or(ex2)
+                .and_then(|ex| ex.inst_trace.as_ref())
+                .map_or("-", |trace| trace.disassembly.as_str());
+            writeln!(
+                f,
+                "| {} | {} | {} | {} | {} |",
+                i + 1,
+                label,
+                cell(ex1),
+                cell(ex2),
+                disassembly
+            )?;
+        }
+        writeln!(f)?;
+        Ok(())
+    }
+}
+
 impl fmt::Display for ExceptionListDiff {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let sim1_name = self.sim1_emulator_type.to_string();
@@ -750,13 +878,41 @@ impl fmt::Display for ExceptionListDiff {
         writeln!(f)?;
         writeln!(f, "| Category | Count |")?;
         writeln!(f, "|----------|-------|")?;
-        writeln!(f, "| Exceptions only in {} | {} |", sim1_name, only_sim1_count)?;
-        writeln!(f, "| Exceptions only in {} | {} |", sim2_name, only_sim2_count)?;
-        writeln!(f, "| Matched exception pairs (total) | {} |", total_paired)?;
-        writeln!(f, "| Matched exception pairs (with differences) | {} |", paired_diffs_count)?;
-        writeln!(f, "| Categorized differences | {} |", self.categorized_summary.len())?;
+        let substituted_count = self
+            .edits
+            .iter()
+            .filter(|edit| matches!(edit, ExceptionEdit::Substituted(_)))
+            .count();
+        writeln!(
+            f,
+            "| Exceptions only in {} (deleted) | {} |",
+            sim1_name, only_sim1_count
+        )?;
+        writeln!(
+            f,
+            "| Exceptions only in {} (inserted) | {} |",
+            sim2_name, only_sim2_count
+        )?;
+        writeln!(f, "| Aligned exception pairs (total) | {} |", total_paired)?;
+        writeln!(
+            f,
+            "| Substituted exceptions (same MEPC, different MCAUSE) | {} |",
+            substituted_count
+        )?;
+        writeln!(
+            f,
+            "| Aligned exception pairs (with differences) | {} |",
+            paired_diffs_count
+        )?;
+        writeln!(
+            f,
+            "| Categorized differences | {} |",
+            self.categorized_summary.len()
+        )?;
         writeln!(f)?;
 
+        self.fmt_alignment(f, &sim1_name, &sim2_name)?;
+
         if !self.list1_only_exceptions.is_empty() {
             significant_diff_found = true;
             writeln!(f, "## Exceptions only in {}", sim1_name)?;
@@ -850,7 +1006,7 @@ impl fmt::Display for ExceptionListDiff {
 
         if !paired_diffs_with_actual_differences.is_empty() {
             significant_diff_found = true;
-            writeln!(f, "## Matched Exception Difference Details")?;
+            writeln!(f, "## Aligned Exception Difference Details")?;
             writeln!(f)?;
             writeln!(
                 f,
@@ -906,8 +1062,15 @@ impl fmt::Display for ExceptionListDiff {
                 if !pair_diff.csrs_differences.is_empty() {
                     writeln!(f, "#### CSR Field Differences")?;
                     writeln!(f)?;
-                    writeln!(f, "| CSR Field | {} | {} | Difference Description |", sim1_name, sim2_name)?;
-                    writeln!(f, "|-----------|------------|------------|----------------------|")?;
+                    writeln!(
+                        f,
+                        "| CSR Field | {} | {} | Difference Description |",
+                        sim1_name, sim2_name
+                    )?;
+                    writeln!(
+                        f,
+                        "|-----------|------------|------------|----------------------|"
+                    )?;
 
                     for (name, val1, val2) in &pair_diff.csrs_differences {
                         let diff_desc = if name == "mcause" {
@@ -932,11 +1095,11 @@ impl fmt::Display for ExceptionListDiff {
                 }
             }
         } else if !self.paired_exceptions_diffs.is_empty() {
-            writeln!(f, "## Matched Exception Status")?;
+            writeln!(f, "## Aligned Exception Status")?;
             writeln!(f)?;
             writeln!(
                 f,
-                "{} matched exception pairs, no differences",
+                "{} aligned exception pairs, no differences",
                 self.paired_exceptions_diffs.len()
             )?;
             writeln!(f)?;
@@ -1018,9 +1181,228 @@ impl fmt::Display for ExceptionListDiff {
     }
 }
 
+/// 超过该规模 (去掉公共前后缀后) 的异常序列改用贪心对齐
+const MAX_ALIGNMENT_CELLS: usize = 1 << 22;
+/// 贪心对齐时向前查找的异常数
+const GREEDY_ALIGNMENT_WINDOW: usize = 64;
+
+/// One step of the alignment of two exception sequences
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ExceptionAlignment {
+    /// mepc 和 mcause 相同
+    Matched { index1: usize, index2: usize },
+    /// 同一 mepc 上 mcause 不同
+    Substituted { index1: usize, index2: usize },
+    /// 仅在第一个序列中出现
+    Deleted { index1: usize },
+    /// 仅在第二个序列中出现
+    Inserted { index2: usize },
+}
+
+/// 对齐所用的异常标识
+fn alignment_key(ex: &ExceptionDump) -> (u64, u64) {
+    (ex.csrs.mepc, ex.csrs.mcause)
+}
+
+/// Aligns two exception sequences in program order with a minimal edit
+/// script over `(mepc, mcause)`: matching exceptions cost nothing, an
+/// exception whose mcause changed at the same mepc is one substitution and
+/// everything else is inserted or deleted.
+pub fn align_exception_lists(
+    list1: &[ExceptionDump],
+    list2: &[ExceptionDump],
+) -> Vec<ExceptionAlignment> {
+    let prefix = list1
+        .iter()
+        .zip(list2)
+        .take_while(|(ex1, ex2)| alignment_key(ex1) == alignment_key(ex2))
+        .count();
+    let suffix = list1[prefix..]
+        .iter()
+        .rev()
+        .zip(list2[prefix..].iter().rev())
+        .take_while(|(ex1, ex2)| alignment_key(ex1) == alignment_key(ex2))
+        .count();
+    let middle1 = &list1[prefix..list1.len() - suffix];
+    let middle2 = &list2[prefix..list2.len() - suffix];
+
+    let mut alignment: Vec<ExceptionAlignment> = (0..prefix)
+        .map(|i| ExceptionAlignment::Matched {
+            index1: i,
+            index2: i,
+        })
+        .collect();
+    let middle = if (middle1.len() + 1) * (middle2.len() + 1) <= MAX_ALIGNMENT_CELLS {
+        edit_distance_alignment(middle1, middle2)
+    } else {
+        warn!(
+            "Exception lists too long for exact alignment ({} x {}), aligning greedily",
+            middle1.len(),
+            middle2.len()
+        );
+        greedy_alignment(middle1, middle2)
+    };
+    alignment.extend(middle.into_iter().map(|step| match step {
+        ExceptionAlignment::Matched { index1, index2 } => ExceptionAlignment::Matched {
+            index1: index1 + prefix,
+            index2: index2 + prefix,
+        },
+        ExceptionAlignment::Substituted { index1, index2 } => ExceptionAlignment::Substituted {
+            index1: index1 + prefix,
+            index2: index2 + prefix,
+        },
+        ExceptionAlignment::Deleted { index1 } => ExceptionAlignment::Deleted {
+            index1: index1 + prefix,
+        },
+        ExceptionAlignment::Inserted { index2 } => ExceptionAlignment::Inserted {
+            index2: index2 + prefix,
+        },
+    }));
+    let (start1, start2) = (list1.len() - suffix, list2.len() - suffix);
+    alignment.extend((0..suffix).map(|i| ExceptionAlignment::Matched {
+        index1: start1 + i,
+        index2: start2 + i,
+    }));
+    alignment
+}
+
+fn edit_distance_alignment(
+    list1: &[ExceptionDump],
+    list2: &[ExceptionDump],
+) -> Vec<ExceptionAlignment> {
+    const DIAGONAL: u8 = 0;
+    const DELETE: u8 = 1;
+    const INSERT: u8 = 2;
+
+    let (n, m) = (list1.len(), list2.len());
+    let width = m + 1;
+    // cost[j] 为 list1[i..] 与 list2[j..] 的编辑距离，从后向前计算以便按程序顺序回溯
+    let mut steps = vec![INSERT; (n + 1) * width];
+    let mut next: Vec<u32> = (0..=m).rev().map(|j| j as u32).collect();
+    let mut cost = vec![0u32; width];
+    for i in (0..n).rev() {
+        cost[m] = (n - i) as u32;
+        steps[i * width + m] = DELETE;
+        for j in (0..m).rev() {
+            let (key1, key2) = (alignment_key(&list1[i]), alignment_key(&list2[j]));
+            let mut best = (next[j] + 1, DELETE);
+            if cost[j + 1] + 1 < best.0 {
+                best = (cost[j + 1] + 1, INSERT);
+            }
+            if key1.0 == key2.0 {
+                let diagonal = next[j + 1] + u32::from(key1.1 != key2.1);
+                if diagonal <= best.0 {
+                    best = (diagonal, DIAGONAL);
+                }
+            }
+            cost[j] = best.0;
+            steps[i * width + j] = best.1;
+        }
+        std::mem::swap(&mut next, &mut cost);
+    }
+
+    let mut alignment = Vec::with_capacity(n.max(m));
+    let (mut i, mut j) = (0, 0);
+    while i < n || j < m {
+        match steps[i * width + j] {
+            DIAGONAL => {
+                alignment.push(if list1[i].csrs.mcause == list2[j].csrs.mcause {
+                    ExceptionAlignment::Matched {
+                        index1: i,
+                        index2: j,
+                    }
+                } else {
+                    ExceptionAlignment::Substituted {
+                        index1: i,
+                        index2: j,
+                    }
+                });
+                i += 1;
+                j += 1;
+            }
+            DELETE => {
+                alignment.push(ExceptionAlignment::Deleted { index1: i });
+                i += 1;
+            }
+            _ => {
+                alignment.push(ExceptionAlignment::Inserted { index2: j });
+                j += 1;
+            }
+        }
+    }
+    alignment
+}
+
+/// 超长序列的线性对齐: 不一致时在窗口内寻找最近的重新同步点
+fn greedy_alignment(list1: &[ExceptionDump], list2: &[ExceptionDump]) -> Vec<ExceptionAlignment> {
+    let mut alignment = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < list1.len() && j < list2.len() {
+        let (key1, key2) = (alignment_key(&list1[i]), alignment_key(&list2[j]));
+        if key1 == key2 {
+            alignment.push(ExceptionAlignment::Matched {
+                index1: i,
+                index2: j,
+            });
+            i += 1;
+            j += 1;
+            continue;
+        }
+        let find = |list: &[ExceptionDump], from: usize, key: (u64, u64)| {
+            list.iter()
+                .skip(from)
+                .take(GREEDY_ALIGNMENT_WINDOW)
+                .position(|ex| alignment_key(ex) == key)
+        };
+        match (find(list2, j, key1), find(list1, i, key2)) {
+            (Some(skip2), skip1) if skip1.is_none_or(|skip1| skip2 <= skip1) => {
+                alignment
+                    .extend((j..j + skip2).map(|index2| ExceptionAlignment::Inserted { index2 }));
+                j += skip2;
+            }
+            (_, Some(skip1)) => {
+                alignment
+                    .extend((i..i + skip1).map(|index1| ExceptionAlignment::Deleted { index1 }));
+                i += skip1;
+            }
+            _ if key1.0 == key2.0 => {
+                alignment.push(ExceptionAlignment::Substituted {
+                    index1: i,
+                    index2: j,
+                });
+                i += 1;
+                j += 1;
+            }
+            _ => {
+                alignment.push(ExceptionAlignment::Deleted { index1: i });
+                alignment.push(ExceptionAlignment::Inserted { index2: j });
+                i += 1;
+                j += 1;
+            }
+        }
+    }
+    alignment.extend((i..list1.len()).map(|index1| ExceptionAlignment::Deleted { index1 }));
+    alignment.extend((j..list2.len()).map(|index2| ExceptionAlignment::Inserted { index2 }));
+    alignment
+}
+
+/// 差异报告中的一步对齐，下标指向 [`ExceptionListDiff`] 中对应的列表
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ExceptionEdit {
+    /// 下标指向 paired_exceptions_diffs
+    Matched(usize),
+    /// 下标指向 paired_exceptions_diffs
+    Substituted(usize),
+    /// 下标指向 list1_only_exceptions
+    Deleted(usize),
+    /// 下标指向 list2_only_exceptions
+    Inserted(usize),
+}
+
 /// Compares two lists of `ExceptionDump`.
-/// Matching is done based on mepc only - this is the ONLY criteria for exception identity.
-/// All other fields (mcause, mtval, etc.) can differ and will be recorded as differences.
+/// The lists are aligned in program order by [`align_exception_lists`]:
+/// aligned pairs (matched or substituted) have all their CSR fields compared,
+/// deleted exceptions exist only in list1 and inserted ones only in list2.
 /// Assumes list1 is from sim1_type and list2 from sim2_type for categorization purposes.
 pub fn compare_exception_dump_lists(
     list1: &[ExceptionDump],
@@ -1028,114 +1410,111 @@ pub fn compare_exception_dump_lists(
     sim1_type: EmulatorType,
     sim2_type: EmulatorType,
 ) -> ExceptionListDiff {
+    let rules = DiffIgnoreRules::current();
     let mut list1_only_exceptions = Vec::new();
+    let mut list2_only_exceptions = Vec::new();
     let mut paired_exceptions_diffs = Vec::new();
+    let mut edits = Vec::new();
     let mut raw_diffs_for_categorization = Vec::<ExceptionDiffInfo>::new();
-
-    // Key: mepc, Value: list of indices in list2
-    let mut list2_map: HashMap<u64, Vec<usize>> = HashMap::new();
-    for (i, ex2) in list2.iter().enumerate() {
-        list2_map.entry(ex2.csrs.mepc).or_default().push(i);
-    }
-
-    let mut list2_matched_indices: Vec<bool> = vec![false; list2.len()];
-
-    // Process list1 exceptions
-    for ex1 in list1.iter() {
-        let mepc = ex1.csrs.mepc;
-
-        if let Some(indices_in_list2) = list2_map.get_mut(&mepc) {
-            // Find the first unmatched exception in list2 with same mepc
-            if let Some(idx_in_list2_vec) = indices_in_list2
-                .iter()
-                .position(|&idx2| !list2_matched_indices[idx2])
-            {
-                let list2_idx = indices_in_list2[idx_in_list2_vec];
-                list2_matched_indices[list2_idx] = true;
-
-                let ex2 = &list2[list2_idx];
+    let mut suppressed = Vec::new();
+
+    // 异常仅在一侧出现且匹配规则时记入 suppressed，返回是否已忽略
+    let suppress_only_in =
+        |suppressed: &mut Vec<SuppressedDiff>, ex: &ExceptionDump, simulator: &EmulatorType| {
+            let Some(rule) = rules.only_in_rule(ex, simulator) else {
+                return false;
+            };
+            let in_sim1 = *simulator == sim1_type;
+            suppressed.push(SuppressedDiff {
+                location: format!("exception @ 0x{:X}", ex.csrs.mepc),
+                item: format!("only in {} (mcause)", simulator),
+                sim1_value: in_sim1.then_some(ex.csrs.mcause),
+                sim2_value: (!in_sim1).then_some(ex.csrs.mcause),
+                rule,
+            });
+            true
+        };
+    let only_in_info =
+        |ex: &ExceptionDump, simulator: &EmulatorType| ExceptionDiffInfo::OnlyInSimulator {
+            simulator: simulator.clone(),
+            pc: ex.csrs.mepc,
+            mcause: ex.csrs.mcause,
+            description: get_exception_description(ex.csrs.mcause),
+            instruction_trace: ex.inst_trace.clone(),
+        };
+
+    for step in align_exception_lists(list1, list2) {
+        match step {
+            ExceptionAlignment::Matched { index1, index2 }
+            | ExceptionAlignment::Substituted { index1, index2 } => {
+                let (ex1, ex2) = (&list1[index1], &list2[index2]);
+                let mepc = ex1.csrs.mepc;
 
                 // Compare all CSR fields for differences
+                let mut all_csrs_diffs = Vec::new();
+                compare_exception_csrs(&ex1.csrs, &ex2.csrs, &mut all_csrs_diffs);
                 let mut csrs_diffs_for_paired = Vec::new();
-                compare_exception_csrs(&ex1.csrs, &ex2.csrs, &mut csrs_diffs_for_paired);
-
-                // Always create a paired diff entry (even if no differences)
-                // This represents that we found matching exceptions by mepc
-                paired_exceptions_diffs.push(PairedExceptionDiff {
-                    exception1: ex1.clone(),
-                    exception2: ex2.clone(),
-                    csrs_differences: csrs_diffs_for_paired.clone(),
-                });
+                for (csr_name, val1, val2) in all_csrs_diffs {
+                    match rules.paired_rule(&csr_name, ex1, ex2) {
+                        Some(rule) => suppressed.push(SuppressedDiff {
+                            location: format!("exception @ 0x{:X}", mepc),
+                            item: csr_name,
+                            sim1_value: Some(val1),
+                            sim2_value: Some(val2),
+                            rule,
+                        }),
+                        None => csrs_diffs_for_paired.push((csr_name, val1, val2)),
+                    }
+                }
 
                 // Add CSR differences to categorization (but NOT the fact that they matched)
-                for (csr_name, val1, val2) in csrs_diffs_for_paired {
-                    let instruction_trace = ex1.inst_trace.clone();
+                for (csr_name, val1, val2) in &csrs_diffs_for_paired {
+                    let describe = |value: u64| {
+                        (csr_name == "mcause").then(|| get_exception_description(value))
+                    };
                     raw_diffs_for_categorization.push(ExceptionDiffInfo::CsrDifference {
                         pc: mepc,
                         csr_name: csr_name.clone(),
-                        sim1_value: val1,
-                        sim2_value: val2,
-                        sim1_description: if csr_name == "mcause" {
-                            Some(get_exception_description(val1))
-                        } else {
-                            None
-                        },
-                        sim2_description: if csr_name == "mcause" {
-                            Some(get_exception_description(val2))
-                        } else {
-                            None
-                        },
-                        instruction_trace,
+                        sim1_value: *val1,
+                        sim2_value: *val2,
+                        sim1_description: describe(*val1),
+                        sim2_description: describe(*val2),
+                        instruction_trace: ex1.inst_trace.clone(),
                     });
                 }
-            } else {
-                // All exceptions with this mepc in list2 are already matched
-                // This exception from list1 has no counterpart in list2
-                list1_only_exceptions.push(ex1.clone());
-                let instruction_trace = ex1.inst_trace.clone();
-                raw_diffs_for_categorization.push(ExceptionDiffInfo::OnlyInSimulator {
-                    simulator: sim1_type,
-                    pc: mepc,
-                    mcause: ex1.csrs.mcause,
-                    description: get_exception_description(ex1.csrs.mcause),
-                    instruction_trace,
+
+                edits.push(match step {
+                    ExceptionAlignment::Substituted { .. } => {
+                        ExceptionEdit::Substituted(paired_exceptions_diffs.len())
+                    }
+                    _ => ExceptionEdit::Matched(paired_exceptions_diffs.len()),
+                });
+                // Always create a paired diff entry (even if no differences)
+                paired_exceptions_diffs.push(PairedExceptionDiff {
+                    exception1: ex1.clone(),
+                    exception2: ex2.clone(),
+                    csrs_differences: csrs_diffs_for_paired,
                 });
             }
-        } else {
-            // No exception in list2 has this mepc
-            list1_only_exceptions.push(ex1.clone());
-            let instruction_trace = ex1.inst_trace.clone();
-            raw_diffs_for_categorization.push(ExceptionDiffInfo::OnlyInSimulator {
-                simulator: sim1_type,
-                pc: mepc,
-                mcause: ex1.csrs.mcause,
-                description: get_exception_description(ex1.csrs.mcause),
-                instruction_trace,
-            });
+            ExceptionAlignment::Deleted { index1 } => {
+                let ex1 = &list1[index1];
+                if !suppress_only_in(&mut suppressed, ex1, &sim1_type) {
+                    raw_diffs_for_categorization.push(only_in_info(ex1, &sim1_type));
+                    edits.push(ExceptionEdit::Deleted(list1_only_exceptions.len()));
+                    list1_only_exceptions.push(ex1.clone());
+                }
+            }
+            ExceptionAlignment::Inserted { index2 } => {
+                let ex2 = &list2[index2];
+                if !suppress_only_in(&mut suppressed, ex2, &sim2_type) {
+                    raw_diffs_for_categorization.push(only_in_info(ex2, &sim2_type));
+                    edits.push(ExceptionEdit::Inserted(list2_only_exceptions.len()));
+                    list2_only_exceptions.push(ex2.clone());
+                }
+            }
         }
     }
 
-    // Process unmatched exceptions from list2
-    let list2_only_exceptions: Vec<ExceptionDump> = list2
-        .iter()
-        .enumerate()
-        .filter_map(|(i, ex2)| {
-            if !list2_matched_indices[i] {
-                let instruction_trace = ex2.inst_trace.clone();
-                raw_diffs_for_categorization.push(ExceptionDiffInfo::OnlyInSimulator {
-                    simulator: sim2_type,
-                    pc: ex2.csrs.mepc,
-                    mcause: ex2.csrs.mcause,
-                    description: get_exception_description(ex2.csrs.mcause),
-                    instruction_trace,
-                });
-                Some(ex2.clone())
-            } else {
-                None
-            }
-        })
-        .collect();
-
     let categorized_summary = if !raw_diffs_for_categorization.is_empty() {
         analyze_and_categorize_exception_diffs(raw_diffs_for_categorization)
     } else {
@@ -1149,6 +1528,8 @@ pub fn compare_exception_dump_lists(
         list2_only_exceptions,
         paired_exceptions_diffs,
         categorized_summary,
+        edits,
+        suppressed,
     }
 }
 
@@ -1215,7 +1596,157 @@ impl Diffable for CommonExecutionOutput {
     }
 }
 
+impl Diffable for RetireTrace {
+    type DiffOutput = RetireTraceDiff;
+    fn diff(&self, other: &Self) -> Self::DiffOutput {
+        trace_diff::compare_retire_traces(self, other)
+    }
+}
+
 /// Generic function to compare two diffable outputs.
 pub fn compare_outputs<T: Diffable>(output1: &T, output2: &T) -> T::DiffOutput {
     output1.diff(output2)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ExceptionAlignment::{Deleted, Inserted, Matched};
+
+    fn ex(mepc: u64, mcause: u64) -> ExceptionDump {
+        ExceptionDump {
+            csrs: ExceptionCSRs {
+                mstatus: 0,
+                mcause,
+                mepc,
+                mtval: 0,
+                mie: 0,
+                mip: 0,
+                mtvec: 0,
+                mscratch: 0,
+                mhartid: 0,
+            },
+            position: 0,
+            inst_trace: None,
+        }
+    }
+
+    fn matched(index1: usize, index2: usize) -> ExceptionAlignment {
+        Matched { index1, index2 }
+    }
+
+    fn substituted(index1: usize, index2: usize) -> ExceptionAlignment {
+        ExceptionAlignment::Substituted { index1, index2 }
+    }
+
+    #[test]
+    fn test_align_inserted_exception() {
+        let list1 = [ex(0x10, 2), ex(0x20, 2), ex(0x30, 2)];
+        let list2 = [ex(0x10, 2), ex(0x18, 5), ex(0x20, 2), ex(0x30, 2)];
+        assert_eq!(
+            align_exception_lists(&list1, &list2),
+            vec![
+                matched(0, 0),
+                Inserted { index2: 1 },
+                matched(1, 2),
+                matched(2, 3),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_align_deleted_exception() {
+        let list1 = [ex(0x10, 2), ex(0x18, 5), ex(0x20, 2)];
+        let list2 = [ex(0x10, 2), ex(0x20, 2)];
+        assert_eq!(
+            align_exception_lists(&list1, &list2),
+            vec![matched(0, 0), Deleted { index1: 1 }, matched(2, 1)]
+        );
+        assert!(
+            align_exception_lists(&list1, &[])
+                .iter()
+                .all(|step| matches!(step, Deleted { .. }))
+        );
+    }
+
+    #[test]
+    fn test_align_substituted_exception() {
+        // 同一 mepc 上 mcause 不同是一次替换，不同 mepc 则是删除加插入
+        let list1 = [ex(0x10, 2), ex(0x20, 2), ex(0x30, 2)];
+        let list2 = [ex(0x10, 2), ex(0x20, 7), ex(0x34, 2)];
+        assert_eq!(
+            align_exception_lists(&list1, &list2),
+            vec![
+                matched(0, 0),
+                substituted(1, 1),
+                Deleted { index1: 2 },
+                Inserted { index2: 2 },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_align_repeated_mepc() {
+        // 同一条指令在 list1 中陷入两次
+        let list1 = [
+            ex(0x10, 2),
+            ex(0x10, 2),
+            ex(0x20, 3),
+            ex(0x30, 5),
+            ex(0x40, 2),
+        ];
+        let list2 = [
+            ex(0x10, 2),
+            ex(0x20, 3),
+            ex(0x30, 7),
+            ex(0x38, 2),
+            ex(0x40, 2),
+        ];
+        assert_eq!(
+            align_exception_lists(&list1, &list2),
+            vec![
+                matched(0, 0),
+                Deleted { index1: 1 },
+                matched(2, 1),
+                substituted(3, 2),
+                Inserted { index2: 3 },
+                matched(4, 4),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_align_greedy_fallback() {
+        // 首尾都不同，中间部分超过 MAX_ALIGNMENT_CELLS
+        let len = 2100;
+        let list1: Vec<ExceptionDump> = (0..len).map(|i| ex(0x1000 + i * 4, 2)).collect();
+        let mut list2 = list1.clone();
+        list2[0] = ex(0x10, 2);
+        list2[len as usize - 1] = ex(0x20, 2);
+        list2.insert(1000, ex(0xFFFF, 3));
+        assert!((list1.len() + 1) * (list2.len() + 1) > MAX_ALIGNMENT_CELLS);
+
+        let alignment = align_exception_lists(&list1, &list2);
+        let inserted: Vec<usize> = alignment
+            .iter()
+            .filter_map(|step| match step {
+                Inserted { index2 } => Some(*index2),
+                _ => None,
+            })
+            .collect();
+        let deleted: Vec<usize> = alignment
+            .iter()
+            .filter_map(|step| match step {
+                Deleted { index1 } => Some(*index1),
+                _ => None,
+            })
+            .collect();
+        let matched = alignment
+            .iter()
+            .filter(|step| matches!(step, Matched { .. }))
+            .count();
+        assert_eq!(inserted, vec![0, 1000, list2.len() - 1]);
+        assert_eq!(deleted, vec![0, list1.len() - 1]);
+        assert_eq!(matched, list1.len() - 2);
+    }
+}
@@ -0,0 +1,843 @@
+use crate::emulators::EmulatorType;
+use crate::output_diff::diff::ignore::{DiffIgnoreRules, RegisterRef};
+use crate::output_diff::diff::{ExceptionAlignment, align_exception_lists};
+use crate::output_parser::common::CommonExecutionOutput;
+use crate::output_parser::debug::DebugExecutionOutput;
+use crate::output_parser::standard::StandardExecutionOutput;
+use crate::output_parser::util::{get_exception_description, get_register_name};
+use crate::output_parser::{ExceptionDump, RegistersDump};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+/// Value recorded for an item that an emulator did not produce
+pub const ABSENT_VALUE: &str = "absent";
+
+/// 投票项类别
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum VoteItemKind {
+    Register,
+    Csr,
+    Exception,
+}
+
+impl fmt::Display for VoteItemKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VoteItemKind::Register => write!(f, "Register"),
+            VoteItemKind::Csr => write!(f, "CSR"),
+            VoteItemKind::Exception => write!(f, "Exception"),
+        }
+    }
+}
+
+/// A single observable value of one emulator's output
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VoteItem {
+    pub kind: VoteItemKind,
+    pub name: String,
+    pub value: String,
+}
+
+/// Outputs that can take part in an N-way comparison.
+///
+/// The output is flattened into named items; emulators are then grouped by the
+/// value they report for each item. Exceptions are aligned across all outputs
+/// before they become items (see [`compare_outputs_n_way`]).
+pub trait Votable {
+    fn emulator_type(&self) -> EmulatorType;
+    /// 除异常外的投票项，不含 `rules` 忽略的寄存器、CSR 和 CSR 位
+    fn vote_items(&self, rules: &DiffIgnoreRules) -> Vec<VoteItem>;
+    fn exceptions(&self) -> &[ExceptionDump] {
+        &[]
+    }
+}
+
+/// 对某一值达成一致的模拟器组
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AgreementGroup {
+    pub value: String,
+    pub emulators: Vec<EmulatorType>,
+}
+
+/// 单个投票项的分组结果
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ItemVote {
+    pub kind: VoteItemKind,
+    pub name: String,
+    /// Groups ordered by size, largest first
+    pub groups: Vec<AgreementGroup>,
+    /// Value held by a strict majority, if any
+    pub majority_value: Option<String>,
+    /// Emulators outside the majority group (empty without a majority)
+    pub suspects: Vec<EmulatorType>,
+}
+
+/// 两个模拟器之间的差异数量
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PairwiseAgreement {
+    pub emulator1: EmulatorType,
+    pub emulator2: EmulatorType,
+    pub differing_items: usize,
+}
+
+/// 某个模拟器被判为少数派的次数
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SuspectSummary {
+    pub emulator: EmulatorType,
+    pub blamed_items: usize,
+    pub blamed_registers: usize,
+    pub blamed_csrs: usize,
+    pub blamed_exceptions: usize,
+}
+
+/// Result of comparing the outputs of N emulators with majority-vote blame
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MultiEmulatorDiff {
+    pub emulators: Vec<EmulatorType>,
+    pub pairwise: Vec<PairwiseAgreement>,
+    /// Items on which at least two emulators disagree
+    pub divergent_items: Vec<ItemVote>,
+    /// Divergent items without a strict majority
+    pub undecided_items: usize,
+    /// Blame per emulator, most blamed first
+    pub suspects: Vec<SuspectSummary>,
+}
+
+impl MultiEmulatorDiff {
+    pub fn is_empty(&self) -> bool {
+        self.divergent_items.is_empty()
+    }
+
+    /// 被判为少数派次数最多的模拟器 (可能并列)
+    pub fn top_suspects(&self) -> Vec<EmulatorType> {
+        let top = self.suspects.first().map_or(0, |s| s.blamed_items);
+        self.suspects
+            .iter()
+            .take_while(|s| top > 0 && s.blamed_items == top)
+            .map(|s| s.emulator.clone())
+            .collect()
+    }
+
+    /// 最可能出错的模拟器；并列时无法判定，返回 None
+    pub fn suspected_faulty(&self) -> Option<EmulatorType> {
+        match self.top_suspects().as_slice() {
+            [emulator] => Some(emulator.clone()),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for MultiEmulatorDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let names: Vec<String> = self.emulators.iter().map(|e| e.to_string()).collect();
+
+        writeln!(f, "# Multi-Emulator Diff Report")?;
+        writeln!(f)?;
+        writeln!(f, "Comparison: {}", names.join(" vs "))?;
+        writeln!(f)?;
+
+        if self.is_empty() {
+            writeln!(f, "## Diff Result")?;
+            writeln!(f)?;
+            writeln!(
+                f,
+                "No differences found - all emulators agree on every item!"
+            )?;
+            writeln!(f)?;
+            return Ok(());
+        }
+
+        writeln!(f, "## Verdict")?;
+        writeln!(f)?;
+        let top_suspects = self.top_suspects();
+        match top_suspects.as_slice() {
+            [] => writeln!(
+                f,
+                "No emulator is in the minority on any item - the majority vote is undecided"
+            )?,
+            [emulator] => writeln!(f, "Suspected faulty emulator: **{}**", emulator)?,
+            tied => {
+                let names: Vec<String> = tied.iter().map(|e| e.to_string()).collect();
+                writeln!(
+                    f,
+                    "Blame is tied between **{}** - no single emulator can be suspected",
+                    names.join("**, **")
+                )?
+            }
+        }
+        writeln!(f)?;
+        writeln!(
+            f,
+            "| Emulator | Blamed Items | Registers | CSRs | Exceptions |"
+        )?;
+        writeln!(
+            f,
+            "|----------|--------------|-----------|------|------------|"
+        )?;
+        for s in &self.suspects {
+            writeln!(
+                f,
+                "| {} | {} | {} | {} | {} |",
+                s.emulator, s.blamed_items, s.blamed_registers, s.blamed_csrs, s.blamed_exceptions
+            )?;
+        }
+        writeln!(f)?;
+        writeln!(
+            f,
+            "Divergent items: {} (undecided: {})",
+            self.divergent_items.len(),
+            self.undecided_items
+        )?;
+        writeln!(f)?;
+
+        writeln!(f, "## Pairwise Differences")?;
+        writeln!(f)?;
+        writeln!(f, "| Pair | Differing Items |")?;
+        writeln!(f, "|------|-----------------|")?;
+        for p in &self.pairwise {
+            writeln!(
+                f,
+                "| {} vs {} | {} |",
+                p.emulator1, p.emulator2, p.differing_items
+            )?;
+        }
+        writeln!(f)?;
+
+        writeln!(f, "## Divergent Items")?;
+        writeln!(f)?;
+        writeln!(f, "| Kind | Item | Majority | Suspected | Groups |")?;
+        writeln!(f, "|------|------|----------|-----------|--------|")?;
+        for vote in &self.divergent_items {
+            let groups: Vec<String> = vote
+                .groups
+                .iter()
+                .map(|g| {
+                    let members: Vec<String> = g.emulators.iter().map(|e| e.to_string()).collect();
+                    format!("{}: `{}`", members.join(", "), g.value)
+                })
+                .collect();
+            let suspects: Vec<String> = vote.suspects.iter().map(|e| e.to_string()).collect();
+            writeln!(
+                f,
+                "| {} | {} | {} | {} | {} |",
+                vote.kind,
+                vote.name,
+                vote.majority_value
+                    .as_deref()
+                    .map_or("-".to_string(), |v| format!("`{}`", v)),
+                if suspects.is_empty() {
+                    "undecided".to_string()
+                } else {
+                    suspects.join(", ")
+                },
+                groups.join("<br>")
+            )?;
+        }
+        writeln!(f)?;
+
+        writeln!(f, "---")?;
+        writeln!(
+            f,
+            "Multi-emulator diff report generated at: {}",
+            chrono::Utc::now().format("%Y-%m-%d %H:%M:%S UTC")
+        )?;
+
+        Ok(())
+    }
+}
+
+fn push_item(items: &mut Vec<VoteItem>, kind: VoteItemKind, name: String, value: u64) {
+    items.push(VoteItem {
+        kind,
+        name,
+        value: format!("0x{:016X}", value),
+    });
+}
+
+/// 忽略规则下参与比较的 CSR 值，整个 CSR 被忽略时为 None
+fn compared_csr(rules: &DiffIgnoreRules, csr_name: &str, value: u64) -> Option<u64> {
+    let mask = rules.compared_bits(csr_name);
+    (mask != 0).then_some(value & mask)
+}
+
+/// 展开寄存器转储为投票项
+fn register_dump_items(
+    dump: &RegistersDump,
+    prefix: &str,
+    rules: &DiffIgnoreRules,
+    items: &mut Vec<VoteItem>,
+) {
+    for (i, value) in dump.int_registers.iter().enumerate() {
+        if rules.register_rule(RegisterRef::Int(i)).is_some() {
+            continue;
+        }
+        push_item(
+            items,
+            VoteItemKind::Register,
+            format!("{}x{} ({})", prefix, i, get_register_name(i)),
+            *value,
+        );
+    }
+    if let Some(float_registers) = &dump.float_registers {
+        for (i, value) in float_registers.iter().enumerate() {
+            if rules.register_rule(RegisterRef::Float(i)).is_some() {
+                continue;
+            }
+            push_item(
+                items,
+                VoteItemKind::Register,
+                format!("{}f{}", prefix, i),
+                *value,
+            );
+        }
+    }
+    if let Some(fcsr) = dump
+        .float_csr
+        .and_then(|fcsr| compared_csr(rules, "fcsr", fcsr))
+    {
+        push_item(items, VoteItemKind::Csr, format!("{}fcsr", prefix), fcsr);
+    }
+
+    let c = &dump.core_csrs;
+    for (name, value) in [
+        ("mstatus", c.mstatus),
+        ("misa", c.misa),
+        ("medeleg", c.medeleg),
+        ("mideleg", c.mideleg),
+        ("mie", c.mie),
+        ("mtvec", c.mtvec),
+        ("mcounteren", c.mcounteren),
+        ("mscratch", c.mscratch),
+        ("mepc", c.mepc),
+        ("mcause", c.mcause),
+        ("mtval", c.mtval),
+        ("mip", c.mip),
+        ("mcycle", c.mcycle),
+        ("minstret", c.minstret),
+        ("mvendorid", c.mvendorid),
+        ("marchid", c.marchid),
+        ("mimpid", c.mimpid),
+        ("mhartid", c.mhartid),
+    ] {
+        let Some(value) = compared_csr(rules, name, value) else {
+            continue;
+        };
+        push_item(
+            items,
+            VoteItemKind::Csr,
+            format!("{}{}", prefix, name),
+            value,
+        );
+    }
+}
+
+/// 异常的投票项；与 `pivot` 的差异被 `[diff.ignore]` 忽略的字段取 `pivot` 的值
+fn exception_item(
+    name: String,
+    ex: &ExceptionDump,
+    pivot: Option<&ExceptionDump>,
+    rules: &DiffIgnoreRules,
+) -> VoteItem {
+    let field = |csr_name: &str, value: fn(&ExceptionDump) -> u64| match pivot {
+        Some(pivot) if rules.paired_rule(csr_name, pivot, ex).is_some() => value(pivot),
+        _ => value(ex),
+    };
+    let mcause = field("mcause", |e| e.csrs.mcause);
+    VoteItem {
+        kind: VoteItemKind::Exception,
+        name,
+        value: format!(
+            "mcause=0x{:X} ({}) mtval=0x{:X} mstatus=0x{:X} mie=0x{:X} mip=0x{:X}",
+            mcause,
+            get_exception_description(mcause),
+            field("mtval", |e| e.csrs.mtval),
+            field("mstatus", |e| e.csrs.mstatus),
+            field("mie", |e| e.csrs.mie),
+            field("mip", |e| e.csrs.mip)
+        ),
+    }
+}
+
+/// 展开各输出的异常列表为投票项
+///
+/// Every list is aligned with the first one by [`align_exception_lists`], so an
+/// extra or missing trap only affects its own item instead of shifting all
+/// later ones. Exceptions of the first list are named by their position there;
+/// extra ones by the position they precede and their mepc, so the same extra
+/// trap on several emulators is one item. An exception missing from some
+/// outputs is dropped when every emulator raising it matches a `[diff.ignore]`
+/// exception rule.
+fn exception_items(
+    lists: &[(EmulatorType, &[ExceptionDump])],
+    rules: &DiffIgnoreRules,
+) -> Vec<Vec<VoteItem>> {
+    let Some(&(_, pivot)) = lists.first() else {
+        return Vec::new();
+    };
+    let per_list: Vec<Vec<(VoteItem, &ExceptionDump)>> = lists
+        .iter()
+        .map(|&(_, list)| {
+            let mut items = Vec::new();
+            let mut extra: HashMap<(usize, u64), usize> = HashMap::new();
+            // 下一个尚未对齐的首个列表中的位置
+            let mut gap = 0;
+            for step in align_exception_lists(pivot, list) {
+                match step {
+                    ExceptionAlignment::Matched { index1, index2 }
+                    | ExceptionAlignment::Substituted { index1, index2 } => {
+                        let name = format!("#{} mepc 0x{:016X}", index1, pivot[index1].csrs.mepc);
+                        let ex = &list[index2];
+                        items.push((exception_item(name, ex, Some(&pivot[index1]), rules), ex));
+                        gap = index1 + 1;
+                    }
+                    ExceptionAlignment::Deleted { index1 } => gap = index1 + 1,
+                    ExceptionAlignment::Inserted { index2 } => {
+                        let ex = &list[index2];
+                        let count = extra.entry((gap, ex.csrs.mepc)).or_insert(0);
+                        let name = format!(
+                            "extra before #{} mepc 0x{:016X} ({})",
+                            gap, ex.csrs.mepc, count
+                        );
+                        items.push((exception_item(name, ex, None, rules), ex));
+                        *count += 1;
+                    }
+                }
+            }
+            items
+        })
+        .collect();
+
+    let mut holders: HashMap<&str, Vec<(&EmulatorType, &ExceptionDump)>> = HashMap::new();
+    for ((emulator, _), items) in lists.iter().zip(&per_list) {
+        for (item, ex) in items {
+            holders
+                .entry(item.name.as_str())
+                .or_default()
+                .push((emulator, *ex));
+        }
+    }
+    let ignored: HashSet<String> = holders
+        .into_iter()
+        .filter(|(_, holders)| {
+            holders.len() < lists.len()
+                && holders
+                    .iter()
+                    .all(|(emulator, ex)| rules.only_in_rule(ex, emulator).is_some())
+        })
+        .map(|(name, _)| name.to_string())
+        .collect();
+
+    per_list
+        .into_iter()
+        .map(|items| {
+            items
+                .into_iter()
+                .map(|(item, _)| item)
+                .filter(|item| !ignored.contains(&item.name))
+                .collect()
+        })
+        .collect()
+}
+
+impl Votable for StandardExecutionOutput {
+    fn emulator_type(&self) -> EmulatorType {
+        self.emulator_type.clone()
+    }
+
+    fn vote_items(&self, rules: &DiffIgnoreRules) -> Vec<VoteItem> {
+        let mut items = Vec::new();
+        if let Some(dump) = &self.register_dump {
+            register_dump_items(dump, "", rules, &mut items);
+        }
+        items
+    }
+
+    fn exceptions(&self) -> &[ExceptionDump] {
+        &self.exceptions
+    }
+}
+
+impl Votable for DebugExecutionOutput {
+    fn emulator_type(&self) -> EmulatorType {
+        self.emulator_type.clone()
+    }
+
+    fn vote_items(&self, rules: &DiffIgnoreRules) -> Vec<VoteItem> {
+        let mut items = Vec::new();
+        for (i, dump) in self.register_dumps.iter().enumerate() {
+            register_dump_items(dump, &format!("dump#{} ", i), rules, &mut items);
+        }
+        items
+    }
+}
+
+impl Votable for CommonExecutionOutput {
+    fn emulator_type(&self) -> EmulatorType {
+        self.emulator_type.clone()
+    }
+
+    fn vote_items(&self, rules: &DiffIgnoreRules) -> Vec<VoteItem> {
+        let mut items = Vec::new();
+        for (i, dump) in self.register_dumps.iter().enumerate() {
+            register_dump_items(dump, &format!("dump#{} ", i), rules, &mut items);
+        }
+        items
+    }
+
+    fn exceptions(&self) -> &[ExceptionDump] {
+        &self.exception_dumps
+    }
+}
+
+/// Compares N outputs item by item and blames the minority.
+///
+/// Every pair is diffed to build the agreement matrix. For each item the
+/// emulators are grouped by reported value; when one group holds a strict
+/// majority, the emulators outside it are suspected. Items an emulator did not
+/// produce count as [`ABSENT_VALUE`]. An emulator tied for the most blame with
+/// another is never singled out (see [`MultiEmulatorDiff::suspected_faulty`]).
+/// Differences matching the `[diff.ignore]` rules do not take part in the vote.
+pub fn compare_outputs_n_way<T: Votable>(outputs: &[T]) -> MultiEmulatorDiff {
+    compare_outputs_n_way_with(outputs, DiffIgnoreRules::current())
+}
+
+/// Like [`compare_outputs_n_way`], leaving out the differences matching `rules`
+pub fn compare_outputs_n_way_with<T: Votable>(
+    outputs: &[T],
+    rules: &DiffIgnoreRules,
+) -> MultiEmulatorDiff {
+    let emulators: Vec<EmulatorType> = outputs.iter().map(|o| o.emulator_type()).collect();
+    let exception_lists: Vec<(EmulatorType, &[ExceptionDump])> = outputs
+        .iter()
+        .map(|o| (o.emulator_type(), o.exceptions()))
+        .collect();
+    let per_output: Vec<Vec<VoteItem>> = outputs
+        .iter()
+        .zip(exception_items(&exception_lists, rules))
+        .map(|(output, exceptions)| {
+            let mut items = output.vote_items(rules);
+            items.extend(exceptions);
+            items
+        })
+        .collect();
+
+    // 按首次出现顺序收集所有投票项
+    let mut item_keys: Vec<(VoteItemKind, String)> = Vec::new();
+    let mut seen = std::collections::HashSet::new();
+    for items in &per_output {
+        for item in items {
+            if seen.insert((item.kind, item.name.clone())) {
+                item_keys.push((item.kind, item.name.clone()));
+            }
+        }
+    }
+
+    let lookup: Vec<std::collections::HashMap<(VoteItemKind, &str), &str>> = per_output
+        .iter()
+        .map(|items| {
+            items
+                .iter()
+                .map(|i| ((i.kind, i.name.as_str()), i.value.as_str()))
+                .collect()
+        })
+        .collect();
+
+    let mut pairwise = Vec::new();
+    for a in 0..outputs.len() {
+        for b in (a + 1)..outputs.len() {
+            let differing_items = item_keys
+                .iter()
+                .filter(|(kind, name)| {
+                    lookup[a].get(&(*kind, name.as_str())) != lookup[b].get(&(*kind, name.as_str()))
+                })
+                .count();
+            pairwise.push(PairwiseAgreement {
+                emulator1: emulators[a].clone(),
+                emulator2: emulators[b].clone(),
+                differing_items,
+            });
+        }
+    }
+
+    let mut divergent_items = Vec::new();
+    let mut undecided_items = 0;
+    let mut suspects: Vec<SuspectSummary> = emulators
+        .iter()
+        .map(|e| SuspectSummary {
+            emulator: e.clone(),
+            blamed_items: 0,
+            blamed_registers: 0,
+            blamed_csrs: 0,
+            blamed_exceptions: 0,
+        })
+        .collect();
+
+    for (kind, name) in &item_keys {
+        let mut groups: Vec<(String, Vec<usize>)> = Vec::new();
+        for (idx, values) in lookup.iter().enumerate() {
+            let value = values
+                .get(&(*kind, name.as_str()))
+                .copied()
+                .unwrap_or(ABSENT_VALUE);
+            match groups.iter_mut().find(|(v, _)| v == value) {
+                Some((_, members)) => members.push(idx),
+                None => groups.push((value.to_string(), vec![idx])),
+            }
+        }
+        if groups.len() <= 1 {
+            continue;
+        }
+        groups.sort_by_key(|g| std::cmp::Reverse(g.1.len()));
+
+        let majority = (groups[0].1.len() * 2 > outputs.len()).then(|| groups[0].clone());
+        let mut item_suspects = Vec::new();
+        match &majority {
+            Some((_, majority_members)) => {
+                for idx in (0..outputs.len()).filter(|i| !majority_members.contains(i)) {
+                    item_suspects.push(emulators[idx].clone());
+                    let s = &mut suspects[idx];
+                    s.blamed_items += 1;
+                    match kind {
+                        VoteItemKind::Register => s.blamed_registers += 1,
+                        VoteItemKind::Csr => s.blamed_csrs += 1,
+                        VoteItemKind::Exception => s.blamed_exceptions += 1,
+                    }
+                }
+            }
+            None => undecided_items += 1,
+        }
+
+        divergent_items.push(ItemVote {
+            kind: *kind,
+            name: name.clone(),
+            groups: groups
+                .into_iter()
+                .map(|(value, members)| AgreementGroup {
+                    value,
+                    emulators: members.into_iter().map(|i| emulators[i].clone()).collect(),
+                })
+                .collect(),
+            majority_value: majority.map(|(value, _)| value),
+            suspects: item_suspects,
+        });
+    }
+
+    suspects.sort_by_key(|s| std::cmp::Reverse(s.blamed_items));
+
+    MultiEmulatorDiff {
+        emulators,
+        pairwise,
+        divergent_items,
+        undecided_items,
+        suspects,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::output_parser::{CoreCSRs, ExceptionCSRs, MarkerType};
+    use EmulatorType::{Qemu, Rocket, Spike};
+
+    fn ex(mepc: u64, mcause: u64) -> ExceptionDump {
+        ExceptionDump {
+            csrs: ExceptionCSRs {
+                mstatus: 0,
+                mcause,
+                mepc,
+                mtval: 0,
+                mie: 0,
+                mip: 0,
+                mtvec: 0,
+                mscratch: 0,
+                mhartid: 0,
+            },
+            position: 0,
+            inst_trace: None,
+        }
+    }
+
+    /// 除 `regs` 中列出的整数寄存器外全为 0 的寄存器转储
+    fn dump(regs: &[(usize, u64)]) -> RegistersDump {
+        let mut int_registers = [0; 32];
+        for &(i, value) in regs {
+            int_registers[i] = value;
+        }
+        RegistersDump {
+            dump_type: MarkerType::RegistersIntOnly,
+            int_registers,
+            core_csrs: CoreCSRs {
+                mstatus: 0,
+                misa: 0,
+                medeleg: 0,
+                mideleg: 0,
+                mie: 0,
+                mtvec: 0,
+                mcounteren: 0,
+                mscratch: 0,
+                mepc: 0,
+                mcause: 0,
+                mtval: 0,
+                mip: 0,
+                mcycle: 0,
+                minstret: 0,
+                mvendorid: 0,
+                marchid: 0,
+                mimpid: 0,
+                mhartid: 0,
+            },
+            float_registers: None,
+            float_csr: None,
+            position: 0,
+        }
+    }
+
+    fn output(
+        emulator_type: EmulatorType,
+        register_dump: Option<RegistersDump>,
+        exceptions: Vec<ExceptionDump>,
+    ) -> StandardExecutionOutput {
+        StandardExecutionOutput {
+            emulator_type,
+            exceptions,
+            register_dump,
+            ..Default::default()
+        }
+    }
+
+    fn compare(outputs: &[StandardExecutionOutput]) -> MultiEmulatorDiff {
+        compare_outputs_n_way_with(outputs, &DiffIgnoreRules::default())
+    }
+
+    #[test]
+    fn test_two_against_one_blames_minority() {
+        let diff = compare(&[
+            output(Spike, Some(dump(&[(10, 1)])), vec![]),
+            output(Rocket, Some(dump(&[(10, 1)])), vec![]),
+            output(Qemu, Some(dump(&[(10, 2)])), vec![]),
+        ]);
+
+        assert_eq!(diff.divergent_items.len(), 1);
+        let vote = &diff.divergent_items[0];
+        assert_eq!(vote.kind, VoteItemKind::Register);
+        assert_eq!(vote.name, "x10 (a0)");
+        assert_eq!(vote.majority_value.as_deref(), Some("0x0000000000000001"));
+        assert_eq!(vote.groups[0].emulators, vec![Spike, Rocket]);
+        assert_eq!(vote.suspects, vec![Qemu]);
+        assert_eq!(diff.undecided_items, 0);
+        assert_eq!(diff.suspected_faulty(), Some(Qemu));
+        assert_eq!(diff.suspects[0].blamed_registers, 1);
+
+        let differing: Vec<usize> = diff.pairwise.iter().map(|p| p.differing_items).collect();
+        assert_eq!(differing, vec![0, 1, 1]);
+    }
+
+    #[test]
+    fn test_three_way_split_stays_undecided() {
+        let diff = compare(&[
+            output(Spike, Some(dump(&[(10, 1)])), vec![]),
+            output(Rocket, Some(dump(&[(10, 2)])), vec![]),
+            output(Qemu, Some(dump(&[(10, 3)])), vec![]),
+        ]);
+
+        assert_eq!(diff.divergent_items.len(), 1);
+        let vote = &diff.divergent_items[0];
+        assert_eq!(vote.groups.len(), 3);
+        assert_eq!(vote.majority_value, None);
+        assert!(vote.suspects.is_empty());
+        assert_eq!(diff.undecided_items, 1);
+        assert!(diff.suspects.iter().all(|s| s.blamed_items == 0));
+        assert!(diff.top_suspects().is_empty());
+        assert_eq!(diff.suspected_faulty(), None);
+    }
+
+    #[test]
+    fn test_blame_tie_suspects_nobody() {
+        // Spike 在 a0 上是少数派，Rocket 在 a1 上是少数派
+        let diff = compare(&[
+            output(Spike, Some(dump(&[(10, 9)])), vec![]),
+            output(Rocket, Some(dump(&[(10, 1), (11, 9)])), vec![]),
+            output(Qemu, Some(dump(&[(10, 1)])), vec![]),
+        ]);
+
+        assert_eq!(diff.divergent_items.len(), 2);
+        assert_eq!(diff.undecided_items, 0);
+        assert_eq!(diff.top_suspects(), vec![Spike, Rocket]);
+        assert_eq!(diff.suspected_faulty(), None);
+    }
+
+    #[test]
+    fn test_missing_register_dump_is_absent() {
+        let diff = compare(&[
+            output(Spike, Some(dump(&[(10, 1)])), vec![]),
+            output(Rocket, Some(dump(&[(10, 1)])), vec![]),
+            output(Qemu, None, vec![]),
+        ]);
+
+        // 32 个整数寄存器和 18 个核心 CSR 均缺失
+        assert_eq!(diff.divergent_items.len(), 50);
+        for vote in &diff.divergent_items {
+            assert_eq!(vote.groups.len(), 2);
+            assert_eq!(vote.groups[1].value, ABSENT_VALUE);
+            assert_eq!(vote.groups[1].emulators, vec![Qemu]);
+            assert_eq!(vote.suspects, vec![Qemu]);
+        }
+        assert_eq!(diff.suspected_faulty(), Some(Qemu));
+        assert_eq!(diff.suspects[0].blamed_registers, 32);
+        assert_eq!(diff.suspects[0].blamed_csrs, 18);
+
+        // 多数派都缺失转储时，产生转储的一方被怀疑
+        let diff = compare(&[
+            output(Spike, Some(dump(&[])), vec![]),
+            output(Rocket, None, vec![]),
+            output(Qemu, None, vec![]),
+        ]);
+        assert!(
+            diff.divergent_items
+                .iter()
+                .all(|vote| vote.majority_value.as_deref() == Some(ABSENT_VALUE))
+        );
+        assert_eq!(diff.suspected_faulty(), Some(Spike));
+    }
+
+    #[test]
+    fn test_shared_extra_exception_is_one_item() {
+        let pivot = vec![ex(0x10, 2), ex(0x20, 2)];
+        let extra = vec![ex(0x10, 2), ex(0x18, 5), ex(0x20, 2)];
+
+        let items = exception_items(
+            &[
+                (Spike, &pivot[..]),
+                (Rocket, &extra[..]),
+                (Qemu, &extra[..]),
+            ],
+            &DiffIgnoreRules::default(),
+        );
+        let names: Vec<&str> = items[1].iter().map(|i| i.name.as_str()).collect();
+        assert_eq!(
+            names,
+            vec![
+                "#0 mepc 0x0000000000000010",
+                "extra before #1 mepc 0x0000000000000018 (0)",
+                "#1 mepc 0x0000000000000020",
+            ]
+        );
+        assert_eq!(items[1], items[2]);
+
+        let diff = compare(&[
+            output(Spike, Some(dump(&[])), pivot),
+            output(Rocket, Some(dump(&[])), extra.clone()),
+            output(Qemu, Some(dump(&[])), extra),
+        ]);
+        assert_eq!(diff.divergent_items.len(), 1);
+        let vote = &diff.divergent_items[0];
+        assert_eq!(vote.kind, VoteItemKind::Exception);
+        assert_eq!(vote.name, "extra before #1 mepc 0x0000000000000018 (0)");
+        assert_eq!(vote.groups[0].emulators, vec![Rocket, Qemu]);
+        assert_eq!(vote.groups[1].value, ABSENT_VALUE);
+        assert_eq!(vote.suspects, vec![Spike]);
+        assert_eq!(diff.suspected_faulty(), Some(Spike));
+        assert_eq!(diff.suspects[0].blamed_exceptions, 1);
+    }
+}
@@ -0,0 +1,449 @@
+use crate::emulators::EmulatorType;
+use crate::emulators::outcome::{
+    RunClassification, RunOutcome, STDERR_TAIL_BYTES, run_outcome_path,
+};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::Path;
+
+/// 运行层面的差异 (模拟器是否正常结束，与输出内容无关)
+///
+/// Every variant names the emulator with the bad outcome and the one that
+/// completed; `completed` exited cleanly, or at least left a usable dump
+/// when the other one did not.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum RunDivergence {
+    /// 仅一个模拟器超时，另一个正常结束
+    Timeout {
+        timed_out: EmulatorType,
+        completed: EmulatorType,
+        timeout_seconds: f64,
+    },
+    /// 仅一个模拟器异常退出 (信号、断言失败等)
+    Crash {
+        crashed: EmulatorType,
+        completed: EmulatorType,
+        exit_code: Option<i32>,
+        signal: Option<i32>,
+        /// stderr 末尾
+        stderr: String,
+    },
+    /// 仅一个模拟器结束时没有任何转储数据
+    NoOutput {
+        silent: EmulatorType,
+        completed: EmulatorType,
+    },
+    /// 仅一个模拟器以非零 tohost 退出码结束
+    FailCode {
+        failed: EmulatorType,
+        completed: EmulatorType,
+        tohost_exit_code: u64,
+    },
+}
+
+impl RunDivergence {
+    /// Detects a one-sided bad outcome: one emulator exited cleanly (or left a
+    /// usable dump) while the other did not. Returns `None` when both sides
+    /// are equally good or equally bad.
+    pub fn from_outcomes(outcome1: &RunOutcome, outcome2: &RunOutcome) -> Option<Self> {
+        let clean = |outcome: &RunOutcome| outcome.classification == RunClassification::CleanExit;
+        let (bad, completed) = match (clean(outcome1), clean(outcome2)) {
+            (true, false) => (outcome2, outcome1),
+            (false, true) => (outcome1, outcome2),
+            // 都未正常结束时，只有一侧的输出可用也算差异
+            (false, false) if outcome1.is_usable() != outcome2.is_usable() => {
+                if outcome1.is_usable() {
+                    (outcome2, outcome1)
+                } else {
+                    (outcome1, outcome2)
+                }
+            }
+            _ => return None,
+        };
+
+        let completed = completed.emulator_type.clone();
+        Some(match bad.classification {
+            RunClassification::Timeout => RunDivergence::Timeout {
+                timed_out: bad.emulator_type.clone(),
+                completed,
+                timeout_seconds: bad.wall_time_secs,
+            },
+            RunClassification::Crash => RunDivergence::Crash {
+                crashed: bad.emulator_type.clone(),
+                completed,
+                exit_code: bad.exit_code,
+                signal: bad.signal,
+                stderr: bad.stderr.clone(),
+            },
+            RunClassification::NoOutput => RunDivergence::NoOutput {
+                silent: bad.emulator_type.clone(),
+                completed,
+            },
+            RunClassification::FailCode => RunDivergence::FailCode {
+                failed: bad.emulator_type.clone(),
+                completed,
+                tohost_exit_code: bad.tohost_exit_code.unwrap_or_default(),
+            },
+            RunClassification::CleanExit => unreachable!("a clean exit is never the bad side"),
+        })
+    }
+}
+
+impl fmt::Display for RunDivergence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "# Run Divergence Report")?;
+        writeln!(f)?;
+        match self {
+            RunDivergence::Timeout {
+                timed_out,
+                completed,
+                timeout_seconds,
+            } => {
+                writeln!(f, "## ⏰ Timeout Divergence")?;
+                writeln!(f)?;
+                writeln!(f, "| Emulator | Result |")?;
+                writeln!(f, "|----------|--------|")?;
+                writeln!(
+                    f,
+                    "| {} | Killed after {:.1}s |",
+                    timed_out, timeout_seconds
+                )?;
+                writeln!(f, "| {} | Completed |", completed)?;
+                writeln!(f)?;
+                writeln!(
+                    f,
+                    "{} did not finish within the wall-clock limit while {} did. \
+                     This usually means a hang, a trap loop or a missing exit in {}.",
+                    timed_out, completed, timed_out
+                )?;
+            }
+            RunDivergence::Crash {
+                crashed,
+                completed,
+                exit_code,
+                signal,
+                stderr,
+            } => {
+                let fmt_opt =
+                    |v: &Option<i32>| v.map_or_else(|| "-".to_string(), |v| v.to_string());
+                writeln!(f, "## 💥 Crash Divergence")?;
+                writeln!(f)?;
+                writeln!(f, "| Emulator | Result |")?;
+                writeln!(f, "|----------|--------|")?;
+                writeln!(
+                    f,
+                    "| {} | Crashed (exit code {}, signal {}) |",
+                    crashed,
+                    fmt_opt(exit_code),
+                    fmt_opt(signal)
+                )?;
+                writeln!(f, "| {} | Completed |", completed)?;
+                writeln!(f)?;
+                writeln!(
+                    f,
+                    "{} exited abnormally (e.g. an assertion or a signal) while {} finished the program.",
+                    crashed, completed
+                )?;
+                if !stderr.trim().is_empty() {
+                    writeln!(f)?;
+                    writeln!(f, "### {} stderr", crashed)?;
+                    writeln!(f)?;
+                    writeln!(f, "```")?;
+                    writeln!(f, "{}", stderr.trim_end())?;
+                    writeln!(f, "```")?;
+                }
+            }
+            RunDivergence::NoOutput { silent, completed } => {
+                writeln!(f, "## 🔇 No Output Divergence")?;
+                writeln!(f)?;
+                writeln!(f, "| Emulator | Result |")?;
+                writeln!(f, "|----------|--------|")?;
+                writeln!(f, "| {} | No dump output |", silent)?;
+                writeln!(f, "| {} | Completed |", completed)?;
+                writeln!(f)?;
+                writeln!(
+                    f,
+                    "{} finished without printing any dump data while {} did. \
+                     The program probably never reached the dump code on {}.",
+                    silent, completed, silent
+                )?;
+            }
+            RunDivergence::FailCode {
+                failed,
+                completed,
+                tohost_exit_code,
+            } => {
+                writeln!(f, "## ❌ Fail Code Divergence")?;
+                writeln!(f)?;
+                writeln!(f, "| Emulator | Result |")?;
+                writeln!(f, "|----------|--------|")?;
+                writeln!(f, "| {} | tohost exit code {} |", failed, tohost_exit_code)?;
+                writeln!(f, "| {} | Clean exit |", completed)?;
+                writeln!(f)?;
+                writeln!(
+                    f,
+                    "{} reported a non-zero exit code through HTIF while {} exited cleanly.",
+                    failed, completed
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Difference between the [`RunOutcome`]s of two emulators.
+///
+/// Only the classification and the tohost exit code count as a divergence;
+/// exit status, signal, wall time and stderr are kept for context.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RunOutcomeDiff {
+    pub sim1_emulator_type: EmulatorType,
+    pub sim2_emulator_type: EmulatorType,
+    pub outcome1: RunOutcome,
+    pub outcome2: RunOutcome,
+    pub classification_changed: Option<(RunClassification, RunClassification)>,
+    pub tohost_exit_code_changed: Option<(Option<u64>, Option<u64>)>,
+}
+
+impl RunOutcomeDiff {
+    pub fn is_empty(&self) -> bool {
+        self.classification_changed.is_none() && self.tohost_exit_code_changed.is_none()
+    }
+}
+
+impl fmt::Display for RunOutcomeDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "# Run Outcome Diff")?;
+        writeln!(f)?;
+
+        if self.is_empty() {
+            writeln!(f, "Both emulators finished with the same outcome")?;
+            writeln!(f)?;
+            return Ok(());
+        }
+
+        let fmt_opt = |v: Option<String>| v.unwrap_or_else(|| "-".to_string());
+        let (o1, o2) = (&self.outcome1, &self.outcome2);
+
+        writeln!(
+            f,
+            "| Property | {} | {} |",
+            self.sim1_emulator_type, self.sim2_emulator_type
+        )?;
+        writeln!(f, "|----------|------------|------------|")?;
+        writeln!(
+            f,
+            "| Classification | {} | {} |",
+            o1.classification, o2.classification
+        )?;
+        writeln!(
+            f,
+            "| tohost Exit Code | {} | {} |",
+            fmt_opt(o1.tohost_exit_code.map(|c| c.to_string())),
+            fmt_opt(o2.tohost_exit_code.map(|c| c.to_string()))
+        )?;
+        writeln!(
+            f,
+            "| Process Exit Code | {} | {} |",
+            fmt_opt(o1.exit_code.map(|c| c.to_string())),
+            fmt_opt(o2.exit_code.map(|c| c.to_string()))
+        )?;
+        writeln!(
+            f,
+            "| Signal | {} | {} |",
+            fmt_opt(o1.signal.map(|s| s.to_string())),
+            fmt_opt(o2.signal.map(|s| s.to_string()))
+        )?;
+        writeln!(
+            f,
+            "| Wall Time | {:.2}s | {:.2}s |",
+            o1.wall_time_secs, o2.wall_time_secs
+        )?;
+        writeln!(
+            f,
+            "| Raw Output Length | {} | {} |",
+            o1.raw_output_length, o2.raw_output_length
+        )?;
+        writeln!(f)?;
+
+        for (emulator, outcome) in [
+            (self.sim1_emulator_type.clone(), o1),
+            (self.sim2_emulator_type.clone(), o2),
+        ] {
+            if !outcome.stderr.trim().is_empty() {
+                writeln!(f, "## {} stderr", emulator)?;
+                writeln!(f)?;
+                writeln!(f, "```")?;
+                writeln!(f, "{}", outcome.stderr.trim_end())?;
+                writeln!(f, "```")?;
+                writeln!(f)?;
+                if let Some(stderr_log) = &outcome.stderr_log {
+                    writeln!(
+                        f,
+                        "Last {} bytes shown, full stderr: `{}`",
+                        STDERR_TAIL_BYTES,
+                        stderr_log.display()
+                    )?;
+                    writeln!(f)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+pub fn compare_run_outcomes(outcome1: &RunOutcome, outcome2: &RunOutcome) -> RunOutcomeDiff {
+    RunOutcomeDiff {
+        sim1_emulator_type: outcome1.emulator_type.clone(),
+        sim2_emulator_type: outcome2.emulator_type.clone(),
+        outcome1: outcome1.clone(),
+        outcome2: outcome2.clone(),
+        classification_changed: (outcome1.classification != outcome2.classification)
+            .then_some((outcome1.classification, outcome2.classification)),
+        tohost_exit_code_changed: (outcome1.tohost_exit_code != outcome2.tohost_exit_code)
+            .then_some((outcome1.tohost_exit_code, outcome2.tohost_exit_code)),
+    }
+}
+
+/// Loads the outcomes saved next to two raw output files and diffs them.
+///
+/// Returns `None` when an outcome file is missing or the outcomes match.
+pub fn load_run_outcome_diff<P: AsRef<Path>>(
+    raw_output1: P,
+    raw_output2: P,
+) -> Option<RunOutcomeDiff> {
+    let outcome1 = RunOutcome::load(run_outcome_path(raw_output1)).ok()?;
+    let outcome2 = RunOutcome::load(run_outcome_path(raw_output2)).ok()?;
+    let diff = compare_run_outcomes(&outcome1, &outcome2);
+    (!diff.is_empty()).then_some(diff)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn outcome(emulator_type: EmulatorType, classification: RunClassification) -> RunOutcome {
+        RunOutcome {
+            emulator_type,
+            classification,
+            exit_code: Some(0),
+            signal: None,
+            tohost_exit_code: (classification == RunClassification::FailCode).then_some(3),
+            wall_time_secs: 1.5,
+            raw_output_length: 64,
+            stderr: "emulator: assertion failed".to_string(),
+            stderr_log: None,
+        }
+    }
+
+    /// Runs both orders of `bad` against `good` and returns the divergences
+    fn both_orders(bad: RunClassification, good: RunClassification) -> [Option<RunDivergence>; 2] {
+        let rocket = outcome(EmulatorType::Rocket, bad);
+        let spike = outcome(EmulatorType::Spike, good);
+        [
+            RunDivergence::from_outcomes(&rocket, &spike),
+            RunDivergence::from_outcomes(&spike, &rocket),
+        ]
+    }
+
+    #[test]
+    fn test_one_sided_timeout() {
+        for divergence in both_orders(RunClassification::Timeout, RunClassification::CleanExit) {
+            assert_eq!(
+                divergence,
+                Some(RunDivergence::Timeout {
+                    timed_out: EmulatorType::Rocket,
+                    completed: EmulatorType::Spike,
+                    timeout_seconds: 1.5,
+                })
+            );
+        }
+    }
+
+    #[test]
+    fn test_one_sided_crash() {
+        for divergence in both_orders(RunClassification::Crash, RunClassification::CleanExit) {
+            assert_eq!(
+                divergence,
+                Some(RunDivergence::Crash {
+                    crashed: EmulatorType::Rocket,
+                    completed: EmulatorType::Spike,
+                    exit_code: Some(0),
+                    signal: None,
+                    stderr: "emulator: assertion failed".to_string(),
+                })
+            );
+        }
+    }
+
+    #[test]
+    fn test_one_sided_no_output() {
+        for divergence in both_orders(RunClassification::NoOutput, RunClassification::CleanExit) {
+            assert_eq!(
+                divergence,
+                Some(RunDivergence::NoOutput {
+                    silent: EmulatorType::Rocket,
+                    completed: EmulatorType::Spike,
+                })
+            );
+        }
+    }
+
+    #[test]
+    fn test_one_sided_fail_code() {
+        for divergence in both_orders(RunClassification::FailCode, RunClassification::CleanExit) {
+            assert_eq!(
+                divergence,
+                Some(RunDivergence::FailCode {
+                    failed: EmulatorType::Rocket,
+                    completed: EmulatorType::Spike,
+                    tohost_exit_code: 3,
+                })
+            );
+        }
+    }
+
+    #[test]
+    fn test_unusable_outcome_against_fail_code() {
+        for bad in [
+            RunClassification::Timeout,
+            RunClassification::Crash,
+            RunClassification::NoOutput,
+        ] {
+            for divergence in both_orders(bad, RunClassification::FailCode) {
+                let divergence = divergence.expect("only one side has a usable dump");
+                let (bad_side, completed) = match &divergence {
+                    RunDivergence::Timeout {
+                        timed_out,
+                        completed,
+                        ..
+                    } => (timed_out, completed),
+                    RunDivergence::Crash {
+                        crashed, completed, ..
+                    } => (crashed, completed),
+                    RunDivergence::NoOutput { silent, completed } => (silent, completed),
+                    RunDivergence::FailCode { .. } => panic!("FailCode is the usable side"),
+                };
+                assert_eq!(
+                    (bad_side, completed),
+                    (&EmulatorType::Rocket, &EmulatorType::Spike)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_no_divergence_when_both_sides_agree() {
+        use RunClassification::*;
+        for (class1, class2) in [
+            (CleanExit, CleanExit),
+            (FailCode, FailCode),
+            (Timeout, Timeout),
+            (Timeout, Crash),
+            (Crash, NoOutput),
+        ] {
+            assert_eq!(both_orders(class1, class2), [None, None]);
+        }
+    }
+}
@@ -1,4 +1,6 @@
 use crate::emulators::EmulatorType;
+use crate::output_diff::diff::ignore::{SuppressedDiff, write_suppressed};
+use crate::output_diff::diff::run_diff::RunOutcomeDiff;
 use crate::output_diff::diff::{
     ExceptionListDiff, RegistersDumpDiff, compare_exception_dump_lists, compare_registers_dumps,
 };
@@ -39,7 +41,11 @@ impl fmt::Display for ConversionStatsDiff {
         let sim1_name = self.sim1_emulator_type.to_string();
         let sim2_name = self.sim2_emulator_type.to_string();
 
-        writeln!(f, "| Statistics | {} | {} | Difference |", sim1_name, sim2_name)?;
+        writeln!(
+            f,
+            "| Statistics | {} | {} | Difference |",
+            sim1_name, sim2_name
+        )?;
         writeln!(f, "|------------|------------|------------|------------|")?;
 
         if let Some((v1, v2)) = self.original_exception_count_changed {
@@ -123,6 +129,11 @@ pub struct StandardExecutionOutputDiff {
     pub register_dump_status: Option<String>,
     pub register_dump_diff: Option<RegistersDumpDiff>,
     pub conversion_stats_diff: Option<ConversionStatsDiff>,
+    #[serde(default)]
+    pub run_outcome_diff: Option<RunOutcomeDiff>,
+    /// 被 `[diff.ignore]` 忽略的差异
+    #[serde(default)]
+    pub suppressed: Vec<SuppressedDiff>,
 }
 
 impl StandardExecutionOutputDiff {
@@ -137,6 +148,7 @@ impl StandardExecutionOutputDiff {
                 .conversion_stats_diff
                 .as_ref()
                 .map_or(true, |c| c.is_empty())
+            && self.run_outcome_diff.as_ref().is_none_or(|r| r.is_empty())
     }
 }
 
@@ -153,8 +165,12 @@ impl fmt::Display for StandardExecutionOutputDiff {
         if self.is_empty() {
             writeln!(f, "## Diff Result")?;
             writeln!(f)?;
-            writeln!(f, "No significant differences found - standard outputs from both simulators match exactly!")?;
+            writeln!(
+                f,
+                "No significant differences found - standard outputs from both simulators match exactly!"
+            )?;
             writeln!(f)?;
+            write_suppressed(f, &self.suppressed, &sim1_name, &sim2_name)?;
             return Ok(());
         }
 
@@ -178,7 +194,15 @@ impl fmt::Display for StandardExecutionOutputDiff {
 
         if self.conversion_stats_diff.is_some() {
             diff_count += 1;
-            writeln!(f, "| Conversion Stats | Conversion process statistics differ |")?;
+            writeln!(
+                f,
+                "| Conversion Stats | Conversion process statistics differ |"
+            )?;
+        }
+
+        if self.run_outcome_diff.is_some() {
+            diff_count += 1;
+            writeln!(f, "| Run Outcome | Emulators finished differently |")?;
         }
 
         if diff_count == 0 {
@@ -206,6 +230,13 @@ impl fmt::Display for StandardExecutionOutputDiff {
             writeln!(f)?;
         }
 
+        if let Some(run_diff) = self.run_outcome_diff.as_ref().filter(|r| !r.is_empty()) {
+            writeln!(f, "### Run Outcome Difference")?;
+            writeln!(f)?;
+            writeln!(f, "{}", run_diff)?;
+            writeln!(f)?;
+        }
+
         if let Some(reg_diff) = &self.register_dump_diff {
             if !reg_diff.is_empty() {
                 writeln!(f, "### Register Dump Content Differences")?;
@@ -233,6 +264,8 @@ impl fmt::Display for StandardExecutionOutputDiff {
             }
         }
 
+        write_suppressed(f, &self.suppressed, &sim1_name, &sim2_name)?;
+
         writeln!(f, "---")?;
         writeln!(
             f,
@@ -286,28 +319,36 @@ pub fn compare_standard_execution_outputs(
     output2: &StandardExecutionOutput,
 ) -> StandardExecutionOutputDiff {
     let mut diff = StandardExecutionOutputDiff {
-        sim1_emulator_type: output1.emulator_type,
-        sim2_emulator_type: output2.emulator_type,
+        sim1_emulator_type: output1.emulator_type.clone(),
+        sim2_emulator_type: output2.emulator_type.clone(),
         exceptions_diff: None,
         register_dump_status: None,
         register_dump_diff: None,
         conversion_stats_diff: None,
+        run_outcome_diff: None,
+        suppressed: Vec::new(),
     };
 
-    let ex_list_diff = compare_exception_dump_lists(
+    let mut ex_list_diff = compare_exception_dump_lists(
         &output1.exceptions,
         &output2.exceptions,
-        output1.emulator_type,
-        output2.emulator_type,
+        output1.emulator_type.clone(),
+        output2.emulator_type.clone(),
     );
+    diff.suppressed.append(&mut ex_list_diff.suppressed);
     if !ex_list_diff.is_empty() {
         diff.exceptions_diff = Some(ex_list_diff);
     }
 
     match (&output1.register_dump, &output2.register_dump) {
         (Some(rd1), Some(rd2)) => {
-            let reg_d_diff =
-                compare_registers_dumps(rd1, rd2, output1.emulator_type, output2.emulator_type);
+            let mut reg_d_diff = compare_registers_dumps(
+                rd1,
+                rd2,
+                output1.emulator_type.clone(),
+                output2.emulator_type.clone(),
+            );
+            diff.suppressed.append(&mut reg_d_diff.suppressed);
             if !reg_d_diff.is_empty() {
                 diff.register_dump_diff = Some(reg_d_diff);
             }
@@ -330,8 +371,8 @@ pub fn compare_standard_execution_outputs(
     let stats_d = compare_conversion_stats(
         &output1.conversion_stats,
         &output2.conversion_stats,
-        output1.emulator_type,
-        output2.emulator_type,
+        output1.emulator_type.clone(),
+        output2.emulator_type.clone(),
     );
     if !stats_d.is_empty() {
         diff.conversion_stats_diff = Some(stats_d);
@@ -0,0 +1,632 @@
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fmt;
+
+use crate::elf::tracer::{ElfTracer, InstructionTrace};
+use crate::emulators::EmulatorType;
+use crate::output_diff::diff::run_diff::RunOutcomeDiff;
+use crate::output_parser::retire_trace::{
+    MemoryAccess, MemoryAccessKind, RegisterKind, RetireEntry, RetireTrace, TraceTrap,
+};
+
+/// 分歧点前后各展示的指令数
+pub const TRACE_DIFF_WINDOW: usize = 5;
+
+/// 第一处分歧的类型
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum TraceDivergenceKind {
+    /// 两边在同一位置提交了不同 PC 的指令 (控制流分歧)
+    PcMismatch,
+    /// 目的寄存器写回不同
+    RegisterWrite,
+    /// 内存写不同
+    MemoryWrite,
+    /// 在该指令之前发生的陷入不同
+    Trap,
+    /// 一边的 trace 提前结束
+    TraceEnded { simulator: EmulatorType },
+}
+
+impl fmt::Display for TraceDivergenceKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TraceDivergenceKind::PcMismatch => write!(f, "PC mismatch"),
+            TraceDivergenceKind::RegisterWrite => write!(f, "Register write mismatch"),
+            TraceDivergenceKind::MemoryWrite => write!(f, "Memory write mismatch"),
+            TraceDivergenceKind::Trap => write!(f, "Trap mismatch"),
+            TraceDivergenceKind::TraceEnded { simulator } => {
+                write!(f, "{} trace ended early", simulator)
+            }
+        }
+    }
+}
+
+/// 分歧点附近的一条指令
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TraceWindowEntry {
+    /// Offset from the divergence (0 is the divergent instruction)
+    pub offset: isize,
+    pub sim1_entry: Option<RetireEntry>,
+    pub sim2_entry: Option<RetireEntry>,
+    pub inst_trace: Option<InstructionTrace>,
+}
+
+/// 第一条行为不一致的指令
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TraceDivergence {
+    pub kind: TraceDivergenceKind,
+    /// Index of the divergent instruction in each trace
+    pub sim1_index: usize,
+    pub sim2_index: usize,
+    pub sim1_entry: Option<RetireEntry>,
+    pub sim2_entry: Option<RetireEntry>,
+    pub sim1_trap: Option<TraceTrap>,
+    pub sim2_trap: Option<TraceTrap>,
+    /// Source of the divergent instruction, resolved from the ELF dump
+    pub inst_trace: Option<InstructionTrace>,
+    pub window: Vec<TraceWindowEntry>,
+}
+
+/// Lockstep diff of two retire traces
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RetireTraceDiff {
+    pub sim1_emulator_type: EmulatorType,
+    pub sim2_emulator_type: EmulatorType,
+    pub sim1_retired: usize,
+    pub sim2_retired: usize,
+    /// 对齐起点 (跳过各自 bootrom 中的指令)
+    pub sim1_start: usize,
+    pub sim2_start: usize,
+    /// Instructions compared in lockstep before the first divergence
+    pub matched_count: usize,
+    /// Register kinds reported by both traces and therefore compared
+    pub compared_register_kinds: Vec<RegisterKind>,
+    pub compared_memory_writes: bool,
+    pub compared_traps: bool,
+    pub first_divergence: Option<TraceDivergence>,
+    #[serde(default)]
+    pub run_outcome_diff: Option<RunOutcomeDiff>,
+}
+
+impl RetireTraceDiff {
+    pub fn is_empty(&self) -> bool {
+        self.first_divergence.is_none()
+            && self.run_outcome_diff.as_ref().is_none_or(|r| r.is_empty())
+    }
+}
+
+impl fmt::Display for RetireTraceDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sim1_name = self.sim1_emulator_type.to_string();
+        let sim2_name = self.sim2_emulator_type.to_string();
+
+        writeln!(f, "# Retire Trace Diff")?;
+        writeln!(f)?;
+        writeln!(f, "| Item | {} | {} |", sim1_name, sim2_name)?;
+        writeln!(f, "|------|------------|------------|")?;
+        writeln!(
+            f,
+            "| Retired Instructions | {} | {} |",
+            self.sim1_retired, self.sim2_retired
+        )?;
+        writeln!(
+            f,
+            "| Lockstep Start | #{} | #{} |",
+            self.sim1_start, self.sim2_start
+        )?;
+        writeln!(f)?;
+        writeln!(
+            f,
+            "Compared {} instructions in lockstep (registers: {:?}, memory writes: {}, traps: {})",
+            self.matched_count,
+            self.compared_register_kinds,
+            self.compared_memory_writes,
+            self.compared_traps
+        )?;
+        writeln!(f)?;
+
+        if let Some(run_diff) = self.run_outcome_diff.as_ref().filter(|r| !r.is_empty()) {
+            writeln!(f, "### Run Outcome Difference")?;
+            writeln!(f)?;
+            writeln!(f, "{}", run_diff)?;
+            writeln!(f)?;
+        }
+
+        let Some(divergence) = &self.first_divergence else {
+            writeln!(f, "✅ **No divergence found in the retire traces**")?;
+            return Ok(());
+        };
+
+        writeln!(f, "## First Divergence: {}", divergence.kind)?;
+        writeln!(f)?;
+        if let Some(inst) = &divergence.inst_trace {
+            writeln!(
+                f,
+                "- **Source:** `{}` (`{}` @ `0x{:016X}`)",
+                inst.original_instruction, inst.disassembly, inst.pc
+            )?;
+        }
+        let fmt_entry = |entry: &Option<RetireEntry>| {
+            entry
+                .as_ref()
+                .map_or_else(|| "-".to_string(), |e| e.to_string())
+        };
+        writeln!(
+            f,
+            "- **{} #{}:** `{}`",
+            sim1_name,
+            divergence.sim1_index,
+            fmt_entry(&divergence.sim1_entry)
+        )?;
+        writeln!(
+            f,
+            "- **{} #{}:** `{}`",
+            sim2_name,
+            divergence.sim2_index,
+            fmt_entry(&divergence.sim2_entry)
+        )?;
+        for (name, trap) in [
+            (&sim1_name, &divergence.sim1_trap),
+            (&sim2_name, &divergence.sim2_trap),
+        ] {
+            if let Some(trap) = trap {
+                writeln!(
+                    f,
+                    "- **{} trap:** `{}` epc=`0x{:016X}`",
+                    name, trap.cause, trap.epc
+                )?;
+            }
+        }
+        writeln!(f)?;
+
+        if !divergence.window.is_empty() {
+            writeln!(f, "### Surrounding Instructions")?;
+            writeln!(f)?;
+            writeln!(
+                f,
+                "| Offset | PC | Source | {} | {} |",
+                sim1_name, sim2_name
+            )?;
+            writeln!(f, "|--------|----|--------|------------|------------|")?;
+            for item in &divergence.window {
+                let pc = item
+                    .sim1_entry
+                    .as_ref()
+                    .or(item.sim2_entry.as_ref())
+                    .map_or_else(|| "-".to_string(), |e| format!("`0x{:016X}`", e.pc));
+                let source = item.inst_trace.as_ref().map_or_else(
+                    || "-".to_string(),
+                    |t| format!("`{}`", t.original_instruction),
+                );
+                let effects = |entry: &Option<RetireEntry>| {
+                    entry.as_ref().map_or_else(
+                        || "-".to_string(),
+                        |e| {
+                            let effects: Vec<String> = e
+                                .register_writes
+                                .iter()
+                                .map(|w| w.to_string())
+                                .chain(e.memory_accesses.iter().map(|m| m.to_string()))
+                                .collect();
+                            if effects.is_empty() {
+                                "-".to_string()
+                            } else {
+                                effects.join("<br>")
+                            }
+                        },
+                    )
+                };
+                let offset = if item.offset == 0 {
+                    "**→ 0**".to_string()
+                } else {
+                    format!("{:+}", item.offset)
+                };
+                writeln!(
+                    f,
+                    "| {} | {} | {} | {} | {} |",
+                    offset,
+                    pc,
+                    source,
+                    effects(&item.sim1_entry),
+                    effects(&item.sim2_entry)
+                )?;
+            }
+            writeln!(f)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// 找到两条 trace 的对齐起点: 第一条两边都出现过的 PC
+fn find_lockstep_start(trace1: &RetireTrace, trace2: &RetireTrace) -> Option<(usize, usize)> {
+    let pcs2: HashSet<u64> = trace2.entries.iter().map(|e| e.pc).collect();
+    let start1 = trace1.entries.iter().position(|e| pcs2.contains(&e.pc))?;
+    let pc = trace1.entries[start1].pc;
+    let start2 = trace2.entries.iter().position(|e| e.pc == pc)?;
+    Some((start1, start2))
+}
+
+fn register_kinds(trace: &RetireTrace) -> HashSet<RegisterKind> {
+    trace
+        .entries
+        .iter()
+        .flat_map(|e| e.register_writes.iter().map(|w| w.kind))
+        .collect()
+}
+
+/// 参与比较的写回 (寄存器名由各解析器给出，不参与比较)
+fn compared_writes<'a>(
+    entry: &'a RetireEntry,
+    kinds: &'a [RegisterKind],
+) -> impl Iterator<Item = (RegisterKind, u32, u64)> + 'a {
+    entry
+        .register_writes
+        .iter()
+        .filter(move |w| kinds.contains(&w.kind))
+        .map(|w| (w.kind, w.index, w.value))
+}
+
+fn memory_writes(entry: &RetireEntry) -> impl Iterator<Item = &MemoryAccess> {
+    entry
+        .memory_accesses
+        .iter()
+        .filter(|m| m.kind == MemoryAccessKind::Store)
+}
+
+fn trap_before(trace: &RetireTrace, index: usize) -> Option<&TraceTrap> {
+    trace.traps.iter().find(|t| t.retired_before == index)
+}
+
+fn same_trap(trap1: Option<&TraceTrap>, trap2: Option<&TraceTrap>) -> bool {
+    match (trap1, trap2) {
+        (None, None) => true,
+        (Some(t1), Some(t2)) => t1.cause == t2.cause && t1.epc == t2.epc,
+        _ => false,
+    }
+}
+
+/// 按 PC 序列逐条对比两条 retire trace，报告第一处分歧
+///
+/// Only effects both traces report are compared: Rocket's verbose log has no
+/// memory accesses, CSR writes or traps, so those are skipped against it and
+/// a divergent trap shows up as a PC mismatch instead.
+pub fn compare_retire_traces(trace1: &RetireTrace, trace2: &RetireTrace) -> RetireTraceDiff {
+    let kinds2 = register_kinds(trace2);
+    let mut compared_register_kinds: Vec<RegisterKind> = register_kinds(trace1)
+        .into_iter()
+        .filter(|k| kinds2.contains(k))
+        .collect();
+    compared_register_kinds.sort();
+    let has_memory = |t: &RetireTrace| t.entries.iter().any(|e| memory_writes(e).next().is_some());
+    let compared_memory_writes = has_memory(trace1) && has_memory(trace2);
+    let compared_traps = !trace1.traps.is_empty() && !trace2.traps.is_empty();
+
+    let mut diff = RetireTraceDiff {
+        sim1_emulator_type: trace1.emulator_type.clone(),
+        sim2_emulator_type: trace2.emulator_type.clone(),
+        sim1_retired: trace1.entries.len(),
+        sim2_retired: trace2.entries.len(),
+        sim1_start: 0,
+        sim2_start: 0,
+        matched_count: 0,
+        compared_register_kinds,
+        compared_memory_writes,
+        compared_traps,
+        first_divergence: None,
+        run_outcome_diff: None,
+    };
+
+    let Some((start1, start2)) = find_lockstep_start(trace1, trace2) else {
+        warn!("Retire traces share no PC, cannot align them");
+        if !trace1.entries.is_empty() || !trace2.entries.is_empty() {
+            diff.first_divergence = Some(build_divergence(
+                trace1,
+                trace2,
+                0,
+                0,
+                TraceDivergenceKind::PcMismatch,
+            ));
+        }
+        return diff;
+    };
+    diff.sim1_start = start1;
+    diff.sim2_start = start2;
+    debug!(
+        "Aligned retire traces at {}#{} / {}#{}",
+        trace1.emulator_type, start1, trace2.emulator_type, start2
+    );
+
+    let (mut i, mut j) = (start1, start2);
+    let kind = loop {
+        if diff.compared_traps && !same_trap(trap_before(trace1, i), trap_before(trace2, j)) {
+            break Some(TraceDivergenceKind::Trap);
+        }
+        let (entry1, entry2) = match (trace1.entries.get(i), trace2.entries.get(j)) {
+            (Some(e1), Some(e2)) => (e1, e2),
+            (None, None) => break None,
+            (None, Some(_)) => {
+                break Some(TraceDivergenceKind::TraceEnded {
+                    simulator: trace1.emulator_type.clone(),
+                });
+            }
+            (Some(_), None) => {
+                break Some(TraceDivergenceKind::TraceEnded {
+                    simulator: trace2.emulator_type.clone(),
+                });
+            }
+        };
+
+        if entry1.pc != entry2.pc {
+            break Some(TraceDivergenceKind::PcMismatch);
+        }
+        if !compared_writes(entry1, &diff.compared_register_kinds)
+            .eq(compared_writes(entry2, &diff.compared_register_kinds))
+        {
+            break Some(TraceDivergenceKind::RegisterWrite);
+        }
+        if diff.compared_memory_writes && !memory_writes(entry1).eq(memory_writes(entry2)) {
+            break Some(TraceDivergenceKind::MemoryWrite);
+        }
+
+        diff.matched_count += 1;
+        i += 1;
+        j += 1;
+    };
+
+    diff.first_divergence = kind.map(|kind| build_divergence(trace1, trace2, i, j, kind));
+    diff
+}
+
+fn build_divergence(
+    trace1: &RetireTrace,
+    trace2: &RetireTrace,
+    index1: usize,
+    index2: usize,
+    kind: TraceDivergenceKind,
+) -> TraceDivergence {
+    let tracer = trace1
+        .dump_path
+        .as_ref()
+        .or(trace2.dump_path.as_ref())
+        .and_then(|path| match ElfTracer::new(path) {
+            Ok(tracer) => Some(tracer),
+            Err(e) => {
+                warn!("Failed to create ELF tracer from {}: {}", path.display(), e);
+                None
+            }
+        });
+    let resolve = |entry1: Option<&RetireEntry>, entry2: Option<&RetireEntry>| {
+        let pc = entry1.or(entry2)?.pc;
+        tracer.as_ref().and_then(|t| t.trace_pc(pc))
+    };
+
+    // 窗口以分歧点为中心，两边按相同偏移对齐
+    let before = TRACE_DIFF_WINDOW.min(index1).min(index2);
+    let window = (0..before + TRACE_DIFF_WINDOW + 1)
+        .filter_map(|k| {
+            let (i, j) = (index1 + k - before, index2 + k - before);
+            let (entry1, entry2) = (trace1.entries.get(i), trace2.entries.get(j));
+            if entry1.is_none() && entry2.is_none() {
+                return None;
+            }
+            Some(TraceWindowEntry {
+                offset: k as isize - before as isize,
+                sim1_entry: entry1.cloned(),
+                sim2_entry: entry2.cloned(),
+                inst_trace: resolve(entry1, entry2),
+            })
+        })
+        .collect();
+
+    let (entry1, entry2) = (trace1.entries.get(index1), trace2.entries.get(index2));
+    TraceDivergence {
+        kind,
+        sim1_index: index1,
+        sim2_index: index2,
+        sim1_entry: entry1.cloned(),
+        sim2_entry: entry2.cloned(),
+        sim1_trap: trap_before(trace1, index1).cloned(),
+        sim2_trap: trap_before(trace2, index2).cloned(),
+        inst_trace: resolve(entry1, entry2),
+        window,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::output_parser::retire_trace::RegisterWrite;
+
+    const BASE: u64 = 0x8000_0000;
+
+    /// `pc` 处的指令，可带一个整数寄存器写回和一个 store
+    fn entry(
+        index: usize,
+        pc: u64,
+        rd: Option<(u32, u64)>,
+        store: Option<(u64, u64)>,
+    ) -> RetireEntry {
+        RetireEntry {
+            index,
+            hart: 0,
+            privilege: Some(3),
+            cycle: None,
+            pc,
+            instruction_bits: 0x13,
+            disassembly: None,
+            register_writes: rd
+                .map(|(index, value)| RegisterWrite {
+                    kind: RegisterKind::Int,
+                    index,
+                    name: format!("x{}", index),
+                    value,
+                })
+                .into_iter()
+                .collect(),
+            memory_accesses: store
+                .map(|(address, value)| MemoryAccess {
+                    kind: MemoryAccessKind::Store,
+                    address,
+                    value: Some(value),
+                })
+                .into_iter()
+                .collect(),
+        }
+    }
+
+    /// 四条指令: 两次写 a0、一次 store、一次写 a1
+    fn program(a0: u64, stored: u64, a1: u64) -> Vec<RetireEntry> {
+        vec![
+            entry(0, BASE, Some((10, 1)), None),
+            entry(1, BASE + 4, Some((10, a0)), None),
+            entry(2, BASE + 8, None, Some((BASE + 0x1000, stored))),
+            entry(3, BASE + 12, Some((11, a1)), None),
+        ]
+    }
+
+    fn trace(emulator_type: EmulatorType, entries: Vec<RetireEntry>) -> RetireTrace {
+        RetireTrace {
+            emulator_type,
+            entries,
+            traps: Vec::new(),
+            dump_path: None,
+        }
+    }
+
+    fn trap(retired_before: usize, cause: &str) -> TraceTrap {
+        TraceTrap {
+            retired_before,
+            hart: 0,
+            cause: cause.to_string(),
+            epc: BASE + 4 * retired_before as u64,
+            tval: None,
+        }
+    }
+
+    #[test]
+    fn test_identical_traces_skip_bootrom() {
+        let mut spike = program(2, 3, 4);
+        spike.insert(0, entry(0, 0x1000, Some((5, 0x8000_0000)), None));
+        let diff = compare_retire_traces(
+            &trace(EmulatorType::Spike, spike),
+            &trace(EmulatorType::Rocket, program(2, 3, 4)),
+        );
+        assert!(diff.is_empty());
+        assert_eq!((diff.sim1_start, diff.sim2_start), (1, 0));
+        assert_eq!(diff.matched_count, 4);
+        assert_eq!(diff.compared_register_kinds, vec![RegisterKind::Int]);
+        assert!(diff.compared_memory_writes);
+        assert!(!diff.compared_traps);
+    }
+
+    #[test]
+    fn test_register_write_divergence() {
+        let diff = compare_retire_traces(
+            &trace(EmulatorType::Spike, program(2, 3, 4)),
+            &trace(EmulatorType::Rocket, program(7, 3, 4)),
+        );
+        let divergence = diff.first_divergence.unwrap();
+        assert_eq!(divergence.kind, TraceDivergenceKind::RegisterWrite);
+        assert_eq!((divergence.sim1_index, divergence.sim2_index), (1, 1));
+        assert_eq!(diff.matched_count, 1);
+        assert_eq!(divergence.sim2_entry.unwrap().register_writes[0].value, 7);
+
+        // 窗口以分歧点为中心，前面只有一条指令
+        let offsets: Vec<isize> = divergence.window.iter().map(|w| w.offset).collect();
+        assert_eq!(offsets, vec![-1, 0, 1, 2]);
+    }
+
+    #[test]
+    fn test_memory_write_divergence() {
+        let spike = trace(EmulatorType::Spike, program(2, 3, 4));
+        let diff = compare_retire_traces(&spike, &trace(EmulatorType::Rocket, program(2, 9, 4)));
+        let divergence = diff.first_divergence.unwrap();
+        assert_eq!(divergence.kind, TraceDivergenceKind::MemoryWrite);
+        assert_eq!(divergence.sim1_index, 2);
+        assert_eq!(diff.matched_count, 2);
+
+        // 一侧没有访存记录时不比较 store (如 Rocket 的 verbose 日志)
+        let mut no_memory = program(2, 9, 4);
+        no_memory.iter_mut().for_each(|e| e.memory_accesses.clear());
+        let diff = compare_retire_traces(&spike, &trace(EmulatorType::Rocket, no_memory));
+        assert!(!diff.compared_memory_writes);
+        assert!(diff.is_empty());
+    }
+
+    #[test]
+    fn test_trap_divergence() {
+        let mut spike = trace(EmulatorType::Spike, program(2, 3, 4));
+        spike.traps = vec![
+            trap(1, "trap_illegal_instruction"),
+            trap(3, "trap_illegal_instruction"),
+        ];
+        let mut sail = trace(EmulatorType::Sail, program(2, 3, 4));
+        sail.traps = vec![
+            trap(1, "trap_illegal_instruction"),
+            trap(3, "trap_load_access_fault"),
+        ];
+
+        let diff = compare_retire_traces(&spike, &sail);
+        assert!(diff.compared_traps);
+        let divergence = diff.first_divergence.unwrap();
+        assert_eq!(divergence.kind, TraceDivergenceKind::Trap);
+        assert_eq!(divergence.sim1_index, 3);
+        assert_eq!(diff.matched_count, 3);
+        assert_eq!(
+            divergence.sim1_trap.unwrap().cause,
+            "trap_illegal_instruction"
+        );
+        assert_eq!(
+            divergence.sim2_trap.unwrap().cause,
+            "trap_load_access_fault"
+        );
+
+        // 一侧没有陷入记录时不比较陷入
+        sail.traps.clear();
+        assert!(compare_retire_traces(&spike, &sail).is_empty());
+    }
+
+    #[test]
+    fn test_trace_ended_early() {
+        let mut short = program(2, 3, 4);
+        short.truncate(2);
+        let diff = compare_retire_traces(
+            &trace(EmulatorType::Spike, program(2, 3, 4)),
+            &trace(EmulatorType::Rocket, short),
+        );
+        let divergence = diff.first_divergence.unwrap();
+        assert_eq!(
+            divergence.kind,
+            TraceDivergenceKind::TraceEnded {
+                simulator: EmulatorType::Rocket
+            }
+        );
+        assert_eq!(divergence.sim1_index, 2);
+        assert!(divergence.sim1_entry.is_some());
+        assert!(divergence.sim2_entry.is_none());
+        assert_eq!(diff.matched_count, 2);
+        assert_eq!((diff.sim1_retired, diff.sim2_retired), (4, 2));
+    }
+
+    #[test]
+    fn test_pc_mismatch_without_common_pc() {
+        let moved: Vec<RetireEntry> = program(2, 3, 4)
+            .into_iter()
+            .map(|e| RetireEntry {
+                pc: e.pc + 0x100,
+                ..e
+            })
+            .collect();
+        let diff = compare_retire_traces(
+            &trace(EmulatorType::Spike, program(2, 3, 4)),
+            &trace(EmulatorType::Rocket, moved),
+        );
+        assert_eq!(
+            diff.first_divergence.unwrap().kind,
+            TraceDivergenceKind::PcMismatch
+        );
+        assert_eq!(diff.matched_count, 0);
+    }
+}
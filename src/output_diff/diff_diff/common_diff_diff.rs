@@ -252,21 +252,21 @@ pub fn compare_common_execution_output_diffs(
     diff2: &CommonExecutionOutputDiff,
 ) -> CommonExecutionOutputDiffDiff {
     let mut ddiff = CommonExecutionOutputDiffDiff {
-        sim1_emulator_type: diff1.sim1_emulator_type,
-        sim2_emulator_type: diff1.sim2_emulator_type,
+        sim1_emulator_type: diff1.sim1_emulator_type.clone(),
+        sim2_emulator_type: diff1.sim2_emulator_type.clone(),
         ..Default::default()
     };
 
     if diff1.sim1_emulator_type != diff2.sim1_emulator_type {
         ddiff.sim1_emulator_type_changed_diff = Some(Change {
-            old: diff1.sim1_emulator_type,
-            new: diff2.sim1_emulator_type,
+            old: diff1.sim1_emulator_type.clone(),
+            new: diff2.sim1_emulator_type.clone(),
         });
     }
     if diff1.sim2_emulator_type != diff2.sim2_emulator_type {
         ddiff.sim2_emulator_type_changed_diff = Some(Change {
-            old: diff1.sim2_emulator_type,
-            new: diff2.sim2_emulator_type,
+            old: diff1.sim2_emulator_type.clone(),
+            new: diff2.sim2_emulator_type.clone(),
         });
     }
 
@@ -250,8 +250,8 @@ pub fn compare_debug_execution_output_diffs(
     diff2: &DebugExecutionOutputDiff,
 ) -> DebugExecutionOutputDiffDiff {
     let mut ddiff = DebugExecutionOutputDiffDiff {
-        sim1_emulator_type: diff1.sim1_emulator_type,
-        sim2_emulator_type: diff1.sim2_emulator_type,
+        sim1_emulator_type: diff1.sim1_emulator_type.clone(),
+        sim2_emulator_type: diff1.sim2_emulator_type.clone(),
         ..Default::default()
     };
 
@@ -268,8 +268,8 @@ pub fn compare_conversion_stats_diffs(
     diff2: &ConversionStatsDiff,
 ) -> ConversionStatsDiffDiff {
     let mut ddiff = ConversionStatsDiffDiff {
-        sim1_emulator_type: diff1.sim1_emulator_type, // 使用 diff1 中的类型
-        sim2_emulator_type: diff1.sim2_emulator_type, // 使用 diff1 中的类型
+        sim1_emulator_type: diff1.sim1_emulator_type.clone(), // 使用 diff1 中的类型
+        sim2_emulator_type: diff1.sim2_emulator_type.clone(), // 使用 diff1 中的类型
         ..Default::default()
     };
     if diff1.original_exception_count_changed != diff2.original_exception_count_changed {
@@ -703,20 +703,20 @@ pub fn compare_exception_list_diffs(
     diff2: &ExceptionListDiff,
 ) -> ExceptionListDiffDiff {
     let mut ddiff = ExceptionListDiffDiff {
-        sim1_emulator_type: diff1.sim1_emulator_type,
-        sim2_emulator_type: diff1.sim2_emulator_type,
+        sim1_emulator_type: diff1.sim1_emulator_type.clone(),
+        sim2_emulator_type: diff1.sim2_emulator_type.clone(),
         ..Default::default()
     };
     if diff1.sim1_emulator_type != diff2.sim1_emulator_type {
         ddiff.sim1_emulator_type_changed = Some(Change {
-            old: diff1.sim1_emulator_type,
-            new: diff2.sim1_emulator_type,
+            old: diff1.sim1_emulator_type.clone(),
+            new: diff2.sim1_emulator_type.clone(),
         });
     }
     if diff1.sim2_emulator_type != diff2.sim2_emulator_type {
         ddiff.sim2_emulator_type_changed = Some(Change {
-            old: diff1.sim2_emulator_type,
-            new: diff2.sim2_emulator_type,
+            old: diff1.sim2_emulator_type.clone(),
+            new: diff2.sim2_emulator_type.clone(),
         });
     }
     if diff1.list1_only_exceptions != diff2.list1_only_exceptions {
@@ -869,21 +869,21 @@ pub fn compare_standard_execution_output_diffs(
     diff2: &StandardExecutionOutputDiff,
 ) -> StandardExecutionOutputDiffDiff {
     let mut ddiff = StandardExecutionOutputDiffDiff {
-        sim1_emulator_type: diff1.sim1_emulator_type,
-        sim2_emulator_type: diff1.sim2_emulator_type,
+        sim1_emulator_type: diff1.sim1_emulator_type.clone(),
+        sim2_emulator_type: diff1.sim2_emulator_type.clone(),
         ..Default::default()
     };
 
     if diff1.sim1_emulator_type != diff2.sim1_emulator_type {
         ddiff.sim1_emulator_type_changed_diff = Some(Change {
-            old: diff1.sim1_emulator_type,
-            new: diff2.sim1_emulator_type,
+            old: diff1.sim1_emulator_type.clone(),
+            new: diff2.sim1_emulator_type.clone(),
         });
     }
     if diff1.sim2_emulator_type != diff2.sim2_emulator_type {
         ddiff.sim2_emulator_type_changed_diff = Some(Change {
-            old: diff1.sim2_emulator_type,
-            new: diff2.sim2_emulator_type,
+            old: diff1.sim2_emulator_type.clone(),
+            new: diff2.sim2_emulator_type.clone(),
         });
     }
 
@@ -897,8 +897,8 @@ pub fn compare_standard_execution_output_diffs(
     }
     if let (Some(ex1), Some(ex2)) = (&diff1.exceptions_diff, &diff2.exceptions_diff) {
         let mut content_ddiff = compare_exception_list_diffs(ex1, ex2);
-        content_ddiff.sim1_emulator_type = ddiff.sim1_emulator_type;
-        content_ddiff.sim2_emulator_type = ddiff.sim2_emulator_type;
+        content_ddiff.sim1_emulator_type = ddiff.sim1_emulator_type.clone();
+        content_ddiff.sim2_emulator_type = ddiff.sim2_emulator_type.clone();
         if !content_ddiff.is_empty() {
             ddiff.exceptions_diff_content_diff = Some(content_ddiff);
         }
@@ -921,8 +921,8 @@ pub fn compare_standard_execution_output_diffs(
     }
     if let (Some(rd1), Some(rd2)) = (&diff1.register_dump_diff, &diff2.register_dump_diff) {
         let mut content_ddiff = compare_registers_dump_diffs(rd1, rd2);
-        content_ddiff.sim1_emulator_type = ddiff.sim1_emulator_type;
-        content_ddiff.sim2_emulator_type = ddiff.sim2_emulator_type;
+        content_ddiff.sim1_emulator_type = ddiff.sim1_emulator_type.clone();
+        content_ddiff.sim2_emulator_type = ddiff.sim2_emulator_type.clone();
         if !content_ddiff.is_empty() {
             ddiff.register_dump_diff_content_diff = Some(content_ddiff);
         }
@@ -930,8 +930,8 @@ pub fn compare_standard_execution_output_diffs(
 
     if let (Some(cs1), Some(cs2)) = (&diff1.conversion_stats_diff, &diff2.conversion_stats_diff) {
         let mut content_ddiff = compare_conversion_stats_diffs(cs1, cs2);
-        content_ddiff.sim1_emulator_type = cs1.sim1_emulator_type;
-        content_ddiff.sim2_emulator_type = cs1.sim2_emulator_type;
+        content_ddiff.sim1_emulator_type = cs1.sim1_emulator_type.clone();
+        content_ddiff.sim2_emulator_type = cs1.sim2_emulator_type.clone();
         if !content_ddiff.is_empty() {
             ddiff.conversion_stats_diff_content_diff = Some(content_ddiff);
         }
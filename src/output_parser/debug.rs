@@ -1,20 +1,14 @@
 use serde::{Deserialize, Serialize};
 use std::fmt;
-use std::path::Path; // Added for Display trait
+use std::path::Path;
 
 use crate::output_parser::common::parse_common_output_from_file;
 use crate::output_parser::util::get_register_name;
 use crate::{
     emulators::EmulatorType,
     error::Result,
-    output_parser::{
-        ExceptionCSRs, // Added back as it's used in DebugExecutionOutputItem
-        MarkerType,
-        OutputParser,
-        RegistersDump,      // Removed CoreCSRs (unused in this file directly)
-        common::OutputItem, // Removed common::self
-    },
-}; // Added import
+    output_parser::{ExceptionCSRs, MarkerType, OutputParser, RegistersDump, common::OutputItem},
+};
 
 /// Debug output single parsing item
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -104,7 +98,7 @@ pub fn parse_debug_output_from_file<P: AsRef<Path>>(
     dump_path: P,
     emulator_type: EmulatorType,
 ) -> Result<DebugExecutionOutput> {
-    let common_output = parse_common_output_from_file(log_path, dump_path, emulator_type)?;
+    let common_output = parse_common_output_from_file(log_path, dump_path, emulator_type.clone())?;
 
     let mut parsed_debug_items = Vec::new();
     let mut total_dumps_encountered = 0;
@@ -1,11 +1,13 @@
 pub mod common;
 pub mod debug;
+pub mod retire_trace;
 pub mod standard;
 pub mod util;
 
 use crate::elf::tracer::InstructionTrace;
 use crate::emulators::EmulatorType;
-use crate::error::Result;
+use crate::error::{Result, RiscvFuzzError};
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use std::fmt;
 use std::fs;
@@ -93,7 +95,10 @@ pub struct ExceptionCSRs {
 }
 
 /// Output parser trait
-pub trait OutputParser: Sized + std::fmt::Display + Serialize {
+///
+/// Parsed outputs are saved as JSON by [`parse_output_from_file`] and can be
+/// loaded back with [`load_output_from_json`].
+pub trait OutputParser: Sized + std::fmt::Display + Serialize + DeserializeOwned {
     fn parse_from_file<P: AsRef<Path>>(
         log_path: P,
         dump_path: P,
@@ -124,3 +129,50 @@ where
 
     Ok(parsed)
 }
+
+/// Loads an output saved by [`parse_output_from_file`]
+pub fn load_output_from_json<T, P: AsRef<Path>>(json_path: P) -> Result<T>
+where
+    T: OutputParser,
+{
+    let path = json_path.as_ref();
+    let content = fs::read_to_string(path).map_err(|e| {
+        RiscvFuzzError::file(format!(
+            "Failed to read parsed output {}: {}",
+            path.display(),
+            e
+        ))
+    })?;
+    serde_json::from_str(&content).map_err(|e| {
+        RiscvFuzzError::output_parsing(
+            "JSON".to_string(),
+            format!(
+                "{} is not a saved output of this format: {}",
+                path.display(),
+                e
+            ),
+        )
+    })
+}
+
+/// Loads `path` as saved JSON when it ends in `.json`, otherwise parses it as
+/// a raw emulator log, which needs the disassembly `dump_path`
+pub fn load_or_parse_output<T, P: AsRef<Path>>(
+    path: P,
+    dump_path: Option<P>,
+    emulator_type: EmulatorType,
+) -> Result<T>
+where
+    T: OutputParser,
+{
+    if path.as_ref().extension().is_some_and(|ext| ext == "json") {
+        return load_output_from_json(path);
+    }
+    let dump_path = dump_path.ok_or_else(|| {
+        RiscvFuzzError::config(format!(
+            "Parsing raw log {} requires the .dump file",
+            path.as_ref().display()
+        ))
+    })?;
+    parse_output_from_file(path, dump_path, emulator_type)
+}
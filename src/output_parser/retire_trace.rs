@@ -0,0 +1,663 @@
+use log::{debug, warn};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::{fmt, fs};
+
+use crate::emulators::EmulatorType;
+use crate::error::{Result, RiscvFuzzError};
+use crate::output_parser::{OutputParser, util};
+
+/// Per-instruction retire trace of one emulator run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RetireTrace {
+    /// Emulator type
+    pub emulator_type: EmulatorType,
+    /// Retired instructions in commit order
+    pub entries: Vec<RetireEntry>,
+    /// Traps taken during the run
+    pub traps: Vec<TraceTrap>,
+    /// ELF dump used to resolve PCs back to the source
+    pub dump_path: Option<PathBuf>,
+}
+
+/// 一条已提交指令
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RetireEntry {
+    /// Position in the retire order
+    pub index: usize,
+    pub hart: u32,
+    /// 提交时的特权级 (0=U, 1=S, 3=M)
+    pub privilege: Option<u8>,
+    /// 提交周期 (仅 RTL 仿真器)
+    pub cycle: Option<u64>,
+    pub pc: u64,
+    /// 指令编码 (压缩指令只有低 16 位)
+    pub instruction_bits: u32,
+    /// 模拟器给出的反汇编文本
+    pub disassembly: Option<String>,
+    pub register_writes: Vec<RegisterWrite>,
+    pub memory_accesses: Vec<MemoryAccess>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum RegisterKind {
+    Int,
+    Float,
+    Csr,
+}
+
+/// 寄存器写回
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RegisterWrite {
+    pub kind: RegisterKind,
+    /// Register number, or the CSR address for [`RegisterKind::Csr`]
+    pub index: u32,
+    pub name: String,
+    pub value: u64,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum MemoryAccessKind {
+    Load,
+    Store,
+}
+
+/// 访存记录 (load 不记录数值)
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct MemoryAccess {
+    pub kind: MemoryAccessKind,
+    pub address: u64,
+    pub value: Option<u64>,
+}
+
+/// 陷入记录
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TraceTrap {
+    /// Number of instructions retired before the trap
+    pub retired_before: usize,
+    pub hart: u32,
+    /// Trap name as printed by the emulator (e.g. `trap_illegal_instruction`)
+    pub cause: String,
+    pub epc: u64,
+    pub tval: Option<u64>,
+}
+
+impl fmt::Display for RegisterWrite {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} = 0x{:016X}", self.name, self.value)
+    }
+}
+
+impl fmt::Display for MemoryAccess {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.kind, self.value) {
+            (MemoryAccessKind::Store, Some(value)) => {
+                write!(f, "store [0x{:016X}] = 0x{:X}", self.address, value)
+            }
+            (MemoryAccessKind::Store, None) => write!(f, "store [0x{:016X}]", self.address),
+            (MemoryAccessKind::Load, _) => write!(f, "load [0x{:016X}]", self.address),
+        }
+    }
+}
+
+impl fmt::Display for RetireEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "0x{:016X} (0x{:08X}) {}",
+            self.pc,
+            self.instruction_bits,
+            self.disassembly.as_deref().unwrap_or("")
+        )?;
+        let effects: Vec<String> = self
+            .register_writes
+            .iter()
+            .map(|w| w.to_string())
+            .chain(self.memory_accesses.iter().map(|m| m.to_string()))
+            .collect();
+        if !effects.is_empty() {
+            write!(f, " → {}", effects.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for RetireTrace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "# 🧵 RISC-V Retire Trace")?;
+        writeln!(f)?;
+        writeln!(f, "**Emulator Type:** `{}`", self.emulator_type)?;
+        writeln!(f)?;
+
+        writeln!(f, "## 📊 Basic Information")?;
+        writeln!(f)?;
+        writeln!(f, "| Item | Value |")?;
+        writeln!(f, "|------|-------|")?;
+        writeln!(f, "| Retired Instructions | `{}` |", self.entries.len())?;
+        writeln!(f, "| Traps | `{}` |", self.traps.len())?;
+        writeln!(f)?;
+
+        if !self.traps.is_empty() {
+            writeln!(f, "## 🚨 Traps")?;
+            writeln!(f)?;
+            writeln!(f, "| After # | Cause | EPC | TVAL |")?;
+            writeln!(f, "|---------|-------|-----|------|")?;
+            for trap in &self.traps {
+                writeln!(
+                    f,
+                    "| {} | `{}` | `0x{:016X}` | {} |",
+                    trap.retired_before,
+                    trap.cause,
+                    trap.epc,
+                    trap.tval
+                        .map_or_else(|| "-".to_string(), |v| format!("`0x{:016X}`", v))
+                )?;
+            }
+            writeln!(f)?;
+        }
+
+        if !self.entries.is_empty() {
+            writeln!(f, "## 📋 Retired Instructions")?;
+            writeln!(f)?;
+            writeln!(f, "```")?;
+            for entry in &self.entries {
+                writeln!(f, "{:>8}: {}", entry.index, entry)?;
+            }
+            writeln!(f, "```")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl OutputParser for RetireTrace {
+    fn parse_from_file<P: AsRef<Path>>(
+        log_path: P,
+        dump_path: P,
+        emulator_type: EmulatorType,
+    ) -> Result<Self> {
+        let mut trace = parse_retire_trace_from_file(log_path, emulator_type)?;
+        trace.dump_path = Some(dump_path.as_ref().to_path_buf());
+        Ok(trace)
+    }
+}
+
+/// 从模拟器的文本提交日志解析 retire trace
+pub fn parse_retire_trace_from_file<P: AsRef<Path>>(
+    log_path: P,
+    emulator_type: EmulatorType,
+) -> Result<RetireTrace> {
+    let content = fs::read_to_string(log_path.as_ref())?;
+    debug!(
+        "📄 Reading commit log: {} ({} bytes) for emulator {:?}",
+        log_path.as_ref().display(),
+        content.len(),
+        emulator_type
+    );
+    match emulator_type {
+        EmulatorType::Spike => Ok(parse_spike_commit_log(&content)),
+        EmulatorType::Rocket => Ok(parse_rocket_verbose_log(&content)),
+        other => Err(RiscvFuzzError::output_parsing(
+            "retire trace",
+            &format!("No retire trace parser for emulator {}", other),
+        )),
+    }
+}
+
+fn spike_commit_re() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(r"^core\s+(\d+):\s+(\d)\s+0x([0-9a-fA-F]+)\s+\(0x([0-9a-fA-F]+)\)(.*)$").unwrap()
+    })
+}
+
+fn spike_disasm_re() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(r"^core\s+(\d+):\s+0x([0-9a-fA-F]+)\s+\(0x([0-9a-fA-F]+)\)\s+(.*)$").unwrap()
+    })
+}
+
+fn spike_exception_re() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(r"^core\s+(\d+):\s+exception\s+(\S+),\s+epc\s+0x([0-9a-fA-F]+)").unwrap()
+    })
+}
+
+fn spike_tval_re() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"^core\s+(\d+):\s+tval\s+0x([0-9a-fA-F]+)").unwrap())
+}
+
+fn parse_hex(s: &str) -> Option<u64> {
+    u64::from_str_radix(s.trim_start_matches("0x"), 16).ok()
+}
+
+/// 解析 Spike `-l --log-commits` 输出
+///
+/// Commit lines look like
+/// `core   0: 3 0x0000000080000000 (0x00000297) x5  0x0000000080000000 mem 0x80001000`;
+/// the `-l` disassembly line printed for the same PC is attached to the entry.
+pub fn parse_spike_commit_log(content: &str) -> RetireTrace {
+    let mut trace = RetireTrace {
+        emulator_type: EmulatorType::Spike,
+        entries: Vec::new(),
+        traps: Vec::new(),
+        dump_path: None,
+    };
+    // 最近一条 -l 反汇编行: (pc, bits, text)
+    let mut pending_disasm: Option<(u64, u32, String)> = None;
+
+    for line in content.lines() {
+        let line = line.trim_end();
+
+        if let Some(caps) = spike_commit_re().captures(line) {
+            let pc = parse_hex(&caps[3]).unwrap_or_default();
+            let instruction_bits = parse_hex(&caps[4]).unwrap_or_default() as u32;
+            let disassembly = pending_disasm
+                .take()
+                .filter(|(dpc, dbits, _)| *dpc == pc && *dbits == instruction_bits)
+                .map(|(_, _, text)| text);
+            let (register_writes, memory_accesses) = parse_spike_commit_effects(&caps[5]);
+            trace.entries.push(RetireEntry {
+                index: trace.entries.len(),
+                hart: caps[1].parse().unwrap_or_default(),
+                privilege: caps[2].parse().ok(),
+                cycle: None,
+                pc,
+                instruction_bits,
+                disassembly,
+                register_writes,
+                memory_accesses,
+            });
+        } else if let Some(caps) = spike_exception_re().captures(line) {
+            pending_disasm = None;
+            trace.traps.push(TraceTrap {
+                retired_before: trace.entries.len(),
+                hart: caps[1].parse().unwrap_or_default(),
+                cause: caps[2].to_string(),
+                epc: parse_hex(&caps[3]).unwrap_or_default(),
+                tval: None,
+            });
+        } else if let Some(caps) = spike_tval_re().captures(line) {
+            if let Some(trap) = trace.traps.last_mut() {
+                trap.tval = parse_hex(&caps[2]);
+            }
+        } else if let Some(caps) = spike_disasm_re().captures(line) {
+            pending_disasm = Some((
+                parse_hex(&caps[2]).unwrap_or_default(),
+                parse_hex(&caps[3]).unwrap_or_default() as u32,
+                caps[4].split_whitespace().collect::<Vec<_>>().join(" "),
+            ));
+        } else if !line.is_empty() {
+            debug!("Skipping unrecognized commit log line: {}", line);
+        }
+    }
+
+    debug!(
+        "Parsed {} retired instructions and {} traps from Spike commit log",
+        trace.entries.len(),
+        trace.traps.len()
+    );
+    trace
+}
+
+fn rocket_verbose_re() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(concat!(
+            r"^C\s*(\d+):\s+(\d+)\s+\[(\d)\]\s+pc=\[([0-9a-fA-F]+)\]\s+",
+            r"W\[r\s*(\d+)=([0-9a-fA-F]+)\]\[(\d)\]\s+",
+            r"R\[r\s*\d+=[0-9a-fA-F]+\]\s+R\[r\s*\d+=[0-9a-fA-F]+\]\s+",
+            r"inst=\[([0-9a-fA-F]+)\]\s*(.*)$"
+        ))
+        .unwrap()
+    })
+}
+
+/// 解析 Rocket `--verbose` 输出
+///
+/// Rocket prints one line per cycle,
+/// `C0:        123 [1] pc=[0000000080000000] W[r 5=0000000080000000][1] R[r 0=...] R[r 0=...] inst=[00000297] DASM(00000297)`,
+/// where `[1]` marks a retiring instruction. Loads, divides and other
+/// long-latency results are written back on a later cycle with `[0]`; such
+/// writes are attached to the latest retired entry whose `rd` field matches.
+/// Rocket reports neither memory accesses, CSR writes nor traps.
+pub fn parse_rocket_verbose_log(content: &str) -> RetireTrace {
+    let mut trace = RetireTrace {
+        emulator_type: EmulatorType::Rocket,
+        entries: Vec::new(),
+        traps: Vec::new(),
+        dump_path: None,
+    };
+
+    for line in content.lines() {
+        let Some(caps) = rocket_verbose_re().captures(line.trim()) else {
+            continue;
+        };
+        let retired = &caps[3] == "1";
+        let waddr: u32 = caps[5].parse().unwrap_or_default();
+        let wdata = parse_hex(&caps[6]).unwrap_or_default();
+        let wen = &caps[7] == "1" && waddr != 0;
+        let write = RegisterWrite {
+            kind: RegisterKind::Int,
+            index: waddr,
+            name: util::get_register_name(waddr as usize).to_string(),
+            value: wdata,
+        };
+
+        if retired {
+            let disassembly = caps[9].trim();
+            trace.entries.push(RetireEntry {
+                index: trace.entries.len(),
+                hart: caps[1].parse().unwrap_or_default(),
+                privilege: None,
+                cycle: caps[2].parse().ok(),
+                pc: parse_hex(&caps[4]).unwrap_or_default(),
+                instruction_bits: parse_hex(&caps[8]).unwrap_or_default() as u32,
+                // 未经 spike-dasm 处理时只有 DASM(...) 占位
+                disassembly: (!disassembly.is_empty() && !disassembly.starts_with("DASM("))
+                    .then(|| disassembly.split_whitespace().collect::<Vec<_>>().join(" ")),
+                register_writes: if wen { vec![write] } else { Vec::new() },
+                memory_accesses: Vec::new(),
+            });
+        } else if wen {
+            // 长延迟写回
+            let target = trace.entries.iter_mut().rev().find(|e| {
+                e.register_writes.is_empty() && (e.instruction_bits >> 7) & 0x1f == waddr
+            });
+            match target {
+                Some(entry) => entry.register_writes.push(write),
+                None => debug!(
+                    "Dropping Rocket writeback without a matching instruction: {}",
+                    line
+                ),
+            }
+        }
+    }
+
+    debug!(
+        "Parsed {} retired instructions from Rocket verbose log",
+        trace.entries.len()
+    );
+    trace
+}
+
+/// 解析提交行中 `(0x....)` 之后的寄存器写回与访存部分
+fn parse_spike_commit_effects(effects: &str) -> (Vec<RegisterWrite>, Vec<MemoryAccess>) {
+    let mut register_writes = Vec::new();
+    let mut memory_accesses = Vec::new();
+    let tokens: Vec<&str> = effects.split_whitespace().collect();
+    let mut i = 0;
+
+    while i < tokens.len() {
+        let token = tokens[i];
+        let next = tokens.get(i + 1).copied();
+
+        if token == "mem" {
+            let Some(address) = next.and_then(parse_hex) else {
+                warn!("Malformed memory access in commit log: {}", effects);
+                break;
+            };
+            // 写操作在地址后紧跟数值
+            let value = tokens
+                .get(i + 2)
+                .filter(|t| t.starts_with("0x"))
+                .and_then(|t| parse_hex(t));
+            memory_accesses.push(MemoryAccess {
+                kind: if value.is_some() {
+                    MemoryAccessKind::Store
+                } else {
+                    MemoryAccessKind::Load
+                },
+                address,
+                value,
+            });
+            i += if value.is_some() { 3 } else { 2 };
+            continue;
+        }
+
+        if let (Some(write), Some(value)) = (parse_register_name(token), next.and_then(parse_hex)) {
+            let (kind, index, name) = write;
+            register_writes.push(RegisterWrite {
+                kind,
+                index,
+                name,
+                value,
+            });
+            i += 2;
+            continue;
+        }
+
+        // 向量寄存器等暂不记录
+        debug!("Skipping commit log token: {}", token);
+        i += 1;
+    }
+
+    (register_writes, memory_accesses)
+}
+
+/// `x5` / `f1` / `c768_mstatus` → (kind, index, name)
+fn parse_register_name(token: &str) -> Option<(RegisterKind, u32, String)> {
+    if let Some(num) = token.strip_prefix('x') {
+        let index: u32 = num.parse().ok().filter(|n| *n < 32)?;
+        return Some((
+            RegisterKind::Int,
+            index,
+            util::get_register_name(index as usize).to_string(),
+        ));
+    }
+    if let Some(num) = token.strip_prefix('f') {
+        let index: u32 = num.parse().ok().filter(|n| *n < 32)?;
+        return Some((RegisterKind::Float, index, format!("f{}", index)));
+    }
+    if let Some(rest) = token.strip_prefix('c') {
+        let (num, name) = rest.split_once('_')?;
+        let index: u32 = num.parse().ok()?;
+        return Some((RegisterKind::Csr, index, name.to_string()));
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SPIKE_COMMIT_LOG: &str = "\
+core   0: 0x0000000080001000 (0x00500293) li      t0, 5
+core   0: 3 0x0000000080001000 (0x00500293) x5  0x0000000000000005
+core   0: 0x0000000080001004 (0x00533023) sd      t0, 0(t1)
+core   0: 3 0x0000000080001004 (0x00533023) mem 0x0000000080002000 0x0000000000000005
+core   0: 0x0000000080001008 (0x00033383) ld      t2, 0(t1)
+core   0: 3 0x0000000080001008 (0x00033383) x7  0x0000000000000005 mem 0x0000000080002000
+core   0: 0x000000008000100c (0x34029073) csrw    mscratch, t0
+core   0: 3 0x000000008000100c (0x34029073) c832_mscratch 0x0000000000000005
+core   0: exception trap_illegal_instruction, epc 0x0000000080001010
+core   0:           tval 0x0000000002628333
+";
+
+    const ROCKET_VERBOSE_LOG: &str = "\
+C0:         10 [1] pc=[0000000080001000] W[r 5=0000000000000005][1] R[r 0=0000000000000000] R[r 0=0000000000000000] inst=[00500293] DASM(00500293)
+C0:         11 [1] pc=[0000000080001004] W[r 7=0000000000000000][0] R[r 6=0000000080002000] R[r 0=0000000000000000] inst=[00033383] ld      t2, 0(t1)
+C0:         12 [0] pc=[0000000080001008] W[r 9=0000000000000001][1] R[r 0=0000000000000000] R[r 0=0000000000000000] inst=[00000013] nop
+C0:         14 [0] pc=[0000000080001008] W[r 7=0000000000000042][1] R[r 0=0000000000000000] R[r 0=0000000000000000] inst=[00000013] nop
+";
+
+    #[test]
+    fn test_parse_spike_commit_log() {
+        let trace = parse_spike_commit_log(SPIKE_COMMIT_LOG);
+        assert_eq!(trace.entries.len(), 4);
+
+        let li = &trace.entries[0];
+        assert_eq!(li.pc, 0x8000_1000);
+        assert_eq!(li.instruction_bits, 0x0050_0293);
+        assert_eq!(li.privilege, Some(3));
+        assert_eq!(li.disassembly.as_deref(), Some("li t0, 5"));
+        assert_eq!(
+            li.register_writes,
+            vec![RegisterWrite {
+                kind: RegisterKind::Int,
+                index: 5,
+                name: "t0".to_string(),
+                value: 5,
+            }]
+        );
+
+        let sd = &trace.entries[1];
+        assert!(sd.register_writes.is_empty());
+        assert_eq!(
+            sd.memory_accesses,
+            vec![MemoryAccess {
+                kind: MemoryAccessKind::Store,
+                address: 0x8000_2000,
+                value: Some(5),
+            }]
+        );
+
+        let ld = &trace.entries[2];
+        assert_eq!(ld.register_writes[0].index, 7);
+        assert_eq!(
+            ld.memory_accesses,
+            vec![MemoryAccess {
+                kind: MemoryAccessKind::Load,
+                address: 0x8000_2000,
+                value: None,
+            }]
+        );
+
+        let csrw = &trace.entries[3].register_writes[0];
+        assert_eq!(csrw.kind, RegisterKind::Csr);
+        assert_eq!(csrw.index, 0x340);
+        assert_eq!(csrw.name, "mscratch");
+
+        assert_eq!(
+            trace.traps,
+            vec![TraceTrap {
+                retired_before: 4,
+                hart: 0,
+                cause: "trap_illegal_instruction".to_string(),
+                epc: 0x8000_1010,
+                tval: Some(0x0262_8333),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_parse_spike_commit_log_float_and_unmatched_disassembly() {
+        let log = "\
+core   1: 0x0000000080001000 (0x00000013) nop
+core   1: 1 0x0000000080001004 (0xd2028053) f0  0xffffffff40a00000 c1_fflags 0x0000000000000001
+core   1: 1 0x0000000080001008 (0x00000073) v1  0x0000000000000001
+core   1: exception trap_user_ecall, epc 0x0000000080001008
+";
+        let trace = parse_spike_commit_log(log);
+        assert_eq!(trace.entries.len(), 2);
+
+        // 反汇编行的 PC 与提交行不同时不关联
+        let fcvt = &trace.entries[0];
+        assert_eq!(fcvt.hart, 1);
+        assert_eq!(fcvt.privilege, Some(1));
+        assert_eq!(fcvt.disassembly, None);
+        assert_eq!(
+            fcvt.register_writes,
+            vec![
+                RegisterWrite {
+                    kind: RegisterKind::Float,
+                    index: 0,
+                    name: "f0".to_string(),
+                    value: 0xffff_ffff_40a0_0000,
+                },
+                RegisterWrite {
+                    kind: RegisterKind::Csr,
+                    index: 1,
+                    name: "fflags".to_string(),
+                    value: 1,
+                },
+            ]
+        );
+
+        // 向量寄存器写回被跳过
+        assert_eq!(trace.entries[1].index, 1);
+        assert!(trace.entries[1].register_writes.is_empty());
+
+        assert_eq!(trace.traps.len(), 1);
+        assert_eq!(trace.traps[0].hart, 1);
+        assert_eq!(trace.traps[0].retired_before, 2);
+        assert_eq!(trace.traps[0].tval, None);
+    }
+
+    #[test]
+    fn test_parse_retire_trace_from_file_rejects_backends_without_trace() {
+        let path =
+            std::env::temp_dir().join(format!("retire_trace_test_{}.log", std::process::id()));
+        fs::write(&path, SPIKE_COMMIT_LOG).unwrap();
+
+        let spike = parse_retire_trace_from_file(&path, EmulatorType::Spike).unwrap();
+        assert_eq!(spike.entries.len(), 4);
+        assert!(parse_retire_trace_from_file(&path, EmulatorType::Qemu).is_err());
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_parse_rocket_verbose_log() {
+        let trace = parse_rocket_verbose_log(ROCKET_VERBOSE_LOG);
+        assert_eq!(trace.entries.len(), 2);
+
+        let li = &trace.entries[0];
+        assert_eq!(li.cycle, Some(10));
+        assert_eq!(li.pc, 0x8000_1000);
+        // 未经 spike-dasm 处理的 DASM(...) 不作为反汇编
+        assert_eq!(li.disassembly, None);
+        assert_eq!(li.register_writes[0].value, 5);
+
+        // 长延迟写回挂到 rd 匹配的最近一条指令上，没有匹配的写回被丢弃
+        let ld = &trace.entries[1];
+        assert_eq!(ld.disassembly.as_deref(), Some("ld t2, 0(t1)"));
+        assert_eq!(
+            ld.register_writes,
+            vec![RegisterWrite {
+                kind: RegisterKind::Int,
+                index: 7,
+                name: "t2".to_string(),
+                value: 0x42,
+            }]
+        );
+        assert!(trace.traps.is_empty());
+    }
+
+    #[test]
+    fn test_parse_rocket_verbose_log_writebacks() {
+        let log = "\
+C1:         20 [1] pc=[0000000080001000] W[r 0=0000000000000007][1] R[r 0=0000000000000000] R[r 0=0000000000000000] inst=[00700013] li      zero, 7
+C1:         21 [1] pc=[0000000080001004] W[r 10=0000000000000000][0] R[r 11=0000000000000006] R[r 12=0000000000000003] inst=[02c5c533] div     a0, a1, a2
+C1:         22 [1] pc=[0000000080001008] W[r 11=0000000000000000][0] R[r 6=0000000080002000] R[r 0=0000000000000000] inst=[00033583] ld      a1, 0(t1)
+C1:         23 garbage line
+C1:         30 [0] pc=[0000000080001008] W[r 10=0000000000000002][1] R[r 0=0000000000000000] R[r 0=0000000000000000] inst=[00000013] nop
+C1:         31 [0] pc=[0000000080001008] W[r 11=0000000000000009][1] R[r 0=0000000000000000] R[r 0=0000000000000000] inst=[00000013] nop
+";
+        let trace = parse_rocket_verbose_log(log);
+        assert_eq!(trace.entries.len(), 3);
+        assert!(trace.entries.iter().all(|e| e.hart == 1));
+        assert!(trace.entries.iter().all(|e| e.memory_accesses.is_empty()));
+
+        // 写 x0 不算写回
+        assert!(trace.entries[0].register_writes.is_empty());
+
+        // 乱序完成的写回各自挂到对应的指令上
+        let div = &trace.entries[1];
+        assert_eq!(div.disassembly.as_deref(), Some("div a0, a1, a2"));
+        assert_eq!(div.register_writes.len(), 1);
+        assert_eq!(div.register_writes[0].name, "a0");
+        assert_eq!(div.register_writes[0].value, 2);
+
+        let ld = &trace.entries[2];
+        assert_eq!(ld.cycle, Some(22));
+        assert_eq!(ld.register_writes.len(), 1);
+        assert_eq!(ld.register_writes[0].name, "a1");
+        assert_eq!(ld.register_writes[0].value, 9);
+    }
+}
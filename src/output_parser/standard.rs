@@ -345,7 +345,7 @@ pub fn parse_standard_output_from_file<P: AsRef<Path>>(
     dump_path: P,
     emulator_type: EmulatorType,
 ) -> Result<StandardExecutionOutput> {
-    let common_output = common::parse_common_output_from_file(file_path, dump_path, emulator_type)?;
+    let common_output = common::parse_common_output_from_file(file_path, dump_path, emulator_type.clone())?;
     let mut warnings = Vec::new();
     let mut conversion_successful = true;
 
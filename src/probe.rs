@@ -0,0 +1,385 @@
+//! 模拟器扩展支持探测
+//!
+//! Runs a few random instructions of every extension on each emulator and
+//! records which extensions (and which opcodes) trap as illegal. The resulting
+//! [`CapabilityProfile`] replaces the hand-maintained
+//! `RV64_ROCKET_SUPPORTED_EXTENSIONS` list in `Random` mode.
+
+use log::{info, warn};
+use rayon::prelude::*;
+use riscv_instruction::separated_instructions::RV64Extensions;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::consts::linker_script::LINKER_SCRIPT;
+use crate::elf::build::build_elf;
+use crate::emulators::registry::EmulatorRegistry;
+use crate::emulators::{EmulatorType, RunLimits, run_and_parse};
+use crate::error::{Result, RiscvFuzzError};
+use crate::output_parser::standard::StandardExecutionOutput;
+use crate::output_parser::util::get_current_timestamp;
+use crate::random_asm::asm_maker::{generate_instructions, generate_standard_asm_from_insts};
+use crate::random_asm::denylist::{OpcodeDenylist, is_csr_opcode};
+use crate::random_asm::inst_generator::GenerationOrder;
+use crate::utils::build_rv64_march;
+
+/// 默认能力描述文件
+pub const DEFAULT_CAPABILITY_PROFILE: &str = "capability_profile.json";
+
+/// 模板本身需要的扩展，探测时与被测扩展一起编译
+pub const PROBE_BASE_EXTENSIONS: &[RV64Extensions] = &[
+    RV64Extensions::I,
+    RV64Extensions::M,
+    RV64Extensions::F,
+    RV64Extensions::D,
+    RV64Extensions::Zicsr,
+    RV64Extensions::Zifencei,
+];
+
+/// 非法指令异常
+const MCAUSE_ILLEGAL_INSTRUCTION: u64 = 2;
+
+/// 扩展支持程度，按从好到坏排序
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum ExtensionSupport {
+    Supported,
+    /// 部分指令触发非法指令异常
+    Partial,
+    Unsupported,
+    /// 编译或运行失败，无法判断
+    Untested,
+}
+
+impl fmt::Display for ExtensionSupport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExtensionSupport::Supported => write!(f, "✅ supported"),
+            ExtensionSupport::Partial => write!(f, "⚠️ partial"),
+            ExtensionSupport::Unsupported => write!(f, "❌ unsupported"),
+            ExtensionSupport::Untested => write!(f, "❓ untested"),
+        }
+    }
+}
+
+/// 单个模拟器对某扩展的探测结果
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EmulatorCapability {
+    pub emulator_type: EmulatorType,
+    pub support: ExtensionSupport,
+    /// Number of probe instructions that trapped as illegal
+    pub illegal_count: usize,
+    /// Mnemonics of the instructions that trapped as illegal
+    pub illegal_opcodes: Vec<String>,
+    /// Why the extension could not be tested
+    pub note: Option<String>,
+}
+
+impl EmulatorCapability {
+    fn untested(emulator_type: EmulatorType, note: String) -> Self {
+        Self {
+            emulator_type,
+            support: ExtensionSupport::Untested,
+            illegal_count: 0,
+            illegal_opcodes: Vec::new(),
+            note: Some(note),
+        }
+    }
+}
+
+/// 某扩展在所有模拟器上的探测结果
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExtensionCapability {
+    pub extension: RV64Extensions,
+    /// Number of probe instructions generated
+    pub probed_count: usize,
+    pub emulators: Vec<EmulatorCapability>,
+}
+
+impl ExtensionCapability {
+    /// 所有模拟器中最差的支持程度
+    pub fn support(&self) -> ExtensionSupport {
+        self.emulators
+            .iter()
+            .map(|e| e.support)
+            .max()
+            .unwrap_or(ExtensionSupport::Untested)
+    }
+}
+
+/// Capability profile written by the `probe` subcommand
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CapabilityProfile {
+    pub created_at: String,
+    pub emulators: Vec<EmulatorType>,
+    /// Instructions generated per extension
+    pub inst_num: usize,
+    pub extensions: Vec<ExtensionCapability>,
+}
+
+impl CapabilityProfile {
+    /// 所有模拟器都至少部分支持的扩展，供随机生成使用
+    pub fn usable_extensions(&self) -> Vec<RV64Extensions> {
+        self.extensions
+            .iter()
+            .filter(|e| e.support() <= ExtensionSupport::Partial)
+            .map(|e| e.extension)
+            .collect()
+    }
+
+    /// Opcodes that trapped as illegal in the usable extensions, per emulator.
+    ///
+    /// `Partial` extensions stay usable, so these opcodes must be denylisted
+    /// before generating from them. Zicsr opcodes are left out, as their traps
+    /// depend on the CSR address.
+    pub fn illegal_opcodes(&self) -> OpcodeDenylist {
+        let mut denylist = OpcodeDenylist::default();
+        for ext in &self.extensions {
+            if ext.support() > ExtensionSupport::Partial {
+                continue;
+            }
+            for capability in &ext.emulators {
+                for opcode in capability
+                    .illegal_opcodes
+                    .iter()
+                    .filter(|opcode| !is_csr_opcode(opcode))
+                {
+                    denylist.insert(capability.emulator_type.clone(), opcode.clone());
+                }
+            }
+        }
+        denylist
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        fs::write(path.as_ref(), serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let content = fs::read_to_string(path.as_ref()).map_err(|e| {
+            RiscvFuzzError::config(format!(
+                "Failed to read capability profile {}: {}",
+                path.as_ref().display(),
+                e
+            ))
+        })?;
+        Ok(serde_json::from_str(&content)?)
+    }
+}
+
+impl fmt::Display for CapabilityProfile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "# 🧪 Emulator Capability Profile")?;
+        writeln!(f)?;
+        writeln!(f, "- **Created:** {}", self.created_at)?;
+        writeln!(f, "- **Instructions per extension:** {}", self.inst_num)?;
+        writeln!(f)?;
+
+        write!(f, "| Extension |")?;
+        for emulator in &self.emulators {
+            write!(f, " {} |", emulator)?;
+        }
+        writeln!(f, " Illegal Opcodes |")?;
+        write!(f, "|-----------|")?;
+        for _ in &self.emulators {
+            write!(f, "------------|")?;
+        }
+        writeln!(f, "-----------------|")?;
+
+        for ext in &self.extensions {
+            write!(f, "| {:?} |", ext.extension)?;
+            let mut illegal = Vec::new();
+            for capability in &ext.emulators {
+                match &capability.note {
+                    Some(note) => write!(f, " {} ({}) |", capability.support, note)?,
+                    None => write!(
+                        f,
+                        " {} ({}/{}) |",
+                        capability.support, capability.illegal_count, ext.probed_count
+                    )?,
+                }
+                if !capability.illegal_opcodes.is_empty() {
+                    illegal.push(format!(
+                        "{}: `{}`",
+                        capability.emulator_type,
+                        capability.illegal_opcodes.join("`, `")
+                    ));
+                }
+            }
+            writeln!(f, " {} |", illegal.join("<br>"))?;
+        }
+        writeln!(f)?;
+
+        let usable: Vec<String> = self
+            .usable_extensions()
+            .iter()
+            .map(|e| format!("{:?}", e))
+            .collect();
+        writeln!(f, "**Usable for random generation:** {}", usable.join(", "))?;
+        Ok(())
+    }
+}
+
+/// 探测 `extensions` 中每个扩展在 `emulators` 上的支持情况
+pub fn probe_extensions(
+    extensions: &[RV64Extensions],
+    emulators: &[EmulatorType],
+    inst_num: usize,
+    build_dir: &Path,
+    limits: &RunLimits,
+) -> Result<CapabilityProfile> {
+    fs::create_dir_all(build_dir)?;
+
+    let results: Vec<ExtensionCapability> = extensions
+        .par_iter()
+        .map(|&extension| probe_extension(extension, emulators, inst_num, build_dir, limits))
+        .collect();
+
+    Ok(CapabilityProfile {
+        created_at: get_current_timestamp(),
+        emulators: emulators.to_vec(),
+        inst_num,
+        extensions: results,
+    })
+}
+
+fn probe_extension(
+    extension: RV64Extensions,
+    emulators: &[EmulatorType],
+    inst_num: usize,
+    build_dir: &Path,
+    limits: &RunLimits,
+) -> ExtensionCapability {
+    let untested_all = |probed_count: usize, note: String| ExtensionCapability {
+        extension,
+        probed_count,
+        emulators: emulators
+            .iter()
+            .map(|e| EmulatorCapability::untested(e.clone(), note.clone()))
+            .collect(),
+    };
+
+    let ext_dir = build_dir.join(format!("{:?}", extension).to_lowercase());
+    if let Err(e) = fs::create_dir_all(&ext_dir) {
+        return untested_all(0, format!("cannot create {}: {}", ext_dir.display(), e));
+    }
+
+    // 探测需要覆盖扩展中的全部指令，不使用黑名单
+    let instruction_counts = HashMap::from([(extension, inst_num)]);
+    let insts = generate_instructions(
+        &instruction_counts,
+        GenerationOrder::Sequential,
+        &OpcodeDenylist::default(),
+        &mut rand::rng(),
+    );
+    if insts.is_empty() {
+        return untested_all(0, "no instructions generated".to_string());
+    }
+
+    let mut exts = PROBE_BASE_EXTENSIONS.to_vec();
+    if !exts.contains(&extension) {
+        exts.push(extension);
+    }
+    let march = build_rv64_march(&exts);
+
+    let assembly_file = ext_dir.join("probe.S");
+    let linker_script = ext_dir.join("linker.ld");
+    let write_result = fs::write(&assembly_file, generate_standard_asm_from_insts(&insts))
+        .and_then(|_| fs::write(&linker_script, LINKER_SCRIPT));
+    if let Err(e) = write_result {
+        return untested_all(insts.len(), format!("cannot write probe program: {}", e));
+    }
+
+    let build_result = match build_elf(&assembly_file, &linker_script, &march) {
+        Ok(build_result) => build_result,
+        Err(e) => {
+            warn!("⚠️ Failed to build probe for {:?}: {}", extension, e);
+            return untested_all(insts.len(), "build failed".to_string());
+        }
+    };
+
+    let registry = match EmulatorRegistry::with_limits(&march, limits).select(emulators) {
+        Ok(registry) => registry,
+        Err(e) => return untested_all(insts.len(), e.to_string()),
+    };
+
+    let capabilities = registry
+        .iter()
+        .map(|emulator| {
+            let log_file = ext_dir.join(format!("{}_output.bin", emulator.name()));
+            match run_and_parse::<StandardExecutionOutput, &Path>(
+                emulator,
+                &build_result.executable_file,
+                &build_result.disassembly_file,
+                &log_file,
+            ) {
+                Ok(output) => classify(emulator.emulator_type(), &output, insts.len()),
+                Err(e) => {
+                    warn!(
+                        "⚠️ {} failed to run probe for {:?}: {}",
+                        emulator.name(),
+                        extension,
+                        e
+                    );
+                    EmulatorCapability::untested(emulator.emulator_type(), "run failed".to_string())
+                }
+            }
+        })
+        .collect();
+
+    let capability = ExtensionCapability {
+        extension,
+        probed_count: insts.len(),
+        emulators: capabilities,
+    };
+    info!("🧪 {:?}: {}", extension, capability.support());
+    capability
+}
+
+/// 根据非法指令异常的数量判断支持程度
+fn classify(
+    emulator_type: EmulatorType,
+    output: &StandardExecutionOutput,
+    probed_count: usize,
+) -> EmulatorCapability {
+    let mut illegal_pcs = HashSet::new();
+    let mut illegal_opcodes = BTreeSet::new();
+    for exception in &output.exceptions {
+        if exception.csrs.mcause != MCAUSE_ILLEGAL_INSTRUCTION {
+            continue;
+        }
+        // 只统计用户代码中的指令
+        let Some(trace) = &exception.inst_trace else {
+            continue;
+        };
+        illegal_pcs.insert(exception.csrs.mepc);
+        if let Some(mnemonic) = trace.original_instruction.split_whitespace().next() {
+            illegal_opcodes.insert(mnemonic.to_lowercase());
+        }
+    }
+
+    let illegal_count = illegal_pcs.len();
+    let support = if illegal_count == 0 {
+        ExtensionSupport::Supported
+    } else if illegal_count >= probed_count {
+        ExtensionSupport::Unsupported
+    } else {
+        ExtensionSupport::Partial
+    };
+
+    EmulatorCapability {
+        emulator_type,
+        support,
+        illegal_count,
+        illegal_opcodes: illegal_opcodes.into_iter().collect(),
+        note: None,
+    }
+}
+
+/// 能力描述文件对应的 Markdown 报告路径
+pub fn capability_report_path(profile_path: &Path) -> PathBuf {
+    profile_path.with_extension("md")
+}
@@ -1,36 +1,43 @@
 use crate::{
     elf::template::generate_standard_asm,
-    random_asm::inst_generator::{
-        GenerationOrder, InstructionsGenerator, remove_special_instructions,
+    random_asm::{
+        denylist::OpcodeDenylist,
+        inst_generator::{GenerationOrder, InstructionsGenerator, remove_special_instructions},
     },
 };
 use rand::prelude::*;
 use riscv_instruction::separated_instructions::{RV64Extensions, RiscvInstruction};
 use std::collections::HashMap;
 
-/// 生成随机指令
-pub fn generate_instructions(
+/// 生成随机指令，跳过 `denylist` 中已知不被支持的操作码
+///
+/// Denied instructions are resampled rather than dropped, so every extension
+/// still gets its configured count. The output depends only on the arguments
+/// and the enabled emulators, so a seeded `rng` (see
+/// [`crate::random_asm::seed`]) reproduces the same program.
+pub fn generate_instructions<R: Rng>(
     instruction_counts: &HashMap<RV64Extensions, usize>,
     generation_order: GenerationOrder,
-    rng: &mut ThreadRng,
+    denylist: &OpcodeDenylist,
+    rng: &mut R,
 ) -> Vec<RiscvInstruction> {
     let mut generator = InstructionsGenerator::new_rv64();
 
-    // 设置每个扩展的指令数量
-    for (&extension, &count) in instruction_counts {
+    // 设置每个扩展的指令数量，按名称排序以不依赖 HashMap 的遍历顺序
+    let mut counts: Vec<_> = instruction_counts.iter().collect();
+    counts.sort_by_key(|(extension, _)| format!("{:?}", extension));
+    for (&extension, &count) in counts {
         generator = generator.with(extension, count);
     }
 
     // 设置生成顺序
     generator = generator.order(generation_order);
 
-    // 生成指令
-    let mut instructions = generator.generate_with_rng(rng);
+    // 生成指令，重新采样已知某个模拟器不支持的指令
+    let instructions = generator.generate_accepted_with_rng(rng, |inst| !denylist.denies(inst));
 
     // 过滤掉可能导致控制流跳转的指令
-    instructions = remove_special_instructions(instructions);
-
-    instructions
+    remove_special_instructions(instructions)
 }
 
 /// 将指令列表格式化为汇编代码字符串
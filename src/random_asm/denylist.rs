@@ -0,0 +1,397 @@
+//! 按模拟器记录的非法操作码黑名单
+//!
+//! Opcodes that trapped as illegal in only one emulator (`OnlyInSimulator { mcause: 2 }`)
+//! are remembered per emulator and resampled by [`generate_instructions`] in later
+//! runs, so known-unsupported instructions are never emitted again. Only the
+//! emulators listed in `[emulators] enabled` are consulted. Zicsr mnemonics are
+//! never learned: their illegal-instruction traps come from the CSR address,
+//! not from the opcode.
+//!
+//! [`generate_instructions`]: crate::random_asm::asm_maker::generate_instructions
+
+use log::info;
+use riscv_instruction::separated_instructions::RiscvInstruction;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::Path;
+use std::sync::Mutex;
+
+use crate::config;
+use crate::emulators::EmulatorType;
+use crate::error::{Result, RiscvFuzzError};
+use crate::output_diff::diff::{ExceptionDiffCategory, ExceptionListDiff};
+
+/// 默认黑名单文件
+pub const DEFAULT_OPCODE_DENYLIST: &str = "opcode_denylist.json";
+
+/// 非法指令异常
+const MCAUSE_ILLEGAL_INSTRUCTION: u64 = 2;
+
+/// 并行测试共享同一个文件，读写时串行化
+static DENYLIST_FILE_LOCK: Mutex<()> = Mutex::new(());
+
+/// Opcodes each emulator is known not to support
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct OpcodeDenylist {
+    /// 以模拟器显示名 (`EmulatorType` 的 `Display`) 为键，JSON 对象的键必须是字符串
+    pub emulators: BTreeMap<String, BTreeSet<String>>,
+}
+
+/// 指令助记符 (小写)，与汇编源码中的写法一致
+pub fn opcode_of(instruction: &str) -> Option<String> {
+    instruction
+        .split_whitespace()
+        .next()
+        .map(|mnemonic| mnemonic.to_lowercase())
+}
+
+/// 是否为 Zicsr 指令 (`csrr`、`csrrw`、`csrwi` 等)
+pub fn is_csr_opcode(opcode: &str) -> bool {
+    opcode.starts_with("csr")
+}
+
+impl OpcodeDenylist {
+    pub fn is_empty(&self) -> bool {
+        self.emulators.values().all(|opcodes| opcodes.is_empty())
+    }
+
+    /// 是否有任一已启用 (`[emulators] enabled`) 的模拟器不支持该助记符
+    pub fn is_denied(&self, opcode: &str) -> bool {
+        let enabled = &config::get().emulators;
+        self.emulators
+            .iter()
+            .any(|(emulator, opcodes)| opcodes.contains(opcode) && enabled.is_enabled(emulator))
+    }
+
+    /// 指令的助记符是否被拒绝
+    pub fn denies(&self, instruction: &RiscvInstruction) -> bool {
+        !self.is_empty()
+            && opcode_of(&instruction.to_string()).is_some_and(|op| self.is_denied(&op))
+    }
+
+    /// 记录一个操作码，返回是否为新增
+    pub fn insert(&mut self, emulator_type: EmulatorType, opcode: String) -> bool {
+        self.insert_named(emulator_type.to_string(), opcode)
+    }
+
+    fn insert_named(&mut self, emulator: String, opcode: String) -> bool {
+        self.emulators.entry(emulator).or_default().insert(opcode)
+    }
+
+    /// 从仅在某个模拟器中出现的非法指令异常中学习，返回新增的操作码数
+    pub fn learn_from_exceptions(&mut self, diff: &ExceptionListDiff) -> usize {
+        let mut learned = 0;
+        for cat_diff in &diff.categorized_summary {
+            let ExceptionDiffCategory::OnlyInSimulator { simulator, mcause } = &cat_diff.category
+            else {
+                continue;
+            };
+            if *mcause != MCAUSE_ILLEGAL_INSTRUCTION {
+                continue;
+            }
+            for trace in cat_diff.pc_instruction_traces.iter().flatten() {
+                let Some(opcode) =
+                    opcode_of(&trace.original_instruction).filter(|opcode| !is_csr_opcode(opcode))
+                else {
+                    continue;
+                };
+                if self.insert(simulator.clone(), opcode.clone()) {
+                    info!("🚫 Denylisting `{}` for {}", opcode, simulator);
+                    learned += 1;
+                }
+            }
+        }
+        learned
+    }
+
+    /// 去掉黑名单中的指令
+    pub fn filter_instructions(
+        &self,
+        instructions: Vec<RiscvInstruction>,
+    ) -> Vec<RiscvInstruction> {
+        if self.is_empty() {
+            return instructions;
+        }
+        instructions
+            .into_iter()
+            .filter(|inst| !self.denies(inst))
+            .collect()
+    }
+
+    /// 合并另一个黑名单，返回新增的操作码数
+    pub fn merge(&mut self, other: &OpcodeDenylist) -> usize {
+        let mut added = 0;
+        for (emulator, opcodes) in &other.emulators {
+            for opcode in opcodes {
+                if self.insert_named(emulator.clone(), opcode.clone()) {
+                    added += 1;
+                }
+            }
+        }
+        added
+    }
+
+    /// 读取黑名单，文件不存在时为空
+    pub fn load_or_default<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let _guard = DENYLIST_FILE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        Self::read(path.as_ref())
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let _guard = DENYLIST_FILE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        fs::write(path.as_ref(), serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Learns from `diff` and merges the result into the file at `path`,
+    /// returning the number of newly denylisted opcodes
+    pub fn record_exceptions<P: AsRef<Path>>(path: P, diff: &ExceptionListDiff) -> Result<usize> {
+        let _guard = DENYLIST_FILE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let mut denylist = Self::read(path.as_ref())?;
+        let learned = denylist.learn_from_exceptions(diff);
+        if learned > 0 {
+            fs::write(path.as_ref(), serde_json::to_string_pretty(&denylist)?)?;
+        }
+        Ok(learned)
+    }
+
+    /// Merges `other` into the file at `path`, returning the number of newly
+    /// denylisted opcodes
+    pub fn record_denylist<P: AsRef<Path>>(path: P, other: &OpcodeDenylist) -> Result<usize> {
+        let _guard = DENYLIST_FILE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let mut denylist = Self::read(path.as_ref())?;
+        let added = denylist.merge(other);
+        if added > 0 {
+            fs::write(path.as_ref(), serde_json::to_string_pretty(&denylist)?)?;
+        }
+        Ok(added)
+    }
+
+    fn read(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = fs::read_to_string(path).map_err(|e| {
+            RiscvFuzzError::config(format!(
+                "Failed to read opcode denylist {}: {}",
+                path.display(),
+                e
+            ))
+        })?;
+        Ok(serde_json::from_str(&content)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::elf::tracer::InstructionTrace;
+    use crate::output_diff::diff::CategorizedExceptionDiffs;
+    use crate::random_asm::asm_maker::generate_instructions;
+    use crate::random_asm::inst_generator::{GenerationOrder, InstructionsGenerator};
+    use crate::random_asm::seed::seeded_rng;
+    use riscv_instruction::separated_instructions::RV64Extensions;
+    use std::collections::HashMap;
+
+    fn only_in(
+        simulator: EmulatorType,
+        mcause: u64,
+        instructions: &[&str],
+    ) -> CategorizedExceptionDiffs {
+        CategorizedExceptionDiffs {
+            category: ExceptionDiffCategory::OnlyInSimulator { simulator, mcause },
+            diffs_summary: Vec::new(),
+            count: instructions.len(),
+            pc_list: (0..instructions.len() as u64)
+                .map(|i| 0x1000 + 4 * i)
+                .collect(),
+            pc_instruction_traces: instructions
+                .iter()
+                .enumerate()
+                .map(|(i, inst)| {
+                    Some(InstructionTrace {
+                        pc: 0x1000 + 4 * i as u64,
+                        disassembly: inst.to_string(),
+                        machine_code: String::new(),
+                        original_instruction: inst.to_string(),
+                    })
+                })
+                .collect(),
+        }
+    }
+
+    fn exceptions(categorized_summary: Vec<CategorizedExceptionDiffs>) -> ExceptionListDiff {
+        ExceptionListDiff {
+            sim1_emulator_type: EmulatorType::Spike,
+            sim2_emulator_type: EmulatorType::Rocket,
+            list1_only_exceptions: Vec::new(),
+            list2_only_exceptions: Vec::new(),
+            paired_exceptions_diffs: Vec::new(),
+            categorized_summary,
+            edits: Vec::new(),
+            suppressed: Vec::new(),
+        }
+    }
+
+    fn denylist(entries: &[(EmulatorType, &str)]) -> OpcodeDenylist {
+        let mut denylist = OpcodeDenylist::default();
+        for (emulator_type, opcode) in entries {
+            denylist.insert(emulator_type.clone(), opcode.to_string());
+        }
+        denylist
+    }
+
+    fn opcodes(instructions: &[RiscvInstruction]) -> Vec<String> {
+        instructions
+            .iter()
+            .filter_map(|inst| opcode_of(&inst.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_learn_only_illegal_instruction_traps() {
+        let diff = exceptions(vec![
+            only_in(
+                EmulatorType::Rocket,
+                2,
+                &["FLI.S fa0, 1.0", "csrr a0, 0x7c0"],
+            ),
+            only_in(EmulatorType::Spike, 5, &["lw a0, 0(a1)"]),
+            CategorizedExceptionDiffs {
+                category: ExceptionDiffCategory::McauseDifference {
+                    sim1_cause: 2,
+                    sim2_cause: 5,
+                },
+                ..only_in(EmulatorType::Spike, 2, &["sw a0, 0(a1)"])
+            },
+        ]);
+
+        let mut learned = OpcodeDenylist::default();
+        assert_eq!(learned.learn_from_exceptions(&diff), 1);
+        assert_eq!(learned, denylist(&[(EmulatorType::Rocket, "fli.s")]));
+        // 未实现的 CSR 地址不应拒绝整个 csrr 助记符
+        assert!(!learned.is_denied("csrr"));
+
+        // 重复学习不产生新的操作码
+        assert_eq!(learned.learn_from_exceptions(&diff), 0);
+    }
+
+    #[test]
+    fn test_csr_traps_do_not_deny_the_mnemonic() {
+        let diff = exceptions(vec![only_in(
+            EmulatorType::Spike,
+            2,
+            &["csrrw a0, 0x7c0, a1", "csrwi 0x5c0, 3", "csrs 0x800, a2"],
+        )]);
+        let mut learned = OpcodeDenylist::default();
+        assert_eq!(learned.learn_from_exceptions(&diff), 0);
+        assert!(learned.is_empty());
+    }
+
+    /// 以 `seed` 生成的 M 扩展指令 (不含控制流指令，数量不受过滤影响)
+    fn m_instructions(count: usize, denylist: &OpcodeDenylist, seed: u64) -> Vec<RiscvInstruction> {
+        generate_instructions(
+            &HashMap::from([(RV64Extensions::M, count)]),
+            GenerationOrder::Sequential,
+            denylist,
+            &mut seeded_rng(seed),
+        )
+    }
+
+    #[test]
+    fn test_filter_instructions_checks_enabled_emulators_only() {
+        let all = opcodes(&m_instructions(32, &OpcodeDenylist::default(), 1));
+        let denied = all[0].clone();
+
+        // 默认配置只启用 Spike 和 Rocket
+        let qemu_only = denylist(&[(EmulatorType::Qemu, &denied)]);
+        assert!(!qemu_only.is_denied(&denied));
+        let kept = qemu_only.filter_instructions(m_instructions(32, &OpcodeDenylist::default(), 1));
+        assert_eq!(opcodes(&kept), all);
+
+        let rocket = denylist(&[(EmulatorType::Rocket, &denied)]);
+        assert!(rocket.is_denied(&denied));
+        let kept = rocket.filter_instructions(m_instructions(32, &OpcodeDenylist::default(), 1));
+        let expected: Vec<String> = all.into_iter().filter(|op| *op != denied).collect();
+        assert_eq!(opcodes(&kept), expected);
+    }
+
+    #[test]
+    fn test_generation_resamples_denied_opcodes() {
+        let denied = denylist(&[(EmulatorType::Rocket, "mul"), (EmulatorType::Spike, "div")]);
+        let ops = opcodes(&m_instructions(64, &denied, 7));
+        assert_eq!(ops.len(), 64);
+        assert!(!ops.iter().any(|op| op == "mul" || op == "div"));
+
+        // 没有被拒绝的指令时不重新采样，种子生成的程序保持不变
+        let generator = InstructionsGenerator::new_rv64().with(RV64Extensions::M, 64);
+        assert_eq!(
+            opcodes(&m_instructions(64, &OpcodeDenylist::default(), 7)),
+            opcodes(&generator.generate_with_rng(&mut seeded_rng(7)))
+        );
+    }
+
+    #[test]
+    fn test_merge_counts_new_opcodes() {
+        let mut base = denylist(&[(EmulatorType::Rocket, "fli.s")]);
+        let other = denylist(&[
+            (EmulatorType::Rocket, "fli.s"),
+            (EmulatorType::Rocket, "csrr"),
+            (EmulatorType::Spike, "fli.s"),
+        ]);
+        assert_eq!(base.merge(&other), 2);
+        assert_eq!(base, other);
+        assert_eq!(base.merge(&other), 0);
+    }
+
+    #[test]
+    fn test_custom_emulator_round_trips_through_json() {
+        let custom = EmulatorType::Custom("my_rtl".to_string());
+        let original = denylist(&[(custom.clone(), "fli.s"), (EmulatorType::Rocket, "fli.s")]);
+        let json = serde_json::to_string(&original).unwrap();
+        assert_eq!(
+            serde_json::from_str::<OpcodeDenylist>(&json).unwrap(),
+            original
+        );
+
+        let path = std::env::temp_dir().join(format!(
+            "opcode_denylist_custom_test_{}.json",
+            std::process::id()
+        ));
+        let _ = fs::remove_file(&path);
+        let more = exceptions(vec![only_in(custom, 2, &["fcvtmod.w.d a0, fa0, rtz"])]);
+        assert_eq!(OpcodeDenylist::record_exceptions(&path, &more).unwrap(), 1);
+        assert_eq!(
+            OpcodeDenylist::record_denylist(&path, &original).unwrap(),
+            2
+        );
+        let loaded = OpcodeDenylist::load_or_default(&path).unwrap();
+        assert_eq!(loaded.emulators["my_rtl"].len(), 2);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_record_exceptions_merges_into_file() {
+        let path =
+            std::env::temp_dir().join(format!("opcode_denylist_test_{}.json", std::process::id()));
+        let _ = fs::remove_file(&path);
+
+        let diff = exceptions(vec![only_in(EmulatorType::Rocket, 2, &["fli.s fa0, 1.0"])]);
+        assert_eq!(OpcodeDenylist::record_exceptions(&path, &diff).unwrap(), 1);
+        assert_eq!(OpcodeDenylist::record_exceptions(&path, &diff).unwrap(), 0);
+
+        let more = exceptions(vec![only_in(EmulatorType::Spike, 2, &["fli.s fa0, 1.0"])]);
+        assert_eq!(OpcodeDenylist::record_exceptions(&path, &more).unwrap(), 1);
+        assert_eq!(
+            OpcodeDenylist::load_or_default(&path).unwrap(),
+            denylist(&[
+                (EmulatorType::Rocket, "fli.s"),
+                (EmulatorType::Spike, "fli.s")
+            ])
+        );
+
+        fs::remove_file(&path).unwrap();
+        assert!(OpcodeDenylist::load_or_default(&path).unwrap().is_empty());
+    }
+}
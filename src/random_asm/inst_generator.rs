@@ -20,7 +20,6 @@ use serde::{Deserialize, Serialize};
 //     Zicsr, Zifencei, Zilsd, Zimop, Zkn, Zknd, Zkne, Zknh, Zks, Zvbb, Zvbc,
 //     Zvfbfmin, Zvfbfwma, Zvkg, Zvkned, Zvknha, Zvks
 // }
-use std::collections::HashMap;
 
 #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum IsaBase {
@@ -39,14 +38,18 @@ pub struct InstructionsGenerator<
     E: Copy + Eq + std::hash::Hash + Serialize + for<'a> Deserialize<'a>,
 > {
     order: GenerationOrder,
-    counts: HashMap<E, usize>,
+    /// 按加入顺序保存，保证同一 rng 状态生成相同的指令
+    counts: Vec<(E, usize)>,
 }
 
 impl<E: Copy + Eq + std::hash::Hash + Serialize + for<'a> Deserialize<'a>>
     InstructionsGenerator<E>
 {
     pub fn with(mut self, ext: E, count: usize) -> Self {
-        *self.counts.entry(ext).or_insert(0) += count;
+        match self.counts.iter_mut().find(|(e, _)| *e == ext) {
+            Some((_, existing)) => *existing += count,
+            None => self.counts.push((ext, count)),
+        }
         self
     }
 
@@ -72,14 +75,35 @@ impl ExtensionRng for RV32Extensions {
     }
 }
 
+/// 每条指令最多采样的次数，扩展中的指令几乎全被拒绝时不再补足数量
+const MAX_SAMPLE_ATTEMPTS: usize = 64;
+
 impl<E: ExtensionRng> InstructionsGenerator<E> {
     pub fn generate_with_rng<R: rand::Rng>(&self, rng: &mut R) -> Vec<RiscvInstruction> {
+        self.generate_accepted_with_rng(rng, |_| true)
+    }
+
+    /// Like [`InstructionsGenerator::generate_with_rng`], resampling every
+    /// instruction `accept` rejects so each extension still gets its count.
+    ///
+    /// A slot is left empty after [`MAX_SAMPLE_ATTEMPTS`] rejections. When
+    /// `accept` takes everything the rng is consumed exactly as by
+    /// `generate_with_rng`, so existing seeds reproduce the same program.
+    pub fn generate_accepted_with_rng<R, F>(&self, rng: &mut R, accept: F) -> Vec<RiscvInstruction>
+    where
+        R: rand::Rng,
+        F: Fn(&RiscvInstruction) -> bool,
+    {
         let mut instructions = Vec::new();
 
-        for (&ext, &count) in &self.counts {
+        for &(ext, count) in &self.counts {
             for _ in 0..count {
-                let instr = ext.random_instruction(rng);
-                instructions.push(instr);
+                if let Some(instr) = (0..MAX_SAMPLE_ATTEMPTS)
+                    .map(|_| ext.random_instruction(rng))
+                    .find(|instr| accept(instr))
+                {
+                    instructions.push(instr);
+                }
             }
         }
 
@@ -100,7 +124,7 @@ impl InstructionsGenerator<RV64Extensions> {
     pub fn new_rv64() -> Self {
         Self {
             order: GenerationOrder::Sequential,
-            counts: HashMap::new(),
+            counts: Vec::new(),
         }
     }
 }
@@ -109,7 +133,7 @@ impl InstructionsGenerator<RV32Extensions> {
     pub fn new_rv32() -> Self {
         Self {
             order: GenerationOrder::Sequential,
-            counts: HashMap::new(),
+            counts: Vec::new(),
         }
     }
 }
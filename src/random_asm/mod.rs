@@ -1,2 +1,4 @@
 pub mod asm_maker;
+pub mod denylist;
 pub mod inst_generator;
+pub mod seed;
@@ -0,0 +1,394 @@
+//! 回归测试
+//!
+//! A regression suite is a directory of `.S` files. `foo.S` may have a golden
+//! `foo.expected.json` (a [`StandardExecutionOutput`], e.g. a saved
+//! `*_output.json`); every selected emulator's output is diffed against it
+//! and any difference in the [`DivergenceSignature`] sense fails the case.
+//! With `bless`, missing goldens are written from the first emulator.
+
+use log::{info, warn};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::consts::linker_script::LINKER_SCRIPT;
+use crate::elf::build::build_elf;
+use crate::emulators::registry::EmulatorRegistry;
+use crate::emulators::{EmulatorType, RunLimits, run_and_parse};
+use crate::error::{Result, RiscvFuzzError};
+use crate::output_diff::analysis::bucket::DivergenceSignature;
+use crate::output_diff::diff::compare_outputs;
+use crate::output_parser::load_output_from_json;
+use crate::output_parser::standard::StandardExecutionOutput;
+use crate::output_parser::util::get_current_timestamp;
+
+/// 期望输出的扩展名: `foo.S` -> `foo.expected.json`
+pub const EXPECTED_EXTENSION: &str = "expected.json";
+/// 回归汇总文件名 (不含扩展名)
+pub const REGRESSION_SUMMARY_FILE: &str = "regression_summary";
+
+/// 回归结果，按从好到坏排序
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum RegressionStatus {
+    Pass,
+    /// 期望输出由本次运行写入
+    Blessed,
+    /// 没有期望输出，未比较
+    NoExpected,
+    Fail,
+    /// 编译或运行失败
+    Error,
+}
+
+impl RegressionStatus {
+    pub fn is_failure(self) -> bool {
+        self >= RegressionStatus::Fail
+    }
+}
+
+impl fmt::Display for RegressionStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RegressionStatus::Pass => write!(f, "✅ pass"),
+            RegressionStatus::Blessed => write!(f, "📌 blessed"),
+            RegressionStatus::NoExpected => write!(f, "➖ no expected output"),
+            RegressionStatus::Fail => write!(f, "❌ fail"),
+            RegressionStatus::Error => write!(f, "💥 error"),
+        }
+    }
+}
+
+/// 单个模拟器在某用例上的结果
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EmulatorRegression {
+    pub emulator_type: EmulatorType,
+    pub status: RegressionStatus,
+    /// 与期望输出的差异签名
+    pub signature: Option<DivergenceSignature>,
+    pub note: Option<String>,
+}
+
+impl EmulatorRegression {
+    fn error(emulator_type: EmulatorType, note: String) -> Self {
+        Self {
+            emulator_type,
+            status: RegressionStatus::Error,
+            signature: None,
+            note: Some(note),
+        }
+    }
+}
+
+/// One `.S` file of the suite
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RegressionCase {
+    pub name: String,
+    pub assembly_file: PathBuf,
+    pub expected_file: PathBuf,
+    pub emulators: Vec<EmulatorRegression>,
+    /// 编译失败等整个用例的错误
+    pub note: Option<String>,
+}
+
+impl RegressionCase {
+    /// 所有模拟器中最差的结果
+    pub fn status(&self) -> RegressionStatus {
+        if self.note.is_some() {
+            return RegressionStatus::Error;
+        }
+        self.emulators
+            .iter()
+            .map(|e| e.status)
+            .max()
+            .unwrap_or(RegressionStatus::NoExpected)
+    }
+}
+
+/// Report of a `regress` run
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RegressionSummary {
+    pub created_at: String,
+    pub suite_dir: PathBuf,
+    pub march: String,
+    pub emulators: Vec<EmulatorType>,
+    pub cases: Vec<RegressionCase>,
+}
+
+impl RegressionSummary {
+    pub fn count(&self, status: RegressionStatus) -> usize {
+        self.cases.iter().filter(|c| c.status() == status).count()
+    }
+
+    pub fn failures(&self) -> usize {
+        self.cases
+            .iter()
+            .filter(|c| c.status().is_failure())
+            .count()
+    }
+
+    /// 保存为 `regression_summary.json` 和 `regression_summary.md`
+    pub fn save<P: AsRef<Path>>(&self, output_dir: P) -> Result<()> {
+        let base = output_dir.as_ref().join(REGRESSION_SUMMARY_FILE);
+        fs::write(
+            base.with_extension("json"),
+            serde_json::to_string_pretty(self)?,
+        )?;
+        fs::write(base.with_extension("md"), self.to_string())?;
+        Ok(())
+    }
+}
+
+impl fmt::Display for RegressionSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "# 🧷 Regression Summary")?;
+        writeln!(f)?;
+        writeln!(f, "- **Suite:** `{}`", self.suite_dir.display())?;
+        writeln!(f, "- **Created:** {}", self.created_at)?;
+        writeln!(f, "- **March:** `{}`", self.march)?;
+        writeln!(f)?;
+
+        writeln!(f, "| Status | Count |")?;
+        writeln!(f, "|--------|-------|")?;
+        for status in [
+            RegressionStatus::Pass,
+            RegressionStatus::Blessed,
+            RegressionStatus::NoExpected,
+            RegressionStatus::Fail,
+            RegressionStatus::Error,
+        ] {
+            writeln!(f, "| {} | `{}` |", status, self.count(status))?;
+        }
+        writeln!(f)?;
+
+        write!(f, "| Case |")?;
+        for emulator in &self.emulators {
+            write!(f, " {} |", emulator)?;
+        }
+        writeln!(f)?;
+        write!(f, "|------|")?;
+        for _ in &self.emulators {
+            write!(f, "------------|")?;
+        }
+        writeln!(f)?;
+        for case in &self.cases {
+            write!(f, "| `{}` |", case.name)?;
+            if let Some(note) = &case.note {
+                for _ in &self.emulators {
+                    write!(f, " {} ({}) |", RegressionStatus::Error, note)?;
+                }
+                writeln!(f)?;
+                continue;
+            }
+            for result in &case.emulators {
+                match (&result.note, &result.signature) {
+                    (Some(note), _) => write!(f, " {} ({}) |", result.status, note)?,
+                    (None, Some(signature)) if !signature.is_empty() => {
+                        write!(f, " {} ({}) |", result.status, signature)?
+                    }
+                    _ => write!(f, " {} |", result.status)?,
+                }
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+/// `foo.S` 的期望输出路径
+pub fn expected_output_path(assembly_file: &Path) -> PathBuf {
+    assembly_file.with_extension(EXPECTED_EXTENSION)
+}
+
+/// 回归目录中的汇编文件，按文件名排序
+pub fn find_regression_cases(suite_dir: &Path) -> Result<Vec<PathBuf>> {
+    let entries = fs::read_dir(suite_dir).map_err(|e| {
+        RiscvFuzzError::file(format!(
+            "Failed to read regression suite {}: {}",
+            suite_dir.display(),
+            e
+        ))
+    })?;
+    let mut cases: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.is_file() && path.extension().is_some_and(|ext| ext == "S" || ext == "s")
+        })
+        .collect();
+    cases.sort();
+    Ok(cases)
+}
+
+/// 并行运行 `suite_dir` 中的所有用例，每个用例在 `build_dir/<name>` 中构建
+pub fn run_regression(
+    suite_dir: &Path,
+    build_dir: &Path,
+    emulators: &[EmulatorType],
+    march: &str,
+    limits: &RunLimits,
+    bless: bool,
+) -> Result<RegressionSummary> {
+    let cases = find_regression_cases(suite_dir)?;
+    if cases.is_empty() {
+        return Err(RiscvFuzzError::file(format!(
+            "No .S files found in {}",
+            suite_dir.display()
+        )));
+    }
+    fs::create_dir_all(build_dir)?;
+    info!(
+        "🧷 Running {} regression cases on {:?}",
+        cases.len(),
+        emulators
+    );
+
+    let cases = cases
+        .par_iter()
+        .map(|assembly_file| run_case(assembly_file, build_dir, emulators, march, limits, bless))
+        .collect();
+
+    Ok(RegressionSummary {
+        created_at: get_current_timestamp(),
+        suite_dir: suite_dir.to_path_buf(),
+        march: march.to_string(),
+        emulators: emulators.to_vec(),
+        cases,
+    })
+}
+
+fn run_case(
+    assembly_file: &Path,
+    build_dir: &Path,
+    emulators: &[EmulatorType],
+    march: &str,
+    limits: &RunLimits,
+    bless: bool,
+) -> RegressionCase {
+    let name = assembly_file
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let expected_file = expected_output_path(assembly_file);
+    let mut case = RegressionCase {
+        name: name.clone(),
+        assembly_file: assembly_file.to_path_buf(),
+        expected_file: expected_file.clone(),
+        emulators: Vec::new(),
+        note: None,
+    };
+    let fail_case = |mut case: RegressionCase, note: String| {
+        warn!("⚠️ Regression case {}: {}", case.name, note);
+        case.note = Some(note);
+        case
+    };
+
+    let expected = if expected_file.exists() {
+        match load_output_from_json::<StandardExecutionOutput, _>(&expected_file) {
+            Ok(expected) => Some(expected),
+            Err(e) => return fail_case(case, format!("cannot load expected output: {}", e)),
+        }
+    } else {
+        None
+    };
+
+    // 在独立目录中构建，避免污染回归目录
+    let case_dir = build_dir.join(&name);
+    let case_assembly = case_dir.join(assembly_file.file_name().unwrap_or_default());
+    let suite_linker = assembly_file.with_file_name("linker.ld");
+    let linker_script = case_dir.join("linker.ld");
+    let prepared = fs::create_dir_all(&case_dir)
+        .and_then(|_| fs::copy(assembly_file, &case_assembly))
+        .and_then(|_| {
+            if suite_linker.exists() {
+                fs::copy(&suite_linker, &linker_script).map(|_| ())
+            } else {
+                fs::write(&linker_script, LINKER_SCRIPT)
+            }
+        });
+    if let Err(e) = prepared {
+        return fail_case(
+            case,
+            format!("cannot prepare {}: {}", case_dir.display(), e),
+        );
+    }
+
+    let build_result = match build_elf(&case_assembly, &linker_script, march) {
+        Ok(build_result) => build_result,
+        Err(e) => return fail_case(case, format!("build failed: {}", e)),
+    };
+    let registry = match EmulatorRegistry::with_limits(march, limits).select(emulators) {
+        Ok(registry) => registry,
+        Err(e) => return fail_case(case, e.to_string()),
+    };
+
+    let mut expected = expected;
+    for emulator in registry.iter() {
+        let emulator_type = emulator.emulator_type();
+        let log_file = case_dir.join(format!("{}_output.bin", emulator.name()));
+        let output = match run_and_parse::<StandardExecutionOutput, &Path>(
+            emulator,
+            &build_result.executable_file,
+            &build_result.disassembly_file,
+            &log_file,
+        ) {
+            Ok(output) => output,
+            Err(e) => {
+                warn!("⚠️ {} failed on {}: {}", emulator.name(), name, e);
+                case.emulators.push(EmulatorRegression::error(
+                    emulator_type,
+                    "run failed".to_string(),
+                ));
+                continue;
+            }
+        };
+
+        let result = match &expected {
+            Some(expected) => {
+                let diff = compare_outputs(expected, &output);
+                let signature = DivergenceSignature::from_standard_diff(&diff);
+                let status = if signature.is_empty() {
+                    RegressionStatus::Pass
+                } else {
+                    let diff_file = case_dir.join(format!("diff_{}_expected.md", emulator.name()));
+                    if let Err(e) = fs::write(&diff_file, diff.to_string()) {
+                        warn!("⚠️ Failed to write {:?}: {}", diff_file, e);
+                    }
+                    RegressionStatus::Fail
+                };
+                EmulatorRegression {
+                    emulator_type,
+                    status,
+                    signature: Some(signature),
+                    note: None,
+                }
+            }
+            None if bless => {
+                let blessed = serde_json::to_string_pretty(&output)
+                    .map_err(RiscvFuzzError::from)
+                    .and_then(|json| Ok(fs::write(&expected_file, json)?));
+                if let Err(e) = blessed {
+                    return fail_case(case, format!("cannot write expected output: {}", e));
+                }
+                info!("📌 Blessed {:?} from {}", expected_file, emulator.name());
+                expected = Some(output);
+                EmulatorRegression {
+                    emulator_type,
+                    status: RegressionStatus::Blessed,
+                    signature: None,
+                    note: None,
+                }
+            }
+            None => EmulatorRegression {
+                emulator_type,
+                status: RegressionStatus::NoExpected,
+                signature: None,
+                note: None,
+            },
+        };
+        case.emulators.push(result);
+    }
+
+    info!("🧷 {}: {}", name, case.status());
+    case
+}
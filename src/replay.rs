@@ -0,0 +1,330 @@
+//! 测试目录重放
+//!
+//! Every run of `process_assembly_file` leaves a [`RunRecord`] in its build
+//! directory. `replay` reads it back together with `seed.json`, rebuilds the
+//! test in a fresh `replay/` subdirectory with the recorded march string and
+//! limits, re-runs the (possibly updated) emulators and compares the new
+//! divergence signature with the one of the stored `diff_standard.json`.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::config::{self, resolve_emulator_path};
+use crate::emulators::{EmulatorType, OutputFormat, RunLimits};
+use crate::error::{Result, RiscvFuzzError};
+use crate::output_diff::analysis::bucket::DivergenceSignature;
+use crate::output_diff::diff::run_diff::RunDivergence;
+use crate::output_diff::diff::standard_diff::StandardExecutionOutputDiff;
+use crate::output_parser::util::get_current_timestamp;
+use crate::random_asm::seed::{SEED_FILE, SeedRecord};
+
+/// 构建目录中记录运行配置的文件
+pub const RUN_RECORD_FILE: &str = "run_config.json";
+/// 重放输出子目录
+pub const REPLAY_DIR: &str = "replay";
+/// 重放报告文件名 (不含扩展名)
+pub const REPLAY_REPORT_FILE: &str = "replay_report";
+
+/// Emulator binaries resolved the same way the emulator backends do
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EmulatorPaths {
+    pub spike: String,
+    pub rocket: String,
+}
+
+impl EmulatorPaths {
+    pub fn current() -> Self {
+        let emulators = &config::get().emulators;
+        Self {
+            spike: emulators.spike_path.clone(),
+            rocket: resolve_emulator_path(
+                "ROCKET_EMULATOR_PATH",
+                emulators.rocket_path.as_ref(),
+                "emulators/rocket_emulator",
+            ),
+        }
+    }
+}
+
+/// Everything `process_assembly_file` was run with
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RunRecord {
+    pub tool_version: String,
+    pub timestamp: String,
+    pub assembly_file: PathBuf,
+    pub march: String,
+    /// 墙钟超时 (秒)，None 表示不限制
+    pub timeout_seconds: Option<u64>,
+    pub max_cycles: Option<u64>,
+    pub max_instructions: Option<u64>,
+    pub format: OutputFormat,
+    pub auto_retry: bool,
+    pub emulators: EmulatorPaths,
+}
+
+impl RunRecord {
+    pub fn capture(
+        assembly_file: &Path,
+        march: &str,
+        limits: &RunLimits,
+        format: OutputFormat,
+        auto_retry: bool,
+    ) -> Self {
+        Self {
+            tool_version: env!("CARGO_PKG_VERSION").to_string(),
+            timestamp: get_current_timestamp(),
+            assembly_file: fs::canonicalize(assembly_file)
+                .unwrap_or_else(|_| assembly_file.to_path_buf()),
+            march: march.to_string(),
+            timeout_seconds: limits.timeout.map(|timeout| timeout.as_secs()),
+            max_cycles: limits.max_cycles,
+            max_instructions: limits.max_instructions,
+            format,
+            auto_retry,
+            emulators: EmulatorPaths::current(),
+        }
+    }
+
+    pub fn run_limits(&self) -> RunLimits {
+        RunLimits {
+            timeout: self.timeout_seconds.map(Duration::from_secs),
+            max_cycles: self.max_cycles,
+            max_instructions: self.max_instructions,
+        }
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, build_dir: P) -> Result<()> {
+        fs::write(
+            build_dir.as_ref().join(RUN_RECORD_FILE),
+            serde_json::to_string_pretty(self)?,
+        )?;
+        Ok(())
+    }
+
+    /// 读取构建目录中的记录，不存在时返回 None
+    pub fn load<P: AsRef<Path>>(build_dir: P) -> Result<Option<Self>> {
+        load_json(&build_dir.as_ref().join(RUN_RECORD_FILE))
+    }
+}
+
+/// 读取测试目录中的 `seed.json`，不存在时返回 None
+pub fn load_seed_record<P: AsRef<Path>>(test_dir: P) -> Result<Option<SeedRecord>> {
+    load_json(&test_dir.as_ref().join(SEED_FILE))
+}
+
+fn load_json<T: for<'de> Deserialize<'de>>(path: &Path) -> Result<Option<T>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let content = fs::read_to_string(path)
+        .map_err(|e| RiscvFuzzError::file(format!("Failed to read {}: {}", path.display(), e)))?;
+    Ok(Some(serde_json::from_str(&content)?))
+}
+
+/// Signature of the initial comparison stored in a build directory:
+/// `diff_standard.json`, else `diff_run.json`, else None (nothing compared)
+pub fn stored_signature<P: AsRef<Path>>(build_dir: P) -> Result<Option<DivergenceSignature>> {
+    let build_dir = build_dir.as_ref();
+    if let Some(diff) =
+        load_json::<StandardExecutionOutputDiff>(&build_dir.join("diff_standard.json"))?
+    {
+        return Ok(Some(DivergenceSignature::from_standard_diff(&diff)));
+    }
+    Ok(
+        load_json::<RunDivergence>(&build_dir.join("diff_run.json"))?
+            .map(|divergence| DivergenceSignature::from_run_divergence(&divergence)),
+    )
+}
+
+/// Emulator pair compared by the `diff_standard.json` stored in a build
+/// directory, None when there is none
+pub fn stored_emulators<P: AsRef<Path>>(build_dir: P) -> Result<Option<[EmulatorType; 2]>> {
+    Ok(
+        load_json::<StandardExecutionOutputDiff>(&build_dir.as_ref().join("diff_standard.json"))?
+            .map(|diff| [diff.sim1_emulator_type, diff.sim2_emulator_type]),
+    )
+}
+
+/// 重放结论
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ReplayStatus {
+    /// 差异签名与存储的一致
+    Reproduced,
+    /// 存储时有差异，重放后消失
+    Fixed,
+    /// 两次都有差异，但签名不同
+    Changed,
+    /// 存储时无差异，重放后出现
+    NewDivergence,
+    /// 两次都无差异
+    StillClean,
+    /// 缺少存储或重放的比较结果
+    Inconclusive,
+}
+
+impl ReplayStatus {
+    pub fn classify(
+        stored: Option<&DivergenceSignature>,
+        replayed: Option<&DivergenceSignature>,
+    ) -> Self {
+        let (Some(stored), Some(replayed)) = (stored, replayed) else {
+            return ReplayStatus::Inconclusive;
+        };
+        match (stored.is_empty(), replayed.is_empty()) {
+            (true, true) => ReplayStatus::StillClean,
+            (false, true) => ReplayStatus::Fixed,
+            (true, false) => ReplayStatus::NewDivergence,
+            (false, false) if stored == replayed => ReplayStatus::Reproduced,
+            (false, false) => ReplayStatus::Changed,
+        }
+    }
+}
+
+impl fmt::Display for ReplayStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplayStatus::Reproduced => write!(f, "🔁 reproduced"),
+            ReplayStatus::Fixed => write!(f, "🎉 fixed"),
+            ReplayStatus::Changed => write!(f, "🔀 changed"),
+            ReplayStatus::NewDivergence => write!(f, "🆕 new divergence"),
+            ReplayStatus::StillClean => write!(f, "✅ still clean"),
+            ReplayStatus::Inconclusive => write!(f, "❓ inconclusive"),
+        }
+    }
+}
+
+/// Result of replaying one test directory
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReplayReport {
+    pub test_dir: PathBuf,
+    pub timestamp: String,
+    pub assembly_file: PathBuf,
+    /// 是否按 seed.json 重新生成了汇编
+    pub regenerated: bool,
+    pub record: Option<RunRecord>,
+    pub seed: Option<SeedRecord>,
+    pub emulators: EmulatorPaths,
+    pub stored_signature: Option<DivergenceSignature>,
+    pub replay_signature: Option<DivergenceSignature>,
+    pub status: ReplayStatus,
+}
+
+impl ReplayReport {
+    /// 保存为 `replay_report.json` 和 `replay_report.md`
+    pub fn save<P: AsRef<Path>>(&self, replay_dir: P) -> Result<()> {
+        let base = replay_dir.as_ref().join(REPLAY_REPORT_FILE);
+        fs::write(
+            base.with_extension("json"),
+            serde_json::to_string_pretty(self)?,
+        )?;
+        fs::write(base.with_extension("md"), self.to_string())?;
+        Ok(())
+    }
+}
+
+impl fmt::Display for ReplayReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "# 🔁 Replay Report")?;
+        writeln!(f)?;
+        writeln!(f, "- **Test:** `{}`", self.test_dir.display())?;
+        writeln!(f, "- **Replayed:** {}", self.timestamp)?;
+        writeln!(f, "- **Assembly:** `{}`", self.assembly_file.display())?;
+        if self.regenerated {
+            writeln!(f, "- **Regenerated from seed.json**")?;
+        }
+        if let Some(seed) = &self.seed {
+            writeln!(f, "- **Seed:** {}", seed.seed)?;
+        }
+        match &self.record {
+            Some(record) => {
+                writeln!(f, "- **March:** `{}`", record.march)?;
+                writeln!(
+                    f,
+                    "- **Recorded:** {} (version {})",
+                    record.timestamp, record.tool_version
+                )?;
+            }
+            None => writeln!(f, "- **Recorded:** no `{}`", RUN_RECORD_FILE)?,
+        }
+        writeln!(f, "- **Status:** {}", self.status)?;
+        writeln!(f)?;
+
+        writeln!(f, "| Emulator | Recorded | Replayed |")?;
+        writeln!(f, "|----------|----------|----------|")?;
+        let recorded = self.record.as_ref().map(|record| &record.emulators);
+        writeln!(
+            f,
+            "| Spike | `{}` | `{}` |",
+            recorded.map_or("-", |paths| paths.spike.as_str()),
+            self.emulators.spike
+        )?;
+        writeln!(
+            f,
+            "| Rocket | `{}` | `{}` |",
+            recorded.map_or("-", |paths| paths.rocket.as_str()),
+            self.emulators.rocket
+        )?;
+        writeln!(f)?;
+
+        let show = |signature: &Option<DivergenceSignature>| match signature {
+            Some(signature) if signature.is_empty() => "no divergence".to_string(),
+            Some(signature) => signature.to_string(),
+            None => "-".to_string(),
+        };
+        writeln!(f, "## 🔍 Signatures")?;
+        writeln!(f)?;
+        writeln!(f, "- **Stored:** {}", show(&self.stored_signature))?;
+        writeln!(f, "- **Replayed:** {}", show(&self.replay_signature))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn signature(register: Option<&str>) -> DivergenceSignature {
+        DivergenceSignature {
+            register: register.map(|r| r.to_string()),
+            ..DivergenceSignature::default()
+        }
+    }
+
+    #[test]
+    fn test_classify_replay_status() {
+        let clean = signature(None);
+        let a0 = signature(Some("int"));
+        let a1 = signature(Some("float"));
+
+        let classify = |stored, replayed| ReplayStatus::classify(stored, replayed);
+        assert_eq!(classify(Some(&a0), Some(&a0)), ReplayStatus::Reproduced);
+        assert_eq!(classify(Some(&a0), Some(&clean)), ReplayStatus::Fixed);
+        assert_eq!(classify(Some(&a0), Some(&a1)), ReplayStatus::Changed);
+        assert_eq!(
+            classify(Some(&clean), Some(&a1)),
+            ReplayStatus::NewDivergence
+        );
+        assert_eq!(
+            classify(Some(&clean), Some(&clean)),
+            ReplayStatus::StillClean
+        );
+        assert_eq!(classify(None, Some(&a0)), ReplayStatus::Inconclusive);
+        assert_eq!(classify(Some(&a0), None), ReplayStatus::Inconclusive);
+        assert_eq!(classify(None, None), ReplayStatus::Inconclusive);
+    }
+
+    #[test]
+    fn test_one_sided_register_dump_is_a_divergence() {
+        let stored = DivergenceSignature {
+            register_dump: Some("Present in Spike, Absent in Rocket".to_string()),
+            ..DivergenceSignature::default()
+        };
+        assert_eq!(
+            ReplayStatus::classify(Some(&stored), Some(&signature(None))),
+            ReplayStatus::Fixed
+        );
+    }
+}
@@ -387,6 +387,27 @@ pub fn build_rv64_march(extensions: &[RV64Extensions]) -> String {
     assemble_march("rv64", std_exts, other_exts)
 }
 
+/// march 字符串包含的扩展名 (单字母扩展拆开，`g` 展开为 `imafd_zicsr_zifencei`)
+pub fn march_components(march: &str) -> BTreeSet<String> {
+    let march = march.to_lowercase();
+    let rest = march
+        .strip_prefix("rv64")
+        .or_else(|| march.strip_prefix("rv32"))
+        .unwrap_or(&march);
+
+    let mut components = BTreeSet::new();
+    let mut parts = rest.split('_');
+    for letter in parts.next().unwrap_or_default().chars() {
+        if letter == 'g' {
+            components.extend(["i", "m", "a", "f", "d", "zicsr", "zifencei"].map(String::from));
+        } else {
+            components.insert(letter.to_string());
+        }
+    }
+    components.extend(parts.filter(|p| !p.is_empty()).map(String::from));
+    components
+}
+
 pub fn extract_user_code_instructions(assembly_code: &str) -> Vec<String> {
     let mut instructions = Vec::new();
     let mut in_user_code_section = false;